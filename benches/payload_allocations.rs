@@ -0,0 +1,90 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Counts the allocations made while a few DAGs of tiny payloads converge, with `Vec<u8>` and
+//! with `SmallPayload` payloads. Run with `cargo bench --bench payload_allocations`.
+
+extern crate dag_gossip;
+extern crate ed25519_dalek;
+extern crate maidsafe_utilities;
+extern crate sha3;
+
+use dag_gossip::{Dag, Payload, SmallPayload};
+use ed25519_dalek::Keypair;
+use maidsafe_utilities::SeededRng;
+use sha3::Sha3_512;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+
+const NODES: usize = 4;
+const PAYLOADS_PER_NODE: usize = 25;
+
+static ALLOCATIONS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// Counts the allocations, leaving the work to the system allocator.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// The number of allocations made for every node to create its payloads, and for all the DAGs to
+// converge by repeated unions.
+fn converge<T: Payload + From<Vec<u8>>>() -> usize {
+    let mut rng = SeededRng::from_seed([1, 2, 3, 4]);
+    let keys: Vec<Keypair> = (0..NODES)
+        .map(|_| Keypair::generate::<Sha3_512>(&mut rng))
+        .collect();
+    let mut dags: Vec<Dag<T>> = keys.iter().map(Dag::new).collect();
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    for (node, dag) in dags.iter_mut().enumerate() {
+        for index in 0..PAYLOADS_PER_NODE {
+            let payload = vec![node as u8, index as u8, 0, 0, 0, 0, 0, 0];
+            let _ = dag.new_payload(T::from(payload), &keys[node]);
+        }
+    }
+    for _ in 0..2 {
+        for from in 0..NODES {
+            let other = dags[from].clone();
+            for to in (0..NODES).filter(|&to| to != from) {
+                let _ = dags[to].union(&other);
+            }
+        }
+    }
+    ALLOCATIONS.load(Ordering::SeqCst) - before
+}
+
+fn main() {
+    let vec_allocations = converge::<Vec<u8>>();
+    let small_allocations = converge::<SmallPayload>();
+    println!("Vec<u8> payloads:      {} allocations", vec_allocations);
+    println!("SmallPayload payloads: {} allocations", small_allocations);
+    println!(
+        "Saved:                 {} allocations",
+        vec_allocations.saturating_sub(small_allocations)
+    );
+}
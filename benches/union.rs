@@ -0,0 +1,68 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Times the union of a DAG holding a chain of units into a DAG holding only the genesis, and
+//! a second, redundant union, for a few sizes of the chain. Run with
+//! `cargo bench --bench union`.
+
+extern crate dag_gossip;
+extern crate ed25519_dalek;
+extern crate maidsafe_utilities;
+extern crate sha3;
+
+use dag_gossip::Dag;
+use ed25519_dalek::Keypair;
+use maidsafe_utilities::SeededRng;
+use sha3::Sha3_512;
+use std::time::{Duration, Instant};
+
+const SIZES: [usize; 4] = [1_000, 2_000, 4_000, 8_000];
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000 + u64::from(duration.subsec_nanos() / 1_000_000)
+}
+
+// The time taken by the first union of a DAG of `size` units, then by a union of the same DAG
+// again.
+fn time_union(size: usize) -> (Duration, Duration) {
+    let mut rng = SeededRng::from_seed([1, 2, 3, 4]);
+    let sender_keys = Keypair::generate::<Sha3_512>(&mut rng);
+    let receiver_keys = Keypair::generate::<Sha3_512>(&mut rng);
+    let mut sender: Dag = Dag::new(&sender_keys);
+    let payloads = (0..size).map(|index| index.to_string().into_bytes()).collect();
+    let _ = sender.new_payloads(payloads, &sender_keys);
+    let mut receiver: Dag = Dag::new(&receiver_keys);
+
+    let start = Instant::now();
+    let _ = receiver.union(&sender);
+    let first = start.elapsed();
+    let start = Instant::now();
+    let _ = receiver.union(&sender);
+    (first, start.elapsed())
+}
+
+fn main() {
+    for size in &SIZES {
+        let (first, again) = time_union(*size);
+        println!(
+            "{:>6} units: first union {:>6} ms, redundant union {:>6} ms",
+            size,
+            millis(first),
+            millis(again)
+        );
+    }
+}
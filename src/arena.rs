@@ -0,0 +1,174 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use id::UnitId;
+use std::collections::BTreeMap;
+
+// The identifiers of the units of a DAG interned into slots, with the links between units and
+// their depths held per slot, so that walking the DAG doesn't compare or clone 32-byte
+// identifiers at every step. Slots are never reused until the arena is cleared, which the DAG
+// does whenever it rebuilds its indices.
+#[derive(Clone, Default)]
+pub struct Arena {
+    slots: BTreeMap<UnitId, usize>,
+    identifiers: Vec<UnitId>,
+    // Per slot, the slots of the units having it as parent.
+    children: Vec<Vec<usize>>,
+    // Per slot, the depth of the unit, if known.
+    depths: Vec<Option<usize>>,
+}
+
+impl Arena {
+    // Drop all the slots.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.identifiers.clear();
+        self.children.clear();
+        self.depths.clear();
+    }
+
+    // The number of slots handed out.
+    pub fn len(&self) -> usize {
+        self.identifiers.len()
+    }
+
+    // The slot of the identifier, handing out a new one if it has none yet.
+    pub fn intern(&mut self, identifier: UnitId) -> usize {
+        if let Some(&slot) = self.slots.get(&identifier) {
+            return slot;
+        }
+        let slot = self.identifiers.len();
+        let _ = self.slots.insert(identifier, slot);
+        self.identifiers.push(identifier);
+        self.children.push(Vec::new());
+        self.depths.push(None);
+        slot
+    }
+
+    // The slot of the identifier, if it has one.
+    pub fn slot(&self, identifier: &UnitId) -> Option<usize> {
+        self.slots.get(identifier).cloned()
+    }
+
+    // The identifier interned into the slot.
+    pub fn identifier(&self, slot: usize) -> UnitId {
+        self.identifiers[slot]
+    }
+
+    // Record `child` as having `parent` as parent. Returns whether it wasn't recorded yet.
+    pub fn add_child(&mut self, parent: usize, child: usize) -> bool {
+        if self.children[parent].contains(&child) {
+            return false;
+        }
+        self.children[parent].push(child);
+        true
+    }
+
+    // The slots of the children of the unit in the slot.
+    pub fn children(&self, slot: usize) -> &[usize] {
+        &self.children[slot]
+    }
+
+    // Whether the unit has any child.
+    pub fn has_children(&self, identifier: &UnitId) -> bool {
+        self.slot(identifier).map_or(false, |slot| !self.children[slot].is_empty())
+    }
+
+    // Forget the children of the unit, as it's been dropped.
+    pub fn clear_children(&mut self, identifier: &UnitId) {
+        if let Some(slot) = self.slot(identifier) {
+            self.children[slot].clear();
+            self.depths[slot] = None;
+        }
+    }
+
+    // The identifiers of the children of the unit, none if it has no slot.
+    pub fn children_of(&self, identifier: &UnitId) -> Vec<UnitId> {
+        self.slot(identifier).map_or_else(Vec::new, |slot| {
+            self.children[slot]
+                .iter()
+                .map(|&child| self.identifiers[child])
+                .collect()
+        })
+    }
+
+    // The identifiers of the units having children, each with the identifiers of its children.
+    pub fn links(&self) -> Vec<(UnitId, Vec<UnitId>)> {
+        self.identifiers
+            .iter()
+            .enumerate()
+            .filter(|&(slot, _)| !self.children[slot].is_empty())
+            .map(|(slot, identifier)| (*identifier, self.children_of(identifier)))
+            .collect()
+    }
+
+    // The depth of the unit in the slot, if known.
+    pub fn depth(&self, slot: usize) -> Option<usize> {
+        self.depths[slot]
+    }
+
+    // The depth of the unit, if known.
+    pub fn depth_of(&self, identifier: &UnitId) -> Option<usize> {
+        self.slot(identifier).and_then(|slot| self.depths[slot])
+    }
+
+    // Record the depth of the unit in the slot.
+    pub fn set_depth(&mut self, slot: usize, depth: usize) {
+        self.depths[slot] = Some(depth);
+    }
+
+    // Forget the depths of all the units.
+    pub fn clear_depths(&mut self) {
+        for depth in &mut self.depths {
+            *depth = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning() {
+        let mut arena = Arena::default();
+        let parent = arena.intern(UnitId([1; 32]));
+        let child = arena.intern(UnitId([2; 32]));
+        assert_eq!(arena.intern(UnitId([1; 32])), parent);
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.identifier(child), UnitId([2; 32]));
+
+        assert!(arena.add_child(parent, child));
+        assert!(!arena.add_child(parent, child));
+        assert_eq!(arena.children(parent), &[child]);
+        assert!(arena.has_children(&UnitId([1; 32])));
+        assert!(!arena.has_children(&UnitId([2; 32])));
+        assert!(!arena.has_children(&UnitId([3; 32])));
+        assert_eq!(arena.links(), vec![(UnitId([1; 32]), vec![UnitId([2; 32])])]);
+
+        arena.set_depth(child, 1);
+        assert_eq!(arena.depth_of(&UnitId([2; 32])), Some(1));
+        arena.clear_children(&UnitId([1; 32]));
+        assert!(arena.children(parent).is_empty());
+        arena.clear_depths();
+        assert_eq!(arena.depth(child), None);
+
+        arena.clear();
+        assert_eq!(arena.len(), 0);
+        assert_eq!(arena.slot(&UnitId([1; 32])), None);
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use dag_gossiper::Gossiper;
+use error::Error;
+use futures::{Async, Poll, Stream};
+use futures::future::{self, FutureResult};
+use id::Id;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio_timer::Interval;
+
+/// A handle to a `Gossiper` shared between tasks, with rounds driven by a timer instead of a
+/// dedicated thread. Clones refer to the same `Gossiper`.
+#[derive(Clone)]
+pub struct AsyncGossiper {
+    gossiper: Arc<Mutex<Gossiper>>,
+}
+
+impl AsyncGossiper {
+    /// Share `gossiper` between tasks.
+    pub fn new(gossiper: Gossiper) -> Self {
+        AsyncGossiper { gossiper: Arc::new(Mutex::new(gossiper)) }
+    }
+
+    /// Run `f` on the gossiper, e.g. to add peers or send new payloads.
+    pub fn with<R, F: FnOnce(&mut Gossiper) -> R>(&self, f: F) -> R {
+        f(&mut self.lock())
+    }
+
+    /// The messages to send, with the peer to send each to, one per `period`. Rounds are skipped
+    /// while there are no peers. Must be polled within a tokio runtime.
+    pub fn rounds(&self, period: Duration) -> Rounds {
+        Rounds {
+            gossiper: self.clone(),
+            interval: Interval::new(Instant::now(), period),
+        }
+    }
+
+    /// Handle an incoming message from the peer. Resolves to the response to send back, if any,
+    /// or fails if the message couldn't be handled.
+    pub fn handle_received_message(
+        &self,
+        peer_id: &Id,
+        serialised_msg: &[u8],
+    ) -> FutureResult<Option<Vec<u8>>, Error> {
+        future::result(self.lock().handle_received_message(peer_id, serialised_msg))
+    }
+
+    /// Start a new round. Resolves to the message to send, with the peer to send it to.
+    pub fn next_round(&self) -> FutureResult<(Id, Vec<u8>), Error> {
+        future::result(self.lock().next_round())
+    }
+
+    // A panic while holding the lock can't leave the gossiper half updated in a way the next
+    // round wouldn't cope with, so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<Gossiper> {
+        self.gossiper.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The stream of outgoing messages returned by `AsyncGossiper::rounds`.
+pub struct Rounds {
+    gossiper: AsyncGossiper,
+    interval: Interval,
+}
+
+impl Stream for Rounds {
+    type Item = (Id, Vec<u8>);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        loop {
+            match self.interval.poll() {
+                Ok(Async::Ready(Some(_))) => (),
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(None)),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(error) => {
+                    return Err(Error::Io(io::Error::new(io::ErrorKind::Other, error.to_string())));
+                }
+            }
+            match self.gossiper.lock().next_round() {
+                Ok(message) => return Ok(Async::Ready(Some(message))),
+                Err(Error::NoPeers) => (),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Future;
+    use maidsafe_utilities::serialisation;
+    use simulation::create_network;
+
+    #[test]
+    fn shared_gossipers() {
+        let mut gossipers = create_network(2).into_iter().map(AsyncGossiper::new);
+        let (sender, receiver) = (unwrap!(gossipers.next()), unwrap!(gossipers.next()));
+        let sender_id = sender.with(|gossiper| gossiper.id());
+        // Clones share the same gossiper.
+        let _ = unwrap!(sender.clone().with(|gossiper| gossiper.send_new(&"Hello")));
+
+        let (peer_id, message) = unwrap!(sender.next_round().wait());
+        assert_eq!(peer_id, receiver.with(|gossiper| gossiper.id()));
+        let _ = unwrap!(receiver.handle_received_message(&sender_id, &message).wait());
+        let payload = unwrap!(serialisation::serialise(&"Hello"));
+        assert!(receiver.with(|gossiper| gossiper.has_payload(&payload)));
+    }
+}
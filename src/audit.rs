@@ -0,0 +1,106 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use clock;
+use dag::Dag;
+use ed25519_dalek::Keypair;
+use error::Error;
+use id::Id;
+use maidsafe_utilities::serialisation;
+use sha3::Sha3_512;
+
+/// A node's DAG, signed by the node, for auditors to check offline both what the node held and
+/// that it came from that node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedSnapshot {
+    /// The node which took the snapshot.
+    pub signer: Id,
+    /// The node's gossip round when the snapshot was taken.
+    pub round: u64,
+    /// When the snapshot was taken, in seconds since the UNIX epoch, as per the node's clock. 0 if
+    /// the node has no clock, e.g. with the `wasm` feature.
+    pub timestamp: u64,
+    /// The serialised DAG.
+    pub dag: Vec<u8>,
+    /// The signer's signature over all the fields above.
+    pub signature: Vec<u8>,
+}
+
+impl SignedSnapshot {
+    /// Take a snapshot of `dag`, signed with `keys`.
+    pub fn new(dag: &Dag, round: u64, keys: &Keypair) -> Result<Self, Error> {
+        let signer = Id::from(keys.public);
+        let timestamp = clock::unix_time();
+        let dag = serialisation::serialise(dag)?;
+        let signed = serialisation::serialise(&(signer, round, timestamp, &dag))?;
+        let signature = keys.sign::<Sha3_512>(&signed).to_bytes().to_vec();
+        Ok(SignedSnapshot {
+            signer,
+            round,
+            timestamp,
+            dag,
+            signature,
+        })
+    }
+
+    /// Whether the signature is the signer's valid signature over the snapshot.
+    pub fn verify(&self) -> bool {
+        match serialisation::serialise(&(self.signer, self.round, self.timestamp, &self.dag)) {
+            Ok(signed) => self.signer.verify(&signed, &self.signature),
+            Err(_) => false,
+        }
+    }
+
+    /// The DAG held in the snapshot.
+    pub fn dag(&self) -> Result<Dag, Error> {
+        Ok(serialisation::deserialise(&self.dag)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+
+    #[test]
+    fn signed_snapshot() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag = Dag::new(&keys);
+        let identifier = unwrap!(dag.new_payload(vec![1, 2, 3], &keys));
+
+        let snapshot = unwrap!(SignedSnapshot::new(&dag, 7, &keys));
+        assert_eq!(snapshot.signer, Id::from(keys.public));
+        assert_eq!(snapshot.round, 7);
+        assert!(snapshot.verify());
+        let audited = unwrap!(snapshot.dag());
+        assert_eq!(audited.unit_versions(), dag.unit_versions());
+        assert!(audited.get(&identifier).is_some());
+
+        // Any change to the signed fields invalidates the signature.
+        let mut tampered = snapshot.clone();
+        tampered.round += 1;
+        assert!(!tampered.verify());
+        let mut tampered = snapshot.clone();
+        let last = tampered.dag.len() - 1;
+        tampered.dag[last] ^= 1;
+        assert!(!tampered.verify());
+        let mut tampered = snapshot;
+        tampered.signer = Id::from(Keypair::generate::<Sha3_512>(&mut rng).public);
+        assert!(!tampered.verify());
+    }
+}
@@ -0,0 +1,390 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! A node gossiping over TCP, for trying the crate end to end. Build and run with
+//! `cargo run --features cli --bin dag-gossip-node -- [options]`.
+//!
+//! Each line read from stdin, or the body of each request made to the HTTP endpoint if enabled,
+//! is sent as a new payload. Payloads are printed once delivered, and the DAG, in the DOT
+//! language, and statistics are written to `dag.dot` and `stats.txt` periodically.
+//!
+//! The TCP transport doesn't learn peers' addresses from their messages: two nodes only gossip
+//! with each other once each was given the other with `--peer`. A node prints the `--peer`
+//! argument to give to others on startup, and keeps its id across restarts with `--key`.
+
+extern crate dag_gossip;
+extern crate maidsafe_utilities;
+#[cfg(test)]
+#[macro_use]
+extern crate unwrap;
+
+use dag_gossip::{DotOptions, Error, Gossiper, Id, Node, TcpTransport};
+use maidsafe_utilities::serialisation;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage: dag-gossip-node [options]
+
+Options:
+    --listen <address>     Address to listen on for peers [default: 127.0.0.1:0]
+    --peer <id>@<address>  A peer to gossip with, its id in hex or base58. Repeatable
+    --key <file>           File holding our secret key, created if missing [default: a new key]
+    --http <address>       Also accept payloads as the bodies of HTTP requests to this address
+    --dump-dir <dir>       Directory to write dag.dot and stats.txt into [default: .]
+    --dump-every <secs>    Seconds between two dumps [default: 10]
+    --round-ms <millis>    Milliseconds between two gossip rounds [default: 500]
+    --help                 Print this help";
+
+// How long the main loop sleeps between two polls.
+const POLL_INTERVAL_MS: u64 = 10;
+// Largest HTTP request accepted, headers included.
+const MAX_REQUEST_LEN: usize = 1 << 20;
+
+struct Options {
+    listen: String,
+    peers: Vec<(Id, SocketAddr)>,
+    key: Option<PathBuf>,
+    http: Option<String>,
+    dump_dir: PathBuf,
+    dump_every: Duration,
+    round_interval: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            listen: "127.0.0.1:0".to_string(),
+            peers: Vec::new(),
+            key: None,
+            http: None,
+            dump_dir: PathBuf::from("."),
+            dump_every: Duration::from_secs(10),
+            round_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+// Parse the command line arguments, failing with the reason.
+fn parse_options<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        if arg == "--help" {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        let value = match args.next() {
+            Some(value) => value,
+            None => return Err(format!("Missing value for {}", arg)),
+        };
+        match arg.as_str() {
+            "--listen" => options.listen = value,
+            "--peer" => options.peers.push(parse_peer(&value)?),
+            "--key" => options.key = Some(PathBuf::from(value)),
+            "--http" => options.http = Some(value),
+            "--dump-dir" => options.dump_dir = PathBuf::from(value),
+            "--dump-every" => options.dump_every = Duration::from_secs(parse_number(&arg, &value)?),
+            "--round-ms" => {
+                options.round_interval = Duration::from_millis(parse_number(&arg, &value)?)
+            }
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_peer(value: &str) -> Result<(Id, SocketAddr), String> {
+    let mut parts = value.splitn(2, '@');
+    let id = parts.next().unwrap_or("");
+    let address = match parts.next() {
+        Some(address) => address,
+        None => return Err(format!("Peer {:?} isn't given as <id>@<address>", value)),
+    };
+    let id = id.parse::<Id>().map_err(|error| error.to_string())?;
+    let address = address.parse::<SocketAddr>().map_err(|error| {
+        format!("Invalid address {:?}: {}", address, error)
+    })?;
+    Ok((id, address))
+}
+
+fn parse_number(arg: &str, value: &str) -> Result<u64, String> {
+    value.parse().map_err(
+        |_| format!("Invalid value {:?} for {}", value, arg),
+    )
+}
+
+// The gossiper owning the key held in `path`, written there first if the file doesn't exist.
+fn load_gossiper(path: Option<&Path>) -> Result<Gossiper, Error> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(Gossiper::default()),
+    };
+    if path.exists() {
+        let mut bytes = Vec::new();
+        let _ = File::open(path)?.read_to_end(&mut bytes)?;
+        return Gossiper::from_secret_bytes(&bytes);
+    }
+    let gossiper = Gossiper::default();
+    File::create(path)?.write_all(&gossiper.export_secret_key())?;
+    Ok(gossiper)
+}
+
+// Send each line read from stdin, until it is closed.
+fn read_stdin(payloads: &Sender<String>) {
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        match line {
+            Ok(line) => {
+                if !line.is_empty() && payloads.send(line).is_err() {
+                    return;
+                }
+            }
+            Err(error) => {
+                eprintln!("Failed to read stdin: {}", error);
+                return;
+            }
+        }
+    }
+}
+
+// Send the body of each HTTP request received, whatever its method and path.
+fn serve_http(listener: &TcpListener, payloads: &Sender<String>) {
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("Failed to accept HTTP connection: {}", error);
+                continue;
+            }
+        };
+        let status = match read_body(&mut stream) {
+            Ok(ref body) if body.is_empty() => "400 Bad Request",
+            Ok(body) => {
+                if payloads.send(body).is_err() {
+                    return;
+                }
+                "202 Accepted"
+            }
+            Err(error) => {
+                eprintln!("Failed to read HTTP request: {}", error);
+                "400 Bad Request"
+            }
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+        );
+        let _ = stream.write_all(response.as_bytes());
+    }
+}
+
+// The body of the request, as long as its `Content-Length` says.
+fn read_body(stream: &mut TcpStream) -> io::Result<String> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_LEN as u64));
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated headers"));
+        }
+        let line = line.trim_right();
+        if line.is_empty() {
+            break;
+        }
+        let mut header = line.splitn(2, ':');
+        let name = header.next().unwrap_or("");
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = header.next().unwrap_or("").trim().parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "Invalid Content-Length")
+            })?;
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidData, "Body isn't UTF-8")
+    })
+}
+
+// Write the DAG in the DOT language and the statistics into `dir`.
+fn dump(gossiper: &Gossiper, dir: &Path) -> io::Result<()> {
+    let dag = gossiper.dag();
+    File::create(dir.join("dag.dot"))?.write_all(
+        dag.to_dot(&DotOptions::default()).as_bytes(),
+    )?;
+    let statistics = gossiper.statistics();
+    let quorum = gossiper.quorum_info();
+    let mean = statistics.mean_time_to_stability().map_or(
+        "n/a".to_string(),
+        |mean| format!("{:?}", mean),
+    );
+    let report = format!(
+        "id: {}\nunits: {}\nstable units: {}\nlive members: {} of {}\nstability threshold: {} \
+         ({})\nrounds: {}\nunits created: {}\nunits received: {}\nbytes sent: {}\n\
+         bytes received: {}\nmean time to stability: {}\nmax time to stability: {:?}\n",
+        gossiper.id(),
+        dag.len(),
+        dag.stable_count(),
+        quorum.live_members,
+        quorum.members,
+        quorum.stability_threshold,
+        quorum.stability_rule,
+        statistics.rounds,
+        statistics.units_created,
+        statistics.units_received,
+        statistics.bytes_sent,
+        statistics.bytes_received,
+        mean,
+        statistics.time_to_stability.max
+    );
+    File::create(dir.join("stats.txt"))?.write_all(report.as_bytes())
+}
+
+fn run(options: Options) -> Result<(), Error> {
+    let mut gossiper = load_gossiper(options.key.as_ref().map(PathBuf::as_path))?;
+    let mut transport = TcpTransport::bind(gossiper.id(), options.listen.as_str())?;
+    for &(peer_id, address) in &options.peers {
+        gossiper.add_peer(peer_id)?;
+        transport.add_peer(peer_id, address);
+    }
+    println!("Listening as --peer {}@{}", gossiper.id(), transport.local_addr());
+    fs::create_dir_all(&options.dump_dir)?;
+
+    let (sender, payloads): (Sender<String>, Receiver<String>) = mpsc::channel();
+    if let Some(ref address) = options.http {
+        let listener = TcpListener::bind(address.as_str())?;
+        println!("Accepting payloads over HTTP on {}", listener.local_addr()?);
+        let sender = sender.clone();
+        let _ = thread::spawn(move || serve_http(&listener, &sender));
+    }
+    let _ = thread::spawn(move || read_stdin(&sender));
+
+    let mut node = Node::new(gossiper, transport, options.round_interval);
+    let mut next_dump_at = Instant::now() + options.dump_every;
+    loop {
+        for payload in payloads.try_iter() {
+            match node.gossiper_mut().send_new(&payload) {
+                Ok(identifier) => println!("Sent {:?} as {}", payload, identifier),
+                Err(error) => eprintln!("Failed to send {:?}: {}", payload, error),
+            }
+        }
+        match node.poll() {
+            Ok(()) | Err(Error::NoPeers) => (),
+            Err(error) => eprintln!("Failed to gossip: {}", error),
+        }
+        for payload in node.gossiper_mut().poll_ordered() {
+            match serialisation::deserialise::<String>(&payload) {
+                Ok(payload) => println!("Delivered {:?}", payload),
+                Err(_) => println!("Delivered {} bytes", payload.len()),
+            }
+        }
+        if Instant::now() >= next_dump_at {
+            next_dump_at += options.dump_every;
+            if let Err(error) = dump(node.gossiper(), &options.dump_dir) {
+                eprintln!("Failed to dump into {:?}: {}", options.dump_dir, error);
+            }
+        }
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    }
+}
+
+fn main() {
+    let options = match parse_options(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(reason) => {
+            eprintln!("{}\n\n{}", reason, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(error) = run(options) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn options() {
+        let id = Gossiper::default().id();
+        let peer = format!("{}@127.0.0.1:5000", id);
+        let options = unwrap!(parse_options(
+            args(&["--peer", &peer, "--round-ms", "20", "--dump-dir", "dumps"]).into_iter(),
+        ));
+        assert_eq!(options.peers, vec![(id, unwrap!("127.0.0.1:5000".parse()))]);
+        assert_eq!(options.round_interval, Duration::from_millis(20));
+        assert_eq!(options.dump_dir, PathBuf::from("dumps"));
+        assert_eq!(options.listen, "127.0.0.1:0");
+
+        assert!(parse_options(args(&["--round-ms", "soon"]).into_iter()).is_err());
+        assert!(parse_options(args(&["--listen"]).into_iter()).is_err());
+        assert!(parse_options(args(&["--verbose", "yes"]).into_iter()).is_err());
+        assert!(parse_peer("127.0.0.1:5000").is_err());
+        assert!(parse_peer(&format!("{}@nowhere", id)).is_err());
+    }
+
+    #[test]
+    fn http_body() {
+        let listener = unwrap!(TcpListener::bind("127.0.0.1:0"));
+        let address = unwrap!(listener.local_addr());
+        let client = thread::spawn(move || {
+            let mut stream = unwrap!(TcpStream::connect(address));
+            let request = "POST / HTTP/1.1\r\ncontent-length: 5\r\n\r\nHello";
+            unwrap!(stream.write_all(request.as_bytes()));
+        });
+        let (mut stream, _) = unwrap!(listener.accept());
+        assert_eq!(unwrap!(read_body(&mut stream)), "Hello");
+        unwrap!(client.join());
+    }
+
+    #[test]
+    fn persistent_key() {
+        let path = env::temp_dir().join("dag_gossip_node_persistent_key");
+        let _ = fs::remove_file(&path);
+        let created = unwrap!(load_gossiper(Some(&path)));
+        let loaded = unwrap!(load_gossiper(Some(&path)));
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.id(), created.id());
+        assert_ne!(unwrap!(load_gossiper(None)).id(), created.id());
+    }
+
+    #[test]
+    fn dump_files() {
+        let dir = env::temp_dir().join("dag_gossip_node_dump_files");
+        unwrap!(fs::create_dir_all(&dir));
+        let gossiper = Gossiper::default();
+        unwrap!(dump(&gossiper, &dir));
+        let mut report = String::new();
+        let _ = unwrap!(unwrap!(File::open(dir.join("stats.txt"))).read_to_string(&mut report));
+        assert!(report.starts_with(&format!("id: {}\n", gossiper.id())));
+        assert!(report.contains("mean time to stability: n/a\n"));
+        assert!(dir.join("dag.dot").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use ed25519_dalek::Keypair;
+use error::Error;
+use id::Id;
+use maidsafe_utilities::serialisation;
+use sha3::Sha3_512;
+use tiny_keccak::sha3_256;
+
+/// A segment of the stable payload sequence, hash-linked to the previous segment and signed by
+/// the producer, giving a coarse-grained chain view over the DAG.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    /// Position in the chain, starting at 0.
+    pub height: u64,
+    /// Hash of the previous block, empty for the first one.
+    pub previous: Vec<u8>,
+    /// The payloads which became stable since the previous block, in total order.
+    pub payloads: Vec<Vec<u8>>,
+    /// The node which produced the block.
+    pub producer: Id,
+    /// The producer's signature over `hash()`.
+    pub signature: Vec<u8>,
+}
+
+impl Block {
+    /// Hash of all the fields but the signature.
+    pub fn hash(&self) -> Result<Vec<u8>, Error> {
+        let serialised = serialisation::serialise(
+            &(self.height, &self.previous, &self.payloads, self.producer),
+        )?;
+        Ok(sha3_256(&serialised).to_vec())
+    }
+
+    /// Whether the block is signed by its producer and follows `previous`, or starts the chain if
+    /// `previous` is `None`.
+    pub fn verify(&self, previous: Option<&Block>) -> bool {
+        let linked = match previous {
+            Some(previous) => {
+                previous.hash().ok().map_or(false, |hash| hash == self.previous) &&
+                    self.height == previous.height + 1
+            }
+            None => self.height == 0 && self.previous.is_empty(),
+        };
+        linked &&
+            self.hash().ok().map_or(false, |hash| {
+                self.producer.verify(&hash, &self.signature)
+            })
+    }
+}
+
+/// Wraps the stable payloads, as returned by `Gossiper::poll_ordered`, into a chain of `Block`s.
+/// A block can be published to the other nodes as a payload of its own through
+/// `Gossiper::send_new`.
+pub struct BlockProducer {
+    keys: Keypair,
+    height: u64,
+    previous: Vec<u8>,
+    pending: Vec<Vec<u8>>,
+}
+
+impl BlockProducer {
+    /// A producer signing its blocks with `keys`, starting a new chain.
+    pub fn new(keys: Keypair) -> Self {
+        BlockProducer {
+            keys,
+            height: 0,
+            previous: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Queue payloads which became stable, in total order, for the next block.
+    pub fn add_payloads(&mut self, payloads: Vec<Vec<u8>>) {
+        self.pending.extend(payloads);
+    }
+
+    /// Wrap all the queued payloads into a block linked to the previous one. Returns `None` if
+    /// no payload is queued.
+    pub fn produce(&mut self) -> Result<Option<Block>, Error> {
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+        let mut block = Block {
+            height: self.height,
+            previous: self.previous.clone(),
+            payloads: self.pending.clone(),
+            producer: Id::from(self.keys.public),
+            signature: Vec::new(),
+        };
+        let hash = block.hash()?;
+        block.signature = self.keys.sign::<Sha3_512>(&hash).to_bytes().to_vec();
+        self.pending.clear();
+        self.height += 1;
+        self.previous = hash;
+        Ok(Some(block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+
+    #[test]
+    fn chain() {
+        let mut rng = SeededRng::new();
+        let mut producer = BlockProducer::new(Keypair::generate::<Sha3_512>(&mut rng));
+        assert!(unwrap!(producer.produce()).is_none());
+
+        producer.add_payloads(vec![vec![1], vec![2]]);
+        let first = unwrap!(unwrap!(producer.produce()));
+        producer.add_payloads(vec![vec![3]]);
+        let second = unwrap!(unwrap!(producer.produce()));
+
+        assert!(first.verify(None));
+        assert!(second.verify(Some(&first)));
+        assert!(!first.verify(Some(&second)));
+
+        let mut tampered = second.clone();
+        tampered.payloads.push(vec![4]);
+        assert!(!tampered.verify(Some(&first)));
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use codec::WireCodec;
+
+/// The protocol features a `Gossiper` supports. Each gossiper advertises its own on every message
+/// it sends, and only uses with a peer the features both support. Until it heard from a peer, it
+/// assumes the peer only supports the baseline returned by `Capabilities::default`: full pushes
+/// of DAGs encoded unit by unit. This keeps networks mixing versions converging during rolling
+/// upgrades.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// The codecs accepted, most preferred first. `WireCodec::Units` is always accepted, even if
+    /// not listed.
+    pub codecs: Vec<WireCodec>,
+    /// Whether partial DAGs pushed by delta sync are handled.
+    pub delta: bool,
+    /// Whether pull requests are answered.
+    pub pull: bool,
+    /// Whether negative acknowledgements of rejected units are handled.
+    pub nack: bool,
+    /// Whether a round's push or pull may be replaced by the digest of the sender's DAG, when the
+    /// receiver is known to hold the same units.
+    pub digest: bool,
+}
+
+impl Capabilities {
+    /// Everything supported by this version of the crate.
+    pub fn all() -> Self {
+        Capabilities {
+            codecs: vec![WireCodec::Units, WireCodec::Columnar],
+            delta: true,
+            pull: true,
+            nack: true,
+            digest: true,
+        }
+    }
+
+    /// The features supported by both `self` and `other`, in the order of preference of `self`.
+    pub fn common_with(&self, other: &Capabilities) -> Self {
+        Capabilities {
+            codecs: self.codecs
+                .iter()
+                .filter(|codec| other.codecs.contains(codec))
+                .cloned()
+                .collect(),
+            delta: self.delta && other.delta,
+            pull: self.pull && other.pull,
+            nack: self.nack && other.nack,
+            digest: self.digest && other.digest,
+        }
+    }
+
+    /// The codec to encode DAGs with: the first listed, or `WireCodec::Units` if none is.
+    pub fn preferred_codec(&self) -> WireCodec {
+        self.codecs.first().cloned().unwrap_or(WireCodec::Units)
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            codecs: vec![WireCodec::Units],
+            delta: false,
+            pull: false,
+            nack: false,
+            digest: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiation() {
+        let all = Capabilities::all();
+        let baseline = Capabilities::default();
+        assert_eq!(all.common_with(&baseline), baseline);
+        assert_eq!(baseline.common_with(&all), baseline);
+        assert_eq!(all.common_with(&all), all);
+
+        // Codecs keep our order of preference, and fall back on units if none is shared.
+        let columnar_first = Capabilities {
+            codecs: vec![WireCodec::Columnar, WireCodec::Units],
+            ..Capabilities::all()
+        };
+        let common = columnar_first.common_with(&all);
+        assert_eq!(common.codecs, vec![WireCodec::Columnar, WireCodec::Units]);
+        assert_eq!(common.preferred_codec(), WireCodec::Columnar);
+        assert_eq!(all.common_with(&columnar_first).preferred_codec(), WireCodec::Units);
+
+        let columnar_only = Capabilities {
+            codecs: vec![WireCodec::Columnar],
+            pull: false,
+            ..Capabilities::all()
+        };
+        let common = columnar_only.common_with(&baseline);
+        assert!(common.codecs.is_empty());
+        assert_eq!(common.preferred_codec(), WireCodec::Units);
+        assert!(!common.delta && !common.pull && !common.nack && !common.digest);
+        let common = columnar_only.common_with(&all);
+        assert!(common.delta && !common.pull && common.nack && common.digest);
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use clock;
+use id::{Id, UnitId};
+use message::Message;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+/// The most units sent in a single page while bootstrapping a `Gossiper`. Larger page sizes asked
+/// for are capped to this.
+pub const MAX_BOOTSTRAP_PAGE: u32 = 1024;
+
+/// How far a `Gossiper` got catching up with the units its peers hold, e.g. during a cold start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CatchUpProgress {
+    /// Units received since catching up started.
+    pub fetched: usize,
+    /// Estimate of the units to receive in total: those received, plus those peers advertised or
+    /// referred to as parents which we still miss.
+    pub estimated_total: usize,
+    /// Bytes of messages received since catching up started.
+    pub bytes: u64,
+    /// Estimated time left at the rate units were received so far, once any was. Always `None`
+    /// with the `wasm` feature, for lack of a clock.
+    pub eta: Option<Duration>,
+}
+
+impl CatchUpProgress {
+    /// Whether every unit known to be missing was received.
+    pub fn is_complete(&self) -> bool {
+        self.fetched >= self.estimated_total
+    }
+}
+
+// Tracks the units known to be missing since a gossiper first heard of one it didn't hold, until
+// it holds them all.
+pub struct CatchUp {
+    started: Option<Instant>,
+    // Number of units held when catching up started.
+    initial: usize,
+    bytes: u64,
+    missing: BTreeSet<UnitId>,
+    last_reported: Option<CatchUpProgress>,
+}
+
+impl CatchUp {
+    pub fn new(held: usize) -> Self {
+        CatchUp {
+            started: clock::now(),
+            initial: held,
+            bytes: 0,
+            missing: BTreeSet::new(),
+            last_reported: None,
+        }
+    }
+
+    // Record units heard of but not held.
+    pub fn add_missing<I: IntoIterator<Item = UnitId>>(&mut self, identifiers: I) {
+        self.missing.extend(identifiers);
+    }
+
+    // Record a message of `bytes` bytes received.
+    pub fn add_bytes(&mut self, bytes: usize) {
+        self.bytes = self.bytes.saturating_add(bytes as u64);
+    }
+
+    // The progress given the units now `held`, and whether it changed since last reported.
+    // `is_held` tells which missing units were received since.
+    pub fn progress<F: Fn(&UnitId) -> bool>(
+        &mut self,
+        held: usize,
+        is_held: F,
+    ) -> (CatchUpProgress, bool) {
+        self.missing = self.missing
+            .iter()
+            .filter(|identifier| !is_held(identifier))
+            .cloned()
+            .collect();
+        let fetched = held.saturating_sub(self.initial);
+        let remaining = self.missing.len();
+        let progress = CatchUpProgress {
+            fetched,
+            estimated_total: fetched + remaining,
+            bytes: self.bytes,
+            eta: eta(self.started, fetched, remaining),
+        };
+        let changed = self.last_reported.map_or(true, |last| {
+            last.fetched != progress.fetched || last.estimated_total != progress.estimated_total
+        });
+        self.last_reported = Some(progress);
+        (progress, changed)
+    }
+
+    // The progress last computed, if any.
+    pub fn last_reported(&self) -> Option<CatchUpProgress> {
+        self.last_reported
+    }
+}
+
+// A bootstrap from a single peer: its units in total order, fetched a page at a time.
+pub struct Bootstrap {
+    pub peer: Id,
+    page_size: u32,
+    // Position in the peer's total order of the first unit of the next page to fetch.
+    next: u32,
+    // Number of units the peer held as of the last page, unknown until the first one arrives.
+    total: Option<u32>,
+    started: Option<Instant>,
+    bytes: u64,
+}
+
+impl Bootstrap {
+    pub fn new(peer: Id, page_size: u32) -> Self {
+        Bootstrap {
+            peer,
+            page_size,
+            next: 0,
+            total: None,
+            started: clock::now(),
+            bytes: 0,
+        }
+    }
+
+    // The request for the next page.
+    pub fn request(&self) -> Message {
+        Message::BootstrapRequest {
+            start: self.next,
+            count: self.page_size,
+        }
+    }
+
+    // Record a page of `count` units from position `start` out of `total`, received in a message
+    // of `bytes` bytes. Returns whether it was the page expected, rather than a stale duplicate.
+    pub fn add_page(&mut self, start: u32, count: u32, total: u32, bytes: usize) -> bool {
+        if start != self.next {
+            return false;
+        }
+        self.next = start.saturating_add(count);
+        // An empty page ends the bootstrap, even if the peer claims to hold more units.
+        self.total = Some(if count == 0 { self.next } else { total });
+        self.bytes = self.bytes.saturating_add(bytes as u64);
+        true
+    }
+
+    // The progress made, once the first page arrived.
+    pub fn progress(&self) -> Option<CatchUpProgress> {
+        let total = match self.total {
+            Some(total) => total,
+            None => return None,
+        };
+        let fetched = self.next as usize;
+        let remaining = total.saturating_sub(self.next) as usize;
+        Some(CatchUpProgress {
+            fetched,
+            estimated_total: fetched + remaining,
+            bytes: self.bytes,
+            eta: eta(self.started, fetched, remaining),
+        })
+    }
+}
+
+// The time left to fetch `remaining` more units at the rate `fetched` were since `started`.
+fn eta(started: Option<Instant>, fetched: usize, remaining: usize) -> Option<Duration> {
+    let started = match started {
+        Some(started) if fetched != 0 => started,
+        _ => return None,
+    };
+    let elapsed = started.elapsed();
+    let elapsed_ms = elapsed.as_secs().saturating_mul(1000) +
+        u64::from(elapsed.subsec_nanos() / 1_000_000);
+    Some(Duration::from_millis(
+        elapsed_ms.saturating_mul(remaining as u64) / fetched as u64,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catch_up() {
+        let (first, second, third) = (UnitId([1; 32]), UnitId([2; 32]), UnitId([3; 32]));
+        let mut catch_up = CatchUp::new(4);
+        assert!(catch_up.last_reported().is_none());
+        catch_up.add_missing(vec![first, second, third]);
+        catch_up.add_bytes(100);
+
+        let (progress, changed) = catch_up.progress(4, |_| false);
+        assert!(changed);
+        assert_eq!((progress.fetched, progress.estimated_total), (0, 3));
+        assert_eq!(progress.bytes, 100);
+        assert!(progress.eta.is_none());
+        assert!(!progress.is_complete());
+        assert!(!catch_up.progress(4, |_| false).1);
+
+        // Units heard of again while missing are only counted once.
+        catch_up.add_missing(vec![first]);
+        catch_up.add_bytes(50);
+        let (progress, changed) = catch_up.progress(5, |identifier| *identifier == first);
+        assert!(changed);
+        assert_eq!((progress.fetched, progress.estimated_total), (1, 3));
+        assert_eq!(progress.bytes, 150);
+        assert_eq!(catch_up.last_reported(), Some(progress));
+
+        let (progress, _) = catch_up.progress(7, |_| true);
+        assert!(progress.is_complete());
+        assert_eq!(progress.fetched, 3);
+    }
+
+    #[test]
+    fn bootstrap() {
+        let mut bootstrap = Bootstrap::new(Id([1; 32]), 2);
+        let next_page = |bootstrap: &Bootstrap| match bootstrap.request() {
+            Message::BootstrapRequest { start, count } => (start, count),
+            _ => panic!("Unexpected request"),
+        };
+        assert_eq!(next_page(&bootstrap), (0, 2));
+        assert!(bootstrap.progress().is_none());
+
+        assert!(bootstrap.add_page(0, 2, 5, 10));
+        let progress = unwrap!(bootstrap.progress());
+        assert_eq!((progress.fetched, progress.estimated_total, progress.bytes), (2, 5, 10));
+        assert_eq!(next_page(&bootstrap), (2, 2));
+
+        // Stale pages are ignored.
+        assert!(!bootstrap.add_page(0, 2, 5, 10));
+        assert_eq!(unwrap!(bootstrap.progress()).bytes, 10);
+
+        assert!(bootstrap.add_page(2, 2, 6, 10));
+        assert_eq!(unwrap!(bootstrap.progress()).estimated_total, 6);
+
+        // An empty page ends the bootstrap whatever the total claimed.
+        assert!(bootstrap.add_page(4, 0, 6, 1));
+        let progress = unwrap!(bootstrap.progress());
+        assert!(progress.is_complete());
+        assert_eq!((progress.fetched, progress.bytes), (4, 21));
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use std::time::Duration;
+
+/// Faults to inject inside a gossiper, so that resilience tests exercise the error paths a
+/// simulated network never reaches. Each probability is between 0 and 1, zero disabling the fault,
+/// as does the default configuration. For testing only: never enable the `chaos` feature in
+/// production builds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChaosConfig {
+    /// The probability of sleeping for `union_delay` before merging a received DAG.
+    pub delay_probability: f64,
+    /// How long a delayed union sleeps for.
+    pub union_delay: Duration,
+    /// The probability of dropping a message once prepared, i.e. of failing to send it with
+    /// `Error::ChaosDrop`, or of not responding at all.
+    pub drop_probability: f64,
+    /// The probability of flipping the bits of a random byte of a message before sending it.
+    pub corrupt_probability: f64,
+}
@@ -0,0 +1,68 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+//! Access to the time, for the features relying on it: rate limits, latency budgets, catch up
+//! estimates and time to stability. The standard library has no clock on
+//! `wasm32-unknown-unknown`, targeted by the `wasm` feature, where `Instant::now` panics: these
+//! features are skipped there instead.
+
+use std::time::Instant;
+#[cfg(not(feature = "wasm"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The current time, if the platform has a clock.
+#[cfg(not(feature = "wasm"))]
+pub fn now() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+// The current time, if the platform has a clock.
+#[cfg(feature = "wasm")]
+pub fn now() -> Option<Instant> {
+    None
+}
+
+// Seconds since the Unix epoch, or 0 if unknown.
+#[cfg(not(feature = "wasm"))]
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// Seconds since the Unix epoch, or 0 if unknown.
+#[cfg(feature = "wasm")]
+pub fn unix_time() -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn availability() {
+        if cfg!(feature = "wasm") {
+            assert!(now().is_none());
+            assert_eq!(unix_time(), 0);
+        } else {
+            assert!(unwrap!(now()) <= Instant::now());
+            // Later than the first release of this crate.
+            assert!(unix_time() > 1_500_000_000);
+        }
+    }
+}
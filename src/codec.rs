@@ -0,0 +1,76 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use dag::Checkpoint;
+use id::{Id, UnitId};
+use std::collections::BTreeMap;
+use unit::{ContentType, QuorumCertificate};
+
+/// An encoding of the DAGs carried in messages. Each `Gossiper` advertises the codecs it accepts
+/// in its `Capabilities`, and encodes the DAGs it sends a peer with the first of its own codecs
+/// the peer advertised, falling back to `WireCodec::Units` until it heard from the peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireCodec {
+    /// The DAG serialised unit by unit. Always accepted.
+    Units,
+    /// The DAG serialised column-wise, as a `ColumnarDag`.
+    Columnar,
+}
+
+/// A DAG encoded column-wise: all the identifiers, then all the parents, then all the payloads,
+/// then all the observers. Grouping similar values compresses better than interleaving them unit
+/// by unit, and avoids the per-unit serialisation overhead on large transfers.
+///
+/// Built by `Dag::to_columnar` and turned back into a DAG by `Dag::from_columnar`. The `i`th
+/// entry of `identifiers`, `parent_counts`, `payload_lengths`, `content_types`, `clocks`,
+/// `metadata`, `certificates` and `observer_counts` all describe the same unit. The genesis is
+/// always among the units, even when encoding a partial DAG without it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnarDag {
+    /// Identifier of the genesis.
+    pub genesis: UnitId,
+    /// Identifier of the original genesis, binding the DAG to its network.
+    pub network: UnitId,
+    /// Number of observers for a unit to be stable.
+    pub majority: u8,
+    /// The stable prefix pruned so far.
+    pub checkpoint: Checkpoint,
+    /// Identifier of each unit.
+    pub identifiers: Vec<UnitId>,
+    /// Number of parents of each unit within `parents`.
+    pub parent_counts: Vec<u32>,
+    /// The parents of all the units, concatenated.
+    pub parents: Vec<UnitId>,
+    /// Length of each unit's encoded payload within `payloads`.
+    pub payload_lengths: Vec<u32>,
+    /// The encoded payloads of all the units, concatenated.
+    pub payloads: Vec<u8>,
+    /// Content type of each unit's payload, if any.
+    pub content_types: Vec<Option<ContentType>>,
+    /// Clock of each unit.
+    pub clocks: Vec<u64>,
+    /// Metadata of each unit.
+    pub metadata: Vec<BTreeMap<String, Vec<u8>>>,
+    /// Certificate of each unit, if certified.
+    pub certificates: Vec<Option<QuorumCertificate>>,
+    /// Number of observers of each unit within `observers`.
+    pub observer_counts: Vec<u32>,
+    /// The observers of all the units, concatenated.
+    pub observers: Vec<Id>,
+    /// The signature of each entry of `observers`, empty for an observer without one.
+    pub signatures: Vec<Vec<u8>>,
+}
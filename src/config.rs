@@ -0,0 +1,54 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Bounds on the messages a `Gossiper` accepts from peers, so that a hostile peer can't make it
+/// allocate without bound, e.g. with a crafted DAG of millions of tiny units or an enormous
+/// payload. Messages breaking them are rejected with `Error::MessageLimitExceeded`, and handled
+/// as malformed, as per the `DecodeFailurePolicy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GossiperConfig {
+    /// The largest message accepted, in bytes, checked before it is decoded and again once
+    /// decompressed.
+    pub max_message_bytes: Option<usize>,
+    /// The most units a message may carry, or refer to, e.g. by their version in a pull request.
+    pub max_units_per_message: Option<usize>,
+    /// The largest payload a unit carried by a message may hold, in bytes.
+    pub max_payload_len: Option<usize>,
+}
+
+impl Default for GossiperConfig {
+    /// 64 MiB messages, as the largest frame of a `TcpTransport`, carrying up to 2^20 units with
+    /// payloads of up to 16 MiB.
+    fn default() -> Self {
+        GossiperConfig {
+            max_message_bytes: Some(64 * 1024 * 1024),
+            max_units_per_message: Some(1 << 20),
+            max_payload_len: Some(16 * 1024 * 1024),
+        }
+    }
+}
+
+impl GossiperConfig {
+    /// No bounds at all, for trusted peers only.
+    pub fn unbounded() -> Self {
+        GossiperConfig {
+            max_message_bytes: None,
+            max_units_per_message: None,
+            max_payload_len: None,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use std::cmp;
+
+/// The application's semantics of payloads, for payloads which aren't byte-equal to still be
+/// recognised as conflicting observations of the same event, e.g. two values for the same key.
+///
+/// When creating a unit, a conflicting payload already observed along the new unit's paths is
+/// resolved against the new one: if the held payload wins, the observation is folded into its unit
+/// instead of creating a new one. Conflicting units received from peers are kept, so that every
+/// node holds the same units, and all but the winner are superseded: `Dag::stable_payloads` and
+/// `Gossiper::poll_ordered` leave them out.
+pub trait PayloadPolicy<T>: Send + Sync {
+    /// Whether the two payloads conflict. Shall be symmetric.
+    fn conflicts(&self, lhs: &T, rhs: &T) -> bool;
+
+    /// A key such that conflicting payloads have equal keys, e.g. the key a value is set for. Each
+    /// payload is only checked against the payloads held with the same key. The same empty key for
+    /// all payloads by default, which has each payload checked against every other one held.
+    fn conflict_key(&self, _payload: &T) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// The winner of two conflicting payloads. Shall be deterministic and symmetric, for every
+    /// node to pick the same winner whatever order it learnt of the payloads in. The greatest of
+    /// the two by default.
+    fn resolve<'a>(&self, lhs: &'a T, rhs: &'a T) -> &'a T
+    where
+        T: Ord,
+    {
+        cmp::max(lhs, rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dag::Dag;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+    use std::sync::Arc;
+
+    // Payloads are (key, value) pairs, conflicting when they set the same key.
+    struct SameKey;
+
+    impl PayloadPolicy<(u8, u8)> for SameKey {
+        fn conflicts(&self, lhs: &(u8, u8), rhs: &(u8, u8)) -> bool {
+            lhs.0 == rhs.0
+        }
+
+        fn conflict_key(&self, payload: &(u8, u8)) -> Vec<u8> {
+            vec![payload.0]
+        }
+    }
+
+    #[test]
+    fn resolution() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<(u8, u8)> = Dag::new(&keys);
+        dag.set_payload_policy(Some(Arc::new(SameKey)));
+
+        let first = unwrap!(dag.new_payload((1, 5), &keys));
+        // Losing to a payload already observed, the observation is folded into its unit.
+        assert_eq!(unwrap!(dag.new_payload((1, 3), &keys)), first);
+        let second = unwrap!(dag.new_payload((1, 7), &keys));
+        assert_ne!(second, first);
+        let _ = unwrap!(dag.new_payload((2, 1), &keys));
+
+        assert!(dag.is_superseded(unwrap!(dag.get(&first))));
+        assert!(!dag.is_superseded(unwrap!(dag.get(&second))));
+        assert_eq!(dag.stable_payloads(), vec![(1, 7), (2, 1)]);
+
+        // Units received, or held before the policy was set, are resolved alike.
+        let mut other: Dag<(u8, u8)> = Dag::new(&keys);
+        let _ = unwrap!(other.new_payload((3, 1), &keys));
+        let loser = unwrap!(other.new_payload((1, 6), &keys));
+        unwrap!(dag.union(&other));
+        assert!(dag.is_superseded(unwrap!(dag.get(&loser))));
+        unwrap!(other.union(&dag));
+        assert!(!other.is_superseded(unwrap!(other.get(&loser))));
+        other.set_payload_policy(Some(Arc::new(SameKey)));
+        assert!(other.is_superseded(unwrap!(other.get(&loser))));
+        assert!(!other.is_superseded(unwrap!(other.get(&second))));
+    }
+}
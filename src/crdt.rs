@@ -0,0 +1,84 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use dag::Dag;
+use error::Error;
+use unit::Payload;
+
+/// A state-based (convergent) replicated data type, which can be replicated through any channel
+/// delivering whole states, gossip or not.
+pub trait CvRdt {
+    /// Error returned when a join fails.
+    type Error;
+
+    /// Merge `other` into `self`, leaving `self` as the least upper bound of both states.
+    fn join(&mut self, other: &Self) -> Result<(), Self::Error>;
+
+    /// The partial order of the states: whether `self` holds no information missing from `other`.
+    fn is_subset_of(&self, other: &Self) -> bool;
+}
+
+impl<T: Payload> CvRdt for Dag<T> {
+    type Error = Error;
+
+    fn join(&mut self, other: &Self) -> Result<(), Error> {
+        self.union(other)
+    }
+
+    fn is_subset_of(&self, other: &Self) -> bool {
+        Dag::<T>::is_subset_of(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+
+    fn equivalent(lhs: &Dag, rhs: &Dag) -> bool {
+        lhs.is_subset_of(rhs) && rhs.is_subset_of(lhs)
+    }
+
+    #[test]
+    fn join_semilattice() {
+        let mut rng = SeededRng::new();
+        let first_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let second_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut first: Dag = Dag::new(&first_keys);
+        let mut second: Dag = Dag::new(&first_keys);
+        let _ = unwrap!(first.new_payload(vec![1], &first_keys));
+        let _ = unwrap!(second.new_payload(vec![2], &second_keys));
+        assert!(!first.is_subset_of(&second));
+        assert!(!second.is_subset_of(&first));
+
+        // Commutative, and the join is an upper bound of both states.
+        let mut joined = first.clone();
+        unwrap!(joined.join(&second));
+        let mut reversed = second.clone();
+        unwrap!(reversed.join(&first));
+        assert!(equivalent(&joined, &reversed));
+        assert!(first.is_subset_of(&joined));
+        assert!(second.is_subset_of(&joined));
+
+        // Idempotent.
+        let before = joined.clone();
+        unwrap!(joined.join(&before));
+        assert!(equivalent(&joined, &before));
+    }
+}
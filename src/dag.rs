@@ -15,263 +15,2849 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use id::Id;
-use std::collections::{BTreeMap, BTreeSet};
+use arena::Arena;
+use codec::ColumnarDag;
+use conflict::PayloadPolicy;
+use diagnostics::{self, Diagnostics};
+use ed25519_dalek::Keypair;
+use error::Error;
+use event::Event;
+use export::{self, DagExport, DotOptions, EXPORT_SCHEMA_VERSION, UnitExport};
+use fault::FaultProof;
+use frozen::FrozenDag;
+use hasher::{self, Hasher};
+use id::{self, Id, UnitId};
+use import::ForeignEvent;
+use invariants::InvariantViolation;
+use journal::JournalEntry;
+use maidsafe_utilities::serialisation;
+use message::Attestation;
+use receipt::Receipt;
+use serde::{Deserialize, Deserializer};
+use snapshot::{self, DAG_FORMAT_VERSION};
+use stability::StabilityPolicy;
+use stats::UnitBreakdown;
+use std::borrow::Borrow;
+use std::cmp::{self, Ordering};
+use std::collections::{BTreeMap, BTreeSet, VecDeque, btree_map, btree_set};
 use std::fmt::{self, Debug, Formatter};
-use unit::Unit;
+use std::sync::{Arc, Mutex};
+use std::vec;
+use tiny_keccak::sha3_256;
+use unit::{self, ContentType, Payload, QuorumCertificate, Unit};
 
 /// DAG handler.
-#[derive(Clone, Serialize, Deserialize)]
-pub struct Dag {
-    units: BTreeMap<Vec<u8>, Unit>,
-    genesis: Unit,
+#[derive(Clone, Serialize)]
+pub struct Dag<T = Vec<u8>> {
+    units: BTreeMap<UnitId, Unit<T>>,
+    genesis: Unit<T>,
+    // The identifier of the original genesis, which binds the DAG to its network however far it
+    // was checkpointed.
+    network: UnitId,
     majority: u8,
+    // The stable prefix pruned so far, `genesis` being the unit it was collapsed into.
+    checkpoint: Checkpoint,
+    // The children and depth of each unit, by interned identifier. The children are derived from
+    // the units' parents, and the depth of a unit is the length of its longest path back to the
+    // genesis, or to an ancestor whose parents aren't held. Rebuilt locally on deserialisation,
+    // rather than trusting peers to send consistent ones.
+    #[serde(skip)]
+    links: Arena,
+    // The units without any child, i.e. the candidate parents for a new unit.
+    #[serde(skip)]
+    tips: BTreeSet<UnitId>,
+    // Local setting only, never sent to peers.
+    #[serde(skip)]
+    max_traversal_depth: Option<usize>,
+    // Local settings, as each node decides for itself when a unit is stable.
+    #[serde(skip)]
+    policy: StabilityPolicy,
+    #[serde(skip)]
+    members: usize,
+    // Whether changes are recorded into `events`. Local setting only.
+    #[serde(skip)]
+    events_enabled: bool,
+    #[serde(skip)]
+    events: Vec<Event<T>>,
+    // Whether changes are recorded into `journal`. Local setting only.
+    #[serde(skip)]
+    journaling: bool,
+    #[serde(skip)]
+    journal: Vec<JournalEntry<T>>,
+    // Equivocations detected while merging peers' DAGs. Local only, as peers can't be trusted to
+    // report them.
+    #[serde(skip)]
+    faults: Vec<FaultProof<T>>,
+    // Local setting only. Units lazily validated are trusted once saved, as `unchecked` isn't.
+    #[serde(skip)]
+    validation: Validation,
+    // The units inserted without their identifier being checked, under `Validation::Lazy`.
+    #[serde(skip)]
+    unchecked: BTreeSet<UnitId>,
+    // The application's semantics of payloads, if it gave any. Local setting only.
+    #[serde(skip)]
+    payload_policy: Option<Arc<PayloadPolicy<T>>>,
+    // Local setting only.
+    #[serde(skip)]
+    config: DagConfig,
+    // Our Lamport clock: the greatest clock of any unit created or received.
+    #[serde(skip)]
+    clock: u64,
+    // Per hash of an encoded payload, the first unit indexed holding it.
+    #[serde(skip)]
+    payloads: BTreeMap<[u8; 32], UnitId>,
+    // The units whose payload loses to that of another unit held, as per the payload policy.
+    // Updated as units are indexed or dropped, comparing only units sharing a conflict key.
+    #[serde(skip)]
+    superseded: BTreeSet<UnitId>,
+    // The units held per conflict key of their payload, as per the payload policy, if any.
+    #[serde(skip)]
+    conflict_keys: BTreeMap<Vec<u8>, BTreeSet<UnitId>>,
+    // The last digest computed, cleared whenever the units, their observers or the checkpoint
+    // change.
+    #[serde(skip)]
+    digest: DigestCache,
+    // The members allowed to observe units received, if restricted. Local setting only.
+    #[serde(skip)]
+    authorised: Option<BTreeSet<Id>>,
+    #[serde(skip)]
+    membership_check: MembershipCheck,
+    // The members retired at least once, with their admission epoch. Local state only.
+    #[serde(skip)]
+    admissions: BTreeMap<Id, Admission>,
+    // Whether units are certified as they become stable. Local setting only.
+    #[serde(skip)]
+    certifying: bool,
+    // The hash function identifiers are derived with. Not saved, as the hash of the genesis
+    // identifier doesn't tell it: a loaded DAG is given it again with `set_hasher`.
+    #[serde(skip)]
+    hasher: Arc<Hasher>,
 }
 
-/// The graph is composed by: a list of units, each holds the parent it points to.
+// The fields of `Dag` sent over the wire.
+#[derive(Deserialize)]
+struct SerialisedDag<T> {
+    units: BTreeMap<UnitId, Unit<T>>,
+    genesis: Unit<T>,
+    network: UnitId,
+    majority: u8,
+    checkpoint: Checkpoint,
+}
+
+// The fields of `Dag` as saved in versions 5 and 6 of the format, with the units as saved then.
+#[derive(Deserialize)]
+struct DagV5<U> {
+    units: BTreeMap<UnitId, U>,
+    genesis: U,
+    network: UnitId,
+    majority: u8,
+    checkpoint: Checkpoint,
+}
+
+impl<U> DagV5<U> {
+    fn into_dag<T: Payload>(self) -> Dag<T>
+    where
+        U: Into<Unit<T>>,
+    {
+        let units = self.units
+            .into_iter()
+            .map(|(identifier, unit)| (identifier, unit.into()))
+            .collect();
+        Dag::from_units(
+            units,
+            self.genesis.into(),
+            self.network,
+            self.majority,
+            self.checkpoint,
+        )
+    }
+}
+
+// The fields of `Dag` as saved in versions 1 to 4 of the format, with the units as saved then.
+#[derive(Deserialize)]
+struct LegacyDag<U> {
+    units: BTreeMap<UnitId, U>,
+    genesis: U,
+    majority: u8,
+    checkpoint: Checkpoint,
+}
+
+impl<U> LegacyDag<U> {
+    // The DAG, bound to the network of the hard-coded genesis as every DAG was then.
+    fn into_dag<T: Payload>(self) -> Dag<T>
+    where
+        U: Into<Unit<T>>,
+    {
+        let units = self.units
+            .into_iter()
+            .map(|(identifier, unit)| (identifier, unit.into()))
+            .collect();
+        let network = Unit::<T>::new_genesis(BTreeSet::new()).identifier;
+        Dag::from_units(
+            units,
+            self.genesis.into(),
+            network,
+            self.majority,
+            self.checkpoint,
+        )
+    }
+
+    // As `into_dag`, its units clocked by their depth as they had no clock then.
+    fn into_clocked_dag<T: Payload>(self) -> Dag<T>
+    where
+        U: Into<Unit<T>>,
+    {
+        let mut dag = self.into_dag();
+        let mut units = dag.units.clone();
+        for unit in units.values_mut() {
+            unit.clock = dag.depth_of(unit) as u64;
+        }
+        dag.genesis.clock = dag.depth_of(&dag.genesis) as u64;
+        dag.reset_units(units);
+        dag
+    }
+}
+
+// A unit as saved in version 1 of the format, before units had a content type.
+#[derive(Deserialize)]
+struct UnitV1<T> {
+    identifier: UnitId,
+    parent: UnitId,
+    payload: T,
+    observers: BTreeSet<Id>,
+    signatures: BTreeMap<Id, Vec<u8>>,
+}
+
+impl<T> From<UnitV1<T>> for Unit<T> {
+    fn from(unit: UnitV1<T>) -> Self {
+        Unit::from(UnitV2 {
+            identifier: unit.identifier,
+            parent: unit.parent,
+            payload: unit.payload,
+            observers: unit.observers,
+            signatures: unit.signatures,
+            content_type: None,
+        })
+    }
+}
+
+// A unit as saved in version 2 of the format, before units could have several parents. The
+// genesis had the default identifier as parent.
+#[derive(Deserialize)]
+struct UnitV2<T> {
+    identifier: UnitId,
+    parent: UnitId,
+    payload: T,
+    observers: BTreeSet<Id>,
+    signatures: BTreeMap<Id, Vec<u8>>,
+    content_type: Option<ContentType>,
+}
+
+impl<T> From<UnitV2<T>> for Unit<T> {
+    fn from(unit: UnitV2<T>) -> Self {
+        Unit::from(UnitV3 {
+            identifier: unit.identifier,
+            parents: Some(unit.parent)
+                .into_iter()
+                .filter(|parent| *parent != UnitId::default())
+                .collect(),
+            payload: unit.payload,
+            observers: unit.observers,
+            signatures: unit.signatures,
+            content_type: unit.content_type,
+        })
+    }
+}
+
+// A unit as saved in version 3 of the format, before units had a clock.
+#[derive(Deserialize)]
+struct UnitV3<T> {
+    identifier: UnitId,
+    parents: BTreeSet<UnitId>,
+    payload: T,
+    observers: BTreeSet<Id>,
+    signatures: BTreeMap<Id, Vec<u8>>,
+    content_type: Option<ContentType>,
+}
+
+impl<T> From<UnitV3<T>> for Unit<T> {
+    fn from(unit: UnitV3<T>) -> Self {
+        Unit::from(UnitV4 {
+            identifier: unit.identifier,
+            parents: unit.parents,
+            payload: unit.payload,
+            observers: unit.observers,
+            signatures: unit.signatures,
+            content_type: unit.content_type,
+            clock: 0,
+        })
+    }
+}
+
+// A unit as saved in versions 4 and 5 of the format, before units had metadata.
+#[derive(Deserialize)]
+struct UnitV4<T> {
+    identifier: UnitId,
+    parents: BTreeSet<UnitId>,
+    payload: T,
+    observers: BTreeSet<Id>,
+    signatures: BTreeMap<Id, Vec<u8>>,
+    content_type: Option<ContentType>,
+    clock: u64,
+}
+
+impl<T> From<UnitV4<T>> for Unit<T> {
+    fn from(unit: UnitV4<T>) -> Self {
+        Unit::from(UnitV6 {
+            identifier: unit.identifier,
+            parents: unit.parents,
+            payload: unit.payload,
+            observers: unit.observers,
+            signatures: unit.signatures,
+            content_type: unit.content_type,
+            clock: unit.clock,
+            metadata: BTreeMap::new(),
+        })
+    }
+}
+
+// A unit as saved in version 6 of the format, before units had a certificate.
+#[derive(Deserialize)]
+struct UnitV6<T> {
+    identifier: UnitId,
+    parents: BTreeSet<UnitId>,
+    payload: T,
+    observers: BTreeSet<Id>,
+    signatures: BTreeMap<Id, Vec<u8>>,
+    content_type: Option<ContentType>,
+    clock: u64,
+    metadata: BTreeMap<String, Vec<u8>>,
+}
+
+impl<T> From<UnitV6<T>> for Unit<T> {
+    fn from(unit: UnitV6<T>) -> Self {
+        Unit {
+            identifier: unit.identifier,
+            parents: unit.parents,
+            payload: unit.payload,
+            observers: unit.observers,
+            signatures: unit.signatures,
+            content_type: unit.content_type,
+            clock: unit.clock,
+            metadata: unit.metadata,
+            certificate: None,
+        }
+    }
+}
+
+impl<'de, T: Payload> Deserialize<'de> for Dag<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let dag = SerialisedDag::<T>::deserialize(deserializer)?;
+        Ok(Dag::from_units(
+            dag.units,
+            dag.genesis,
+            dag.network,
+            dag.majority,
+            dag.checkpoint,
+        ))
+    }
+}
+
+/// The stable prefix of a DAG pruned by `Dag::checkpoint`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Number of units pruned since the original genesis.
+    pub height: usize,
+    /// Digest of the payloads of the pruned units, chained in order.
+    pub digest: Vec<u8>,
+}
+
+/// When the identifiers of the units received from peers are checked against their content.
+/// Whatever the mode, received DAGs are always checked to start from our genesis, to have all
+/// their parents held and to be free of cycles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Validation {
+    /// Check every unit as it is received. The safest.
+    Eager,
+    /// Only check units, and their ancestors, once new units are built on top of them or once
+    /// they become stable or get pruned. Much faster for large catch-ups. A unit found invalid is
+    /// dropped, along with its descendants.
+    Lazy,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Validation::Eager
+    }
+}
+
+/// Bounds on the memory used by a DAG, so that a flood of bogus payloads can't exhaust it, and how
+/// duplicate payloads are handled. Unbounded, and allowing duplicates, by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DagConfig {
+    /// The most units held. Once reached, room is made for new units as per `eviction`.
+    pub max_units: Option<usize>,
+    /// The largest payload accepted, once serialised, in bytes. Larger ones are rejected with
+    /// `Error::PayloadTooLarge`, by `new_payload` as well as by `union`.
+    pub max_payload_size: Option<usize>,
+    /// What to do once `max_units` is reached.
+    pub eviction: Eviction,
+    /// Whether a payload already held can be held again by another unit.
+    pub duplicates: Duplicates,
+}
+
+/// How a DAG holding as many units as allowed by `DagConfig::max_units` makes room for new ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eviction {
+    /// Evict the oldest stable units, by collapsing them into a checkpoint as `Dag::checkpoint`
+    /// does, reporting it with `Event::Evicted`. The evicted payloads are no longer available,
+    /// e.g. to `stable_payloads`. Inserts are refused with `Error::CapacityExceeded` if that
+    /// doesn't make enough room.
+    OldestStable,
+    /// Refuse inserts with `Error::CapacityExceeded`.
+    Refuse,
+}
+
+impl Default for Eviction {
+    fn default() -> Self {
+        Eviction::OldestStable
+    }
+}
+
+/// Whether a payload already held by a unit can be held by another one, on a branch the first unit
+/// isn't an ancestor of, e.g. when two nodes observed it on top of different units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Only a unit along the paths above the parents holding the payload is observed instead of
+    /// adding a new one, so the payload can be re-submitted intentionally on another branch.
+    Allow,
+    /// Any unit holding the payload, wherever it sits, is observed instead of adding a new one,
+    /// so that observations of a payload are never split between units.
+    Merge,
+}
+
+impl Default for Duplicates {
+    fn default() -> Self {
+        Duplicates::Allow
+    }
+}
+
+/// What `union` does with the observers of received units which aren't among the members set by
+/// `Dag::set_authorised_members`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MembershipCheck {
+    /// Drop them, merging the rest of the unit. No unit then counts more observers than there are
+    /// members, however many a malicious peer made up.
+    Strip,
+    /// Reject the DAG carrying the unit with `Error::UnauthorisedObservers`, merging nothing.
+    Reject,
+}
+
+impl Default for MembershipCheck {
+    fn default() -> Self {
+        MembershipCheck::Strip
+    }
+}
+
+// The admission state of a member retired at least once, as per `Dag::retire_member`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Admission {
+    // Number of times the member was re-admitted.
+    epoch: u32,
+    // Our Lamport clock when it was last re-admitted, plus one: its observations of older units
+    // may be replays from an earlier epoch.
+    since: u64,
+    retired: bool,
+}
+
+// The cached digest of a DAG. Behind a mutex, as it's filled in by `Dag::digest` which only
+// borrows the DAG, and the DAG has to stay `Sync`.
+#[derive(Default)]
+struct DigestCache(Mutex<Option<[u8; 32]>>);
+
+impl DigestCache {
+    fn get(&self) -> Option<[u8; 32]> {
+        *self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn set(&self, digest: Option<[u8; 32]>) {
+        *self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = digest;
+    }
+}
+
+impl Clone for DigestCache {
+    fn clone(&self) -> Self {
+        DigestCache(Mutex::new(self.get()))
+    }
+}
+
+/// The changes a union with another DAG would make.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnionOutcome {
+    /// Identifiers of the units which would be inserted.
+    pub new_units: BTreeSet<UnitId>,
+    /// Observers which would be added to units already held, keyed by unit identifier.
+    pub new_observers: BTreeMap<UnitId, BTreeSet<Id>>,
+}
+
+impl UnionOutcome {
+    /// Whether the union would leave the DAG unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.new_units.is_empty() && self.new_observers.is_empty()
+    }
+}
+
+/// How two DAGs differ, e.g. after a network partition healed, as per `Dag::diff`. Empty once they
+/// converged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DagDiff {
+    /// Identifiers of the units held by us only.
+    pub only_in_self: BTreeSet<UnitId>,
+    /// Identifiers of the units held by the other DAG only.
+    pub only_in_other: BTreeSet<UnitId>,
+    /// Identifiers of the units held by both, with different observers.
+    pub differing_observers: BTreeSet<UnitId>,
+    /// Identifiers of the units held by both, with different children.
+    pub differing_children: BTreeSet<UnitId>,
+}
+
+impl DagDiff {
+    /// Whether the two DAGs hold the same units, with the same observers and children.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty() &&
+            self.differing_observers.is_empty() && self.differing_children.is_empty()
+    }
+}
+
+/// The graph is composed by: a list of units, each holds the parents it points to.
 /// The graph starts with a genesis unit, which is a hard-coded unit.
+/// A new unit takes all the tips, i.e. the childless units, as its parents, so that every branch
+/// known when it is created gets merged, and there is no parent to choose.
 
-impl Dag {
-    /// Creating a new DAG, with the gensis block inserted.
-    pub fn new(id: Id) -> Self {
-        let mut observers = BTreeSet::new();
-        let _ = observers.insert(id);
-        let gensis_unit = Unit::new_genesis(observers);
+impl<T: Payload> Dag<T> {
+    /// Creating a new DAG, with the gensis block inserted and observed by the owner of `keys`.
+    pub fn new(keys: &Keypair) -> Self {
+        Dag::with_genesis(Unit::new_genesis(BTreeSet::new()), keys)
+    }
+
+    /// Create a new DAG for the network or application identified by `network_id`, observed by
+    /// the owner of `keys`. Its genesis identifier is bound to `network_id`, so that DAGs of
+    /// different networks are never merged, even by nodes mistakenly connected to each other.
+    pub fn new_with_genesis(network_id: &[u8], keys: &Keypair) -> Self {
+        Dag::with_genesis(Unit::new_network_genesis(network_id, BTreeSet::new()), keys)
+    }
+
+    /// As `new_with_genesis`, the identifiers of all units, the genesis included, being derived
+    /// with `hasher` rather than SHA3-256. Peers' DAGs must have been created with the same.
+    pub fn new_with_hasher(network_id: &[u8], keys: &Keypair, hasher: Arc<Hasher>) -> Self {
+        let genesis = Unit::new_network_genesis_with(&*hasher, network_id, BTreeSet::new());
+        let mut dag = Dag::with_genesis(genesis, keys);
+        dag.hasher = hasher;
+        dag
+    }
+
+    fn with_genesis(mut gensis_unit: Unit<T>, keys: &Keypair) -> Self {
+        gensis_unit.add_signed_observer(keys);
+        let network = gensis_unit.identifier;
         let mut units = BTreeMap::new();
-        let _ = units.insert(gensis_unit.identifier.clone(), gensis_unit.clone());
-        Dag {
-            units,
-            genesis: gensis_unit,
-            majority: 0,
+        let _ = units.insert(gensis_unit.identifier, gensis_unit.clone());
+        Dag::from_units(units, gensis_unit, network, 0, Checkpoint::default())
+    }
+
+    // A DAG holding the given units, with the children index built from them.
+    fn from_units(
+        units: BTreeMap<UnitId, Unit<T>>,
+        genesis: Unit<T>,
+        network: UnitId,
+        majority: u8,
+        checkpoint: Checkpoint,
+    ) -> Self {
+        let mut dag = Dag {
+            units: BTreeMap::new(),
+            genesis,
+            network,
+            majority,
+            checkpoint,
+            links: Arena::default(),
+            tips: BTreeSet::new(),
+            max_traversal_depth: None,
+            policy: StabilityPolicy::default(),
+            members: 0,
+            events_enabled: false,
+            events: Vec::new(),
+            journaling: false,
+            journal: Vec::new(),
+            faults: Vec::new(),
+            validation: Validation::default(),
+            unchecked: BTreeSet::new(),
+            payload_policy: None,
+            config: DagConfig::default(),
+            clock: 0,
+            payloads: BTreeMap::new(),
+            superseded: BTreeSet::new(),
+            conflict_keys: BTreeMap::new(),
+            digest: DigestCache::default(),
+            authorised: None,
+            membership_check: MembershipCheck::default(),
+            admissions: BTreeMap::new(),
+            certifying: false,
+            hasher: hasher::default_hasher(),
+        };
+        dag.reset_units(units);
+        dag
+    }
+
+    // Replace all the units held, rebuilding the indices.
+    fn reset_units(&mut self, units: BTreeMap<UnitId, Unit<T>>) {
+        self.units.clear();
+        self.links.clear();
+        self.tips.clear();
+        self.payloads.clear();
+        self.superseded.clear();
+        self.conflict_keys.clear();
+        self.digest.set(None);
+        for (_, unit) in units {
+            self.clock = cmp::max(self.clock, unit.clock);
+            self.index_unit(unit);
+        }
+        self.rebuild_depths();
+    }
+
+    // Recompute the depths of all the units, parents first.
+    fn rebuild_depths(&mut self) {
+        self.links.clear_depths();
+        // Per slot, the number of held parents of its unit whose depth isn't known yet, or `None`
+        // if its unit isn't held.
+        let mut pending: Vec<Option<usize>> = vec![None; self.links.len()];
+        let mut ready = Vec::new();
+        for unit in self.units.values() {
+            if let Some(slot) = self.links.slot(&unit.identifier) {
+                let count = self.held_parents(unit).len();
+                pending[slot] = Some(count);
+                if count == 0 {
+                    ready.push(slot);
+                }
+            }
+        }
+        while let Some(slot) = ready.pop() {
+            let depth = match self.units.get(&self.links.identifier(slot)) {
+                Some(unit) => self.depth_through_parents(unit),
+                None => continue,
+            };
+            self.links.set_depth(slot, depth);
+            for &child in self.links.children(slot) {
+                if let Some(ref mut count) = pending[child] {
+                    *count = count.saturating_sub(1);
+                    if *count == 0 {
+                        ready.push(child);
+                    }
+                }
+            }
+        }
+    }
+
+    // Recompute the depth of the unit from its parents', then of its descendants whose depth
+    // changes as a result.
+    fn refresh_depths(&mut self, identifier: UnitId) {
+        let mut to_visit = vec![self.links.intern(identifier)];
+        while let Some(slot) = to_visit.pop() {
+            let depth = match self.units.get(&self.links.identifier(slot)) {
+                Some(unit) => self.depth_through_parents(unit),
+                None => continue,
+            };
+            // A depth beyond the number of units can only be reached by going round a cycle.
+            if self.links.depth(slot) == Some(depth) || depth > self.units.len() {
+                continue;
+            }
+            self.links.set_depth(slot, depth);
+            to_visit.extend_from_slice(self.links.children(slot));
+        }
+    }
+
+    // The depth of the unit, one more than the deepest of its held parents.
+    fn depth_through_parents(&self, unit: &Unit<T>) -> usize {
+        self.held_parents(unit)
+            .iter()
+            .filter_map(|parent| self.links.depth_of(&parent.identifier))
+            .max()
+            .map_or(0, |depth| depth + 1)
+    }
+
+    // The parents of the unit which are held. The genesis has none, even if the units it was
+    // checkpointed from are still around.
+    fn held_parents(&self, unit: &Unit<T>) -> Vec<&Unit<T>> {
+        if unit.identifier == self.genesis.identifier {
+            return vec![];
+        }
+        unit.parents
+            .iter()
+            .filter_map(|parent| self.units.get(parent))
+            .collect()
+    }
+
+    /// Collapse the longest stable chain starting from the genesis, along which every unit has a
+    /// single child, into its last unit, which becomes the new genesis. The payloads of the
+    /// dropped units are folded into the checkpoint digest. Returns the number of units dropped.
+    /// Their payloads are no longer available, e.g. to `stable_payloads`, so they shall be
+    /// consumed first.
+    pub fn checkpoint(&mut self) -> usize {
+        self.collapse_stable_chain(None)
+    }
+
+    /// Like `checkpoint`, but only dropping units whose clock is less than `clock`, to keep the
+    /// recent history around.
+    pub fn checkpoint_before(&mut self, clock: u64) -> usize {
+        self.collapse_stable_chain(Some(clock))
+    }
+
+    // Collapse the stable chain as per `checkpoint`, stopping at the first unit whose clock isn't
+    // less than `before`, if given.
+    fn collapse_stable_chain(&mut self, before: Option<u64>) -> usize {
+        let _ = self.check_stable();
+        let (chain, root) = self.stable_chain(before);
+        if chain.is_empty() {
+            return 0;
+        }
+
+        for identifier in &chain {
+            if let Some(unit) = self.units.remove(identifier) {
+                self.unindex_conflicts(&unit);
+                let encoded = unit.encoded_payload().unwrap_or_default();
+                if self.payloads.get(&sha3_256(&encoded)) == Some(identifier) {
+                    let _ = self.payloads.remove(&sha3_256(&encoded));
+                }
+                let mut chained = self.checkpoint.digest.clone();
+                chained.extend(encoded);
+                self.checkpoint.digest = sha3_256(&chained).to_vec();
+            }
+            self.links.clear_children(identifier);
+        }
+        self.checkpoint.height = self.checkpoint.height.saturating_add(chain.len());
+        self.digest.set(None);
+        if let Some(root) = self.units.get(&root) {
+            self.genesis = root.clone();
+        }
+        self.rebuild_depths();
+        self.refresh_superseded();
+        chain.len()
+    }
+
+    // The units `collapse_stable_chain` would drop, with the unit they would be collapsed into.
+    fn stable_chain(&self, before: Option<u64>) -> (Vec<UnitId>, UnitId) {
+        let mut chain = Vec::new();
+        let mut root = self.genesis.identifier;
+        loop {
+            if let Some(before) = before {
+                if self.units.get(&root).map_or(false, |unit| unit.clock >= before) {
+                    break;
+                }
+            }
+            let children = self.children_of(&root);
+            if children.len() != 1 {
+                break;
+            }
+            let child = match children.into_iter().next() {
+                Some(child) => child,
+                None => break,
+            };
+            match self.units.get(&child) {
+                Some(unit) if self.is_stable(unit) => (),
+                _ => break,
+            }
+            chain.push(root);
+            root = child;
         }
+        (chain, root)
+    }
+
+    /// The stable prefix pruned so far.
+    pub fn last_checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    /// Our Lamport clock: the greatest clock of any unit created or received so far.
+    pub fn clock(&self) -> u64 {
+        self.clock
     }
 
     /// Update the majority counter.
     pub fn set_majority(&mut self, majority: u8) {
-        self.majority = majority;
+        self.change_stability(|dag| dag.majority = majority);
+    }
+
+    /// Update the number of members, including ourself, used by `StabilityPolicy::Supermajority`.
+    pub fn set_members(&mut self, members: usize) {
+        self.change_stability(|dag| dag.members = members);
+    }
+
+    /// Set what makes a unit stable. Local setting only, `StabilityPolicy::Majority` by default.
+    pub fn set_stability_policy(&mut self, policy: StabilityPolicy) {
+        self.change_stability(|dag| dag.policy = policy);
+    }
+
+    /// Give each observer the weight of its vote, for a unit to become stable once its observers
+    /// weigh more than half of the total weight. Observers without a weight count for nothing. A
+    /// shortcut for `StabilityPolicy::weighted_majority`: other thresholds are set through
+    /// `set_stability_policy`.
+    pub fn set_weights(&mut self, weights: BTreeMap<Id, u64>) {
+        self.set_stability_policy(StabilityPolicy::weighted_majority(weights));
+    }
+
+    /// The stability policy in use.
+    pub fn stability_policy(&self) -> &StabilityPolicy {
+        &self.policy
+    }
+
+    // Apply a change to what makes a unit stable, recording the units it made stable.
+    fn change_stability<F: FnOnce(&mut Self)>(&mut self, change: F) {
+        if !self.events_enabled {
+            change(self);
+            return;
+        }
+        let unstable: Vec<UnitId> = self.units
+            .values()
+            .filter(|unit| !self.is_stable(unit))
+            .map(|unit| unit.identifier)
+            .collect();
+        change(self);
+        let newly_stable: Vec<UnitId> = unstable
+            .into_iter()
+            .filter(|identifier| {
+                self.units.get(identifier).map_or(
+                    false,
+                    |unit| self.is_stable(unit),
+                )
+            })
+            .collect();
+        self.events.extend(newly_stable.into_iter().map(|identifier| {
+            Event::UnitStable { identifier }
+        }));
+    }
+
+    /// Start or stop recording the changes made to the DAG as events, to be collected through
+    /// `take_events`. Stopping discards the events not collected yet.
+    pub fn set_events_enabled(&mut self, enabled: bool) {
+        self.events_enabled = enabled;
+        if !enabled {
+            self.events.clear();
+        }
+    }
+
+    /// The equivocations detected so far by `union`: observers which signed two different units
+    /// sharing the same parent.
+    pub fn detected_faults(&self) -> Vec<FaultProof<T>> {
+        self.faults.clone()
+    }
+
+    /// Serialise this DAG for persistence, in a versioned format which `load` keeps reading once
+    /// the format changes.
+    pub fn save(&self) -> Result<Vec<u8>, Error> {
+        snapshot::encode_versioned(DAG_FORMAT_VERSION, self)
+    }
+
+    /// Load a DAG saved by `save`, possibly by an older version of this crate. Local settings,
+    /// e.g. the maximum traversal depth, aren't saved and have to be set again.
+    pub fn load(bytes: &[u8]) -> Result<Self, Error> {
+        match snapshot::decode_versioned(bytes)? {
+            (DAG_FORMAT_VERSION, body) => Ok(serialisation::deserialise(&body)?),
+            (1, body) => {
+                let dag: LegacyDag<UnitV1<T>> = serialisation::deserialise(&body)?;
+                Ok(dag.into_clocked_dag())
+            }
+            (2, body) => {
+                let dag: LegacyDag<UnitV2<T>> = serialisation::deserialise(&body)?;
+                Ok(dag.into_clocked_dag())
+            }
+            (3, body) => {
+                let dag: LegacyDag<UnitV3<T>> = serialisation::deserialise(&body)?;
+                Ok(dag.into_clocked_dag())
+            }
+            (4, body) => {
+                let dag: LegacyDag<UnitV4<T>> = serialisation::deserialise(&body)?;
+                Ok(dag.into_dag())
+            }
+            (5, body) => {
+                let dag: DagV5<UnitV4<T>> = serialisation::deserialise(&body)?;
+                Ok(dag.into_dag())
+            }
+            (6, body) => {
+                let dag: DagV5<UnitV6<T>> = serialisation::deserialise(&body)?;
+                Ok(dag.into_dag())
+            }
+            (version, _) => Err(Error::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Take the events recorded since the last call.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        ::std::mem::replace(&mut self.events, Vec::new())
+    }
+
+    /// Start or stop recording the units inserted and the observers added as journal entries, to
+    /// be collected through `take_journal_entries`. Stopping discards the entries not collected
+    /// yet.
+    pub fn set_journaling(&mut self, enabled: bool) {
+        self.journaling = enabled;
+        if !enabled {
+            self.journal.clear();
+        }
+    }
+
+    /// Collect the journal entries recorded since the last call.
+    pub fn take_journal_entries(&mut self) -> Vec<JournalEntry<T>> {
+        ::std::mem::replace(&mut self.journal, Vec::new())
+    }
+
+    /// Apply a journal entry. Replaying an entry already applied changes nothing. Signatures are
+    /// not checked, so only entries recorded by ourself shall be replayed.
+    pub fn replay(&mut self, entry: JournalEntry<T>) {
+        let (identifier, observers, signatures) = match entry {
+            JournalEntry::Unit(unit) => {
+                if !self.units.contains_key(&unit.identifier) {
+                    let identifier = unit.identifier;
+                    self.insert_unit(unit);
+                    self.record_change(&identifier, None);
+                    return;
+                }
+                (unit.identifier, unit.observers, unit.signatures)
+            }
+            JournalEntry::Observers {
+                identifier,
+                observers,
+                signatures,
+            } => (identifier, observers, signatures),
+        };
+        let before = match self.units.get_mut(&identifier) {
+            Some(unit) => {
+                let before = unit.observers.clone();
+                unit.observers.extend(observers);
+                unit.signatures.extend(signatures);
+                before
+            }
+            None => return,
+        };
+        self.record_change(&identifier, Some(before));
+    }
+
+    /// Restrict the observers of units received to `members`, ourself included, handling those
+    /// outside as per `check`. With `None`, the default, any observer with a valid signature is
+    /// accepted. Only applies to units merged from now on.
+    pub fn set_authorised_members(
+        &mut self,
+        members: Option<BTreeSet<Id>>,
+        check: MembershipCheck,
+    ) {
+        self.authorised = members;
+        self.membership_check = check;
+    }
+
+    /// The members observers are restricted to, if any.
+    pub fn authorised_members(&self) -> Option<&BTreeSet<Id>> {
+        self.authorised.as_ref()
+    }
+
+    /// Retire a member, e.g. removed from the network: its observations received from now on,
+    /// by attestation or union, are dropped until it is re-admitted. Those already held are kept.
+    pub fn retire_member(&mut self, member: Id) {
+        self.admissions
+            .entry(member)
+            .or_insert(Admission {
+                epoch: 0,
+                since: 0,
+                retired: false,
+            })
+            .retired = true;
+    }
+
+    /// Admit a member. A retired member starts a new admission epoch, in which only its
+    /// observations of units with a clock greater than ours at re-admission are accepted: its
+    /// signatures being over payloads, attestations from its earlier epoch could otherwise be
+    /// replayed toward stability. A unit it observed again since, but created before, then needs
+    /// other observers to become stable. Admitting a member never retired changes nothing.
+    pub fn admit_member(&mut self, member: Id) {
+        let since = self.clock.saturating_add(1);
+        if let Some(admission) = self.admissions.get_mut(&member) {
+            if admission.retired {
+                admission.epoch = admission.epoch.saturating_add(1);
+                admission.since = since;
+                admission.retired = false;
+            }
+        }
+    }
+
+    /// The admission epoch of the member: the number of times it was re-admitted after being
+    /// retired.
+    pub fn member_epoch(&self, member: &Id) -> u32 {
+        self.admissions.get(member).map_or(0, |admission| admission.epoch)
+    }
+
+    // Whether an observation of the unit by `observer`, newly received, may belong to the
+    // observer's current admission epoch: it isn't retired, and the unit, as held by us if we do,
+    // isn't older than its last admission.
+    fn is_current_observation(&self, observer: &Id, unit: &Unit<T>) -> bool {
+        let admission = match self.admissions.get(observer) {
+            Some(admission) => admission,
+            None => return true,
+        };
+        let clock = self.units.get(&unit.identifier).map_or(unit.clock, |held| held.clock);
+        !admission.retired && clock >= admission.since
+    }
+
+    /// Set when received units get their identifier checked, eagerly by default. Switching to
+    /// `Validation::Eager` checks the units still pending at once, dropping the invalid ones.
+    pub fn set_validation(&mut self, validation: Validation) {
+        self.validation = validation;
+        if validation == Validation::Eager {
+            let _ = self.validate_pending();
+        }
+    }
+
+    /// Check all the units lazily validated so far, dropping the invalid ones along with their
+    /// descendants. Returns the identifiers of the units found invalid.
+    pub fn validate_pending(&mut self) -> Vec<UnitId> {
+        let pending = self.unchecked.clone();
+        self.check_paths(&pending)
+    }
+
+    /// Whether the unit is held, and had its identifier checked.
+    pub fn is_validated(&self, identifier: &UnitId) -> bool {
+        self.units.contains_key(identifier) && !self.unchecked.contains(identifier)
+    }
+
+    /// Set the bounds on the memory used, and how duplicate payloads are handled. Local setting
+    /// only. The units already held are only
+    /// evicted once new ones are inserted.
+    pub fn set_config(&mut self, config: DagConfig) {
+        self.config = config;
+    }
+
+    /// Set the application's semantics of payloads, for conflicting payloads to be resolved to a
+    /// single winner. `None`, the default, only treats byte-equal payloads as the same event.
+    pub fn set_payload_policy(&mut self, policy: Option<Arc<PayloadPolicy<T>>>) {
+        self.payload_policy = policy;
+        self.rebuild_superseded();
+    }
+
+    /// Set the hash function identifiers are derived with, which isn't saved: a DAG created with
+    /// `new_with_hasher` must be given it again once loaded. Units already held are kept as they
+    /// are, so this shall be the one they were created with.
+    pub fn set_hasher(&mut self, hasher: Arc<Hasher>) {
+        self.hasher = hasher;
+    }
+
+    /// The hash function identifiers are derived with, `Sha3Hasher` by default.
+    pub fn hasher(&self) -> &Arc<Hasher> {
+        &self.hasher
+    }
+
+    /// Whether the unit is superseded: another unit held has a payload conflicting with its own,
+    /// which wins as per the payload policy.
+    pub fn is_superseded(&self, unit: &Unit<T>) -> bool {
+        self.superseded.contains(&unit.identifier)
+    }
+
+    /// Limit the number of units walked when searching a path for a payload. Past the limit the
+    /// payload is treated as not found. `None` removes the limit.
+    pub fn set_max_traversal_depth(&mut self, max_traversal_depth: Option<usize>) {
+        self.max_traversal_depth = max_traversal_depth;
+    }
+
+    /// Check that `other` is consistent, and can be unioned with us:
+    ///     * it starts from the same genesis, and belongs to the same network.
+    ///     * every unit other than the genesis has parents, all held by either DAG.
+    ///     * every unit's identifier matches its content, unless validating lazily.
+    ///     * every path leads back to the genesis, without cycles.
+    pub fn validate(&self, other: &Dag<T>) -> Result<(), Error> {
+        if other.network != self.network || other.genesis.identifier != self.genesis.identifier {
+            return Err(Error::GenesisMismatch);
+        }
+        for (identifier, unit) in &other.units {
+            if *identifier != unit.identifier {
+                return Err(Error::InvalidUnit(*identifier));
+            }
+            if unit.identifier == self.genesis.identifier {
+                if unit.parents != self.genesis.parents || unit.payload != self.genesis.payload {
+                    return Err(Error::InvalidUnit(*identifier));
+                }
+                continue;
+            }
+            if unit.parents.is_empty() {
+                return Err(Error::InvalidUnit(*identifier));
+            }
+            let mut parent_payloads = Vec::new();
+            for parent in &unit.parents {
+                match find(&other.units, &self.units, parent) {
+                    Some(parent) => parent_payloads.push(&parent.payload),
+                    None => return Err(Error::MissingParent(*parent)),
+                }
+                // Our copy of the parent may have a greater clock than the peer's, which only
+                // gets smaller once merged, so the clock is only checked against the peer's.
+                if other.units.get(parent).map_or(false, |parent| parent.clock >= unit.clock) {
+                    return Err(Error::InvalidUnit(*identifier));
+                }
+            }
+            if self.validation == Validation::Eager &&
+                unit::compute_identifier_with(&*self.hasher, &parent_payloads, &unit.payload)? !=
+                    unit.identifier
+            {
+                return Err(Error::InvalidUnit(*identifier));
+            }
+        }
+
+        // The units known to lead back to the genesis. Ours do, having been validated already.
+        let mut acyclic = BTreeSet::new();
+        let _ = acyclic.insert(self.genesis.identifier);
+        for unit in other.units.values() {
+            // Walk depth first along the parents, keeping the units on the path from `unit` to the
+            // current one, each with the parents still to be walked.
+            let mut path: Vec<(UnitId, Vec<UnitId>)> = Vec::new();
+            let mut on_path = BTreeSet::new();
+            if !acyclic.contains(&unit.identifier) {
+                path.push((unit.identifier, unit.parents.iter().cloned().collect()));
+                let _ = on_path.insert(unit.identifier);
+            }
+            loop {
+                let (current, next) = match path.last_mut() {
+                    Some(&mut (current, ref mut parents)) => (current, parents.pop()),
+                    None => break,
+                };
+                let parent = match next {
+                    Some(parent) => parent,
+                    None => {
+                        let _ = path.pop();
+                        let _ = on_path.remove(&current);
+                        let _ = acyclic.insert(current);
+                        continue;
+                    }
+                };
+                if acyclic.contains(&parent) {
+                    continue;
+                }
+                if on_path.contains(&parent) {
+                    let traversal = path.iter().map(|&(identifier, _)| identifier).collect();
+                    let diagnostics = Diagnostics::new(traversal, (current, parent));
+                    return Err(Error::CycleDetected(diagnostics));
+                }
+                match other.units.get(&parent) {
+                    Some(parent_unit) => {
+                        path.push((parent, parent_unit.parents.iter().cloned().collect()));
+                        let _ = on_path.insert(parent);
+                    }
+                    None => {
+                        let _ = acyclic.insert(parent);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Union with the other DAG, once validated by `validate`.
+    ///     * If don't know a unit from other, insert it into graph.
+    ///     * If already know a unit, union the units.
+    /// Observers without a valid signature are dropped, and reported through
+    /// `Error::ForgedObservers` once everything else has been merged. Observers outside the
+    /// authorised members, if set, are dropped or fail the union as per the `MembershipCheck`.
+    /// Retired observers, and those re-admitted observing units older than their admission, are
+    /// dropped.
+    /// A DAG checkpointed behind us only has the units descending from our genesis merged in.
+    /// A DAG checkpointed ahead of us makes us drop everything not descending from its genesis,
+    /// which has to be stable with the valid signatures of both copies, and if held by us, to be
+    /// reached by pruning our own units into the same checkpoint. Nothing changes unless the
+    /// other DAG is valid once we are fast forwarded.
+    /// Fails without changing anything if the other DAG is invalid or breaks the bounds of our
+    /// `DagConfig`: stable units are only evicted to make room once it is accepted.
+    pub fn union(&mut self, other: &Dag<T>) -> Result<(), Error> {
+        // Checked before anything else, as checkpoints of different networks can't be compared.
+        if other.network != self.network {
+            return Err(Error::GenesisMismatch);
+        }
+        let rebased;
+        let mut forwarded = None;
+        let other = match other.checkpoint.height.cmp(&self.checkpoint.height) {
+            Ordering::Equal => other,
+            Ordering::Less => {
+                rebased = other.rebased_on(&self.genesis, &self.checkpoint);
+                &rebased
+            }
+            Ordering::Greater => {
+                // Fast forwarded on a copy, so that nothing changes until `other` is validated.
+                let mut dag = self.clone();
+                dag.fast_forward(other)?;
+                forwarded = Some(dag);
+                other
+            }
+        };
+        let incoming = {
+            let base = forwarded.as_ref().unwrap_or(&*self);
+            base.validate(other)?;
+            if base.membership_check == MembershipCheck::Reject {
+                base.check_membership(other)?;
+            }
+            base.check_limits(other)?
+        };
+        if let Some(dag) = forwarded {
+            *self = dag;
+        }
+        // Only evicting once `other` is accepted, so that a DAG failing the checks above can't
+        // have us drop anything. Evicting makes a checkpoint, which `other` may fall behind.
+        if let Some(max) = self.config.max_units {
+            self.make_room(incoming, max)?;
+        }
+        let rebased_after_eviction;
+        let other = if other.checkpoint.height < self.checkpoint.height {
+            rebased_after_eviction = other.rebased_on(&self.genesis, &self.checkpoint);
+            &rebased_after_eviction
+        } else {
+            other
+        };
+        let mut forged = BTreeSet::new();
+        for (identifier, other_unit) in &other.units {
+            let stripped;
+            let other_unit = match self.without_ineligible(other_unit) {
+                Some(unit) => {
+                    stripped = unit;
+                    &stripped
+                }
+                None => other_unit,
+            };
+            if let Some(unit) = self.units.get_mut(identifier) {
+                // If already see the unit, union these two.
+                let before = unit.observers.clone();
+                forged.extend(unit.union(other_unit));
+                self.record_change(identifier, Some(before));
+                self.detect_forks(identifier);
+                continue;
+            }
+            // If already observed the payload along the paths the other uint sits on, or anywhere
+            // if merging duplicates, only union the observers.
+            let observed = self.has_observed_in(&other_unit.parents, &other_unit.payload, false)
+                .or_else(|| self.duplicate_of(&other_unit.payload));
+            if let Some(identifier) = observed {
+                if let Some(unit) = self.units.get_mut(&identifier) {
+                    let before = unit.observers.clone();
+                    forged.extend(unit.union(other_unit));
+                    self.record_change(&identifier, Some(before));
+                }
+                self.detect_forks(&identifier);
+            } else {
+                let mut unit = other_unit.without_observers();
+                forged.extend(unit.union(other_unit));
+                if self.validation == Validation::Lazy {
+                    let _ = self.unchecked.insert(unit.identifier);
+                }
+                self.insert_unit(unit);
+                self.record_change(&other_unit.identifier, None);
+                self.detect_forks(&other_unit.identifier);
+            }
+        }
+        let clock = other.units.values().map(|unit| unit.clock).max().unwrap_or(0);
+        self.clock = cmp::max(self.clock, clock);
+        if let Some(identifier) = self.check_stable().first() {
+            return Err(Error::InvalidUnit(*identifier));
+        }
+        if forged.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ForgedObservers(forged))
+        }
+    }
+
+    // Fail with the first unit of `other`, other than our genesis, which has observers outside the
+    // authorised members.
+    fn check_membership(&self, other: &Dag<T>) -> Result<(), Error> {
+        let authorised = match self.authorised {
+            Some(ref authorised) => authorised,
+            None => return Ok(()),
+        };
+        for unit in other.units.values() {
+            if unit.identifier == self.genesis.identifier {
+                continue;
+            }
+            let unauthorised: BTreeSet<Id> =
+                unit.observers.difference(authorised).cloned().collect();
+            if !unauthorised.is_empty() {
+                warn!("Unit {:?} observed by non-members {:?}", unit.identifier, unauthorised);
+                return Err(Error::UnauthorisedObservers(unit.identifier, unauthorised));
+            }
+        }
+        Ok(())
+    }
+
+    // A copy of the unit without its observers outside the authorised members, or whose
+    // observation isn't from their current admission epoch, if it has any.
+    fn without_ineligible(&self, unit: &Unit<T>) -> Option<Unit<T>> {
+        let ineligible: BTreeSet<Id> = unit.observers
+            .iter()
+            .filter(|observer| {
+                self.authorised.as_ref().map_or(false, |authorised| {
+                    !authorised.contains(*observer)
+                }) || !self.is_current_observation(observer, unit)
+            })
+            .cloned()
+            .collect();
+        if ineligible.is_empty() {
+            return None;
+        }
+        debug!("Dropping observers {:?} of {:?}", ineligible, unit.identifier);
+        let mut unit = unit.clone();
+        unit.observers = unit.observers.difference(&ineligible).cloned().collect();
+        unit.signatures = unit.signatures
+            .iter()
+            .filter(|&(observer, _)| !ineligible.contains(observer))
+            .map(|(observer, signature)| (*observer, signature.clone()))
+            .collect();
+        Some(unit)
+    }
+
+    // Record a fault for every signed observer of the unit which also signed one of its siblings,
+    // i.e. a unit with the same parents, unless already recorded.
+    fn detect_forks(&mut self, identifier: &UnitId) {
+        let unit = match self.units.get(identifier) {
+            Some(unit) if unit.identifier != self.genesis.identifier => unit,
+            _ => return,
+        };
+        let first_parent = match unit.parents.iter().next() {
+            Some(parent) => *parent,
+            None => return,
+        };
+        for sibling in self.children_of(&first_parent) {
+            let sibling = match self.units.get(&sibling) {
+                Some(sibling) if sibling.identifier != unit.identifier &&
+                    sibling.parents == unit.parents => sibling,
+                _ => continue,
+            };
+            for observer in unit.signatures.keys() {
+                if !sibling.signatures.contains_key(observer) {
+                    continue;
+                }
+                let known = self.faults.iter().any(|fault| {
+                    let pair = (fault.first.identifier, fault.second.identifier);
+                    fault.observer == *observer &&
+                        (pair == (sibling.identifier, unit.identifier) ||
+                             pair == (unit.identifier, sibling.identifier))
+                });
+                if known {
+                    continue;
+                }
+                if let Some(fault) = FaultProof::new(*observer, sibling, unit) {
+                    warn!(
+                        "Observer {:?} signed both {:?} and {:?}",
+                        observer,
+                        sibling.identifier,
+                        unit.identifier
+                    );
+                    self.faults.push(fault);
+                }
+            }
+        }
+    }
+
+    // The units of this DAG descending from `root`, as a DAG checkpointed at `root`.
+    fn rebased_on(&self, root: &Unit<T>, checkpoint: &Checkpoint) -> Dag<T> {
+        let mut units = BTreeMap::new();
+        if let Some(unit) = self.units.get(&root.identifier) {
+            let mut to_visit = vec![unit];
+            while let Some(unit) = to_visit.pop() {
+                for child in self.children_of(&unit.identifier) {
+                    if let Some(child) = self.units.get(&child) {
+                        to_visit.push(child);
+                    }
+                }
+                let _ = units.insert(unit.identifier, unit.clone());
+            }
+        }
+        Dag::from_units(
+            units,
+            root.clone(),
+            self.network,
+            self.majority,
+            checkpoint.clone(),
+        )
+    }
+
+    // Move our checkpoint up to the one of `other`, ahead of us. Its genesis has to be stable,
+    // given the observers of both copies. If we hold it, pruning our units up to it also has to
+    // give the checkpoint of `other`.
+    fn fast_forward(&mut self, other: &Dag<T>) -> Result<(), Error> {
+        let root = match self.units.get(&other.genesis.identifier) {
+            Some(held) => {
+                let pruned = other.checkpoint.height.saturating_sub(self.checkpoint.height);
+                if self.checkpoint_up_to(held, pruned).as_ref() != Some(&other.checkpoint) {
+                    return Err(Error::UnverifiedCheckpoint(other.checkpoint.height));
+                }
+                let mut root = held.clone();
+                let _ = root.union(&other.genesis);
+                root
+            }
+            None => {
+                let mut root = other.genesis.without_observers();
+                let _ = root.union(&other.genesis);
+                root
+            }
+        };
+        if !self.is_stable(&root) {
+            return Err(Error::UnverifiedCheckpoint(other.checkpoint.height));
+        }
+        let rebased = self.rebased_on(&root, &other.checkpoint);
+        self.genesis = root.clone();
+        self.checkpoint = rebased.checkpoint;
+        let mut units = rebased.units;
+        let _ = units.entry(root.identifier).or_insert(root);
+        self.reset_units(units);
+        Ok(())
+    }
+
+    // The checkpoint made by pruning the chain of units from our genesis up to `root`, excluded,
+    // along which each unit is the only held parent of the next one, as `checkpoint` would.
+    // `None` if there is no such chain of at most `max_len` units.
+    fn checkpoint_up_to(&self, root: &Unit<T>, max_len: usize) -> Option<Checkpoint> {
+        let mut chain = Vec::new();
+        let mut current = root;
+        while current.identifier != self.genesis.identifier {
+            if chain.len() >= max_len {
+                return None;
+            }
+            let parents = self.held_parents(current);
+            if parents.len() != 1 {
+                return None;
+            }
+            current = parents[0];
+            chain.push(current);
+        }
+        let mut digest = self.checkpoint.digest.clone();
+        for unit in chain.iter().rev() {
+            digest.extend(unit.encoded_payload().unwrap_or_default());
+            digest = sha3_256(&digest).to_vec();
+        }
+        Some(Checkpoint {
+            height: self.checkpoint.height.saturating_add(chain.len()),
+            digest,
+        })
+    }
+
+    /// Number of units held.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Whether no units are held at all. Only partial DAGs, e.g. deltas, can be empty.
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// A digest of the units held and of their observers, equal for any two DAGs checkpointed at
+    /// the same height which hold the same units with the same observers. Comparing digests tells
+    /// whether two DAGs have anything to exchange without sending them. Cached until the DAG
+    /// changes.
+    pub fn digest(&self) -> [u8; 32] {
+        if let Some(digest) = self.digest.get() {
+            return digest;
+        }
+        let mut hashed = Vec::new();
+        push_u64(&mut hashed, self.checkpoint.height as u64);
+        for unit in self.units.values() {
+            hashed.extend_from_slice(&unit.identifier.0);
+            push_u64(&mut hashed, unit.observers.len() as u64);
+            for observer in &unit.observers {
+                hashed.extend_from_slice(&observer.0);
+            }
+        }
+        let digest = sha3_256(&hashed);
+        self.digest.set(Some(digest));
+        digest
+    }
+
+    /// The version of every unit held, keyed by unit identifier.
+    pub fn unit_versions(&self) -> BTreeMap<UnitId, usize> {
+        self.units
+            .iter()
+            .map(|(identifier, unit)| (*identifier, unit.version()))
+            .collect()
+    }
+
+    /// A partial copy of this DAG holding only the units missing from `known`, or more recent than
+    /// the version recorded in it.
+    pub fn delta_since(&self, known: &BTreeMap<UnitId, usize>) -> Dag<T> {
+        let units = self.units
+            .iter()
+            .filter(|&(identifier, unit)| {
+                known.get(identifier).map_or(true, |&version| unit.version() > version)
+            })
+            .map(|(identifier, unit)| (*identifier, unit.clone()))
+            .collect();
+        Dag::from_units(
+            units,
+            self.genesis.clone(),
+            self.network,
+            self.majority,
+            self.checkpoint.clone(),
+        )
+    }
+
+    /// All the units held, in identifier order.
+    pub fn units(&self) -> btree_map::Values<UnitId, Unit<T>> {
+        self.units.values()
+    }
+
+    /// The genesis: the root of the DAG, or the unit the stable prefix was collapsed into.
+    pub fn genesis(&self) -> &Unit<T> {
+        &self.genesis
+    }
+
+    /// The identifier of the original genesis, which stays that of the network the DAG belongs
+    /// to once the genesis was collapsed by a checkpoint.
+    pub fn network(&self) -> &UnitId {
+        &self.network
+    }
+
+    /// The DAG in the DOT language, for rendering with graphviz.
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        export::to_dot(self, options)
+    }
+
+    /// The DAG as a JSON graph of nodes and links, for web visualisers.
+    pub fn to_json_graph(&self) -> String {
+        export::to_json_graph(self)
+    }
+
+    /// This DAG as plain serde structs for external tooling, e.g. to be written as JSON, as per the
+    /// schema documented in the `export` module. Fails if a payload can't be encoded.
+    pub fn export(&self) -> Result<DagExport, Error> {
+        let mut units = Vec::with_capacity(self.units.len());
+        for unit in self.units.values() {
+            units.push(UnitExport::new(unit, self.is_stable(unit), self.depth_of(unit))?);
+        }
+        Ok(DagExport {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            network: self.network.to_hex(),
+            genesis: self.genesis.identifier.to_hex(),
+            majority: self.majority,
+            checkpoint_height: self.checkpoint.height,
+            checkpoint_digest: diagnostics::to_hex(&self.checkpoint.digest),
+            units,
+        })
+    }
+
+    /// Rebuild a DAG from its export, with the local settings of a new DAG. The stability and
+    /// depths exported are ignored, being derived again. Like `load`, it trusts the units to be
+    /// valid: `check_invariants` tells whether they are. Fails with
+    /// `Error::UnsupportedVersion` if the export follows another version of the schema, or with
+    /// `Error::MalformedExport` if it doesn't follow it, e.g. without its genesis among the units.
+    pub fn from_export(export: &DagExport) -> Result<Self, Error> {
+        if export.schema_version != EXPORT_SCHEMA_VERSION {
+            return Err(Error::UnsupportedVersion(export.schema_version));
+        }
+        let mut units = BTreeMap::new();
+        for unit in &export.units {
+            let unit: Unit<T> = unit.to_unit()?;
+            let _ = units.insert(unit.identifier, unit);
+        }
+        let genesis = export.genesis.parse::<UnitId>()?;
+        let genesis = match units.get(&genesis) {
+            Some(genesis) => genesis.clone(),
+            None => return Err(Error::MalformedExport("missing genesis".to_string())),
+        };
+        let digest = match id::from_hex(&export.checkpoint_digest) {
+            Some(digest) => digest,
+            None => return Err(Error::MalformedExport("invalid checkpoint digest".to_string())),
+        };
+        let checkpoint = Checkpoint {
+            height: export.checkpoint_height,
+            digest,
+        };
+        Ok(Dag::from_units(
+            units,
+            genesis,
+            export.network.parse()?,
+            export.majority,
+            checkpoint,
+        ))
+    }
+
+    /// A compact, read-only copy of this DAG, for analytics and export.
+    pub fn freeze(&self) -> FrozenDag<T> {
+        FrozenDag::new(self)
+    }
+
+    /// Whether the unit is held.
+    pub fn contains(&self, identifier: &UnitId) -> bool {
+        self.units.contains_key(identifier)
+    }
+
+    /// The unit with the given identifier, if held.
+    pub fn get(&self, identifier: &UnitId) -> Option<&Unit<T>> {
+        self.units.get(identifier)
+    }
+
+    /// The observers of the unit with the given identifier, if held.
+    pub fn observers_of(&self, identifier: &UnitId) -> Option<&BTreeSet<Id>> {
+        self.units.get(identifier).map(|unit| &unit.observers)
+    }
+
+    /// The metadata of the unit with the given identifier, if held.
+    pub fn metadata_of(&self, identifier: &UnitId) -> Option<&BTreeMap<String, Vec<u8>>> {
+        self.units.get(identifier).map(|unit| &unit.metadata)
+    }
+
+    /// The units whose metadata has `value` under `name`, in their total order as per
+    /// `ordered_units`.
+    pub fn units_with_metadata(&self, name: &str, value: &[u8]) -> Vec<&Unit<T>> {
+        self.ordered_units()
+            .into_iter()
+            .filter(|unit| unit.metadata.get(name).map_or(false, |held| &held[..] == value))
+            .collect()
+    }
+
+    /// Identifiers of the units without any child, i.e. the candidate parents for a new unit.
+    pub fn tips(&self) -> btree_set::Iter<UnitId> {
+        self.tips.iter()
+    }
+
+    /// All the units held in topological order: the genesis first, then every unit after its
+    /// parent, as per `ordered_units`.
+    pub fn topological(&self) -> vec::IntoIter<&Unit<T>> {
+        let genesis = self.units.get(&self.genesis.identifier);
+        let mut units: Vec<&Unit<T>> = genesis.into_iter().collect();
+        units.extend(self.ordered_units());
+        units.into_iter()
+    }
+
+    /// Copies of the held units among `identifiers`, in the order requested. Unknown identifiers
+    /// are skipped.
+    pub fn units_by_ids(&self, identifiers: &[UnitId]) -> Vec<Unit<T>> {
+        identifiers
+            .iter()
+            .filter_map(|identifier| self.units.get(identifier).cloned())
+            .collect()
+    }
+
+    /// A partial DAG holding only the given units, e.g. as fetched from a peer, on top of our
+    /// genesis. Can be merged in with `union`, like a delta.
+    pub fn partial(&self, units: Vec<Unit<T>>) -> Dag<T> {
+        let units = units.into_iter().map(|unit| (unit.identifier, unit)).collect();
+        Dag::from_units(
+            units,
+            self.genesis.clone(),
+            self.network,
+            self.majority,
+            self.checkpoint.clone(),
+        )
+    }
+
+    /// This DAG encoded column-wise, for sending large DAGs more compactly.
+    pub fn to_columnar(&self) -> Result<ColumnarDag, Error> {
+        let mut columns = ColumnarDag {
+            genesis: self.genesis.identifier,
+            network: self.network,
+            majority: self.majority,
+            checkpoint: self.checkpoint.clone(),
+            ..ColumnarDag::default()
+        };
+        let genesis = if self.units.contains_key(&self.genesis.identifier) {
+            None
+        } else {
+            Some(&self.genesis)
+        };
+        for unit in genesis.into_iter().chain(self.units.values()) {
+            let payload = unit.encoded_payload()?;
+            columns.identifiers.push(unit.identifier);
+            columns.parent_counts.push(unit.parents.len() as u32);
+            columns.parents.extend(unit.parents.iter().cloned());
+            columns.payload_lengths.push(payload.len() as u32);
+            columns.payloads.extend(payload);
+            columns.content_types.push(unit.content_type.clone());
+            columns.clocks.push(unit.clock);
+            columns.metadata.push(unit.metadata.clone());
+            columns.certificates.push(unit.certificate.clone());
+            columns.observer_counts.push(unit.observers.len() as u32);
+            for observer in &unit.observers {
+                columns.observers.push(*observer);
+                columns.signatures.push(
+                    unit.signatures.get(observer).cloned().unwrap_or_default(),
+                );
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Rebuild a DAG from its column-wise encoding. Fails with `Error::MalformedColumns` if the
+    /// columns don't describe the same units, or if the genesis is missing from them.
+    pub fn from_columnar(columns: ColumnarDag) -> Result<Self, Error> {
+        let count = columns.identifiers.len();
+        if columns.parent_counts.len() != count || columns.payload_lengths.len() != count ||
+            columns.content_types.len() != count || columns.observer_counts.len() != count ||
+            columns.clocks.len() != count || columns.metadata.len() != count ||
+            columns.certificates.len() != count ||
+            columns.observers.len() != columns.signatures.len()
+        {
+            return Err(Error::MalformedColumns);
+        }
+        let mut units = BTreeMap::new();
+        let mut parent_start = 0;
+        let mut payload_start = 0;
+        let mut observer_start = 0;
+        for index in 0..count {
+            let parent_count = columns.parent_counts[index] as usize;
+            let payload_length = columns.payload_lengths[index] as usize;
+            let observer_count = columns.observer_counts[index] as usize;
+            if parent_count > columns.parents.len() - parent_start ||
+                payload_length > columns.payloads.len() - payload_start ||
+                observer_count > columns.observers.len() - observer_start
+            {
+                return Err(Error::MalformedColumns);
+            }
+            let parent_end = parent_start + parent_count;
+            let payload_end = payload_start + payload_length;
+            let observer_end = observer_start + observer_count;
+            let payload = &columns.payloads[payload_start..payload_end];
+            let mut unit = Unit {
+                identifier: columns.identifiers[index],
+                parents: columns.parents[parent_start..parent_end].iter().cloned().collect(),
+                payload: serialisation::deserialise(payload)?,
+                observers: BTreeSet::new(),
+                signatures: BTreeMap::new(),
+                content_type: columns.content_types[index].clone(),
+                clock: columns.clocks[index],
+                metadata: columns.metadata[index].clone(),
+                certificate: columns.certificates[index].clone(),
+            };
+            for position in observer_start..observer_end {
+                let observer = columns.observers[position];
+                let _ = unit.observers.insert(observer);
+                if !columns.signatures[position].is_empty() {
+                    let signature = columns.signatures[position].clone();
+                    let _ = unit.signatures.insert(observer, signature);
+                }
+            }
+            let _ = units.insert(unit.identifier, unit);
+            parent_start = parent_end;
+            payload_start = payload_end;
+            observer_start = observer_end;
+        }
+        if parent_start != columns.parents.len() || payload_start != columns.payloads.len() ||
+            observer_start != columns.observers.len()
+        {
+            return Err(Error::MalformedColumns);
+        }
+        let genesis = match units.get(&columns.genesis) {
+            Some(genesis) => genesis.clone(),
+            None => return Err(Error::MalformedColumns),
+        };
+        Ok(Dag::from_units(
+            units,
+            genesis,
+            columns.network,
+            columns.majority,
+            columns.checkpoint,
+        ))
+    }
+
+    /// The identifiers of the units having the given unit as parent.
+    pub fn children_of(&self, identifier: &UnitId) -> BTreeSet<UnitId> {
+        self.links.children_of(identifier).into_iter().collect()
+    }
+
+    /// The ancestors of the unit, walking its parents back to the genesis, nearest first. Empty
+    /// if the unit isn't held.
+    pub fn ancestors(&self, identifier: &UnitId) -> Traversal<T> {
+        Traversal::new(self, identifier, true)
+    }
+
+    /// The descendants of the unit, walking its children down to the tips, nearest first. Empty
+    /// if the unit isn't held.
+    pub fn descendants(&self, identifier: &UnitId) -> Traversal<T> {
+        Traversal::new(self, identifier, false)
+    }
+
+    /// The shortest path along the parents from `from` back to `to`, both included, or `None` if
+    /// `to` isn't an ancestor of `from`. Fails with `Error::CycleDetected` should the walk come
+    /// back to `from`.
+    pub fn path_between(&self, from: &UnitId, to: &UnitId) -> Result<Option<Vec<UnitId>>, Error> {
+        if from == to {
+            return Ok(if self.units.contains_key(from) {
+                Some(vec![*from])
+            } else {
+                None
+            });
+        }
+        let mut ancestors = self.ancestors(from);
+        while let Some(unit) = ancestors.next() {
+            if unit?.identifier == *to {
+                return Ok(Some(ancestors.path_to(to)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether every unit held by us is also held by `other`, with at least the same observers.
+    pub fn is_subset_of(&self, other: &Dag<T>) -> bool {
+        self.units.iter().all(|(identifier, unit)| {
+            other.units.get(identifier).map_or(false, |theirs| {
+                unit.observers.is_subset(&theirs.observers)
+            })
+        })
+    }
+
+    /// How this DAG differs from `other`, unit by unit. Units pruned by a checkpoint count as not
+    /// held, so DAGs checkpointed at different heights differ by the units in between.
+    pub fn diff(&self, other: &Dag<T>) -> DagDiff {
+        let mut diff = DagDiff::default();
+        for (identifier, unit) in &self.units {
+            match other.units.get(identifier) {
+                None => {
+                    let _ = diff.only_in_self.insert(*identifier);
+                }
+                Some(theirs) => {
+                    if unit.observers != theirs.observers {
+                        let _ = diff.differing_observers.insert(*identifier);
+                    }
+                    if self.children_of(identifier) != other.children_of(identifier) {
+                        let _ = diff.differing_children.insert(*identifier);
+                    }
+                }
+            }
+        }
+        diff.only_in_other = other
+            .units
+            .keys()
+            .filter(|identifier| !self.units.contains_key(identifier))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// As `diff`, against a DAG known only by the version of each of its units, as per
+    /// `unit_versions`. Observers are compared by number only, and children not at all.
+    pub fn diff_versions(&self, versions: &BTreeMap<UnitId, usize>) -> DagDiff {
+        let mut diff = DagDiff::default();
+        for (identifier, unit) in &self.units {
+            match versions.get(identifier) {
+                None => {
+                    let _ = diff.only_in_self.insert(*identifier);
+                }
+                Some(version) if *version != unit.version() => {
+                    let _ = diff.differing_observers.insert(*identifier);
+                }
+                Some(_) => (),
+            }
+        }
+        diff.only_in_other = versions
+            .keys()
+            .filter(|identifier| !self.units.contains_key(identifier))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Compute what `union(other)` would change, without applying it.
+    pub fn preview_union(&self, other: &Dag<T>) -> Result<UnionOutcome, Error> {
+        let mut merged = self.clone();
+        match merged.union(other) {
+            Ok(()) |
+            Err(Error::ForgedObservers(_)) => (),
+            Err(error) => return Err(error),
+        }
+
+        let mut outcome = UnionOutcome::default();
+        for (identifier, unit) in &merged.units {
+            match self.units.get(identifier) {
+                None => {
+                    let _ = outcome.new_units.insert(*identifier);
+                }
+                Some(ours) => {
+                    let added: BTreeSet<Id> =
+                        unit.observers.difference(&ours.observers).cloned().collect();
+                    if !added.is_empty() {
+                        let _ = outcome.new_observers.insert(*identifier, added);
+                    }
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// A new event being observed.
+    ///     * take all the childless units as parents, merging every branch.
+    ///     * if any of their ancestors already holds the incoming event, i.e. others observed it
+    ///       and notified, we shall only be inserted into that unit as an observer.
+    ///     * otherwise, create a new unit and insert into graph.
+    /// Our observation is signed with `keys`. Returns the identifier of the unit holding the
+    /// payload.
+    pub fn new_payload(&mut self, payload: T, keys: &Keypair) -> Result<UnitId, Error> {
+        let own_id = Id::from(keys.public);
+        let parents = self.new_parents()?;
+        self.insert_observed(parents, payload, None, BTreeMap::new(), &own_id, Some(keys))
+    }
+
+    /// As `new_payload`, attaching `metadata` to the unit. A unit already holding the payload has
+    /// the metadata merged into its own, as per `Unit::merge_metadata`.
+    pub fn new_payload_with_metadata(
+        &mut self,
+        payload: T,
+        metadata: BTreeMap<String, Vec<u8>>,
+        keys: &Keypair,
+    ) -> Result<UnitId, Error> {
+        let own_id = Id::from(keys.public);
+        let parents = self.new_parents()?;
+        self.insert_observed(parents, payload, None, metadata, &own_id, Some(keys))
+    }
+
+    /// As `new_payload` for each of the payloads in turn, but chaining them: the tips only get
+    /// merged by the first new unit, and every following one has the previous one as its single
+    /// parent. Returns the identifiers of the units holding the payloads, in order. Fails at the
+    /// first payload which can't be added, those before it having been added.
+    pub fn new_payloads(&mut self, payloads: Vec<T>, keys: &Keypair) -> Result<Vec<UnitId>, Error> {
+        let own_id = Id::from(keys.public);
+        let mut parents = self.new_parents()?;
+        let mut identifiers = Vec::with_capacity(payloads.len());
+        for payload in payloads {
+            let identifier = self.insert_observed(
+                parents.clone(),
+                payload,
+                None,
+                BTreeMap::new(),
+                &own_id,
+                Some(keys),
+            )?;
+            // A payload already held by an ancestor doesn't extend the chain.
+            if self.tips.contains(&identifier) {
+                parents.clear();
+                let _ = parents.insert(identifier);
+            }
+            identifiers.push(identifier);
+        }
+        Ok(identifiers)
+    }
+
+    /// As `new_payload`, tagging the unit with the payload's content type. A unit already holding
+    /// the payload keeps the content type it has, if any.
+    pub fn new_typed_payload(
+        &mut self,
+        payload: T,
+        content_type: ContentType,
+        keys: &Keypair,
+    ) -> Result<UnitId, Error> {
+        let own_id = Id::from(keys.public);
+        let parents = self.new_parents()?;
+        self.insert_observed(
+            parents,
+            payload,
+            Some(content_type),
+            BTreeMap::new(),
+            &own_id,
+            Some(keys),
+        )
+    }
+
+    /// Our own signed observation of the unit, if we observed it.
+    pub fn attestation(&self, identifier: &UnitId, own_id: &Id) -> Option<Attestation> {
+        self.units.get(identifier).and_then(|unit| {
+            unit.signatures.get(own_id).map(|signature| {
+                Attestation {
+                    identifier: *identifier,
+                    observer: *own_id,
+                    signature: signature.clone(),
+                }
+            })
+        })
+    }
+
+    /// The evidence of who acknowledged the unit, with the authorised members as the membership
+    /// snapshot, if the unit is held and stable.
+    pub fn receipt(&self, identifier: &UnitId) -> Option<Receipt<T>> {
+        let unit = match self.units.get(identifier) {
+            Some(unit) if self.is_stable(unit) => unit,
+            _ => return None,
+        };
+        let threshold = self.policy.threshold(self.majority as usize, self.members);
+        Some(Receipt::new(unit, self.authorised.clone(), &self.policy, threshold))
+    }
+
+    /// Set whether units get certified as they become stable, unless already certified by a peer.
+    /// Local setting only, off by default. Certificates are passed on to peers along with the
+    /// units.
+    pub fn set_certifying(&mut self, certifying: bool) {
+        self.certifying = certifying;
+    }
+
+    /// The certificate of the unit, as attached by the node which first noticed it became stable,
+    /// if it is held and certified.
+    pub fn certificate(&self, identifier: &UnitId) -> Option<&QuorumCertificate> {
+        self.units.get(identifier).and_then(|unit| unit.certificate.as_ref())
+    }
+
+    /// Whether the unit is certified stable: it carries a certificate whose signatures are all
+    /// valid, by signers meeting our stability policy, among the authorised members if set.
+    /// Doesn't count the observers of the unit.
+    pub fn is_certified(&self, identifier: &UnitId) -> bool {
+        let unit = match self.units.get(identifier) {
+            Some(unit) => unit,
+            None => return false,
+        };
+        let certificate = match unit.certificate {
+            Some(ref certificate) if certificate.verify(unit) => certificate,
+            _ => return false,
+        };
+        let signers: BTreeSet<Id> = match self.authorised {
+            Some(ref authorised) => {
+                certificate.signers().intersection(authorised).cloned().collect()
+            }
+            None => certificate.signers(),
+        };
+        self.policy.is_met(&signers, self.majority as usize, self.members)
+    }
+
+    /// Apply an attestation received from a peer. Returns whether it added a new observer: the
+    /// unit must be held, the signature valid, and the observer neither retired nor attesting a
+    /// unit older than its last admission, as per `admit_member`.
+    pub fn apply_attestation(&mut self, attestation: &Attestation) -> bool {
+        let current = match self.units.get(&attestation.identifier) {
+            Some(unit) => self.is_current_observation(&attestation.observer, unit),
+            None => return false,
+        };
+        if !current {
+            debug!(
+                "Dropping attestation of {:?} by {:?} from an earlier epoch",
+                attestation.identifier,
+                attestation.observer
+            );
+            return false;
+        }
+        let (before, added) = match self.units.get_mut(&attestation.identifier) {
+            Some(unit) => {
+                if unit.observers.contains(&attestation.observer) {
+                    return false;
+                }
+                let before = unit.observers.clone();
+                (
+                    before,
+                    unit.add_attested_observer(&attestation.observer, &attestation.signature),
+                )
+            }
+            None => return false,
+        };
+        if added {
+            self.record_change(&attestation.identifier, Some(before));
+        }
+        added
+    }
+
+    /// Import the events of a foreign event graph, attaching parentless events to our genesis.
+    /// `map_creator` gives the `Id` under which an event's creator is recorded as observer.
+    /// Events may be given in any order, but all their parents must be among them.
+    /// Imported observers carry no signature, so they are dropped by peers receiving them.
+    pub fn import<E, F>(&mut self, events: &[E], map_creator: F) -> Result<(), Error>
+    where
+        E: ForeignEvent,
+        F: Fn(&E::Creator) -> Id,
+        T: From<Vec<u8>>,
+    {
+        // Foreign hash to the identifier of the unit holding that event's payload.
+        let mut imported: BTreeMap<Vec<u8>, UnitId> = BTreeMap::new();
+        let mut pending: Vec<&E> = events.iter().collect();
+        while !pending.is_empty() {
+            let mut still_pending = Vec::new();
+            for event in &pending {
+                let parents = event.parents();
+                if parents.iter().any(|parent| !imported.contains_key(parent)) {
+                    still_pending.push(*event);
+                    continue;
+                }
+                let mut unit_parents: BTreeSet<UnitId> =
+                    parents.iter().map(|parent| imported[parent]).collect();
+                if unit_parents.is_empty() {
+                    let _ = unit_parents.insert(self.genesis.identifier);
+                }
+                let identifier = self.insert_observed(
+                    unit_parents,
+                    event.payload().into(),
+                    None,
+                    BTreeMap::new(),
+                    &map_creator(event.creator()),
+                    None,
+                )?;
+                let _ = imported.insert(event.hash(), identifier);
+            }
+            if still_pending.len() == pending.len() {
+                let missing = still_pending[0]
+                    .parents()
+                    .into_iter()
+                    .find(|parent| !imported.contains_key(parent))
+                    .unwrap_or_default();
+                return Err(Error::MissingForeignParent(missing));
+            }
+            pending = still_pending;
+        }
+        Ok(())
+    }
+
+    // Record `observer` as having observed `payload` on top of `parents`, signing the observation
+    // if its `keys` are given, tagging the unit with `content_type` if it has none and merging
+    // `metadata` into its own:
+    //     * if the payload was already observed along the paths, e.g. seen by others first, we
+    //       shall only be inserted into that unit as an observer.
+    //     * otherwise, create a new unit and insert into graph.
+    // Returns the identifier of the unit holding the payload.
+    fn insert_observed(
+        &mut self,
+        parents: BTreeSet<UnitId>,
+        payload: T,
+        content_type: Option<ContentType>,
+        metadata: BTreeMap<String, Vec<u8>>,
+        observer: &Id,
+        keys: Option<&Keypair>,
+    ) -> Result<UnitId, Error> {
+        if let Some(max) = self.config.max_payload_size {
+            let size = serialisation::serialise(&payload)?.len();
+            if size > max {
+                return Err(Error::PayloadTooLarge(size));
+            }
+        }
+        let observed = self.has_observed_in(&parents, &payload, true).or_else(|| {
+            self.duplicate_of(&payload)
+        });
+        if let Some(observed) = observed {
+            if let Some(unit) = self.units.get_mut(&observed) {
+                let before = unit.observers.clone();
+                if unit.content_type.is_none() {
+                    unit.content_type = content_type;
+                }
+                unit.merge_metadata(&metadata);
+                observe(unit, observer, keys);
+                self.record_change(&observed, Some(before));
+            }
+            return Ok(observed);
+        }
+
+        if let Some(max) = self.config.max_units {
+            self.make_room(1, max)?;
+        }
+        let mut unit = {
+            let mut parent_units = Vec::new();
+            for parent in &parents {
+                match self.units.get(parent) {
+                    Some(parent) => parent_units.push(parent),
+                    None => return Err(Error::MissingParent(*parent)),
+                }
+            }
+            Unit::new_with(&*self.hasher, &parent_units, payload, BTreeSet::new())?
+        };
+        unit.content_type = content_type;
+        unit.metadata = metadata;
+        unit.clock = cmp::max(unit.clock, self.clock.saturating_add(1));
+        self.clock = unit.clock;
+        observe(&mut unit, observer, keys);
+
+        let identifier = unit.identifier;
+        let before = self.units.get(&identifier).map(|unit| unit.observers.clone());
+        if let Some(existing) = self.units.get_mut(&identifier) {
+            let _ = existing.union(&unit);
+        }
+        if before.is_none() {
+            self.insert_unit(unit);
+        }
+        self.record_change(&identifier, before);
+        Ok(identifier)
+    }
+
+    // Insert a unit not held yet, updating the indices.
+    fn insert_unit(&mut self, unit: Unit<T>) {
+        let identifier = unit.identifier;
+        self.index_unit(unit);
+        self.refresh_depths(identifier);
+    }
+
+    // Insert a unit not held yet, indexing it as a child of its parents and as a tip if childless.
+    fn index_unit(&mut self, unit: Unit<T>) {
+        let slot = self.links.intern(unit.identifier);
+        if unit.identifier != self.genesis.identifier {
+            for parent in &unit.parents {
+                let parent_slot = self.links.intern(*parent);
+                let _ = self.links.add_child(parent_slot, slot);
+                let _ = self.tips.remove(parent);
+            }
+        }
+        if !self.links.has_children(&unit.identifier) {
+            let _ = self.tips.insert(unit.identifier);
+        }
+        if let Ok(encoded) = unit.encoded_payload() {
+            let _ = self.payloads.entry(sha3_256(&encoded)).or_insert(unit.identifier);
+        }
+        let identifier = unit.identifier;
+        let _ = self.units.insert(identifier, unit);
+        self.digest.set(None);
+        self.index_conflicts(&identifier);
+    }
+
+    // Record which of the unit and the units it conflicts with lose, as per the payload policy.
+    // Only the units sharing its conflict key are compared against it.
+    fn index_conflicts(&mut self, identifier: &UnitId) {
+        let (key, superseded) = match (self.payload_policy.as_ref(), self.units.get(identifier)) {
+            (Some(policy), Some(unit)) => {
+                let key = policy.conflict_key(&unit.payload);
+                let superseded: Vec<UnitId> = self.conflict_units(&key)
+                    .into_iter()
+                    .filter(|other| other.payload != unit.payload)
+                    .filter_map(|other| if self.wins_over(&other.payload, &unit.payload) {
+                        Some(unit.identifier)
+                    } else if self.wins_over(&unit.payload, &other.payload) {
+                        Some(other.identifier)
+                    } else {
+                        None
+                    })
+                    .collect();
+                (key, superseded)
+            }
+            _ => return,
+        };
+        self.superseded.extend(superseded);
+        let _ = self.conflict_keys
+            .entry(key)
+            .or_insert_with(BTreeSet::new)
+            .insert(*identifier);
+    }
+
+    // Remove a unit dropped from the conflict keys.
+    fn unindex_conflicts(&mut self, unit: &Unit<T>) {
+        let key = match self.payload_policy {
+            Some(ref policy) => policy.conflict_key(&unit.payload),
+            None => return,
+        };
+        let now_empty = match self.conflict_keys.get_mut(&key) {
+            Some(identifiers) => {
+                let _ = identifiers.remove(&unit.identifier);
+                identifiers.is_empty()
+            }
+            None => false,
+        };
+        if now_empty {
+            let _ = self.conflict_keys.remove(&key);
+        }
+    }
+
+    // The units held whose payload has the conflict key `key`.
+    fn conflict_units(&self, key: &[u8]) -> Vec<&Unit<T>> {
+        self.conflict_keys
+            .get(key)
+            .into_iter()
+            .flat_map(|identifiers| identifiers.iter())
+            .filter_map(|identifier| self.units.get(identifier))
+            .collect()
+    }
+
+    // Recompute the superseded units from scratch, once the payload policy changed.
+    fn rebuild_superseded(&mut self) {
+        self.superseded.clear();
+        self.conflict_keys.clear();
+        let identifiers: Vec<UnitId> = self.units.keys().cloned().collect();
+        for identifier in &identifiers {
+            self.index_conflicts(identifier);
+        }
+    }
+
+    // Forget the superseded units dropped, and those only superseded by units dropped.
+    fn refresh_superseded(&mut self) {
+        self.superseded = self.superseded
+            .iter()
+            .filter(|identifier| match (self.payload_policy.as_ref(), self.units.get(identifier)) {
+                (Some(policy), Some(unit)) => {
+                    let key = policy.conflict_key(&unit.payload);
+                    self.conflict_units(&key).into_iter().any(|other| {
+                        other.payload != unit.payload &&
+                            self.wins_over(&other.payload, &unit.payload)
+                    })
+                }
+                _ => false,
+            })
+            .cloned()
+            .collect();
+    }
+
+    // Record the events caused by a change to the unit, which had `observers_before` as observers
+    // before the change, or didn't exist if `None`.
+    // Also journals the change if journaling.
+    fn record_change(&mut self, identifier: &UnitId, observers_before: Option<BTreeSet<Id>>) {
+        self.digest.set(None);
+        let stable = match self.units.get(identifier) {
+            Some(unit) => self.is_stable(unit),
+            None => return,
+        };
+        let was_stable = observers_before.as_ref().map_or(false, |before| {
+            self.policy.is_met(before, self.majority as usize, self.members)
+        });
+        if stable && !was_stable && self.certifying {
+            if let Some(unit) = self.units.get_mut(identifier) {
+                if unit.certificate.is_none() {
+                    unit.certify();
+                }
+            }
+        }
+        if self.journaling {
+            self.journal_change(identifier, observers_before.as_ref());
+        }
+        if !self.events_enabled {
+            return;
+        }
+        if observers_before.is_none() {
+            if let Some(unit) = self.units.get(identifier) {
+                self.events.push(Event::NewUnit {
+                    identifier: *identifier,
+                    payload: unit.payload.clone(),
+                });
+            }
+        }
+        if stable && !was_stable {
+            self.events.push(Event::UnitStable { identifier: *identifier });
+        }
+    }
+
+    // Record the change to the unit, which had `observers_before` as observers before the change,
+    // as a journal entry.
+    fn journal_change(&mut self, identifier: &UnitId, observers_before: Option<&BTreeSet<Id>>) {
+        let unit = match self.units.get(identifier) {
+            Some(unit) => unit,
+            None => return,
+        };
+        let before = match observers_before {
+            Some(before) => before,
+            None => {
+                self.journal.push(JournalEntry::Unit(unit.clone()));
+                return;
+            }
+        };
+        let observers: BTreeSet<Id> = unit.observers.difference(before).cloned().collect();
+        if observers.is_empty() {
+            return;
+        }
+        let signatures = unit.signatures
+            .iter()
+            .filter(|&(observer, _)| observers.contains(observer))
+            .map(|(observer, signature)| (*observer, signature.clone()))
+            .collect();
+        self.journal.push(JournalEntry::Observers {
+            identifier: *identifier,
+            observers,
+            signatures,
+        });
+    }
+
+    /// Whether any unit holds the given payload, e.g. a `&[u8]` for a `Dag<Vec<u8>>`.
+    pub fn contains_payload<Q: ?Sized + Eq>(&self, payload: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.units.values().any(|unit| unit.payload.borrow() == payload)
+    }
+
+    /// The units other than the genesis in their total order: by depth along the path from the
+    /// genesis, then by clock, then by identifier. Any two nodes holding the same units order them
+    /// identically.
+    pub fn ordered_units(&self) -> Vec<&Unit<T>> {
+        let mut units: Vec<(usize, &Unit<T>)> = self.units
+            .values()
+            .filter(|unit| unit.identifier != self.genesis.identifier)
+            .map(|unit| (self.depth_of(unit), unit))
+            .collect();
+        units.sort_by(|lhs, rhs| {
+            (lhs.0, lhs.1.clock, &lhs.1.identifier).cmp(&(rhs.0, rhs.1.clock, &rhs.1.identifier))
+        });
+        units.into_iter().map(|(_, unit)| unit).collect()
+    }
+
+    /// The payloads of the stable units which aren't superseded, in total order. Identical across
+    /// all nodes once their DAGs converge.
+    pub fn stable_payloads(&self) -> Vec<T> {
+        self.ordered_units()
+            .into_iter()
+            .filter(|unit| self.is_stable(unit) && !self.is_superseded(unit))
+            .map(|unit| unit.payload.clone())
+            .collect()
+    }
+
+    /// Whether the unit is stable as per the stability policy.
+    pub fn is_stable(&self, unit: &Unit<T>) -> bool {
+        self.policy.is_met(&unit.observers, self.majority as usize, self.members)
+    }
+
+    /// Whether every unit held is stable.
+    pub fn all_stable(&self) -> bool {
+        self.units.values().all(|unit| self.is_stable(unit))
+    }
+
+    /// Number of stable units held.
+    pub fn stable_count(&self) -> usize {
+        self.units.values().filter(|unit| self.is_stable(unit)).count()
+    }
+
+    /// Rebuild the indices kept alongside the units from the units themselves.
+    pub fn rebuild_indices(&mut self) {
+        let units = self.units.clone();
+        self.reset_units(units);
+    }
+
+    /// Breakdown of the held units by depth and payload size.
+    pub fn unit_breakdown(&self) -> UnitBreakdown {
+        let mut breakdown = UnitBreakdown::default();
+        for unit in self.units.values() {
+            let size = unit.encoded_payload().map(|encoded| encoded.len()).unwrap_or(0);
+            breakdown.add_unit(self.depth_of(unit), size);
+        }
+        breakdown
+    }
+
+    // Number of hops along the longest path from the unit back to the genesis, or to an ancestor
+    // whose parents aren't held.
+    fn depth_of(&self, unit: &Unit<T>) -> usize {
+        self.links.depth_of(&unit.identifier).unwrap_or(0)
     }
 
-    /// Union with the other DAG.
-    ///     * If don't know a unit from other, insert it into graph.
-    ///     * If already know a unit, union the units.
-    pub fn union(&mut self, other: &Dag) {
-        for (identifier, other_unit) in &other.units {
-            if let Some(unit) = self.units.get_mut(identifier) {
-                // If already see the unit, union these two.
-                unit.union(other_unit);
-                continue;
+    // Travel breadth first along the paths started from the input units, to find out whether the
+    // payload has been observed before. If so, return the identifier of the unit holds such
+    // payload. Each unit is visited once, so the search ends even if the paths loop.
+    // If `resolve_conflicts`, a unit holding a payload conflicting with `payload` and winning over
+    // it counts as holding it.
+    // Units are marked as visited by slot, the identifiers without any, which can't be held, being
+    // only counted towards the depth limit.
+    fn has_observed_in(
+        &self,
+        starts: &BTreeSet<UnitId>,
+        payload: &T,
+        resolve_conflicts: bool,
+    ) -> Option<UnitId> {
+        let mut visited = vec![false; self.links.len()];
+        let mut unheld = BTreeSet::new();
+        let mut steps = 0;
+        let mut queue = VecDeque::new();
+        for identifier in starts {
+            steps += 1;
+            match self.links.slot(identifier) {
+                Some(slot) => {
+                    visited[slot] = true;
+                    queue.push_back(slot);
+                }
+                None => {
+                    let _ = unheld.insert(*identifier);
+                }
             }
-            // If already observed the payload along the path the other uint sits, only union
-            // the observers.
-            if let Some(identifier) = self.has_observed_in(
-                other_unit.parent.clone(),
-                &other_unit.payload,
-            )
+        }
+        while let Some(slot) = queue.pop_front() {
+            let unit = match self.units.get(&self.links.identifier(slot)) {
+                Some(unit) => unit,
+                None => continue,
+            };
+            if unit.payload == *payload ||
+                (resolve_conflicts && self.wins_over(&unit.payload, payload))
             {
-                if let Some(unit) = self.units.get_mut(&identifier) {
-                    unit.observers = unit.observers
-                        .union(&other_unit.observers)
-                        .cloned()
-                        .collect();
+                return Some(unit.identifier);
+            }
+            if self.depth_limit_reached(steps) {
+                trace!("Stopped searching for payload after {} units", steps);
+                break;
+            }
+            for parent in &unit.parents {
+                match self.links.slot(parent) {
+                    Some(slot) => {
+                        if !visited[slot] {
+                            visited[slot] = true;
+                            steps += 1;
+                            queue.push_back(slot);
+                        }
+                    }
+                    None => {
+                        if unheld.insert(*parent) {
+                            steps += 1;
+                        }
+                    }
                 }
-            } else {
-                let _ = self.units.insert(
-                    other_unit.identifier.clone(),
-                    other_unit.clone(),
-                );
             }
         }
-        // Only merge a child in when it was not a child to us yet.
-        let mut our_known_children = BTreeSet::new();
-        for unit in self.units.values() {
-            our_known_children = our_known_children.union(&unit.children).cloned().collect();
-        }
-        let mut children_to_union = BTreeMap::new();
-        for (k, v) in &other.units {
-            let non_exist_children = v.children
-                .intersection(&our_known_children)
-                .cloned()
-                .collect();
-            let _ = children_to_union.insert(k.clone(), non_exist_children);
+        None
+    }
+
+    // The unit holding the payload anywhere in the DAG, if merging duplicates.
+    fn duplicate_of(&self, payload: &T) -> Option<UnitId> {
+        if self.config.duplicates != Duplicates::Merge {
+            return None;
         }
-        for (identifier, children) in children_to_union {
-            if let Some(unit) = self.units.get_mut(&identifier) {
-                unit.children = unit.children.union(&children).cloned().collect();
-            }
+        match serialisation::serialise(payload) {
+            Ok(encoded) => self.payloads.get(&sha3_256(&encoded)).cloned(),
+            Err(_) => None,
         }
     }
 
-    /// A new event being observed.
-    ///     * pickup a best parent
-    ///     * if the best parent is alread the incoming event, i.e. others observed it and notified,
-    ///       we shall only be inserted into that unit as an observer.
-    ///     * otherwise, create a new unit and insert into graph.
-    pub fn new_payload(&mut self, payload: Vec<u8>, own_id: &Id) {
-        let mut observers = BTreeSet::new();
-        let _ = observers.insert(*own_id);
-        let parent = self.get_best_parent(own_id);
-
-        // In case the parent is regarding the same event but be seen by others first
-        // we shall only add us as an observer to it
-        if let Some(observed) = self.has_observed_in(parent.identifier.clone(), &payload) {
-            if let Some(unit) = self.units.get_mut(&observed) {
-                unit.add_observer(own_id);
-                return;
+    // Check the units of `other` we don't hold yet against the bounds of the config, counting the
+    // stable units which could be evicted to make room for them. Returns the number of units of
+    // `other` we don't hold.
+    fn check_limits(&self, other: &Dag<T>) -> Result<usize, Error> {
+        let incoming: Vec<&Unit<T>> = other
+            .units
+            .values()
+            .filter(|unit| !self.units.contains_key(&unit.identifier))
+            .collect();
+        if let Some(max) = self.config.max_payload_size {
+            for unit in &incoming {
+                let size = unit.encoded_payload()?.len();
+                if size > max {
+                    return Err(Error::PayloadTooLarge(size));
+                }
+            }
+        }
+        if let Some(max) = self.config.max_units {
+            let evictable = if self.config.eviction == Eviction::OldestStable {
+                self.stable_chain(None).0.len()
             } else {
-                panic!("just find a best parent but cann't fetch it from graph");
+                0
+            };
+            if self.units.len().saturating_sub(evictable).saturating_add(incoming.len()) > max {
+                return Err(Error::CapacityExceeded(max));
             }
         }
+        Ok(incoming.len())
+    }
 
-        let unit = Unit::new(parent.clone(), payload, observers);
-        if let Some(parent) = self.units.get_mut(&parent.identifier) {
-            parent.add_child(unit.identifier.clone());
+    // Evict stable units as per the config, if need be, for `incoming` more units to fit within
+    // `max` units.
+    fn make_room(&mut self, incoming: usize, max: usize) -> Result<(), Error> {
+        if self.units.len().saturating_add(incoming) <= max {
+            return Ok(());
+        }
+        if self.config.eviction == Eviction::OldestStable {
+            let count = self.checkpoint();
+            if count > 0 && self.events_enabled {
+                self.events.push(Event::Evicted { count });
+            }
+        }
+        if self.units.len().saturating_add(incoming) <= max {
+            Ok(())
         } else {
-            panic!("just find a best parent but cann't fetch it from graph");
+            Err(Error::CapacityExceeded(max))
         }
+    }
 
-        self.units
-            .entry(unit.identifier.clone())
-            .or_insert_with(|| unit.clone())
-            .union(&unit);
+    // Whether `held` conflicts with `payload` and wins over it, as per the payload policy.
+    fn wins_over(&self, held: &T, payload: &T) -> bool {
+        self.payload_policy.as_ref().map_or(false, |policy| {
+            policy.conflicts(held, payload) && *policy.resolve(held, payload) == *held
+        })
     }
 
-    // Travel along the path started from the input tip, to find out whether the payload has been
-    // observed before. If so, return the identifier of the unit holds such payload.
-    fn has_observed_in(&self, tip: Vec<u8>, payload: &[u8]) -> Option<Vec<u8>> {
-        let mut iterator = tip;
-        let mut steps = 0;
-        while let Some(unit) = self.units.get(&iterator) {
-            if unit.payload == payload.to_vec() {
-                return Some(unit.identifier.clone());
-            }
-            if unit.identifier == self.genesis.identifier {
-                break;
-            }
-            steps += 1;
-            if steps > self.units.len() {
-                for unit in self.units.values() {
-                    println!("{:?}", unit);
-                }
-                panic!("breaking as experiencing a cycle in {:?}", self);
-            }
-            iterator = unit.parent.clone();
+    // Whether a traversal which has already taken `steps` steps shall stop.
+    fn depth_limit_reached(&self, steps: usize) -> bool {
+        self.max_traversal_depth.map_or(false, |max| steps >= max)
+    }
+
+    // The parents of a new unit: all the childless units, so that the new unit merges every
+    // branch known to us. Those lazily validated are checked first.
+    fn new_parents(&mut self) -> Result<BTreeSet<UnitId>, Error> {
+        let tips = self.tips.clone();
+        let _ = self.check_paths(&tips);
+        if self.tips.is_empty() {
+            return Err(Error::NoTips);
         }
-        None
+        Ok(self.tips.clone())
     }
 
-    // The parent shall be a clildless unit, and:
-    //  * any if a stable unit, otherwise:
-    //  * having the longest length of stable units along the path back to the GENESIS
-    //  * if still multiple, choose the ones self observed
-    //  * if still multiple, choose the ones having the majority votes
-    //  * if still multiple, choose the one by its name order.
-    fn get_best_parent(&self, own_id: &Id) -> Unit {
-        // Pick up childless units
-        let childless: Vec<Unit> = self.units
-            .values()
-            .filter_map(|unit| if unit.children.is_empty() {
-                Some(unit.clone())
-            } else {
-                None
+    // Check the lazily validated units which are stable, and their ancestors.
+    fn check_stable(&mut self) -> Vec<UnitId> {
+        let stable: BTreeSet<UnitId> = self.unchecked
+            .iter()
+            .filter(|identifier| {
+                self.units.get(identifier).map_or(
+                    false,
+                    |unit| self.is_stable(unit),
+                )
             })
+            .cloned()
             .collect();
-        if childless.len() == 1 {
-            return childless[0].clone();
-        }
+        self.check_paths(&stable)
+    }
 
-        // Find the childless stable unit
-        let stable_childless: Vec<Unit> = childless
+    // Check the lazily validated units among `starts` and their ancestors, dropping those whose
+    // identifier doesn't match their content along with their descendants. Returns the identifiers
+    // of the units found invalid.
+    fn check_paths(&mut self, starts: &BTreeSet<UnitId>) -> Vec<UnitId> {
+        let mut stack: Vec<UnitId> = starts
             .iter()
-            .filter_map(|unit| if unit.observers.len() as u8 >= self.majority {
-                Some(unit.clone())
-            } else {
-                None
-            })
+            .filter(|identifier| self.unchecked.contains(identifier))
+            .cloned()
             .collect();
-        if stable_childless.len() == 1 {
-            return stable_childless[0].clone();
-        }
-
-        // Travel along the path from the childless unit to the gensis to collect the scores
-        // The score is so far defined as :
-        //      (the length of the path, number of stable unit alongs the path)
-        let mut path_counters = BTreeMap::new();
-        for child in &childless {
-            let mut stats = (0, 0);
-            let mut iterator = &child.parent;
-            while let Some(parent) = self.units.get(iterator) {
-                stats.0 += 1;
-                // The unit is stable
-                if parent.observers.len() as u8 >= self.majority {
-                    stats.1 += 1;
-                }
-                // Reached the genesis.
-                if parent.identifier == self.genesis.identifier {
-                    break;
+        let mut invalid = Vec::new();
+        while let Some(identifier) = stack.pop() {
+            if !self.unchecked.remove(&identifier) {
+                continue;
+            }
+            let valid = match self.units.get(&identifier) {
+                Some(unit) => {
+                    stack.extend(unit.parents.iter().filter(|parent| {
+                        self.unchecked.contains(parent)
+                    }));
+                    self.has_valid_identifier(unit)
+                }
+                None => true,
+            };
+            if !valid {
+                warn!("Dropping lazily validated unit {:?}, not matching its content", identifier);
+                invalid.push(identifier);
+            }
+        }
+        if !invalid.is_empty() {
+            self.remove_with_descendants(&invalid);
+        }
+        invalid
+    }
+
+    /// Check the invariants this DAG upholds, e.g. after arbitrary sequences of `union` in property
+    /// tests or fuzzing harnesses:
+    ///     * the genesis is held, and every other unit has at least one parent, all of which are
+    ///       held. After a checkpoint, the genesis is the unit the pruned prefix was collapsed
+    ///       into, and its parents aren't held anymore.
+    ///     * no unit is its own ancestor.
+    ///     * the index of the children of each unit matches the units having it as parent.
+    ///     * every identifier matches its unit's content, but for the units not validated yet
+    ///       under `Validation::Lazy`.
+    ///     * every observer is among the authorised members, if set.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let genesis = self.genesis.identifier;
+        if !self.units.contains_key(&genesis) {
+            return Err(InvariantViolation::MissingGenesis(genesis));
+        }
+        for unit in self.units.values() {
+            if unit.identifier != genesis {
+                if unit.parents.is_empty() {
+                    return Err(InvariantViolation::NoParent(unit.identifier));
+                }
+                for parent in &unit.parents {
+                    if !self.units.contains_key(parent) {
+                        return Err(InvariantViolation::MissingParent {
+                            unit: unit.identifier,
+                            parent: *parent,
+                        });
+                    }
+                    if !self.links.children_of(parent).contains(&unit.identifier) {
+                        return Err(InvariantViolation::ChildrenMismatch(*parent));
+                    }
+                }
+                if !self.unchecked.contains(&unit.identifier) && !self.has_valid_identifier(unit) {
+                    return Err(InvariantViolation::InvalidIdentifier(unit.identifier));
                 }
-                iterator = &parent.parent;
             }
-            let _ = path_counters.insert(child.identifier.clone(), stats);
+            if let Some(ref authorised) = self.authorised {
+                if let Some(observer) = unit.observers.difference(authorised).next() {
+                    return Err(InvariantViolation::UnauthorisedObserver {
+                        unit: unit.identifier,
+                        observer: *observer,
+                    });
+                }
+            }
+        }
+        for (parent, children) in self.links.links() {
+            let indexed_correctly = children.iter().all(|child| {
+                self.units.get(child).map_or(false, |child| child.parents.contains(&parent))
+            });
+            if !indexed_correctly {
+                return Err(InvariantViolation::ChildrenMismatch(parent));
+            }
         }
+        self.check_acyclic()
+    }
 
-        // Pick the childless units who have the most stable units along it.
-        let mut max = 0;
-        let mut max_childless: Vec<(u8, Unit)> = Vec::new();
-        for child in &childless {
-            if let Some(counters) = path_counters.get(&child.identifier) {
-                if counters.1 == max {
-                    max_childless.push((counters.0, child.clone()));
-                } else if counters.1 > max {
-                    max = counters.1;
-                    max_childless.clear();
-                    max_childless.push((counters.0, child.clone()));
+    // Fail with a unit which is its own ancestor, if any. Walks depth first along the parents,
+    // keeping the units on the path to the current one, each with the parents still to be walked.
+    fn check_acyclic(&self) -> Result<(), InvariantViolation> {
+        let mut done = BTreeSet::new();
+        for unit in self.units.values() {
+            if done.contains(&unit.identifier) {
+                continue;
+            }
+            let mut path: Vec<(UnitId, Vec<UnitId>)> =
+                vec![(unit.identifier, unit.parents.iter().cloned().collect())];
+            let mut on_path = BTreeSet::new();
+            let _ = on_path.insert(unit.identifier);
+            loop {
+                let (current, next) = match path.last_mut() {
+                    Some(&mut (current, ref mut parents)) => (current, parents.pop()),
+                    None => break,
+                };
+                let parent = match next {
+                    Some(parent) => parent,
+                    None => {
+                        let _ = path.pop();
+                        let _ = on_path.remove(&current);
+                        let _ = done.insert(current);
+                        continue;
+                    }
+                };
+                if on_path.contains(&parent) {
+                    return Err(InvariantViolation::Cycle(parent));
+                }
+                if done.contains(&parent) {
+                    continue;
+                }
+                if let Some(parent_unit) = self.units.get(&parent) {
+                    path.push((parent, parent_unit.parents.iter().cloned().collect()));
+                    let _ = on_path.insert(parent);
                 }
             }
         }
-        if max_childless.len() == 1 {
-            return max_childless[0].1.clone();
+        Ok(())
+    }
+
+    // Whether the unit's identifier matches its content. A unit whose parents aren't all held
+    // anymore can't be checked, and is assumed valid.
+    fn has_valid_identifier(&self, unit: &Unit<T>) -> bool {
+        if unit.identifier == self.genesis.identifier {
+            return true;
+        }
+        let mut parent_payloads = Vec::new();
+        for parent in &unit.parents {
+            match self.units.get(parent) {
+                Some(parent) => parent_payloads.push(&parent.payload),
+                None => return true,
+            }
         }
+        unit::compute_identifier_with(&*self.hasher, &parent_payloads, &unit.payload).ok() ==
+            Some(unit.identifier)
+    }
 
-        // Pick the candidates which self observed.
-        let self_observed_childless: Vec<Unit> = max_childless
+    // Drop the units along with all their descendants, rebuilding the indices.
+    fn remove_with_descendants(&mut self, identifiers: &[UnitId]) {
+        let mut units = self.units.clone();
+        let mut stack = identifiers.to_vec();
+        while let Some(identifier) = stack.pop() {
+            if units.remove(&identifier).is_some() {
+                stack.extend(self.children_of(&identifier));
+            }
+        }
+        self.unchecked = self.unchecked
             .iter()
-            .filter_map(|entry| if entry.1.observers.contains(own_id) {
-                Some(entry.1.clone())
-            } else {
-                None
-            })
+            .filter(|identifier| units.contains_key(identifier))
+            .cloned()
             .collect();
-        if self_observed_childless.len() == 1 {
-            return self_observed_childless[0].clone();
-        }
+        self.reset_units(units);
+    }
+}
 
-        // Pick the candidates which has the most observers.
-        let mut max_votes = 0;
-        let mut max_votes_childless = Vec::new();
-        for entry in &max_childless {
-            if entry.1.observers.len() == max_votes {
-                max_votes_childless.push(entry.1.clone());
-            } else if entry.1.observers.len() > max_votes {
-                max_votes = entry.1.observers.len();
-                max_votes_childless.clear();
-                max_votes_childless.push(entry.1.clone());
-            }
+// Look a unit up in `first`, then in `second`.
+fn find<'a, T>(
+    first: &'a BTreeMap<UnitId, Unit<T>>,
+    second: &'a BTreeMap<UnitId, Unit<T>>,
+    identifier: &UnitId,
+) -> Option<&'a Unit<T>> {
+    first.get(identifier).or_else(|| second.get(identifier))
+}
+
+// Append `value` to `bytes`, big-endian.
+fn push_u64(bytes: &mut Vec<u8>, value: u64) {
+    for shift in (0..8).rev() {
+        bytes.push((value >> (shift * 8)) as u8);
+    }
+}
+
+fn observe<T: Payload>(unit: &mut Unit<T>, observer: &Id, keys: Option<&Keypair>) {
+    match keys {
+        Some(keys) => unit.add_signed_observer(keys),
+        None => unit.add_observer(observer),
+    }
+}
+
+/// A breadth-first walk from a unit along either its parents, as by `Dag::ancestors`, or its
+/// children, as by `Dag::descendants`, yielding each unit reached once. Should the walk come back
+/// to the unit it started from, which a valid DAG never does, it yields `Error::CycleDetected`
+/// and ends.
+pub struct Traversal<'a, T: 'a> {
+    dag: &'a Dag<T>,
+    start: UnitId,
+    towards_parents: bool,
+    // The units still to visit, each with the unit it was reached from.
+    queue: VecDeque<(UnitId, UnitId)>,
+    // The units visited so far, each with the unit it was first reached from.
+    via: BTreeMap<UnitId, UnitId>,
+    // The units visited so far, in order, reported along with a cycle.
+    order: Vec<UnitId>,
+    done: bool,
+}
+
+impl<'a, T: Payload> Traversal<'a, T> {
+    fn new(dag: &'a Dag<T>, start: &UnitId, towards_parents: bool) -> Self {
+        let mut traversal = Traversal {
+            dag,
+            start: *start,
+            towards_parents,
+            queue: VecDeque::new(),
+            via: BTreeMap::new(),
+            order: Vec::new(),
+            done: false,
+        };
+        if let Some(unit) = dag.units.get(start) {
+            traversal.enqueue_next(unit);
         }
+        traversal
+    }
 
-        if let Some(best_parent) = max_votes_childless.pop() {
-            best_parent.clone()
+    fn enqueue_next(&mut self, unit: &Unit<T>) {
+        let next = if self.towards_parents {
+            unit.parents.clone()
         } else {
-            panic!("cannot find a best parent in the DAG of {:?} ", self);
+            self.dag.children_of(&unit.identifier)
+        };
+        self.queue.extend(next.into_iter().map(|next| (unit.identifier, next)));
+    }
+
+    // The path from the start to `identifier`, which has been visited.
+    fn path_to(&self, identifier: &UnitId) -> Vec<UnitId> {
+        let mut path = vec![*identifier];
+        let mut current = *identifier;
+        while let Some(&previous) = self.via.get(&current) {
+            path.push(previous);
+            current = previous;
+        }
+        path.reverse();
+        path
+    }
+}
+
+impl<'a, T: Payload> Iterator for Traversal<'a, T> {
+    type Item = Result<&'a Unit<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let dag = self.dag;
+        while let Some((previous, identifier)) = self.queue.pop_front() {
+            if identifier == self.start {
+                self.done = true;
+                let back_edge = if self.towards_parents {
+                    (previous, identifier)
+                } else {
+                    (identifier, previous)
+                };
+                let diagnostics = Diagnostics::new(self.order.clone(), back_edge);
+                return Some(Err(Error::CycleDetected(diagnostics)));
+            }
+            if self.via.contains_key(&identifier) {
+                continue;
+            }
+            let unit = match dag.units.get(&identifier) {
+                Some(unit) => unit,
+                None => continue,
+            };
+            let _ = self.via.insert(identifier, previous);
+            self.order.push(identifier);
+            self.enqueue_next(unit);
+            return Some(Ok(unit));
         }
+        None
     }
 }
 
-impl Debug for Dag {
+impl<T: Payload> Debug for Dag<T> {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         writeln!(
             formatter,
-            "DAG has majority of {} and contains graph of :",
-            self.majority
+            "DAG is stable with {} and contains graph of :",
+            self.policy.rule(self.majority as usize, self.members)
         )?;
         writeln!(formatter, "```graphviz")?;
         writeln!(formatter, "digraph hierarchy {{")?;
@@ -279,25 +2865,25 @@ impl Debug for Dag {
         let mut units_state = BTreeMap::new();
         for unit in self.units.values() {
             let graph_node_name = unit.graphviz();
-            if unit.observers.len() as u8 >= self.majority {
+            if self.is_stable(unit) {
                 writeln!(formatter, "    node [color=Black,fontname=Courier]")?;
-                let _ = units_state.insert(unit.identifier.clone(), (
+                let _ = units_state.insert(unit.identifier, (
                     "stable",
                     graph_node_name.clone(),
-                    unit.parent.clone(),
+                    unit.parents.clone(),
                 ));
             } else {
                 writeln!(formatter, "    node [color=Red,fontname=Courier]")?;
-                let _ = units_state.insert(unit.identifier.clone(), (
+                let _ = units_state.insert(unit.identifier, (
                     "unstable",
                     graph_node_name.clone(),
-                    unit.parent.clone(),
+                    unit.parents.clone(),
                 ));
             }
             writeln!(formatter, "    {}", graph_node_name)?;
         }
         for entry in units_state.values() {
-            if let Some(parent) = units_state.get(&entry.2) {
+            for parent in entry.2.iter().filter_map(|parent| units_state.get(parent)) {
                 if entry.0 == "stable" {
                     writeln!(formatter, "    edge [color=black, style=line]")?;
                 } else {
@@ -310,3 +2896,852 @@ impl Debug for Dag {
         writeln!(formatter, "```")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+    use std::iter;
+
+    // The attestation of the unit by the owner of `keys`.
+    fn attest(dag: &Dag<String>, identifier: &UnitId, keys: &Keypair) -> Attestation {
+        let mut unit = unwrap!(dag.get(identifier)).clone();
+        unit.add_signed_observer(keys);
+        let observer = Id::from(keys.public);
+        Attestation {
+            identifier: *identifier,
+            observer,
+            signature: unwrap!(unit.signatures.get(&observer)).clone(),
+        }
+    }
+
+    // The units of the DAG, with their parents and observers, to check it is left unchanged.
+    fn contents(dag: &Dag<String>) -> Vec<(UnitId, BTreeSet<UnitId>, BTreeSet<Id>)> {
+        dag.units
+            .values()
+            .map(|unit| (unit.identifier, unit.parents.clone(), unit.observers.clone()))
+            .collect()
+    }
+
+    // Our DAG, and a peer's holding one more unit, returned with it.
+    fn diverged(rng: &mut SeededRng) -> (Dag<String>, Dag<String>, UnitId) {
+        let keys = Keypair::generate::<Sha3_512>(rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let _ = unwrap!(dag.new_payload("ours".to_string(), &keys));
+        let mut other = dag.clone();
+        let identifier = unwrap!(other.new_payload("theirs".to_string(), &peer_keys));
+        (dag, other, identifier)
+    }
+
+    #[test]
+    fn admission_epochs() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer = Id::from(peer_keys.public);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        dag.set_majority(2);
+        let old = unwrap!(dag.new_payload("old".to_string(), &keys));
+        let replayed = attest(&dag, &old, &peer_keys);
+
+        // Until retired, the peer's attestation counts.
+        let mut control = dag.clone();
+        assert!(control.apply_attestation(&replayed));
+        assert!(control.is_stable(unwrap!(control.get(&old))));
+
+        dag.retire_member(peer);
+        assert!(!dag.apply_attestation(&replayed));
+        dag.admit_member(peer);
+        assert_eq!(dag.member_epoch(&peer), 1);
+
+        // Once re-admitted, its attestation from the earlier epoch is dropped, by itself or
+        // carried by a peer's DAG, but it observes new units again.
+        assert!(!dag.apply_attestation(&replayed));
+        let _ = unwrap!(dag.union(&control));
+        assert!(!unwrap!(dag.get(&old)).observers.contains(&peer));
+        assert!(!dag.is_stable(unwrap!(dag.get(&old))));
+        let new = unwrap!(dag.new_payload("new".to_string(), &keys));
+        let attestation = attest(&dag, &new, &peer_keys);
+        assert!(dag.apply_attestation(&attestation));
+        assert!(dag.is_stable(unwrap!(dag.get(&new))));
+    }
+
+    #[test]
+    fn validate_mismatched_identifier() {
+        let mut rng = SeededRng::new();
+        let (mut dag, mut other, identifier) = diverged(&mut rng);
+        let unit = unwrap!(other.units.remove(&identifier));
+        let _ = other.units.insert(UnitId([9; 32]), unit);
+        let before = contents(&dag);
+
+        match dag.validate(&other) {
+            Err(Error::InvalidUnit(invalid)) => assert_eq!(invalid, UnitId([9; 32])),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(dag.union(&other).is_err());
+        assert_eq!(contents(&dag), before);
+    }
+
+    #[test]
+    fn validate_missing_parent() {
+        let mut rng = SeededRng::new();
+        let (mut dag, mut other, identifier) = diverged(&mut rng);
+        let _ = unwrap!(other.units.get_mut(&identifier))
+            .parents
+            .insert(UnitId([9; 32]));
+        let before = contents(&dag);
+
+        match dag.validate(&other) {
+            Err(Error::MissingParent(parent)) => assert_eq!(parent, UnitId([9; 32])),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(dag.union(&other).is_err());
+        assert_eq!(contents(&dag), before);
+    }
+
+    #[test]
+    fn validate_genesis_mismatch() {
+        let mut rng = SeededRng::new();
+        let (mut dag, _, _) = diverged(&mut rng);
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut other: Dag<String> = Dag::new_with_genesis(b"other network", &keys);
+        let _ = unwrap!(other.new_payload("theirs".to_string(), &keys));
+        let before = contents(&dag);
+
+        match dag.validate(&other) {
+            Err(Error::GenesisMismatch) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        match dag.union(&other) {
+            Err(Error::GenesisMismatch) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(contents(&dag), before);
+    }
+
+    #[test]
+    fn validate_parent_cycle() {
+        let mut rng = SeededRng::new();
+        let (mut dag, mut other, identifier) = diverged(&mut rng);
+        // Identifiers aren't checked when validating lazily, so only the cycle gives the units
+        // away.
+        dag.set_validation(Validation::Lazy);
+        let unit = unwrap!(other.units.remove(&identifier));
+        let (first, second) = (UnitId([1; 32]), UnitId([2; 32]));
+        let links = [(first, second, unit.clock), (second, first, unit.clock + 1)];
+        for &(identifier, parent, clock) in &links {
+            let mut cyclic = unit.clone();
+            cyclic.identifier = identifier;
+            cyclic.parents = iter::once(parent).collect();
+            cyclic.clock = clock;
+            let _ = other.units.insert(identifier, cyclic);
+        }
+        let before = contents(&dag);
+
+        // As clocks increase from parents to children, the cycle is caught by the clock of the
+        // unit whose parent claims to be more recent.
+        match dag.validate(&other) {
+            Err(Error::InvalidUnit(invalid)) => assert_eq!(invalid, first),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(dag.union(&other).is_err());
+        assert_eq!(contents(&dag), before);
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let first = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let _ = unwrap!(dag.new_payload("second".to_string(), &keys));
+        let last = unwrap!(dag.new_payload("last".to_string(), &keys));
+        let original = dag.clone();
+
+        // Everything being stable, the chain collapses into its last unit.
+        assert_eq!(dag.checkpoint(), 3);
+        assert_eq!(dag.last_checkpoint().height, 3);
+        assert_eq!(dag.genesis().identifier, last);
+        assert_eq!(dag.len(), 1);
+        assert_eq!(dag.checkpoint(), 0);
+
+        // A peer behind us only has the units descending from our genesis merged in.
+        let mut behind = original.clone();
+        let theirs = unwrap!(behind.new_payload("theirs".to_string(), &peer_keys));
+        unwrap!(dag.union(&behind));
+        assert_eq!(dag.last_checkpoint().height, 3);
+        assert_eq!(dag.genesis().identifier, last);
+        assert!(dag.get(&first).is_none());
+        assert!(dag.get(&theirs).is_some());
+        assert_eq!(dag.len(), 2);
+
+        // A peer ahead of us has us fast forward to its checkpoint.
+        let mut lagging = original;
+        unwrap!(lagging.union(&dag));
+        assert_eq!(lagging.last_checkpoint(), dag.last_checkpoint());
+        assert_eq!(lagging.genesis().identifier, last);
+        assert!(lagging.get(&first).is_none());
+        assert!(lagging.get(&theirs).is_some());
+        assert_eq!(lagging.digest(), dag.digest());
+    }
+
+    #[test]
+    fn forged_checkpoints() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        dag.set_majority(2);
+        let _ = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let tip = unwrap!(dag.new_payload("tip".to_string(), &keys));
+        let before = dag.clone();
+        let unchanged = |dag: &Dag<String>| {
+            assert_eq!(dag.last_checkpoint(), before.last_checkpoint());
+            assert_eq!(dag.genesis().identifier, before.genesis().identifier);
+            assert_eq!(dag.len(), before.len());
+        };
+
+        // A DAG rooted at our unstable tip, claiming to have pruned everything before it.
+        let forge = |dag: &Dag<String>, height: usize, digest: Vec<u8>| {
+            let root = unwrap!(dag.get(&tip)).clone();
+            let units = iter::once((tip, root.clone())).collect();
+            let checkpoint = Checkpoint { height, digest };
+            Dag::from_units(units, root, dag.network, dag.majority, checkpoint)
+        };
+        let ahead = forge(&dag, 1, vec![]);
+        match dag.union(&ahead) {
+            Err(Error::UnverifiedCheckpoint(1)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        unchanged(&dag);
+
+        // Once the tip is stable, the checkpoint still has to match our own prefix.
+        dag.set_majority(0);
+        let mut pruned = dag.clone();
+        assert_eq!(pruned.checkpoint(), 2);
+        assert_eq!(pruned.genesis().identifier, tip);
+        let ahead = forge(&dag, 2, vec![0; 32]);
+        match dag.union(&ahead) {
+            Err(Error::UnverifiedCheckpoint(2)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        unchanged(&dag);
+        let ahead = forge(&dag, 3, pruned.last_checkpoint().digest.clone());
+        assert!(dag.union(&ahead).is_err());
+        unchanged(&dag);
+
+        // Nor is anything dropped if the DAG turns out invalid once fast forwarded on.
+        let mut invalid = pruned.clone();
+        let extra = unwrap!(invalid.new_payload("extra".to_string(), &keys));
+        unwrap!(invalid.units.get_mut(&extra)).parents = iter::once(UnitId([7; 32])).collect();
+        assert!(dag.union(&invalid).is_err());
+        unchanged(&dag);
+
+        unwrap!(dag.union(&pruned));
+        assert_eq!(dag.last_checkpoint(), pruned.last_checkpoint());
+        assert_eq!(dag.genesis().identifier, tip);
+    }
+
+    #[test]
+    fn unit_breakdown() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let _ = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let _ = unwrap!(dag.new_payload("second".to_string(), &keys));
+
+        let breakdown = dag.unit_breakdown();
+        let depths: Vec<(usize, usize)> = breakdown
+            .by_depth
+            .iter()
+            .map(|(depth, bucket)| (*depth, bucket.units))
+            .collect();
+        assert_eq!(depths, vec![(0, 1), (1, 1), (2, 1)]);
+        let units: usize = breakdown.by_payload_size.values().map(|bucket| bucket.units).sum();
+        assert_eq!(units, dag.len());
+        assert_eq!(breakdown.reclaimable_up_to(1).units, 2);
+    }
+
+    #[test]
+    fn traversal_depth_limit() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let old = unwrap!(dag.new_payload("old".to_string(), &keys));
+        for index in 0..3 {
+            let _ = unwrap!(dag.new_payload(index.to_string(), &keys));
+        }
+
+        // Without a limit, the payload is found along the path and observed again.
+        let mut unlimited = dag.clone();
+        assert_eq!(unwrap!(unlimited.new_payload("old".to_string(), &keys)), old);
+        assert_eq!(unlimited.len(), dag.len());
+
+        // Past the limit, it is treated as not found and held by a new unit.
+        dag.set_max_traversal_depth(Some(1));
+        assert_ne!(unwrap!(dag.new_payload("old".to_string(), &keys)), old);
+        assert_eq!(dag.len(), unlimited.len() + 1);
+    }
+
+    #[test]
+    fn union_preview() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer = Id::from(peer_keys.public);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let ours = unwrap!(dag.new_payload("ours".to_string(), &keys));
+        let mut other = dag.clone();
+        assert_eq!(unwrap!(other.new_payload("ours".to_string(), &peer_keys)), ours);
+        let theirs = unwrap!(other.new_payload("theirs".to_string(), &peer_keys));
+        let before = contents(&dag);
+
+        let outcome = unwrap!(dag.preview_union(&other));
+        assert_eq!(outcome.new_units, iter::once(theirs).collect());
+        let mut observers = BTreeMap::new();
+        let _ = observers.insert(ours, iter::once(peer).collect());
+        assert_eq!(outcome.new_observers, observers);
+        assert!(!outcome.is_empty());
+        assert!(unwrap!(other.preview_union(&other.clone())).is_empty());
+        assert_eq!(contents(&dag), before);
+
+        let foreign: Dag<String> = Dag::new_with_genesis(b"other network", &peer_keys);
+        match dag.preview_union(&foreign) {
+            Err(Error::GenesisMismatch) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn delta_since() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let observed = unwrap!(dag.new_payload("observed".to_string(), &keys));
+        let old = dag.clone();
+        let known = dag.unit_versions();
+        assert!(dag.delta_since(&known).is_empty());
+
+        // Only the new unit, and the one gaining an observer, are sent.
+        assert_eq!(unwrap!(dag.new_payload("observed".to_string(), &peer_keys)), observed);
+        let new = unwrap!(dag.new_payload("new".to_string(), &keys));
+        let delta = dag.delta_since(&known);
+        let identifiers: Vec<UnitId> = delta.units().map(|unit| unit.identifier).collect();
+        let mut expected = vec![observed, new];
+        expected.sort();
+        assert_eq!(identifiers, expected);
+
+        let mut receiver = old;
+        unwrap!(receiver.union(&delta));
+        assert_eq!(receiver.unit_versions(), dag.unit_versions());
+    }
+
+    #[test]
+    fn total_order() {
+        let mut rng = SeededRng::new();
+        let first_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let second_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut first: Dag<String> = Dag::new(&first_keys);
+        let mut second: Dag<String> = Dag::new(&first_keys);
+        for dag in vec![&mut first, &mut second] {
+            dag.set_majority(2);
+        }
+        let _ = unwrap!(first.new_payload("a".to_string(), &first_keys));
+        let _ = unwrap!(second.new_payload("b".to_string(), &second_keys));
+        unwrap!(first.union(&second));
+        unwrap!(second.union(&first));
+
+        // Both order the concurrent units identically, but only deliver the stable ones.
+        let order = |dag: &Dag<String>| -> Vec<String> {
+            dag.ordered_units().into_iter().map(|unit| unit.payload.clone()).collect()
+        };
+        assert_eq!(order(&first), order(&second));
+        assert_eq!(order(&first).len(), 2);
+        assert!(first.stable_payloads().is_empty());
+        let _ = unwrap!(first.new_payload("b".to_string(), &first_keys));
+        assert_eq!(first.stable_payloads(), vec!["b".to_string()]);
+
+        let _ = unwrap!(second.new_payload("a".to_string(), &second_keys));
+        unwrap!(first.union(&second));
+        unwrap!(second.union(&first));
+        assert_eq!(first.stable_payloads(), order(&first));
+        assert_eq!(second.stable_payloads(), order(&first));
+    }
+
+    #[test]
+    fn events() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        dag.set_majority(2);
+        let _ = unwrap!(dag.new_payload("unrecorded".to_string(), &keys));
+        assert!(dag.take_events().is_empty());
+
+        dag.set_events_enabled(true);
+        let identifier = unwrap!(dag.new_payload("payload".to_string(), &keys));
+        assert_eq!(
+            dag.take_events(),
+            vec![
+                Event::NewUnit {
+                    identifier,
+                    payload: "payload".to_string(),
+                },
+            ]
+        );
+        assert!(dag.take_events().is_empty());
+
+        // A unit gaining observers is only reported once it becomes stable, from a union too.
+        let mut other = dag.clone();
+        assert_eq!(unwrap!(other.new_payload("payload".to_string(), &peer_keys)), identifier);
+        unwrap!(dag.union(&other));
+        assert_eq!(dag.take_events(), vec![Event::UnitStable { identifier }]);
+        unwrap!(dag.union(&other));
+        assert!(dag.take_events().is_empty());
+    }
+
+    #[test]
+    fn attestations() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let own_id = Id::from(keys.public);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let identifier = unwrap!(dag.new_payload("payload".to_string(), &keys));
+        let mut other: Dag<String> = Dag::new(&peer_keys);
+        let theirs = unwrap!(other.new_payload("payload".to_string(), &peer_keys));
+        assert_eq!(theirs, identifier);
+        assert!(dag.attestation(&identifier, &Id::from(peer_keys.public)).is_none());
+        assert!(dag.attestation(&UnitId([9; 32]), &own_id).is_none());
+
+        // Our attestation adds us as observer of the peer's copy, but not once tampered with.
+        let attestation = unwrap!(dag.attestation(&identifier, &own_id));
+        let mut tampered = attestation.clone();
+        tampered.signature[0] ^= 1;
+        assert!(!other.apply_attestation(&tampered));
+        assert!(other.apply_attestation(&attestation));
+        assert!(unwrap!(other.get(&identifier)).observers.contains(&own_id));
+        assert!(!other.apply_attestation(&attestation));
+    }
+
+    #[test]
+    fn no_parent() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let dag: Dag<String> = Dag::new(&keys);
+        // A partial DAG holds no unit to build on, which fails rather than panics.
+        let mut empty = dag.partial(Vec::new());
+        match empty.new_payload("payload".to_string(), &keys) {
+            Err(Error::NoTips) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn children_index() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let mut other = dag.clone();
+        let first = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let second = unwrap!(other.new_payload("second".to_string(), &peer_keys));
+        unwrap!(dag.union(&other));
+        let merged = unwrap!(dag.new_payload("merged".to_string(), &keys));
+
+        let genesis = dag.genesis().identifier;
+        let expected: BTreeSet<UnitId> = vec![first, second].into_iter().collect();
+        assert_eq!(dag.children_of(&genesis), expected);
+        assert_eq!(dag.children_of(&first), iter::once(merged).collect());
+        assert!(dag.children_of(&merged).is_empty());
+
+        // Not serialised, but rebuilt from the parents once deserialised.
+        let serialised = unwrap!(serialisation::serialise(&dag));
+        let deserialised: Dag<String> = unwrap!(serialisation::deserialise(&serialised));
+        for unit in dag.units() {
+            assert_eq!(
+                deserialised.children_of(&unit.identifier),
+                dag.children_of(&unit.identifier)
+            );
+        }
+    }
+
+    #[test]
+    fn tips_index() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let mut other = dag.clone();
+        let first = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let second = unwrap!(other.new_payload("second".to_string(), &peer_keys));
+        assert_eq!(dag.tips().cloned().collect::<Vec<_>>(), vec![first]);
+
+        // The tips are the childless units, whichever way units were added.
+        unwrap!(dag.union(&other));
+        let childless = |dag: &Dag<String>| -> BTreeSet<UnitId> {
+            dag.units()
+                .filter(|unit| dag.children_of(&unit.identifier).is_empty())
+                .map(|unit| unit.identifier)
+                .collect()
+        };
+        let expected: BTreeSet<UnitId> = vec![first, second].into_iter().collect();
+        assert_eq!(dag.tips().cloned().collect::<BTreeSet<_>>(), expected);
+        assert_eq!(childless(&dag), expected);
+        let merged = unwrap!(dag.new_payload("merged".to_string(), &keys));
+        assert_eq!(dag.tips().cloned().collect::<Vec<_>>(), vec![merged]);
+
+        let _ = dag.checkpoint();
+        dag.rebuild_indices();
+        assert_eq!(dag.tips().cloned().collect::<BTreeSet<_>>(), childless(&dag));
+        assert_eq!(dag.stable_count(), dag.len());
+    }
+
+    #[test]
+    fn queries() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let own_id = Id::from(keys.public);
+        let mut dag: Dag = Dag::new(&keys);
+        let first = unwrap!(dag.new_payload(vec![1], &keys));
+        let second = unwrap!(dag.new_payload(vec![2], &keys));
+
+        assert_eq!(unwrap!(dag.get(&first)).payload, vec![1]);
+        assert!(dag.get(&UnitId([9; 32])).is_none());
+        assert_eq!(dag.observers_of(&second), Some(&iter::once(own_id).collect()));
+        assert!(dag.observers_of(&UnitId([9; 32])).is_none());
+        assert!(dag.contains_payload(&[2u8][..]));
+        assert!(!dag.contains_payload(&[3u8][..]));
+        let order: Vec<UnitId> = dag.topological().map(|unit| unit.identifier).collect();
+        assert_eq!(order, vec![dag.genesis().identifier, first, second]);
+        assert_eq!(dag.units_by_ids(&[second, UnitId([9; 32]), first]).len(), 2);
+    }
+
+    #[test]
+    fn weighted_stability() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let heavy_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let mut weights = BTreeMap::new();
+        let _ = weights.insert(Id::from(keys.public), 1);
+        let _ = weights.insert(Id::from(heavy_keys.public), 3);
+        dag.set_weights(weights);
+
+        // Our own weight of 1 out of 4 isn't enough, but the heavy peer's 3 are.
+        let light = unwrap!(dag.new_payload("light".to_string(), &keys));
+        assert!(!dag.is_stable(unwrap!(dag.get(&light))));
+        let heavy = unwrap!(dag.new_payload("heavy".to_string(), &heavy_keys));
+        assert!(dag.is_stable(unwrap!(dag.get(&heavy))));
+        assert_eq!(dag.stability_policy().threshold(0, 0), 3);
+
+        // Units already held are re-evaluated at once when the weights change.
+        let mut weights = BTreeMap::new();
+        let _ = weights.insert(Id::from(keys.public), 1);
+        dag.set_weights(weights);
+        assert!(dag.is_stable(unwrap!(dag.get(&light))));
+    }
+
+    #[test]
+    fn ancestry() {
+        let keys = Keypair::generate::<Sha3_512>(&mut SeededRng::new());
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let payloads = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let chain = unwrap!(dag.new_payloads(payloads, &keys));
+        let genesis = dag.genesis().identifier;
+        let identifiers = |traversal: Traversal<String>| -> Vec<UnitId> {
+            traversal.map(|unit| unwrap!(unit).identifier).collect()
+        };
+        assert_eq!(identifiers(dag.ancestors(&chain[2])), vec![chain[1], chain[0], genesis]);
+        assert_eq!(identifiers(dag.descendants(&chain[0])), vec![chain[1], chain[2]]);
+        assert!(identifiers(dag.descendants(&chain[2])).is_empty());
+        assert_eq!(
+            unwrap!(dag.path_between(&chain[2], &genesis)),
+            Some(vec![chain[2], chain[1], chain[0], genesis])
+        );
+        assert_eq!(unwrap!(dag.path_between(&chain[0], &chain[2])), None);
+        assert_eq!(unwrap!(dag.path_between(&UnitId([9; 32]), &genesis)), None);
+    }
+
+    #[test]
+    fn diff() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let mut other: Dag<String> = Dag::new(&peer_keys);
+        let genesis = dag.genesis().identifier;
+        let left = unwrap!(dag.new_payload("left".to_string(), &keys));
+        let right = unwrap!(other.new_payload("right".to_string(), &peer_keys));
+        assert!(dag.diff(&dag.clone()).is_empty());
+
+        let diff = dag.diff(&other);
+        assert_eq!(diff.only_in_self, iter::once(left).collect());
+        assert_eq!(diff.only_in_other, iter::once(right).collect());
+        assert_eq!(diff.differing_children, iter::once(genesis).collect());
+        let diff = dag.diff_versions(&other.unit_versions());
+        assert_eq!(diff.only_in_self, iter::once(left).collect());
+        assert_eq!(diff.only_in_other, iter::once(right).collect());
+        assert!(dag.diff_versions(&dag.unit_versions()).is_empty());
+
+        // Once merged, only the units the other side still misses differ.
+        let mut merged = dag.clone();
+        unwrap!(merged.union(&other));
+        let diff = other.diff(&merged);
+        assert!(diff.only_in_self.is_empty());
+        assert_eq!(diff.only_in_other, iter::once(left).collect());
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn networks() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut first: Dag<String> = Dag::new_with_genesis(b"first", &keys);
+        let second: Dag<String> = Dag::new_with_genesis(b"second", &keys);
+        let peer: Dag<String> = Dag::new_with_genesis(b"first", &peer_keys);
+        assert_ne!(first.network(), second.network());
+        assert_eq!(first.network(), peer.network());
+        assert_eq!(first.network(), &first.genesis().identifier);
+        // The hard-coded genesis is that of a network identified by its parent payload.
+        let legacy: Dag<String> = Dag::new(&keys);
+        assert_eq!(legacy.network(), Dag::<String>::new_with_genesis(&[0, 0, 0], &keys).network());
+
+        // The network outlives the genesis once collapsed by a checkpoint.
+        let network = *first.network();
+        let _ = unwrap!(first.new_payload("Hello".to_string(), &keys));
+        assert!(first.checkpoint() > 0);
+        assert_eq!(first.network(), &network);
+        assert!(!first.contains(&network));
+    }
+
+    #[test]
+    fn logical_clocks() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let mut other: Dag<String> = Dag::new(&peer_keys);
+        assert_eq!(dag.clock(), 0);
+        let _ = unwrap!(dag.new_payload("Hello".to_string(), &keys));
+        let _ = unwrap!(dag.new_payload("World".to_string(), &keys));
+        assert_eq!(dag.clock(), 2);
+        unwrap!(other.union(&dag));
+        assert_eq!(other.clock(), 2);
+
+        // Our next unit is clocked after everything received.
+        let identifier = unwrap!(other.new_payload("!".to_string(), &peer_keys));
+        assert_eq!(unwrap!(other.get(&identifier)).clock, 3);
+        assert_eq!(other.clock(), 3);
+        let clocks: Vec<u64> = other.ordered_units().iter().map(|unit| unit.clock).collect();
+        assert_eq!(clocks, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn bounded_memory() {
+        let keys = Keypair::generate::<Sha3_512>(&mut SeededRng::new());
+        let mut dag: Dag<String> = Dag::new(&keys);
+        dag.set_events_enabled(true);
+        dag.set_config(DagConfig {
+            max_units: Some(2),
+            max_payload_size: Some(16),
+            eviction: Eviction::OldestStable,
+            ..DagConfig::default()
+        });
+        match dag.new_payload(iter::repeat('x').take(64).collect(), &keys) {
+            Err(Error::PayloadTooLarge(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // Stable units get evicted to make room for new ones.
+        let first = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let second = unwrap!(dag.new_payload("second".to_string(), &keys));
+        assert_eq!(dag.len(), 2);
+        assert!(dag.contains(&first) && dag.contains(&second));
+        assert!(dag.take_events().contains(&Event::Evicted { count: 1 }));
+
+        // Without eviction, units beyond the cap are refused, whether created or received.
+        let mut other = dag.clone();
+        other.set_config(DagConfig::default());
+        dag.set_config(DagConfig {
+            max_units: Some(2),
+            eviction: Eviction::Refuse,
+            ..DagConfig::default()
+        });
+        match dag.new_payload("refused".to_string(), &keys) {
+            Err(Error::CapacityExceeded(2)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        let refused = unwrap!(other.new_payload("refused".to_string(), &keys));
+        match dag.union(&other) {
+            Err(Error::CapacityExceeded(2)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(dag.len(), 2);
+
+        // A DAG failing validation doesn't have anything evicted, unlike a valid one.
+        dag.set_config(DagConfig {
+            max_units: Some(2),
+            eviction: Eviction::OldestStable,
+            ..DagConfig::default()
+        });
+        let mut invalid = other.clone();
+        unwrap!(invalid.units.get_mut(&refused)).parents = iter::once(UnitId([7; 32])).collect();
+        assert!(dag.union(&invalid).is_err());
+        assert_eq!(dag.len(), 2);
+        assert!(dag.contains(&first));
+        assert_eq!(dag.last_checkpoint().height, 1);
+        unwrap!(dag.union(&other));
+        assert_eq!(dag.len(), 2);
+        assert!(!dag.contains(&first));
+        assert!(dag.contains(&second) && dag.contains(&refused));
+        assert_eq!(dag.last_checkpoint().height, 2);
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        for &(duplicates, expected) in &[(Duplicates::Allow, 2), (Duplicates::Merge, 1)] {
+            let mut dag: Dag<String> = Dag::new(&keys);
+            dag.set_config(DagConfig {
+                duplicates,
+                ..DagConfig::default()
+            });
+            let mut other: Dag<String> = Dag::new(&peer_keys);
+            // Each node observes the payload on its own branch.
+            let _ = unwrap!(dag.new_payload("a".to_string(), &keys));
+            let _ = unwrap!(dag.new_payload("Hello".to_string(), &keys));
+            let _ = unwrap!(other.new_payload("b".to_string(), &peer_keys));
+            let _ = unwrap!(other.new_payload("Hello".to_string(), &peer_keys));
+
+            unwrap!(dag.union(&other));
+            let holding = dag.units().filter(|unit| unit.payload == "Hello").count();
+            assert_eq!(holding, expected);
+            assert_eq!(dag.len(), 3 + expected);
+        }
+    }
+
+    #[test]
+    fn metadata() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let tags = |entries: &[(&str, &[u8])]| -> BTreeMap<String, Vec<u8>> {
+            entries
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_vec()))
+                .collect()
+        };
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let mut other: Dag<String> = Dag::new(&peer_keys);
+        let identifier = unwrap!(dag.new_payload_with_metadata(
+            "Hello".to_string(),
+            tags(&[("topic", &b"news"[..])]),
+            &keys,
+        ));
+        // Metadata doesn't affect the identifier.
+        assert_eq!(
+            unwrap!(other.new_payload_with_metadata(
+                "Hello".to_string(),
+                tags(&[("topic", &b"alerts"[..]), ("priority", &[1][..])]),
+                &peer_keys,
+            )),
+            identifier
+        );
+
+        // Whatever the order of the unions, the same metadata is kept.
+        let theirs = other.clone();
+        unwrap!(other.union(&dag));
+        unwrap!(dag.union(&theirs));
+        let expected = tags(&[("priority", &[1][..]), ("topic", &b"alerts"[..])]);
+        for dag in &[dag, other] {
+            assert_eq!(dag.metadata_of(&identifier), Some(&expected));
+            let tagged = dag.units_with_metadata("topic", b"alerts");
+            assert_eq!(tagged.len(), 1);
+            assert_eq!(tagged[0].identifier, identifier);
+            assert!(dag.units_with_metadata("topic", b"news").is_empty());
+        }
+        assert!(theirs.metadata_of(&UnitId([9; 32])).is_none());
+    }
+
+    #[test]
+    fn cached_digest() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        // The digest computed from scratch, to check the cached one against.
+        let uncached = |dag: &Dag<String>| {
+            dag.digest.set(None);
+            dag.digest()
+        };
+        let mut digests = BTreeSet::new();
+        let _ = digests.insert(dag.digest());
+
+        // Inserting a unit.
+        let first = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let digest = dag.digest();
+        assert!(digests.insert(digest));
+        assert_eq!(uncached(&dag), digest);
+        assert_eq!(dag.clone().digest(), digest);
+
+        // Merging a unit, then only an observer of a unit held.
+        let mut peer = dag.clone();
+        let _ = unwrap!(peer.new_payload("second".to_string(), &peer_keys));
+        unwrap!(dag.union(&peer));
+        let digest = dag.digest();
+        assert!(digests.insert(digest));
+        assert_eq!(uncached(&dag), digest);
+        assert_eq!(unwrap!(peer.new_payload("first".to_string(), &peer_keys)), first);
+        unwrap!(dag.union(&peer));
+        let digest = dag.digest();
+        assert!(digests.insert(digest));
+        assert_eq!(uncached(&dag), digest);
+
+        // Pruning the stable prefix.
+        assert!(dag.checkpoint() > 0);
+        let digest = dag.digest();
+        assert!(digests.insert(digest));
+        assert_eq!(uncached(&dag), digest);
+    }
+
+    #[test]
+    fn forged_observers() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let honest_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let honest = Id::from(honest_keys.public);
+        let victim_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let victim = Id::from(victim_keys.public);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let identifier = unwrap!(dag.new_payload("payload".to_string(), &keys));
+
+        // The peer claims the victim observed the unit, with a tampered signature.
+        let mut other = dag.clone();
+        {
+            let unit = unwrap!(other.units.get_mut(&identifier));
+            unit.add_signed_observer(&honest_keys);
+            unit.add_signed_observer(&victim_keys);
+            let signature = unwrap!(unit.signatures.get_mut(&victim));
+            signature[0] ^= 1;
+        }
+
+        match dag.union(&other) {
+            Err(Error::ForgedObservers(forged)) => {
+                assert_eq!(forged, iter::once(victim).collect());
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        let unit = unwrap!(dag.get(&identifier));
+        assert!(unit.observers.contains(&honest));
+        assert!(!unit.observers.contains(&victim));
+        assert!(!unit.signatures.contains_key(&victim));
+    }
+}
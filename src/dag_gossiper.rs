@@ -17,173 +17,3493 @@
 
 #![allow(dead_code)]
 
-use dag::Dag;
-use ed25519_dalek::Keypair;
+use audit::SignedSnapshot;
+use capabilities::Capabilities;
+use catch_up::{Bootstrap, CatchUp, CatchUpProgress, MAX_BOOTSTRAP_PAGE};
+use clock;
+#[cfg(feature = "chaos")]
+use chaos::ChaosConfig;
+use codec::WireCodec;
+use config::GossiperConfig;
+use conflict::PayloadPolicy;
+use dag::{Dag, DagConfig, DagDiff, MembershipCheck, Validation};
+#[cfg(feature = "encryption")]
+use encryption::GroupKey;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey};
 use error::Error;
-use id::Id;
-#[cfg(test)]
+use event::Event;
+use extension::{Extensions, RoundHook};
+use fault::FaultProof;
+use hasher::Hasher;
+use id::{Id, UnitId};
+use invariants;
+use journal::Journal;
+#[cfg(all(test, not(feature = "wasm")))]
 use maidsafe_utilities::SeededRng as rand;
 use maidsafe_utilities::serialisation;
-#[cfg(not(test))]
+use message::{Attestation, Envelope, Message, Nack, WireDag};
+use peer_score::{self, PeerScore, PeerSelector};
+use quorum::{self, MembershipReconciliation, QuorumInfo, SmallNetworkMode};
+use rate_limit::{PeerStatus, PeerUsage, RateLimit};
+#[cfg(not(any(test, feature = "wasm")))]
 use rand;
 use rand::Rng;
+#[cfg(feature = "wasm")]
+use rand::{SeedableRng, XorShiftRng};
 
 use serde::ser::Serialize;
 use sha3::Sha3_512;
+use snapshot::{self, GOSSIPER_FORMAT_VERSION, GossiperState};
+use stability::StabilityPolicy;
+use stats::{Statistics, UnitBreakdown};
+use telemetry::{Operation, SlowOperation, Telemetry};
+use topic::Topics;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(feature = "chaos")]
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "wasm")]
+use tiny_keccak::sha3_256;
+use unit::{ContentType, Unit};
+use watchdog::{Watchdog, WatchdogConfig};
+use wire::{self, CompressionLevel, WireFormat};
+
+/// How a `Gossiper` chooses the peer to gossip with each round, unless a custom `PeerSelector` is
+/// set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerSelection {
+    /// Pick a peer at random every round, with a chance proportional to `PeerScore::weight`,
+    /// favouring the peers which sent us the most new units and those we didn't gossip with for
+    /// the longest. The default.
+    Adaptive,
+    /// Pick a peer uniformly at random every round.
+    Uniform,
+    /// Walk through a random permutation of the peers, reshuffling once all were contacted. With
+    /// `K` peers, each is contacted at least once in any `2K - 1` consecutive rounds.
+    Permutation,
+}
+
+/// How a `Gossiper` exchanges units with the peer it picked for a round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GossipStrategy {
+    /// Send our units to the peer.
+    Push,
+    /// Ask the peer for the units we are missing.
+    Pull,
+    /// Send our units to the peer and ask for the ones we are missing.
+    PushPull,
+}
+
+/// What a `Gossiper` pushes to a peer each round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Push the whole DAG every round.
+    Full,
+    /// Push only the units the peer isn't known to hold yet. As pushes may get lost, the whole DAG
+    /// is pushed instead on every `full_sync_every`th round.
+    Delta {
+        /// Number of rounds between two full pushes.
+        full_sync_every: u64,
+    },
+}
+
+/// What a `Gossiper` does when a message from a peer can't be deserialised. Whatever the policy,
+/// an `Event::MalformedMessage` is sent to subscribers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeFailurePolicy {
+    /// Drop the message.
+    Ignore,
+    /// Drop the message and add one to the peer's penalty, as returned by `Gossiper::penalty`.
+    Penalize,
+    /// Drop the message and ask the peer for the units we are missing, as a `Pull` would.
+    RequestRetransmission,
+    /// Drop the message and remove the peer.
+    Disconnect,
+}
+
+/// How far a payload sent by `Gossiper::send_new` got, as returned by `Gossiper::status_of`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadStatus {
+    /// The unit isn't held, either because it never was or because it was pruned by a checkpoint.
+    Unknown,
+    /// The unit isn't known to have been observed by any peer yet.
+    Pending,
+    /// The unit was observed by this many peers besides us, but isn't stable yet.
+    SeenBy(usize),
+    /// The unit is stable.
+    Stable,
+}
+
+/// The state of a unit seen as a rumor. A unit is hot while still worth gossiping, and turns cold
+/// once it was gossiped for a number of rounds without changing. Any change, i.e. a new observer,
+/// makes it hot again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RumorState {
+    /// Still worth gossiping.
+    Hot {
+        /// Number of rounds the unit was gossiped in since it last changed.
+        rounds: u32,
+    },
+    /// Not worth gossiping any more, until it changes.
+    Cold,
+}
+
+// The gossip state of a unit held, since it last changed.
+#[derive(Clone, Copy)]
+struct Rumor {
+    // The unit's version when it last changed.
+    version: usize,
+    // Number of rounds since.
+    rounds: u32,
+    // Number of pushes which carried it since.
+    pushes: u32,
+}
+
+impl Rumor {
+    fn new(version: usize) -> Self {
+        Rumor {
+            version,
+            rounds: 0,
+            pushes: 0,
+        }
+    }
+}
+
+// The outcome of a union with a DAG received from a peer.
+enum Merged {
+    // The units were merged, but for those whose parents, listed, we are missing.
+    Missing(Vec<UnitId>),
+    // Units were rejected, to be reported back to the peer.
+    Rejected(Nack),
+}
 
 /// An entity on the network which will gossip messages.
 pub struct Gossiper {
     keys: Keypair,
+    // The source of randomness of every randomised decision, e.g. the choice of peers, if one was
+    // injected. The thread's otherwise.
+    rng: Option<Box<Rng + Send>>,
     peers: Vec<Id>,
+    peer_selection: PeerSelection,
+    // The peers still to be contacted in the current permutation, when using
+    // `PeerSelection::Permutation`.
+    peer_cycle: Vec<Id>,
+    // Per peer, how useful gossiping with it has been. Peers get an entry once involved in a
+    // round or a message.
+    peer_scores: BTreeMap<Id, PeerScore>,
+    peer_selector: Option<Box<PeerSelector>>,
+    // The peers we have received at least one message from.
+    heard_from: BTreeSet<Id>,
+    strategy: GossipStrategy,
+    // The number of peers gossiped to per round by `next_round_multi`.
+    fanout: usize,
+    sync_mode: SyncMode,
+    // Per peer, the version of each unit the peer is known to hold, having been sent it by us or
+    // having sent it to us.
+    peer_cursors: BTreeMap<Id, BTreeMap<UnitId, usize>>,
+    round: u64,
+    // Identifiers of the units whose payload was returned by `poll_ordered`.
+    delivered: BTreeSet<UnitId>,
+    telemetry: Telemetry,
+    statistics: Statistics,
+    // The units held which aren't stable yet, with when we first held them.
+    first_held: BTreeMap<UnitId, Instant>,
+    subscribers: Vec<Sender<Event>>,
+    // Our own recent observations, with the round they were made in, piggybacked on every
+    // outgoing message for as many rounds as we have peers.
+    fresh_attestations: Vec<(u64, Attestation)>,
+    decode_failure_policy: DecodeFailurePolicy,
+    wire_format: WireFormat,
+    compression: CompressionLevel,
+    // Tracks the units known to be missing, from the first heard of until all are received.
+    catch_up: Option<CatchUp>,
+    // The bootstrap from a single peer in progress, if any, during which rounds are suspended.
+    bootstrap: Option<Bootstrap>,
+    // Per peer, the number of malformed messages received, under `DecodeFailurePolicy::Penalize`.
+    penalties: BTreeMap<Id, u32>,
+    // Whether payloads may be sent without any peers, every unit then being stable at once.
+    single_node: bool,
+    small_network_mode: SmallNetworkMode,
+    rumors: BTreeMap<UnitId, Rumor>,
+    // Rounds after which an unchanged unit turns cold, derived from the peer count if `None`.
+    rumor_rounds: Option<u32>,
+    // Rounds between anti-entropy full syncs if cold rumors are left out of round pushes, `None`
+    // if they aren't.
+    anti_entropy_every: Option<u32>,
+    // How observers of received units which aren't our peers are handled, if they are checked.
+    membership_check: Option<MembershipCheck>,
+    topics: Topics,
+    reconciliation: MembershipReconciliation,
+    // Members which aren't our peers, learned from peers under `MembershipReconciliation::Union`.
+    learned_members: BTreeSet<Id>,
+    // Per peer, the membership digest carried by the last message received from it.
+    peer_memberships: BTreeMap<Id, [u8; 32]>,
+    // The peers whose member set differs from ours, still to be sent ours.
+    members_owed: BTreeSet<Id>,
+    capabilities: Capabilities,
+    // Per peer, the capabilities it advertised on the last message received from it.
+    peer_capabilities: BTreeMap<Id, Capabilities>,
+    // Per peer, the digest of its DAG carried by the last message received from it.
+    peer_digests: BTreeMap<Id, [u8; 32]>,
+    config: GossiperConfig,
+    rate_limit: Option<RateLimit>,
+    // Per peer, its traffic in the current window of the rate limit.
+    peer_usage: BTreeMap<Id, PeerUsage>,
+    watchdog: Option<Watchdog>,
+    // Where changes to the DAG are persisted, if anywhere.
+    journal: Option<Journal>,
+    round_hook: Option<Box<RoundHook>>,
+    strict_checks: bool,
+    // The first invariant found violated by strict checks, to be returned by the call it was found
+    // during.
+    violation: Option<Error>,
+    #[cfg(feature = "chaos")]
+    chaos: ChaosConfig,
+    #[cfg(feature = "encryption")]
+    group_key: Option<GroupKey>,
     dag: Dag,
 }
 
 impl Gossiper {
+    /// Create a gossiper owning `keys`, e.g. provisioned by an operator, with a fresh DAG. With the
+    /// `wasm` feature, which has no entropy, its randomised decisions are seeded from the keys.
+    pub fn from_keypair(keys: Keypair) -> Self {
+        let dag = Dag::new(&keys);
+        Gossiper::with_dag(keys, dag)
+    }
+
+    /// Create a gossiper owning `keys`, with a fresh DAG for the network identified by
+    /// `network_id`. Gossipers of different networks reject each other's DAGs.
+    pub fn for_network(keys: Keypair, network_id: &[u8]) -> Self {
+        let dag = Dag::new_with_genesis(network_id, &keys);
+        Gossiper::with_dag(keys, dag)
+    }
+
+    /// As `for_network`, unit identifiers being derived with `hasher` rather than SHA3-256. Every
+    /// gossiper of the network must use the same: messages from peers using another one are
+    /// dropped with `Error::HashAlgorithmMismatch`.
+    pub fn for_network_with_hasher(keys: Keypair, network_id: &[u8], hasher: Arc<Hasher>) -> Self {
+        let dag = Dag::new_with_hasher(network_id, &keys, hasher);
+        Gossiper::with_dag(keys, dag)
+    }
+
+    /// Create a gossiper whose keys are generated by `rng`, and which makes every randomised
+    /// decision, e.g. the choice of peers in `next_round`, with `rng`. Seeding `rng` makes the
+    /// gossiper behave identically across runs, to reproduce issues in simulations.
+    pub fn with_rng<R: Rng + Send + 'static>(mut rng: R) -> Self {
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut gossiper = Gossiper::from_keypair(keys);
+        gossiper.rng = Some(Box::new(rng));
+        gossiper
+    }
+
+    /// Create a gossiper owning the ed25519 secret key given as bytes, as exported by
+    /// `export_secret_key`, with a fresh DAG. Fails with `Error::InvalidSecretKey` if the bytes
+    /// aren't a valid secret key.
+    pub fn from_secret_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let secret = SecretKey::from_bytes(bytes).map_err(|_| Error::InvalidSecretKey)?;
+        let public = PublicKey::from_secret::<Sha3_512>(&secret);
+        Ok(Gossiper::from_keypair(Keypair { secret, public }))
+    }
+
+    /// The bytes of our secret key, for our identity to persist across restarts through
+    /// `from_secret_bytes`. To be stored as securely as any private key.
+    pub fn export_secret_key(&self) -> Vec<u8> {
+        self.keys.secret.to_bytes().to_vec()
+    }
+
     /// The ID of this `Gossiper`, i.e. its public key.
     pub fn id(&self) -> Id {
         self.keys.public.into()
     }
 
-    /// Add the ID of another node on the network.
+    /// Add the ID of another node on the network. Adding a peer already known has no effect.
     pub fn add_peer(&mut self, peer_id: Id) -> Result<(), Error> {
+        if self.peers.contains(&peer_id) {
+            return Ok(());
+        }
         self.peers.push(peer_id);
-        self.dag.set_majority((self.peers.len() / 2 + 1) as u8);
+        self.dag.admit_member(peer_id);
+        self.update_majority();
         Ok(())
     }
 
-    /// Send a new message starting at this `Gossiper`.
-    /// This is interpreted as an new event observed by this node.
-    pub fn send_new<T: Serialize>(&mut self, message: &T) -> Result<(), Error> {
-        self.dag.new_payload(
-            serialisation::serialise(message)?,
-            &self.keys.public.into(),
-        );
-        Ok(())
+    /// Remove a peer, forgetting everything known about it. Returns whether it was a peer.
+    pub fn remove_peer(&mut self, peer_id: &Id) -> bool {
+        let count = self.peers.len();
+        self.peers.retain(|peer| peer != peer_id);
+        if self.peers.len() == count {
+            return false;
+        }
+        self.peer_cycle.retain(|peer| peer != peer_id);
+        self.forget_peer(peer_id);
+        self.dag.retire_member(*peer_id);
+        self.update_majority();
+        true
     }
 
-    /// Start a new round.
-    pub fn next_round(&mut self) -> Result<(Id, Vec<u8>), Error> {
-        let peer_id = match rand::thread_rng().choose(&self.peers) {
-            Some(id) => *id,
-            None => return Err(Error::NoPeers),
+    /// Replace the whole peer list, recomputing the majority once. Everything known about the
+    /// peers which aren't in `peers` is forgotten, and repeated IDs are only kept once.
+    pub fn update_peers(&mut self, peers: Vec<Id>) {
+        let mut unique = BTreeSet::new();
+        let peers: Vec<Id> = peers
+            .into_iter()
+            .filter(|peer| unique.insert(*peer))
+            .collect();
+        let removed: Vec<Id> = self.peers
+            .iter()
+            .filter(|peer| !peers.contains(peer))
+            .cloned()
+            .collect();
+        for peer_id in &removed {
+            self.forget_peer(peer_id);
+            self.dag.retire_member(*peer_id);
+        }
+        for peer_id in &peers {
+            self.dag.admit_member(*peer_id);
+        }
+        self.peers = peers;
+        self.peer_cycle.clear();
+        self.update_majority();
+    }
+
+    /// Replace the whole peer list, e.g. with one from `export_peers`. Same as `update_peers`.
+    pub fn set_peers(&mut self, peers: Vec<Id>) {
+        self.update_peers(peers)
+    }
+
+    /// The IDs of all the peers currently known.
+    pub fn export_peers(&self) -> Vec<Id> {
+        self.peers.clone()
+    }
+
+    /// Set how our member set is reconciled with those of peers which know other members,
+    /// `MembershipReconciliation::Ignore` by default. Switching back to it forgets the members
+    /// learned from peers.
+    pub fn set_membership_reconciliation(&mut self, reconciliation: MembershipReconciliation) {
+        self.reconciliation = reconciliation;
+        if reconciliation == MembershipReconciliation::Ignore {
+            self.learned_members.clear();
+        }
+        self.update_majority();
+    }
+
+    /// The members the stability threshold is computed from: ourself, our peers, and the members
+    /// learned from them under `MembershipReconciliation::Union`.
+    pub fn members(&self) -> BTreeSet<Id> {
+        let mut members: BTreeSet<Id> = self.peers.iter().cloned().collect();
+        members.extend(self.learned_members.iter().cloned());
+        let _ = members.insert(self.id());
+        members
+    }
+
+    /// The agreement parameters currently in use, and how they were computed.
+    pub fn quorum_info(&self) -> QuorumInfo {
+        let members = self.members().len();
+        let others = members - 1;
+        let majority = quorum::stability_threshold(others, self.small_network_mode);
+        let policy = self.dag.stability_policy();
+        let stability_rule = match *policy {
+            StabilityPolicy::Majority => quorum::stability_rule(others, self.small_network_mode),
+            _ => policy.rule(majority, members),
         };
-        let message = self.prepare_to_send();
-        debug!(
-            "{:?} pushing to {:?} with DAG {:?}",
-            self,
-            peer_id,
-            self.dag
-        );
-        Ok((peer_id, message))
+        QuorumInfo {
+            members,
+            live_members: self.heard_from.len() + 1,
+            stability_threshold: policy.threshold(majority, members) as usize,
+            stability_rule,
+        }
     }
 
-    /// Handles an incoming DAG from peer.
-    pub fn handle_received_message(&mut self, peer_id: &Id, serialised_msg: &[u8]) {
-        debug!("{:?} handling DAG from {:?}", self, peer_id);
-        let dag: Dag = if let Ok(dag) = serialisation::deserialise(serialised_msg) {
-            dag
-        } else {
-            error!("Failed to deserialise message");
-            return;
+    /// Set what makes a unit stable, `StabilityPolicy::Majority` by default. Units already held
+    /// become stable, or stop being so, at once.
+    pub fn set_stability_policy(&mut self, policy: StabilityPolicy) {
+        self.dag.set_stability_policy(policy);
+        let _ = self.dispatch_events();
+    }
+
+    /// Set how the peer to gossip with is chosen each round.
+    pub fn set_peer_selection(&mut self, peer_selection: PeerSelection) {
+        self.peer_selection = peer_selection;
+        self.peer_cycle.clear();
+    }
+
+    /// Set a custom strategy choosing the peers to gossip with, overriding the `PeerSelection`.
+    /// `None` reverts to the `PeerSelection`.
+    pub fn set_peer_selector(&mut self, selector: Option<Box<PeerSelector>>) {
+        self.peer_selector = selector;
+    }
+
+    /// How useful gossiping with each of our peers has been so far.
+    pub fn peer_scores(&self) -> BTreeMap<Id, PeerScore> {
+        self.peers
+            .iter()
+            .map(|peer_id| {
+                let score = self.peer_scores.get(peer_id).cloned().unwrap_or_default();
+                (*peer_id, score)
+            })
+            .collect()
+    }
+
+    /// Set whether we push, pull, or both each round.
+    pub fn set_gossip_strategy(&mut self, strategy: GossipStrategy) {
+        self.strategy = strategy;
+    }
+
+    /// Set the number of distinct peers gossiped to per round by `next_round_multi`. Gossiping to
+    /// several peers per round shortens convergence in networks of more than a dozen nodes. A
+    /// fanout of zero is taken as one.
+    pub fn set_fanout(&mut self, fanout: usize) {
+        self.fanout = cmp::max(fanout, 1);
+    }
+
+    /// Set whether the whole DAG or only deltas are pushed to peers.
+    pub fn set_sync_mode(&mut self, sync_mode: SyncMode) {
+        self.sync_mode = sync_mode;
+        self.peer_cursors.clear();
+    }
+
+    /// Bound the number of units walked per path when handling payloads, to bound the CPU spent
+    /// per incoming unit. `None` removes the limit.
+    pub fn set_max_traversal_depth(&mut self, max_traversal_depth: Option<usize>) {
+        self.dag.set_max_traversal_depth(max_traversal_depth);
+    }
+
+    /// Set when the units received from peers get their identifier checked, eagerly by default.
+    pub fn set_validation(&mut self, validation: Validation) {
+        self.dag.set_validation(validation);
+    }
+
+    /// Only accept observers of the units received which are among our peers, or ourself, handling
+    /// the others as per `check`, so that made-up ids can't push a unit past the stability
+    /// threshold. The members are kept up to date as peers are added and removed. `None`, the
+    /// default, accepts any observer with a valid signature, e.g. where nodes only know part of
+    /// the network.
+    pub fn set_membership_check(&mut self, check: Option<MembershipCheck>) {
+        self.membership_check = check;
+        self.update_authorised_members();
+    }
+
+    /// Allow sending payloads while we have no peers. Without peers every unit is stable as soon
+    /// as we observe it, which only makes sense for a network of a single node, so `send_new`
+    /// fails with `Error::NoPeers` in that case unless this mode is enabled.
+    pub fn set_single_node_mode(&mut self, enabled: bool) {
+        self.single_node = enabled;
+    }
+
+    /// Set what stability means while we have at most one peer. Defaults to
+    /// `SmallNetworkMode::AllMembers`.
+    pub fn set_small_network_mode(&mut self, mode: SmallNetworkMode) {
+        self.small_network_mode = mode;
+        self.update_majority();
+    }
+
+    /// Set the codecs accepted for the DAGs carried in messages, most preferred first. DAGs sent to
+    /// a peer are encoded with the first of these the peer accepts too. `WireCodec::Units` is
+    /// always accepted, and used when the peer accepts none of them.
+    pub fn set_wire_codecs(&mut self, codecs: Vec<WireCodec>) {
+        self.capabilities.codecs = codecs;
+    }
+
+    /// Set the serialisation format of the messages we send, `WireFormat::Bincode` by default.
+    /// Received messages are decoded with whichever format they were sent with.
+    pub fn set_wire_format(&mut self, format: WireFormat) {
+        self.wire_format = format;
+    }
+
+    /// Set how much to compress the messages we send, not at all by default. Received messages are
+    /// decompressed whatever the level set.
+    pub fn set_compression(&mut self, level: CompressionLevel) {
+        self.compression = level;
+    }
+
+    /// Set the protocol features we advertise to peers, all those of this version of the crate by
+    /// default. Restricting them lets a node behave like an older version would.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// The protocol features usable with the peer: those supported by both of us, or the baseline
+    /// until we heard from the peer.
+    pub fn negotiated_capabilities(&self, peer_id: &Id) -> Capabilities {
+        match self.peer_capabilities.get(peer_id) {
+            Some(theirs) => self.capabilities.common_with(theirs),
+            None => self.capabilities.common_with(&Capabilities::default()),
+        }
+    }
+
+    /// Enable the watchdog with the given configuration, or disable it with `None`. Checked once
+    /// per round, it detects stalls and takes the configured corrective actions. Disabled by
+    /// default.
+    pub fn set_watchdog(&mut self, config: Option<WatchdogConfig>) {
+        self.watchdog = config.map(Watchdog::new);
+    }
+
+    /// The units the watchdog reported as stalled which are still unstable. Empty if the watchdog
+    /// is disabled.
+    pub fn stalled_units(&self) -> BTreeSet<UnitId> {
+        match self.watchdog {
+            Some(ref watchdog) => watchdog.stalled_units().clone(),
+            None => BTreeSet::new(),
+        }
+    }
+
+    /// Limit the messages and new units accepted from each peer, or remove the limit with `None`.
+    /// The messages of a peer exceeding the limit are dropped until it is unmuted. Unlimited by
+    /// default, and always with the `wasm` feature, for lack of a clock.
+    pub fn set_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.rate_limit = limit;
+        self.peer_usage.clear();
+    }
+
+    /// How the peer stands with respect to the rate limit.
+    pub fn peer_status(&self, peer_id: &Id) -> PeerStatus {
+        if !self.peers.contains(peer_id) {
+            return PeerStatus::Unknown;
+        }
+        match (self.peer_usage.get(peer_id), clock::now()) {
+            (Some(usage), Some(now)) => usage.status(now),
+            _ => {
+                PeerStatus::Active {
+                    messages: 0,
+                    new_units: 0,
+                }
+            }
+        }
+    }
+
+    /// Set the bounds on the memory used by our DAG, and how it handles duplicate payloads. Units
+    /// received from a peer breaking the bounds are dropped, and new payloads breaking them fail
+    /// to be sent.
+    pub fn set_dag_config(&mut self, config: DagConfig) {
+        self.dag.set_config(config);
+    }
+
+    /// Set whether units get certified by us as they become stable, as per `Dag::set_certifying`.
+    pub fn set_certifying(&mut self, certifying: bool) {
+        self.dag.set_certifying(certifying);
+    }
+
+    /// Set the hash function unit identifiers are derived with, as it isn't part of snapshots: a
+    /// gossiper created with `for_network_with_hasher` must be given it again once restored. The
+    /// DAGs of topics subscribed to from then on use it too.
+    pub fn set_hasher(&mut self, hasher: Arc<Hasher>) {
+        self.dag.set_hasher(hasher);
+    }
+
+    /// Set the bounds on the messages accepted from peers. `GossiperConfig::default()` by default.
+    pub fn set_config(&mut self, config: GossiperConfig) {
+        self.config = config;
+    }
+
+    /// The bounds on the messages accepted from peers.
+    pub fn config(&self) -> &GossiperConfig {
+        &self.config
+    }
+
+    /// Set the application's semantics of payloads, as serialised by `send_new`, for conflicting
+    /// payloads to be resolved to a single winner. Superseded payloads aren't returned by
+    /// `poll_ordered`.
+    pub fn set_payload_policy(&mut self, policy: Option<Arc<PayloadPolicy<Vec<u8>>>>) {
+        self.dag.set_payload_policy(policy);
+    }
+
+    /// Set the hooks attaching application blobs to the messages we send and consuming those
+    /// attached to the messages we receive. `None` removes them.
+    pub fn set_round_hook(&mut self, hook: Option<Box<RoundHook>>) {
+        self.round_hook = hook;
+    }
+
+    /// Set the faults to inject, for resilience tests.
+    #[cfg(feature = "chaos")]
+    pub fn set_chaos(&mut self, config: ChaosConfig) {
+        self.chaos = config;
+    }
+
+    /// Seal the payloads we send with the group's key, and open those we read with it, or stop
+    /// with `None`. Payloads sent before are left as they are. As a `PayloadPolicy` is given the
+    /// sealed payloads, it can only tell conflicting payloads apart by their sealed form.
+    #[cfg(feature = "encryption")]
+    pub fn set_group_key(&mut self, key: Option<GroupKey>) {
+        self.group_key = key;
+    }
+
+    /// Check the invariants of the DAG after every change, and that stability is monotone after
+    /// every union. Slow, for tests and staging deployments only. Disabled by default.
+    ///
+    /// Once enabled, a call which finds an invariant violated, as that is a bug, fails with
+    /// `Error::InvariantViolated` once done: sending a message, or handling a received one.
+    pub fn set_strict_checks(&mut self, enabled: bool) {
+        self.strict_checks = enabled;
+    }
+
+    /// Set what to do with messages which can't be deserialised.
+    pub fn set_decode_failure_policy(&mut self, policy: DecodeFailurePolicy) {
+        self.decode_failure_policy = policy;
+    }
+
+    /// The number of malformed messages received from the peer under
+    /// `DecodeFailurePolicy::Penalize`.
+    pub fn penalty(&self, peer_id: &Id) -> u32 {
+        self.penalties.get(peer_id).cloned().unwrap_or(0)
+    }
+
+    /// Subscribe to the changes of our DAG: new units and units becoming stable, along with the
+    /// malformed messages received. Events are sent until the returned receiver is dropped.
+    pub fn events(&mut self) -> Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Set the number of rounds after which a unit which didn't change turns cold. By default, this
+    /// is the number of rounds a push takes to reach every peer with high probability, i.e. one
+    /// more than the base 2 logarithm of the number of nodes.
+    pub fn set_rumor_rounds(&mut self, rounds: Option<u32>) {
+        self.rumor_rounds = rounds;
+    }
+
+    /// Leave cold rumors out of the DAGs pushed in rounds: units which didn't change since they
+    /// were carried by as many pushes as rounds set by `set_rumor_rounds`, or since every peer
+    /// observed them. As a fallback for peers which still miss some, every `anti_entropy_every`
+    /// rounds is a full push-pull sync instead. `None`, the default, pushes every unit as per the
+    /// sync mode.
+    pub fn set_cold_suppression(&mut self, anti_entropy_every: Option<u32>) {
+        self.anti_entropy_every = anti_entropy_every;
+    }
+
+    /// How far the unit, e.g. as returned by `send_new`, got towards stability.
+    pub fn status_of(&self, identifier: &UnitId) -> PayloadStatus {
+        let unit = match self.dag.get(identifier) {
+            Some(unit) => unit,
+            None => return PayloadStatus::Unknown,
         };
-        self.dag.union(&dag);
+        if self.dag.is_stable(unit) {
+            return PayloadStatus::Stable;
+        }
+        let own_id = self.id();
+        match unit.observers.iter().filter(|observer| **observer != own_id).count() {
+            0 => PayloadStatus::Pending,
+            count => PayloadStatus::SeenBy(count),
+        }
     }
 
-    fn prepare_to_send(&mut self) -> Vec<u8> {
-        if let Ok(serialised) = serialisation::serialise(&self.dag) {
-            serialised
+    /// The rumor state of the unit, if held.
+    pub fn rumor_state(&self, identifier: &UnitId) -> Option<RumorState> {
+        let rumor_rounds = self.rumor_rounds();
+        self.rumors.get(identifier).map(|rumor| if rumor.rounds < rumor_rounds {
+            RumorState::Hot { rounds: rumor.rounds }
         } else {
-            panic!("cannot serialise own DAG");
+            RumorState::Cold
+        })
+    }
+
+    /// The number of pushes which carried the unit since it last changed, if held.
+    pub fn push_count(&self, identifier: &UnitId) -> Option<u32> {
+        self.rumors.get(identifier).map(|rumor| rumor.pushes)
+    }
+
+    /// Whether gossiping can stop: every unit held is stable, and cold. Receiving a new unit or
+    /// observer, or sending a new payload, makes us not converged again.
+    pub fn is_converged(&self) -> bool {
+        self.dag.all_stable() &&
+            self.dag.unit_versions().iter().all(|(identifier, version)| {
+                self.rumors.get(identifier).map_or(false, |rumor| rumor.version == *version) &&
+                    self.rumor_state(identifier) == Some(RumorState::Cold)
+            })
+    }
+
+    /// Warn whenever a union, a serialisation or the insertion of a new payload takes longer than
+    /// `budget`. `None` disables the warnings, as does the `wasm` feature for lack of a clock.
+    pub fn set_latency_budget(&mut self, budget: Option<Duration>) {
+        self.telemetry.set_budget(budget);
+    }
+
+    /// Take the operations which exceeded the latency budget since the last call.
+    pub fn take_slow_operations(&mut self) -> Vec<SlowOperation> {
+        self.telemetry.take_slow_operations()
+    }
+
+    /// Send a new message starting at this `Gossiper`.
+    /// This is interpreted as an new event observed by this node.
+    /// Returns the identifier of the unit holding the message, to track its progress with
+    /// `status_of`: a new unit, or the one already holding it if it was observed before.
+    /// Fails with `Error::NoPeers` if we have no peers, unless in single node mode.
+    pub fn send_new<T: Serialize>(&mut self, message: &T) -> Result<UnitId, Error> {
+        self.send_payload(message, None, BTreeMap::new())
+    }
+
+    /// As `send_new`, tagging the unit with the content type of the message, to be returned along
+    /// with its payload by `poll_ordered_typed`.
+    pub fn send_new_typed<T: Serialize>(
+        &mut self,
+        message: &T,
+        content_type: ContentType,
+    ) -> Result<UnitId, Error> {
+        self.send_payload(message, Some(content_type), BTreeMap::new())
+    }
+
+    /// As `send_new`, attaching application metadata to the unit, e.g. a topic or a priority,
+    /// which peers can query with `Dag::metadata_of`. It isn't signed, so it can't be relied upon.
+    pub fn send_new_with_metadata<T: Serialize>(
+        &mut self,
+        message: &T,
+        metadata: BTreeMap<String, Vec<u8>>,
+    ) -> Result<UnitId, Error> {
+        self.send_payload(message, None, metadata)
+    }
+
+    /// Send several new messages at once, chaining their units: each new unit has the previous
+    /// one as its single parent, so that the tips are only merged by the first. Returns the
+    /// identifiers of the units holding the messages, in order. Fails at the first message which
+    /// can't be sent, those before it having been sent.
+    pub fn send_new_batch<T: Serialize>(&mut self, messages: &[T]) -> Result<Vec<UnitId>, Error> {
+        if self.peers.is_empty() && !self.single_node {
+            return Err(Error::NoPeers);
         }
+        let payloads = messages
+            .iter()
+            .map(|message| Ok(self.seal(serialisation::serialise(message)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let start = clock::now();
+        let result = self.dag.new_payloads(payloads, &self.keys);
+        self.telemetry.check(Operation::NewPayload, start, self.dag.len());
+        let _ = self.dispatch_events();
+        let identifiers = result?;
+        self.record_sent(&identifiers);
+        self.take_violation()?;
+        Ok(identifiers)
     }
 
-    #[cfg(test)]
-    pub fn print_dag(&self) {
-        println!("{:?} has DAG : \n {:?}", self, self.dag);
+    /// Subscribe to `topic`, creating an independent DAG for it, and only exchanged with the peers
+    /// which subscribed to it too. Returns the announcement of all our subscriptions, to be sent
+    /// to every peer.
+    pub fn subscribe(&mut self, topic: &str) -> Result<Vec<u8>, Error> {
+        let hasher = self.dag.hasher().clone();
+        if self.topics.subscribe(topic, self.dag.network(), &self.keys, hasher) {
+            self.update_majority();
+        }
+        let subscriptions = self.topics.subscriptions();
+        self.prepare_to_send(Message::Subscriptions(subscriptions))
     }
-}
 
-impl Debug for Gossiper {
-    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
-        write!(formatter, "{:?}", self.id())
+    /// Unsubscribe from `topic`, dropping its DAG. Returns the announcement of all our remaining
+    /// subscriptions, to be sent to every peer.
+    pub fn unsubscribe(&mut self, topic: &str) -> Result<Vec<u8>, Error> {
+        let _ = self.topics.unsubscribe(topic);
+        let subscriptions = self.topics.subscriptions();
+        self.prepare_to_send(Message::Subscriptions(subscriptions))
     }
-}
 
-impl Default for Gossiper {
-    fn default() -> Self {
-        let mut rng = rand::thread_rng();
-        let keys = Keypair::generate::<Sha3_512>(&mut rng);
-        let id: Id = keys.public.into();
-        let dag = Dag::new(id);
-        Gossiper {
-            keys,
-            peers: Vec::new(),
-            dag,
+    /// The topics subscribed to.
+    pub fn subscriptions(&self) -> BTreeSet<String> {
+        self.topics.subscriptions()
+    }
+
+    /// The DAG of `topic`, if subscribed to.
+    pub fn topic_dag(&self, topic: &str) -> Option<&Dag> {
+        self.topics.dag(topic)
+    }
+
+    /// As `send_new`, on the DAG of `topic`. Fails with `Error::NotSubscribed` unless subscribed
+    /// to it, and with `Error::NoPeers` if none of our peers is, unless in single node mode.
+    pub fn send_new_on<T: Serialize>(&mut self, topic: &str, message: &T) -> Result<UnitId, Error> {
+        if self.topics.dag(topic).is_none() {
+            return Err(Error::NotSubscribed(topic.to_string()));
+        }
+        if self.topics.subscribers(topic).is_empty() && !self.single_node {
+            return Err(Error::NoPeers);
+        }
+        let payload = self.seal(serialisation::serialise(message)?);
+        match self.topics.dag_mut(topic) {
+            Some(dag) => dag.new_payload(payload, &self.keys),
+            None => Err(Error::NotSubscribed(topic.to_string())),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use itertools::{self, Itertools};
-    use maidsafe_utilities::SeededRng;
-    use rand::Rng;
-    use std::collections::BTreeMap;
+    // Send a new message, tagging its unit with the content type if given, else attaching the
+    // metadata.
+    fn send_payload<T: Serialize>(
+        &mut self,
+        message: &T,
+        content_type: Option<ContentType>,
+        metadata: BTreeMap<String, Vec<u8>>,
+    ) -> Result<UnitId, Error> {
+        if self.peers.is_empty() && !self.single_node {
+            return Err(Error::NoPeers);
+        }
+        let payload = self.seal(serialisation::serialise(message)?);
+        let start = clock::now();
+        let result = match content_type {
+            Some(content_type) => self.dag.new_typed_payload(payload, content_type, &self.keys),
+            None => self.dag.new_payload_with_metadata(payload, metadata, &self.keys),
+        };
+        self.telemetry.check(Operation::NewPayload, start, self.dag.len());
+        let _ = self.dispatch_events();
+        let identifier = result?;
+        self.record_sent(&[identifier]);
+        self.take_violation()?;
+        Ok(identifier)
+    }
 
-    fn create_network(node_count: u32) -> Vec<Gossiper> {
-        let mut gossipers = itertools::repeat_call(Gossiper::default)
-            .take(node_count as usize)
-            .collect_vec();
-        // Connect all the gossipers.
-        for i in 0..(gossipers.len() - 1) {
-            let lhs_id = gossipers[i].id();
-            for j in (i + 1)..gossipers.len() {
-                let rhs_id = gossipers[j].id();
-                let _ = gossipers[j].add_peer(lhs_id);
-                let _ = gossipers[i].add_peer(rhs_id);
+    // Account for the units holding the messages we just sent, and queue our attestations of
+    // them.
+    fn record_sent(&mut self, identifiers: &[UnitId]) {
+        self.statistics.units_created += identifiers.len() as u64;
+        if self.strict_checks {
+            let result = invariants::check_invariants(&self.dag);
+            self.enforce(result);
+        }
+        let own_id = self.id();
+        for identifier in identifiers {
+            if let Some(attestation) = self.dag.attestation(identifier, &own_id) {
+                self.fresh_attestations.push((self.round, attestation));
             }
         }
-        gossipers
     }
 
-    fn send_messages(gossipers: &mut Vec<Gossiper>, num_of_msgs: u32, rng: &mut SeededRng) {
-        let mut msg_pool: Vec<Vec<Vec<u8>>> = Vec::new();
-        let mut msgs = Vec::new();
-        for j in 1..(num_of_msgs + 1) as u8 {
-            msgs.push(vec![j, j, j]);
+    /// Start a new round.
+    ///
+    /// While bootstrapping, no round is started: the message returned is the request for the next
+    /// page, to the peer bootstrapping from.
+    pub fn next_round(&mut self) -> Result<(Id, Vec<u8>), Error> {
+        if let Some((peer_id, request)) = self.pending_page_request() {
+            return Ok((peer_id, self.prepare_to_send(request)?));
         }
-        for _ in 0..gossipers.len() as u8 {
-            msg_pool.push(msgs.clone())
+        let peer_id = match self.choose_peer(&[]) {
+            Some(id) => id,
+            None => return Err(Error::NoPeers),
+        };
+        let full_sync = self.start_round();
+        let message = self.gossip_to(&peer_id, full_sync)?;
+        Ok((peer_id, message))
+    }
+
+    /// Start a new round, gossiping to as many distinct peers as the fanout, or to all of them if
+    /// we have fewer, and pushing the DAG of each topic subscribed to to one of the peers which
+    /// subscribed to it too. Returns the message for each chosen peer.
+    ///
+    /// While bootstrapping, no round is started: the only message returned is the request for the
+    /// next page, to the peer bootstrapping from.
+    pub fn next_round_multi(&mut self) -> Result<Vec<(Id, Vec<u8>)>, Error> {
+        if let Some((peer_id, request)) = self.pending_page_request() {
+            return Ok(vec![(peer_id, self.prepare_to_send(request)?)]);
+        }
+        let fanout = self.fanout;
+        let peers = self.choose_peers(fanout);
+        if peers.is_empty() {
+            return Err(Error::NoPeers);
+        }
+        let full_sync = self.start_round();
+        let mut messages = Vec::with_capacity(peers.len());
+        for peer_id in peers {
+            let message = self.gossip_to(&peer_id, full_sync)?;
+            messages.push((peer_id, message));
         }
+        messages.extend(self.topic_pushes()?);
+        Ok(messages)
+    }
 
-        // Polling
-        // TODO: need to decide a termination condition. So far, it's using a fixed rounds.
-        //       The number of rounds shall be adjusted in relation to the network size.
-        //       The result from safe_gossip can be a reference.
-        for _ in 0..30 {
-            let mut messages = BTreeMap::new();
-            // Call `next_round()` on each node to gather a list of serialised DAG.
-            for (i, gossiper) in gossipers.iter_mut().enumerate() {
-                if !msg_pool[i].is_empty() && rng.gen() {
-                    let index = rng.gen_range(0, msg_pool[i].len() as u32) as usize;
-                    let _ = gossiper.send_new(&msg_pool[i][index].clone());
-                    let _ = msg_pool[i].remove(index);
-                }
-                let (dst_id, push_msg) = unwrap!(gossiper.next_round());
-                let _ = messages.insert((gossiper.id(), dst_id), push_msg);
-            }
+    // The request for the next page of the bootstrap in progress, if any, with the peer to send it
+    // to.
+    fn pending_page_request(&self) -> Option<(Id, Message)> {
+        self.bootstrap.as_ref().map(|bootstrap| (bootstrap.peer, bootstrap.request()))
+    }
 
-            // Send all Push DAGs.
-            for ((src_id, dst_id), push_msg) in messages {
-                let mut dst = unwrap!(gossipers.iter_mut().find(|node| node.id() == dst_id));
-                dst.handle_received_message(&src_id, &push_msg);
-            }
+    /// Start bootstrapping from `peer_id`: fetching all the units it holds, from its checkpoint on,
+    /// in pages of up to `page_size` units in total order. Meant for a node joining a network whose
+    /// DAG is already large, before it takes part in gossip rounds. Returns the request for the
+    /// first page, to be sent to the peer; the request for each next page is returned as the
+    /// response to the previous one, and again by `next_round` or `next_round_multi` if a page got
+    /// lost. Progress is reported by `catch_up_progress` and `Event::CatchingUp`.
+    ///
+    /// Fails with `Error::UnknownPeer` if `peer_id` isn't one of our peers.
+    pub fn start_bootstrap(&mut self, peer_id: &Id, page_size: u32) -> Result<Vec<u8>, Error> {
+        if !self.peers.contains(peer_id) {
+            return Err(Error::UnknownPeer(*peer_id));
         }
+        let page_size = cmp::min(cmp::max(page_size, 1), MAX_BOOTSTRAP_PAGE);
+        let bootstrap = Bootstrap::new(*peer_id, page_size);
+        let request = bootstrap.request();
+        self.bootstrap = Some(bootstrap);
+        self.prepare_to_send(request)
+    }
 
-        for gossiper in gossipers {
-            gossiper.print_dag();
+    /// Whether a bootstrap started by `start_bootstrap` is still in progress.
+    pub fn is_bootstrapping(&self) -> bool {
+        self.bootstrap.is_some()
+    }
+
+    /// How our DAG differs from `peer_id`'s, as far as we know: empty if the digest carried by the
+    /// last message received from it matches ours, otherwise as per `Dag::diff_versions` against
+    /// the units the peer is known to hold, having been sent them by us or having sent them to us.
+    /// Meant for debugging a partition once healed, and for checking convergence in tests.
+    ///
+    /// Fails with `Error::UnknownPeer` if `peer_id` isn't one of our peers.
+    pub fn divergence_from(&self, peer_id: &Id) -> Result<DagDiff, Error> {
+        if !self.peers.contains(peer_id) {
+            return Err(Error::UnknownPeer(*peer_id));
+        }
+        if self.peer_digests.get(peer_id) == Some(&self.dag.digest()) {
+            return Ok(DagDiff::default());
+        }
+        Ok(match self.peer_cursors.get(peer_id) {
+            Some(versions) => self.dag.diff_versions(versions),
+            None => self.dag.diff_versions(&BTreeMap::new()),
+        })
+    }
+
+    /// A request for the units with the given identifiers, to be sent to a peer. The peer responds
+    /// with those it holds, which are merged in when its response is handled.
+    pub fn fetch_units(&mut self, identifiers: Vec<UnitId>) -> Result<Vec<u8>, Error> {
+        self.note_missing(identifiers.iter().cloned());
+        self.prepare_to_send(Message::FetchUnits(identifiers))
+    }
+
+    /// An advertisement of the identifiers of all the units we hold, to be sent to a peer. The
+    /// peer responds with a request for those it is missing.
+    pub fn advertise_units(&mut self) -> Result<Vec<u8>, Error> {
+        let identifiers = self.dag.unit_versions().keys().cloned().collect();
+        self.prepare_to_send(Message::Have(identifiers))
+    }
+
+    /// Handles an incoming message from peer. Returns the response to be sent back to the peer,
+    /// if any: the units requested by the peer, a request for the units it advertised or referred
+    /// to as parents which we are missing, or a NACK of the units it sent which were rejected.
+    ///
+    /// Fails if the peer is muted for exceeding the rate limit, in which case the message is
+    /// dropped unread, if the message was sent with a protocol version or a wire format we don't
+    /// support, or if it couldn't be decoded. A message which couldn't be decoded is first handled
+    /// according to the `DecodeFailurePolicy`: under `RequestRetransmission`, the request for the
+    /// units we are missing is returned as the response rather than the error. With the `bare-dag`
+    /// feature, a message which isn't an envelope but a bare serialised `Dag`, as sent by older
+    /// peers, is merged like a full DAG. Under strict checks, also fails with
+    /// `Error::InvariantViolated` if handling the message broke an invariant.
+    pub fn handle_received_message(
+        &mut self,
+        peer_id: &Id,
+        serialised_msg: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let response = self.receive_message(peer_id, serialised_msg);
+        self.take_violation()?;
+        response
+    }
+
+    // Handle an incoming message as per `handle_received_message`, but for strict checks.
+    fn receive_message(
+        &mut self,
+        peer_id: &Id,
+        serialised_msg: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        debug!("{:?} handling message from {:?}", self, peer_id);
+        if !self.accept_message(peer_id) {
+            debug!("{:?} dropped message from muted {:?}", self, peer_id);
+            return Err(Error::PeerMuted(*peer_id));
+        }
+        let _ = self.heard_from.insert(*peer_id);
+        if let Some(score) = self.peer_score_mut(peer_id) {
+            score.messages_received += 1;
+        }
+        self.statistics.bytes_received += serialised_msg.len() as u64;
+        let decoded = wire::decode_bounded(serialised_msg, self.config.max_message_bytes);
+        if decoded.is_err() {
+            if let Some(dag) = decode_bare_dag(serialised_msg, self.config.max_message_bytes) {
+                debug!("{:?} received a bare DAG from {:?}", self, peer_id);
+                let merged = self.merge_from(peer_id, &dag);
+                return Ok(self.respond_to_merge(peer_id, merged));
+            }
+        }
+        let envelope: Envelope = match decoded {
+            Ok(envelope) => envelope,
+            Err(Error::IncompatibleProtocol(version)) => {
+                warn!(
+                    "{:?} dropped message from {:?} speaking protocol version {}",
+                    self,
+                    peer_id,
+                    version
+                );
+                self.publish(vec![Event::IncompatibleMessage { peer: *peer_id }]);
+                return Err(Error::IncompatibleProtocol(version));
+            }
+            Err(Error::UnsupportedWireFormat(tag)) => {
+                warn!("{:?} dropped message from {:?} in wire format {}", self, peer_id, tag);
+                self.publish(vec![Event::IncompatibleMessage { peer: *peer_id }]);
+                return Err(Error::UnsupportedWireFormat(tag));
+            }
+            Err(error) => {
+                error!("{:?} failed to deserialise message from {:?}: {}", self, peer_id, error);
+                return self.handle_malformed(peer_id, error);
+            }
+        };
+        // Units hashed differently would all fail validation: the peer isn't at fault, merely
+        // misconfigured.
+        if envelope.hash_algorithm != self.dag.hasher().tag() {
+            warn!(
+                "{:?} dropped message from {:?} using hash algorithm {}",
+                self,
+                peer_id,
+                envelope.hash_algorithm
+            );
+            self.publish(vec![Event::IncompatibleMessage { peer: *peer_id }]);
+            return Err(Error::HashAlgorithmMismatch(envelope.hash_algorithm));
+        }
+        if let Err(error) = self.check_message_limits(&envelope.message) {
+            warn!("{:?} rejected message from {:?}: {}", self, peer_id, error);
+            return self.handle_malformed(peer_id, error);
+        }
+        let _ = self.peer_capabilities.insert(*peer_id, envelope.capabilities);
+        let _ = self.peer_digests.insert(*peer_id, envelope.digest);
+        if let Some(extensions) = envelope.extensions {
+            self.receive_extensions(peer_id, &envelope.message, &extensions);
+        }
+        // With the same units on both sides, a round's push or pull has nothing to exchange.
+        let in_sync = envelope.digest == self.dag.digest();
+        let response = match envelope.message {
+            Message::Full(_) |
+            Message::Delta(_) |
+            Message::PullResponse(_) |
+            Message::Pull(_) |
+            Message::PushPull(..) |
+            Message::InSync if in_sync => None,
+            Message::Full(dag) |
+            Message::Delta(dag) |
+            Message::PullResponse(dag) => {
+                match self.receive_dag(peer_id, dag) {
+                    Ok(merged) => self.respond_to_merge(peer_id, merged),
+                    Err(error) => return self.handle_malformed(peer_id, error),
+                }
+            }
+            Message::Pull(versions) => {
+                self.note_missing(versions.keys().cloned());
+                self.pull_response(peer_id, &versions)
+            }
+            Message::PushPull(dag, versions) => {
+                self.note_missing(versions.keys().cloned());
+                // Only a single response can be sent: answering the pull request takes priority
+                // over requesting missing parents or rejecting units.
+                match self.receive_dag(peer_id, dag) {
+                    Ok(_) => self.pull_response(peer_id, &versions),
+                    Err(error) => return self.handle_malformed(peer_id, error),
+                }
+            }
+            Message::FetchUnits(identifiers) => self.fetch_response(peer_id, &identifiers),
+            Message::Units(units) => {
+                let dag = self.dag.partial(units);
+                let merged = self.merge_from(peer_id, &dag);
+                self.respond_to_merge(peer_id, merged)
+            }
+            Message::Have(identifiers) => {
+                let missing = identifiers
+                    .into_iter()
+                    .filter(|identifier| !self.dag.contains(identifier))
+                    .collect();
+                self.request_missing(peer_id, missing)
+            }
+            Message::Nack(nack) => self.handle_nack(peer_id, nack),
+            // The peer's view of our DAG is stale: push ours.
+            Message::InSync => self.push_response(peer_id),
+            Message::Members(members) => {
+                self.learn_members(peer_id, members);
+                None
+            }
+            Message::Subscriptions(topics) => {
+                if self.peers.contains(peer_id) {
+                    self.topics.set_peer_topics(*peer_id, topics);
+                    self.update_majority();
+                }
+                None
+            }
+            Message::TopicPush { topic, dag } => {
+                self.receive_topic_push(peer_id, &topic, dag);
+                None
+            }
+            Message::BootstrapRequest { start, count } => {
+                self.bootstrap_response(peer_id, start, count)
+            }
+            Message::BootstrapPage { start, total, dag } => {
+                let bytes = serialised_msg.len();
+                self.receive_bootstrap_page(peer_id, start, total, dag, bytes)
+            }
+        };
+        let response = self.reconcile_membership(peer_id, envelope.membership, response);
+        for attestation in &envelope.attestations {
+            let _ = self.dag.apply_attestation(attestation);
+        }
+        let _ = self.dispatch_events();
+        self.report_catch_up(serialised_msg.len());
+        Ok(response)
+    }
+
+    /// How far catching up with the units held by peers got, while any is known to be missing, or
+    /// how far bootstrapping got, once the first page arrived. Also reported to subscribers by
+    /// `Event::CatchingUp`.
+    pub fn catch_up_progress(&self) -> Option<CatchUpProgress> {
+        match self.bootstrap {
+            Some(ref bootstrap) => bootstrap.progress(),
+            None => self.catch_up.as_ref().and_then(CatchUp::last_reported),
+        }
+    }
+
+    /// The payloads, as serialised by `send_new`, which became deliverable since the last call,
+    /// in total order. Delivery follows the total order of `Dag::ordered_units` and stops at the
+    /// first unit which isn't stable yet, so that a payload is only delivered once all the
+    /// payloads ordered before it are. With a group key set, payloads are opened first, and those
+    /// which can't be are skipped.
+    pub fn poll_ordered(&mut self) -> Vec<Vec<u8>> {
+        self.poll_ordered_typed()
+            .into_iter()
+            .map(|(payload, _)| payload)
+            .collect()
+    }
+
+    /// As `poll_ordered`, along with the content type of each payload, if its unit has one.
+    pub fn poll_ordered_typed(&mut self) -> Vec<(Vec<u8>, Option<ContentType>)> {
+        let mut payloads = Vec::new();
+        for unit in self.dag.ordered_units() {
+            if self.delivered.contains(&unit.identifier) {
+                continue;
+            }
+            if !self.dag.is_stable(unit) {
+                break;
+            }
+            let _ = self.delivered.insert(unit.identifier);
+            if self.dag.is_superseded(unit) {
+                continue;
+            }
+            match self.open(&unit.payload) {
+                Ok(payload) => payloads.push((payload, unit.content_type.clone())),
+                Err(error) => {
+                    warn!("{:?} skipped payload of {:?}: {}", self, unit.identifier, error)
+                }
+            }
+        }
+        payloads
+    }
+
+    /// The equivocations detected in the DAGs received from peers, for the caller to punish the
+    /// offending observers, e.g. by removing them with `remove_peer`.
+    pub fn detected_faults(&self) -> Vec<FaultProof> {
+        self.dag.detected_faults()
+    }
+
+    /// Serialise our state for persistence across restarts: the DAG, the peers, the stability
+    /// settings, and what each peer is known to hold and what was delivered by `poll_ordered`.
+    /// Other settings, e.g. the gossip strategy, aren't persisted and have to be set again.
+    pub fn snapshot(&self) -> Result<Vec<u8>, Error> {
+        let state = GossiperState {
+            owner: self.id(),
+            dag: self.dag.save()?,
+            peers: self.peers.clone(),
+            single_node: self.single_node,
+            small_network_mode: self.small_network_mode,
+            round: self.round,
+            peer_cursors: self.peer_cursors.clone(),
+            delivered: self.delivered.clone(),
+        };
+        snapshot::encode_versioned(GOSSIPER_FORMAT_VERSION, &state)
+    }
+
+    /// Restore a gossiper from a snapshot taken by `snapshot`, possibly by an older version of
+    /// this crate. Fails with `Error::ForeignSnapshot` if the snapshot wasn't taken by the owner
+    /// of `keys`.
+    pub fn restore(bytes: &[u8], keys: Keypair) -> Result<Self, Error> {
+        let state: GossiperState = match snapshot::decode_versioned(bytes)? {
+            (GOSSIPER_FORMAT_VERSION, body) => serialisation::deserialise(&body)?,
+            (version, _) => return Err(Error::UnsupportedVersion(version)),
+        };
+        if state.owner != Id::from(keys.public) {
+            return Err(Error::ForeignSnapshot(state.owner));
+        }
+        let mut gossiper = Gossiper::with_dag(keys, Dag::load(&state.dag)?);
+        gossiper.peers = state.peers;
+        gossiper.single_node = state.single_node;
+        gossiper.small_network_mode = state.small_network_mode;
+        gossiper.round = state.round;
+        gossiper.peer_cursors = state.peer_cursors;
+        gossiper.delivered = state.delivered;
+        gossiper.update_majority();
+        Ok(gossiper)
+    }
+
+    /// Create a gossiper owning `keys`, whose DAG is persisted to the journal kept in `dir`, and
+    /// recovered from it if it exists. The journal is compacted every `compact_every` changes.
+    /// Gossiper-level state, e.g. the peers, isn't journaled: use `snapshot` for it.
+    pub fn with_journal<P: AsRef<Path>>(
+        dir: P,
+        keys: Keypair,
+        compact_every: usize,
+    ) -> Result<Self, Error> {
+        let (journal, mut dag) = Journal::open(dir, &keys, compact_every)?;
+        dag.set_journaling(true);
+        let mut gossiper = Gossiper::with_dag(keys, dag);
+        gossiper.journal = Some(journal);
+        Ok(gossiper)
+    }
+
+    /// Our DAG, signed by us along with the current round and time, for external audit.
+    pub fn export_signed_snapshot(&self) -> Result<SignedSnapshot, Error> {
+        SignedSnapshot::new(&self.dag, self.round, &self.keys)
+    }
+
+    /// Counters of our activity since we were created or they were last reset.
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// Reset all the counters of `statistics`. The units held but not stable yet still have their
+    /// time to stability measured from when we first held them.
+    pub fn reset_statistics(&mut self) {
+        self.statistics = Statistics::default();
+    }
+
+    /// Breakdown of the units held in our DAG by age and payload size, to help picking a
+    /// checkpoint which reclaims the most memory for the least history lost.
+    pub fn unit_breakdown(&self) -> UnitBreakdown {
+        self.dag.unit_breakdown()
+    }
+
+    // Advance the round count and everything aged per round. Returns whether the round shall be a
+    // full sync, as asked by the watchdog or as an anti-entropy round.
+    fn start_round(&mut self) -> bool {
+        self.round += 1;
+        self.statistics.rounds += 1;
+        self.age_rumors();
+        for peer_id in &self.peers {
+            let score = self.peer_scores.entry(*peer_id).or_insert_with(PeerScore::default);
+            score.rounds_since_contact += 1;
+        }
+        self.forget_settled_units();
+        let watchdog_sync = self.check_watchdog();
+        let anti_entropy = match self.anti_entropy_every {
+            Some(every) => self.round % cmp::max(u64::from(every), 1) == 0,
+            None => false,
+        };
+        watchdog_sync || anti_entropy
+    }
+
+    // The message gossiping to `peer_id` this round.
+    fn gossip_to(&mut self, peer_id: &Id, full_sync: bool) -> Result<Vec<u8>, Error> {
+        if let Some(score) = self.peer_score_mut(peer_id) {
+            score.messages_sent += 1;
+            score.rounds_since_contact = 0;
+        }
+        let capabilities = self.negotiated_capabilities(peer_id);
+        if capabilities.digest && !full_sync &&
+            self.peer_digests.get(peer_id) == Some(&self.dag.digest())
+        {
+            debug!("{:?} in sync with {:?}", self, peer_id);
+            self.statistics.in_sync_rounds += 1;
+            return self.prepare_to_send(Message::InSync);
+        }
+        let delta_supported = capabilities.delta && !full_sync;
+        let strategy = if !capabilities.pull {
+            GossipStrategy::Push
+        } else if full_sync {
+            GossipStrategy::PushPull
+        } else {
+            self.strategy
+        };
+        let message = match strategy {
+            GossipStrategy::Push => self.push_to(peer_id, delta_supported, !full_sync)?,
+            GossipStrategy::Pull => Message::Pull(self.dag.unit_versions()),
+            GossipStrategy::PushPull => {
+                let (dag, _) = self.rumors_to_push(peer_id, delta_supported, !full_sync);
+                let dag = self.encode_for(peer_id, dag)?;
+                Message::PushPull(dag, self.dag.unit_versions())
+            }
+        };
+        let message = self.prepare_to_send(message)?;
+        debug!(
+            "{:?} gossiping to {:?} with DAG {:?}",
+            self,
+            peer_id,
+            self.dag
+        );
+        Ok(message)
+    }
+
+    // A push of our DAG, or of the delta the peer is missing if it handles deltas, without the
+    // cold rumors if `suppress_cold`.
+    fn push_to(
+        &mut self,
+        peer_id: &Id,
+        delta_supported: bool,
+        suppress_cold: bool,
+    ) -> Result<Message, Error> {
+        let (dag, full) = self.rumors_to_push(peer_id, delta_supported, suppress_cold);
+        let dag = self.encode_for(peer_id, dag)?;
+        if full {
+            Ok(Message::Full(dag))
+        } else {
+            Ok(Message::Delta(dag))
+        }
+    }
+
+    // Up to `count` distinct peers, chosen as per the peer selection.
+    fn choose_peers(&mut self, count: usize) -> Vec<Id> {
+        let distinct = self.peers.iter().collect::<BTreeSet<_>>().len();
+        let count = cmp::min(count, distinct);
+        let mut chosen = Vec::with_capacity(count);
+        while chosen.len() < count {
+            match self.choose_peer(&chosen) {
+                Some(peer_id) => {
+                    if !chosen.contains(&peer_id) {
+                        chosen.push(peer_id);
+                    }
+                }
+                None => break,
+            }
+        }
+        chosen
+    }
+
+    // A peer, chosen as per the custom peer selector if any, else the peer selection, unless a
+    // peer which didn't observe a stalled unit is to be chosen first. The custom selector and the
+    // adaptive selection never choose a peer in `exclude`.
+    fn choose_peer(&mut self, exclude: &[Id]) -> Option<Id> {
+        if let Some(peer_id) = self.choose_stalled_target(exclude) {
+            return Some(peer_id);
+        }
+        if self.peer_selector.is_some() {
+            return self.choose_scored_peer(exclude);
+        }
+        match self.peer_selection {
+            PeerSelection::Adaptive => self.choose_scored_peer(exclude),
+            PeerSelection::Uniform => {
+                match self.rng {
+                    Some(ref mut rng) => rng.choose(&self.peers).cloned(),
+                    None => fallback_rng().choose(&self.peers).cloned(),
+                }
+            }
+            PeerSelection::Permutation => {
+                if self.peer_cycle.is_empty() {
+                    self.peer_cycle = self.peers.clone();
+                    match self.rng {
+                        Some(ref mut rng) => rng.shuffle(&mut self.peer_cycle),
+                        None => fallback_rng().shuffle(&mut self.peer_cycle),
+                    }
+                }
+                self.peer_cycle.pop()
+            }
+        }
+    }
+
+    // A peer not in `exclude`, chosen given the peer scores by the custom peer selector if any,
+    // else at random as per their weights.
+    fn choose_scored_peer(&mut self, exclude: &[Id]) -> Option<Id> {
+        let candidates: BTreeMap<Id, PeerScore> = self.peer_scores()
+            .into_iter()
+            .filter(|&(peer_id, _)| !exclude.contains(&peer_id))
+            .collect();
+        if let Some(ref mut selector) = self.peer_selector {
+            return match self.rng {
+                Some(ref mut rng) => selector.choose(&candidates, &mut **rng),
+                None => selector.choose(&candidates, &mut fallback_rng()),
+            };
+        }
+        match self.rng {
+            Some(ref mut rng) => peer_score::choose_weighted(&candidates, &mut **rng),
+            None => peer_score::choose_weighted(&candidates, &mut fallback_rng()),
+        }
+    }
+
+    fn rumor_rounds(&self) -> u32 {
+        self.rumor_rounds.unwrap_or_else(|| {
+            let nodes = self.peers.len() as u64 + 1;
+            64 - nodes.leading_zeros() + 1
+        })
+    }
+
+    // Count one more round for every unit held, restarting the count of those which changed.
+    fn age_rumors(&mut self) {
+        let versions = self.dag.unit_versions();
+        self.rumors.retain(
+            |identifier, _| versions.contains_key(identifier),
+        );
+        for (identifier, version) in versions {
+            let rumor = self.rumors.entry(identifier).or_insert_with(|| Rumor::new(version));
+            if rumor.version != version {
+                *rumor = Rumor::new(version);
+            }
+            rumor.rounds = rumor.rounds.saturating_add(1);
+        }
+    }
+
+    // Whether the unit is a cold rumor, to be left out of round pushes: unchanged since it was
+    // carried by as many pushes as rounds a rumor takes to turn cold, or since every peer observed
+    // it.
+    fn is_cold_rumor(&self, unit: &Unit) -> bool {
+        match self.rumors.get(&unit.identifier) {
+            Some(rumor) if rumor.version == unit.version() => {
+                rumor.pushes >= self.rumor_rounds() ||
+                    self.peers.iter().all(|peer| unit.observers.contains(peer))
+            }
+            _ => false,
+        }
+    }
+
+    // Recompute the stability threshold from the current membership. As stability is evaluated
+    // against the threshold in use, units already held become stable, or stop being so, at once.
+    fn update_majority(&mut self) {
+        let members = self.members().len();
+        let threshold = quorum::stability_threshold(members - 1, self.small_network_mode);
+        self.dag.set_majority(threshold as u8);
+        self.dag.set_members(members);
+        self.update_authorised_members();
+        self.topics.update_majorities(self.small_network_mode);
+        let _ = self.dispatch_events();
+    }
+
+    // Restrict the observers of units received to our peers and ourself, if checked.
+    fn update_authorised_members(&mut self) {
+        match self.membership_check {
+            Some(check) => {
+                let members = self.members();
+                self.dag.set_authorised_members(Some(members), check);
+            }
+            None => self.dag.set_authorised_members(None, MembershipCheck::default()),
+        }
+    }
+
+    fn forget_peer(&mut self, peer_id: &Id) {
+        let _ = self.peer_cursors.remove(peer_id);
+        let _ = self.heard_from.remove(peer_id);
+        let _ = self.penalties.remove(peer_id);
+        let _ = self.peer_capabilities.remove(peer_id);
+        let _ = self.peer_digests.remove(peer_id);
+        let _ = self.peer_usage.remove(peer_id);
+        let _ = self.peer_scores.remove(peer_id);
+        self.topics.forget_peer(peer_id);
+        let _ = self.learned_members.remove(peer_id);
+        let _ = self.peer_memberships.remove(peer_id);
+        let _ = self.members_owed.remove(peer_id);
+        if self.bootstrap.as_ref().map_or(false, |bootstrap| bootstrap.peer == *peer_id) {
+            warn!("{:?} abandoned bootstrapping from removed {:?}", self, peer_id);
+            self.bootstrap = None;
+        }
+    }
+
+    // The score of the peer, or `None` if it isn't one of our peers.
+    fn peer_score_mut(&mut self, peer_id: &Id) -> Option<&mut PeerScore> {
+        if !self.peers.contains(peer_id) {
+            return None;
+        }
+        Some(self.peer_scores.entry(*peer_id).or_insert_with(PeerScore::default))
+    }
+
+    // Count a message from the peer against the rate limit. Returns whether it is accepted,
+    // publishing `Event::PeerMuted` if it got the peer muted.
+    fn accept_message(&mut self, peer_id: &Id) -> bool {
+        let limit = match self.rate_limit {
+            Some(limit) => limit,
+            None => return true,
+        };
+        let now = match clock::now() {
+            Some(now) => now,
+            None => return true,
+        };
+        let (accepted, newly_muted) = {
+            let usage = self.peer_usage.entry(*peer_id).or_insert_with(|| PeerUsage::new(now));
+            let was_muted = usage.is_muted(now);
+            let accepted = usage.record_message(&limit, now);
+            (accepted, !was_muted && usage.is_muted(now))
+        };
+        if newly_muted {
+            self.mute(peer_id);
+        }
+        accepted
+    }
+
+    // Count the new units merged from the peer against the rate limit.
+    fn record_new_units(&mut self, peer_id: &Id, count: usize) {
+        let limit = match self.rate_limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        let now = match clock::now() {
+            Some(now) => now,
+            None => return,
+        };
+        let newly_muted = {
+            let usage = self.peer_usage.entry(*peer_id).or_insert_with(|| PeerUsage::new(now));
+            let was_muted = usage.is_muted(now);
+            usage.record_new_units(count, &limit, now);
+            !was_muted && usage.is_muted(now)
+        };
+        if newly_muted {
+            self.mute(peer_id);
+        }
+    }
+
+    fn mute(&mut self, peer_id: &Id) {
+        warn!("{:?} muted {:?} for exceeding the rate limit", self, peer_id);
+        self.publish(vec![Event::PeerMuted { peer: *peer_id }]);
+    }
+
+    // Decode and union the DAG received from the peer. Fails if the DAG can't be decoded.
+    fn receive_dag(&mut self, peer_id: &Id, dag: WireDag) -> Result<Merged, Error> {
+        match dag.decode() {
+            Ok(dag) => Ok(self.merge_from(peer_id, &dag)),
+            Err(error) => {
+                error!("{:?} failed to decode DAG from {:?}: {}", self, peer_id, error);
+                Err(error)
+            }
+        }
+    }
+
+    // Union the DAG received from the peer, recording that the peer holds its units.
+    fn merge_from(&mut self, peer_id: &Id, dag: &Dag) -> Merged {
+        self.record_known(peer_id, &dag.unit_versions());
+        let duplicates = dag.units()
+            .filter(|unit| self.dag.contains(&unit.identifier))
+            .count();
+        self.statistics.duplicate_units += duplicates as u64;
+        #[cfg(feature = "chaos")]
+        self.delay_union();
+        let before = if self.strict_checks {
+            Some(self.dag.clone())
+        } else {
+            None
+        };
+        let start = clock::now();
+        let result = self.dag.union(dag);
+        self.telemetry.check(Operation::Union, start, self.dag.len());
+        if let Some(before) = before {
+            let result = invariants::check_invariants(&self.dag)
+                .and_then(|()| invariants::check_stability_monotone(&before, &self.dag));
+            self.enforce(result);
+        }
+        let new_units = self.dispatch_events();
+        self.statistics.units_received += new_units as u64;
+        self.record_new_units(peer_id, new_units);
+        if let Some(score) = self.peer_score_mut(peer_id) {
+            score.new_units += new_units as u64;
+        }
+        let succeeded = result.is_ok();
+        let merged = match result {
+            Ok(()) => Merged::Missing(vec![]),
+            Err(Error::MissingParent(parent)) => {
+                debug!("{:?} missing {:?} from {:?}", self, parent, peer_id);
+                Merged::Missing(vec![parent])
+            }
+            Err(error) => {
+                error!("{:?} failed to union DAG from {:?}: {}", self, peer_id, error);
+                match Nack::for_error(&error) {
+                    Some(nack) => {
+                        self.statistics.rejected_units += nack.identifiers.len() as u64;
+                        Merged::Rejected(nack)
+                    }
+                    None => Merged::Missing(vec![]),
+                }
+            }
+        };
+        if let Some(ref mut watchdog) = self.watchdog {
+            let missing_parent = match merged {
+                Merged::Missing(ref missing) => missing.first().cloned(),
+                Merged::Rejected(_) => None,
+            };
+            watchdog.record_union(succeeded, missing_parent);
+        }
+        merged
+    }
+
+    // The response to the peer whose DAG was merged: a request for the missing parents, or a NACK
+    // of the rejected units if the peer handles those.
+    fn respond_to_merge(&mut self, peer_id: &Id, merged: Merged) -> Option<Vec<u8>> {
+        match merged {
+            Merged::Missing(missing) => self.request_missing(peer_id, missing),
+            Merged::Rejected(nack) => {
+                if !self.negotiated_capabilities(peer_id).nack {
+                    return None;
+                }
+                match self.prepare_to_send(Message::Nack(nack)) {
+                    Ok(response) => Some(response),
+                    Err(error) => {
+                        error!("{:?} failed to reject units of {:?}: {}", self, peer_id, error);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    // A peer rejected units we sent: our DAG may be corrupted. Let subscribers know, and pull the
+    // peer's units to resync if it answers pull requests. Our indices aren't rebuilt, as a peer
+    // could have us do so on every message, and rebuilding them from the same units wouldn't
+    // repair the units themselves: that is left to the watchdog, if configured to.
+    fn handle_nack(&mut self, peer_id: &Id, nack: Nack) -> Option<Vec<u8>> {
+        warn!("{:?} had units rejected by {:?}: {:?}", self, peer_id, nack);
+        self.publish(vec![Event::Rejected {
+            peer: *peer_id,
+            nack,
+        }]);
+        if !self.negotiated_capabilities(peer_id).pull {
+            return None;
+        }
+        let request = Message::Pull(self.dag.unit_versions());
+        match self.prepare_to_send(request) {
+            Ok(request) => Some(request),
+            Err(error) => {
+                error!("{:?} failed to request from {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // Check for stalls, taking the configured corrective actions on any. Returns whether the
+    // round shall be a full sync.
+    fn check_watchdog(&mut self) -> bool {
+        let (stalls, stalled_units) = match self.watchdog {
+            Some(ref mut watchdog) => {
+                let dag = &self.dag;
+                let peers_live = !self.heard_from.is_empty();
+                let progress_expected = peers_live && !dag.all_stable();
+                let stalls = watchdog.check(dag.stable_count(), progress_expected, |parent| {
+                    dag.contains(parent)
+                });
+                let unstable = dag.units()
+                    .filter(|unit| !dag.is_stable(unit))
+                    .map(|unit| unit.identifier);
+                (stalls, watchdog.check_units(unstable, peers_live))
+            }
+            None => return false,
+        };
+        if stalls.is_empty() && stalled_units.is_empty() {
+            return false;
+        }
+        let config = match self.watchdog {
+            Some(ref watchdog) => watchdog.config,
+            None => return false,
+        };
+        for stall in &stalls {
+            warn!("{:?} stalled: {:?}", self, stall);
+        }
+        for &(identifier, rounds) in &stalled_units {
+            warn!("{:?} unit {:?} unstable for {} rounds", self, identifier, rounds);
+        }
+        if config.rebuild_indices && !stalls.is_empty() {
+            self.dag.rebuild_indices();
+        }
+        if config.alert {
+            let mut events: Vec<Event> = stalls
+                .iter()
+                .map(|&stall| Event::Stalled { stall })
+                .collect();
+            events.extend(stalled_units.into_iter().map(|(identifier, rounds)| {
+                Event::UnitStalled { identifier, rounds }
+            }));
+            self.publish(events);
+        }
+        config.full_sync && !stalls.is_empty()
+    }
+
+    // The stalled units to regossip aggressively, if the watchdog is configured so.
+    fn regossiped_units(&self) -> BTreeSet<UnitId> {
+        match self.watchdog {
+            Some(ref watchdog) if watchdog.config.regossip_stalled => {
+                watchdog.stalled_units().clone()
+            }
+            _ => BTreeSet::new(),
+        }
+    }
+
+    // A peer not in `exclude` which didn't observe one of the units to regossip, if any, chosen
+    // at random.
+    fn choose_stalled_target(&mut self, exclude: &[Id]) -> Option<Id> {
+        let mut candidates: BTreeSet<Id> = BTreeSet::new();
+        for identifier in self.regossiped_units() {
+            if let Some(unit) = self.dag.get(&identifier) {
+                candidates.extend(self.peers.iter().cloned().filter(|peer| {
+                    !exclude.contains(peer) && !unit.observers.contains(peer)
+                }));
+            }
+        }
+        let candidates: Vec<Id> = candidates.into_iter().collect();
+        match self.rng {
+            Some(ref mut rng) => rng.choose(&candidates).cloned(),
+            None => fallback_rng().choose(&candidates).cloned(),
+        }
+    }
+
+    // A request to the peer for the missing units, if any.
+    fn request_missing(&mut self, peer_id: &Id, missing: Vec<UnitId>) -> Option<Vec<u8>> {
+        if missing.is_empty() {
+            return None;
+        }
+        self.note_missing(missing.iter().cloned());
+        match self.prepare_to_send(Message::FetchUnits(missing)) {
+            Ok(request) => Some(request),
+            Err(error) => {
+                error!("{:?} failed to request from {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // Apply the decode failure policy to a peer which sent a malformed message, returning the
+    // response to send back, if any.
+    fn handle_malformed(&mut self, peer_id: &Id, error: Error) -> Result<Option<Vec<u8>>, Error> {
+        self.publish(vec![Event::MalformedMessage { peer: *peer_id }]);
+        match self.decode_failure_policy {
+            DecodeFailurePolicy::Ignore => (),
+            DecodeFailurePolicy::Penalize => {
+                let penalty = self.penalties.entry(*peer_id).or_insert(0);
+                *penalty = penalty.saturating_add(1);
+            }
+            DecodeFailurePolicy::RequestRetransmission => {
+                let request = Message::Pull(self.dag.unit_versions());
+                return self.prepare_to_send(request).map(Some);
+            }
+            DecodeFailurePolicy::Disconnect => {
+                let _ = self.remove_peer(peer_id);
+            }
+        }
+        Err(error)
+    }
+
+    // Send the events recorded by the DAG to all subscribers, and append the changes it recorded
+    // to the journal, if any. Returns the number of units inserted.
+    fn dispatch_events(&mut self) -> usize {
+        let events = self.dag.take_events();
+        let new_units = self.track_stability(&events);
+        self.publish(events);
+        let entries = self.dag.take_journal_entries();
+        let result = match self.journal {
+            Some(ref mut journal) => journal.record(&self.dag, &entries),
+            None => Ok(()),
+        };
+        if let Err(error) = result {
+            error!("{:?} failed to journal changes: {}", self, error);
+        }
+        new_units
+    }
+
+    // The payload sealed with the group key, if any.
+    #[cfg(feature = "encryption")]
+    fn seal(&self, payload: Vec<u8>) -> Vec<u8> {
+        match self.group_key {
+            Some(ref key) => key.seal(&payload),
+            None => payload,
+        }
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn seal(&self, payload: Vec<u8>) -> Vec<u8> {
+        payload
+    }
+
+    // The payload opened with the group key, if any.
+    #[cfg(feature = "encryption")]
+    fn open(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        match self.group_key {
+            Some(ref key) => key.open(payload),
+            None => Ok(payload.to_vec()),
+        }
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn open(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(payload.to_vec())
+    }
+
+    // Record an invariant found violated by strict checks, unless one already was.
+    fn enforce(&mut self, result: Result<(), Error>) {
+        if let Err(error) = result {
+            error!("{:?} failed strict checks: {}", self, error);
+            if self.violation.is_none() {
+                self.violation = Some(error);
+            }
+        }
+    }
+
+    // Fail with the invariant found violated by strict checks since last called, if any.
+    fn take_violation(&mut self) -> Result<(), Error> {
+        match self.violation.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    // Record when the units inserted were first held, and the time to stability of those which
+    // became stable. Returns the number of units inserted.
+    fn track_stability(&mut self, events: &[Event]) -> usize {
+        let now = clock::now();
+        let mut new_units = 0;
+        for event in events {
+            match *event {
+                Event::NewUnit { identifier, .. } => {
+                    new_units += 1;
+                    if let Some(now) = now {
+                        let _ = self.first_held.insert(identifier, now);
+                    }
+                }
+                Event::UnitStable { identifier } => {
+                    if let (Some(now), Some(held_since)) =
+                        (now, self.first_held.remove(&identifier))
+                    {
+                        self.statistics.time_to_stability.add(now - held_since);
+                    }
+                }
+                _ => (),
+            }
+        }
+        new_units
+    }
+
+    // Drop when we first held the units which were pruned, evicted or became stable without an
+    // event saying so, so `first_held` only keeps units still waiting for stability.
+    fn forget_settled_units(&mut self) {
+        let first_held = self.first_held
+            .iter()
+            .filter(|&(identifier, _)| {
+                self.dag.get(identifier).map_or(
+                    false,
+                    |unit| !self.dag.is_stable(unit),
+                )
+            })
+            .map(|(identifier, held_since)| (*identifier, *held_since))
+            .collect();
+        self.first_held = first_held;
+    }
+
+    // Record the units among `identifiers` which we don't hold, starting to catch up if not yet.
+    fn note_missing<I: IntoIterator<Item = UnitId>>(&mut self, identifiers: I) {
+        let missing: Vec<UnitId> = identifiers
+            .into_iter()
+            .filter(|identifier| !self.dag.contains(identifier))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        let held = self.dag.len();
+        self.catch_up
+            .get_or_insert_with(|| CatchUp::new(held))
+            .add_missing(missing);
+    }
+
+    // Account for a message of `bytes` bytes received while catching up, reporting the progress
+    // to subscribers if it changed, and ending the catch-up once nothing is known to be missing.
+    fn report_catch_up(&mut self, bytes: usize) {
+        let (progress, changed) = match self.catch_up {
+            Some(ref mut catch_up) => {
+                let dag = &self.dag;
+                catch_up.add_bytes(bytes);
+                catch_up.progress(dag.len(), |identifier| dag.contains(identifier))
+            }
+            None => return,
+        };
+        if progress.is_complete() {
+            self.catch_up = None;
+        }
+        if changed {
+            self.publish(vec![Event::CatchingUp { progress }]);
+        }
+    }
+
+    // Send the events to the subscribers, with the payloads opened if they can be.
+    fn publish(&mut self, events: Vec<Event>) {
+        if events.is_empty() {
+            return;
+        }
+        for mut event in events {
+            if let Event::NewUnit { ref mut payload, .. } = event {
+                if let Ok(opened) = self.open(payload) {
+                    *payload = opened;
+                }
+            }
+            self.subscribers.retain(
+                |subscriber| subscriber.send(event.clone()).is_ok(),
+            );
+        }
+    }
+
+    // A push of our DAG to the peer, as a response.
+    fn push_response(&mut self, peer_id: &Id) -> Option<Vec<u8>> {
+        let delta_supported = self.negotiated_capabilities(peer_id).delta;
+        let result = match self.push_to(peer_id, delta_supported, false) {
+            Ok(message) => self.prepare_to_send(message),
+            Err(error) => Err(error),
+        };
+        match result {
+            Ok(message) => Some(message),
+            Err(error) => {
+                error!("{:?} failed to push to {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // The response to a pull request from the peer: the units it doesn't hold yet, if any.
+    fn pull_response(
+        &mut self,
+        peer_id: &Id,
+        versions: &BTreeMap<UnitId, usize>,
+    ) -> Option<Vec<u8>> {
+        self.record_known(peer_id, versions);
+        let delta = self.dag.delta_since(versions);
+        if delta.is_empty() {
+            return None;
+        }
+        self.record_known(peer_id, &delta.unit_versions());
+        let response = self.encode_for(peer_id, delta).and_then(|delta| {
+            self.prepare_to_send(Message::PullResponse(delta))
+        });
+        match response {
+            Ok(response) => Some(response),
+            Err(error) => {
+                error!("{:?} failed to respond to {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // The response to a fetch request from the peer: those of the units requested we hold, if any.
+    fn fetch_response(&mut self, peer_id: &Id, identifiers: &[UnitId]) -> Option<Vec<u8>> {
+        let units = self.dag.units_by_ids(identifiers);
+        if units.is_empty() {
+            return None;
+        }
+        let versions = units
+            .iter()
+            .map(|unit| (unit.identifier, unit.version()))
+            .collect();
+        self.record_known(peer_id, &versions);
+        match self.prepare_to_send(Message::Units(units)) {
+            Ok(response) => Some(response),
+            Err(error) => {
+                error!("{:?} failed to respond to {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // The response to a bootstrap request from the peer: the page of our units requested.
+    fn bootstrap_response(&mut self, peer_id: &Id, start: u32, count: u32) -> Option<Vec<u8>> {
+        let (page, total) = {
+            let ordered = self.dag.ordered_units();
+            let page: Vec<_> = ordered
+                .iter()
+                .skip(start as usize)
+                .take(cmp::min(count, MAX_BOOTSTRAP_PAGE) as usize)
+                .map(|unit| (*unit).clone())
+                .collect();
+            (page, ordered.len() as u32)
+        };
+        let page = self.dag.partial(page);
+        self.record_known(peer_id, &page.unit_versions());
+        let response = self.encode_for(peer_id, page).and_then(|dag| {
+            self.prepare_to_send(Message::BootstrapPage { start, total, dag })
+        });
+        match response {
+            Ok(response) => Some(response),
+            Err(error) => {
+                error!("{:?} failed to respond to {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // Merge a page received from the peer bootstrapping from, in a message of `bytes` bytes.
+    // Returns the request for the next page, or `None` once the bootstrap is over.
+    fn receive_bootstrap_page(
+        &mut self,
+        peer_id: &Id,
+        start: u32,
+        total: u32,
+        dag: WireDag,
+        bytes: usize,
+    ) -> Option<Vec<u8>> {
+        let dag = match dag.decode() {
+            Ok(dag) => dag,
+            Err(error) => {
+                error!("{:?} failed to decode page from {:?}: {}", self, peer_id, error);
+                return None;
+            }
+        };
+        let count = dag.len() as u32;
+        let merged = self.merge_from(peer_id, &dag);
+        if let Merged::Rejected(_) = merged {
+            self.bootstrap = None;
+            return self.respond_to_merge(peer_id, merged);
+        }
+        let (accepted, progress, request) = match self.bootstrap {
+            Some(ref mut bootstrap) if bootstrap.peer == *peer_id => {
+                let accepted = bootstrap.add_page(start, count, total, bytes);
+                (accepted, bootstrap.progress(), bootstrap.request())
+            }
+            // Units sent unsolicited are merged like any others.
+            _ => return self.respond_to_merge(peer_id, merged),
+        };
+        if !accepted {
+            return None;
+        }
+        let progress = match progress {
+            Some(progress) => progress,
+            None => return None,
+        };
+        self.publish(vec![Event::CatchingUp { progress }]);
+        if progress.is_complete() {
+            debug!("{:?} bootstrapped from {:?}", self, peer_id);
+            self.bootstrap = None;
+            // Fetch whatever parents the last page still misses, as in normal gossip.
+            return self.respond_to_merge(peer_id, merged);
+        }
+        match self.prepare_to_send(request) {
+            Ok(request) => Some(request),
+            Err(error) => {
+                error!("{:?} failed to request page from {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // Compare the membership digest carried by a message from the peer with ours, reporting the
+    // peer if it changed to one differing from ours, and sending it our member set unless
+    // `response` is already to be sent back.
+    fn reconcile_membership(
+        &mut self,
+        peer_id: &Id,
+        digest: [u8; 32],
+        response: Option<Vec<u8>>,
+    ) -> Option<Vec<u8>> {
+        if !self.peers.contains(peer_id) {
+            return response;
+        }
+        let members = self.members();
+        if digest == quorum::membership_digest(&members) {
+            let _ = self.peer_memberships.insert(*peer_id, digest);
+            let _ = self.members_owed.remove(peer_id);
+            return response;
+        }
+        if self.peer_memberships.insert(*peer_id, digest) != Some(digest) {
+            warn!("{:?} disagrees with {:?} on membership", self, peer_id);
+            self.publish(vec![Event::MembershipDisagreement { peer: *peer_id }]);
+            let _ = self.members_owed.insert(*peer_id);
+        }
+        if response.is_some() || !self.members_owed.remove(peer_id) {
+            return response;
+        }
+        match self.prepare_to_send(Message::Members(members)) {
+            Ok(message) => Some(message),
+            Err(error) => {
+                error!("{:?} failed to send members to {:?}: {}", self, peer_id, error);
+                None
+            }
+        }
+    }
+
+    // Count the members the peer knows of towards our stability threshold, under
+    // `MembershipReconciliation::Union`.
+    fn learn_members(&mut self, peer_id: &Id, members: BTreeSet<Id>) {
+        if self.reconciliation != MembershipReconciliation::Union || !self.peers.contains(peer_id) {
+            return;
+        }
+        let known = self.members();
+        let before = self.learned_members.len();
+        self.learned_members.extend(members.difference(&known).cloned());
+        if self.learned_members.len() != before {
+            debug!("{:?} learned members from {:?}", self, peer_id);
+            self.update_majority();
+        }
+    }
+
+    // A push of the DAG of each topic subscribed to, to a peer which subscribed to it too, chosen
+    // at random.
+    fn topic_pushes(&mut self) -> Result<Vec<(Id, Vec<u8>)>, Error> {
+        let mut pushes = Vec::new();
+        for topic in self.topics.subscriptions() {
+            let subscribers = self.topics.subscribers(&topic);
+            let chosen = match self.rng {
+                Some(ref mut rng) => rng.choose(&subscribers).cloned(),
+                None => fallback_rng().choose(&subscribers).cloned(),
+            };
+            let peer_id = match chosen {
+                Some(peer_id) => peer_id,
+                None => continue,
+            };
+            let dag = match self.topics.dag(&topic) {
+                Some(dag) => dag.clone(),
+                None => continue,
+            };
+            let dag = self.encode_for(&peer_id, dag)?;
+            let message = self.prepare_to_send(Message::TopicPush { topic, dag })?;
+            pushes.push((peer_id, message));
+        }
+        Ok(pushes)
+    }
+
+    // Union the topic's DAG pushed by the peer into ours, if subscribed to the topic.
+    fn receive_topic_push(&mut self, peer_id: &Id, topic: &str, dag: WireDag) {
+        if self.topics.dag(topic).is_none() {
+            debug!("{:?} dropped DAG of unsubscribed {:?} from {:?}", self, topic, peer_id);
+            return;
+        }
+        let dag = match dag.decode() {
+            Ok(dag) => dag,
+            Err(error) => {
+                error!(
+                    "{:?} failed to decode DAG of {:?} from {:?}: {}",
+                    self,
+                    topic,
+                    peer_id,
+                    error
+                );
+                return;
+            }
+        };
+        let result = match self.topics.dag_mut(topic) {
+            Some(ours) => ours.union(&dag),
+            None => return,
+        };
+        if let Err(error) = result {
+            error!("{:?} failed to union DAG of {:?} from {:?}: {}", self, topic, peer_id, error);
+        }
+    }
+
+    // Record the peer as holding the given unit versions, for delta sync.
+    fn record_known(&mut self, peer_id: &Id, versions: &BTreeMap<UnitId, usize>) {
+        if let SyncMode::Full = self.sync_mode {
+            return;
+        }
+        let cursor = self.peer_cursors.entry(*peer_id).or_insert_with(BTreeMap::new);
+        for (identifier, version) in versions {
+            let known = cursor.entry(*identifier).or_insert(*version);
+            *known = cmp::max(*known, *version);
+        }
+    }
+
+    // The DAG to push to the peer, and whether it is our whole DAG rather than a delta. Always the
+    // whole DAG if the peer doesn't handle deltas.
+    fn dag_to_push(&mut self, peer_id: &Id, delta_supported: bool) -> (Dag, bool) {
+        match self.sync_mode {
+            SyncMode::Full => (self.dag.clone(), true),
+            SyncMode::Delta { full_sync_every } => {
+                let cursor = self.peer_cursors.entry(*peer_id).or_insert_with(BTreeMap::new);
+                if !delta_supported || self.round % cmp::max(full_sync_every, 1) == 0 {
+                    *cursor = self.dag.unit_versions();
+                    (self.dag.clone(), true)
+                } else {
+                    let delta = self.dag.delta_since(cursor);
+                    cursor.extend(delta.unit_versions());
+                    (delta, false)
+                }
+            }
+        }
+    }
+
+    // The DAG to push to the peer as per `dag_to_push`, without the cold rumors if `suppress_cold`
+    // and cold suppression is enabled, but with any unit to regossip the peer didn't observe.
+    // Counts a push of every unit left in.
+    fn rumors_to_push(
+        &mut self,
+        peer_id: &Id,
+        delta_supported: bool,
+        suppress_cold: bool,
+    ) -> (Dag, bool) {
+        let (mut dag, full) = self.dag_to_push(peer_id, delta_supported);
+        if suppress_cold && self.anti_entropy_every.is_some() {
+            let (hot, cold): (Vec<_>, Vec<_>) = dag.units().cloned().partition(|unit| {
+                !self.is_cold_rumor(unit)
+            });
+            if !cold.is_empty() {
+                self.statistics.cold_units_suppressed += cold.len() as u64;
+                dag = self.dag.partial(hot);
+            }
+        }
+        let stalled: Vec<Unit> = self.regossiped_units()
+            .iter()
+            .filter_map(|identifier| self.dag.get(identifier))
+            .filter(|unit| !unit.observers.contains(peer_id) && !dag.contains(&unit.identifier))
+            .cloned()
+            .collect();
+        if !stalled.is_empty() {
+            let mut units: Vec<Unit> = dag.units().cloned().collect();
+            units.extend(stalled);
+            dag = self.dag.partial(units);
+        }
+        for unit in dag.units() {
+            let version = unit.version();
+            let rumor = self.rumors
+                .entry(unit.identifier)
+                .or_insert_with(|| Rumor::new(version));
+            if rumor.version != version {
+                *rumor = Rumor::new(version);
+            }
+            rumor.pushes = rumor.pushes.saturating_add(1);
+        }
+        (dag, full)
+    }
+
+    // Encode the DAG with the codec negotiated with the peer.
+    fn encode_for(&self, peer_id: &Id, dag: Dag) -> Result<WireDag, Error> {
+        let codec = self.negotiated_capabilities(peer_id).preferred_codec();
+        WireDag::encode(dag, codec)
+    }
+
+    // Fail with `Error::MessageLimitExceeded` if the message carries more units, or a larger
+    // payload, than allowed by the config.
+    fn check_message_limits(&self, message: &Message) -> Result<(), Error> {
+        if let Some(max) = self.config.max_units_per_message {
+            let count = message.unit_count();
+            if count > max {
+                return Err(Error::MessageLimitExceeded("number of units", count, max));
+            }
+        }
+        if let Some(max) = self.config.max_payload_len {
+            let len = message.largest_payload();
+            if len > max {
+                return Err(Error::MessageLimitExceeded("payload length", len, max));
+            }
+        }
+        Ok(())
+    }
+
+    // Hand the extension blobs attached to a message from `peer_id` to the round hook, if they
+    // are authenticated as sent by the peer along with `message`.
+    fn receive_extensions(&mut self, peer_id: &Id, message: &Message, extensions: &Extensions) {
+        if !extensions.verify(message, peer_id) {
+            warn!("{:?} dropped extensions from {:?} with an invalid signature", self, peer_id);
+            return;
+        }
+        if let Some(ref mut hook) = self.round_hook {
+            hook.incoming(peer_id, &extensions.blobs);
+        }
+    }
+
+    fn prepare_to_send(&mut self, message: Message) -> Result<Vec<u8>, Error> {
+        let round = self.round;
+        let lifetime = cmp::max(self.peers.len() as u64, 1);
+        self.fresh_attestations.retain(
+            |&(made_in, _)| round.saturating_sub(made_in) < lifetime,
+        );
+        let extensions = match self.round_hook {
+            Some(ref mut hook) => Some(hook.outgoing()),
+            None => None,
+        };
+        let extensions = match extensions {
+            Some(ref blobs) if blobs.is_empty() => None,
+            Some(blobs) => Some(Extensions::sign(blobs, &message, &self.keys)?),
+            None => None,
+        };
+        let envelope = Envelope {
+            message,
+            attestations: self.fresh_attestations
+                .iter()
+                .map(|&(_, ref attestation)| attestation.clone())
+                .collect(),
+            capabilities: self.capabilities.clone(),
+            extensions,
+            digest: self.dag.digest(),
+            membership: quorum::membership_digest(&self.members()),
+            hash_algorithm: self.dag.hasher().tag(),
+        };
+        let start = clock::now();
+        let result = wire::encode(self.wire_format, self.compression, &envelope);
+        self.telemetry.check(Operation::Serialisation, start, self.dag.len());
+        #[cfg(feature = "chaos")]
+        let result = result.and_then(|message| self.inject_faults(message));
+        if let Ok(ref message) = result {
+            self.statistics.bytes_sent += message.len() as u64;
+        }
+        result
+    }
+
+    // Sleep before a union, as configured by the chaos config.
+    #[cfg(feature = "chaos")]
+    fn delay_union(&mut self) {
+        let (probability, delay) = (self.chaos.delay_probability, self.chaos.union_delay);
+        if self.chance(probability) {
+            thread::sleep(delay);
+        }
+    }
+
+    // Drop or corrupt a message about to be sent, as configured by the chaos config.
+    #[cfg(feature = "chaos")]
+    fn inject_faults(&mut self, mut message: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let drop_probability = self.chaos.drop_probability;
+        let corrupt_probability = self.chaos.corrupt_probability;
+        if self.chance(drop_probability) {
+            return Err(Error::ChaosDrop);
+        }
+        if !message.is_empty() && self.chance(corrupt_probability) {
+            let len = message.len();
+            let index = match self.rng {
+                Some(ref mut rng) => rng.gen_range(0, len),
+                None => fallback_rng().gen_range(0, len),
+            };
+            message[index] ^= 0xff;
+        }
+        Ok(message)
+    }
+
+    // Whether an event of the given probability happens this time.
+    #[cfg(feature = "chaos")]
+    fn chance(&mut self, probability: f64) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+        let sample: f64 = match self.rng {
+            Some(ref mut rng) => rng.gen(),
+            None => fallback_rng().gen(),
+        };
+        sample < probability
+    }
+
+    // A gossiper with the default configuration, owning `keys` and holding `dag`.
+    fn with_dag(keys: Keypair, mut dag: Dag) -> Self {
+        // Events are always recorded, to keep the statistics.
+        dag.set_events_enabled(true);
+        let rng = default_rng(&keys);
+        Gossiper {
+            keys,
+            rng,
+            peers: Vec::new(),
+            peer_selection: PeerSelection::Adaptive,
+            peer_cycle: Vec::new(),
+            peer_scores: BTreeMap::new(),
+            peer_selector: None,
+            heard_from: BTreeSet::new(),
+            strategy: GossipStrategy::Push,
+            fanout: 1,
+            sync_mode: SyncMode::Full,
+            peer_cursors: BTreeMap::new(),
+            round: 0,
+            delivered: BTreeSet::new(),
+            telemetry: Telemetry::default(),
+            statistics: Statistics::default(),
+            first_held: BTreeMap::new(),
+            subscribers: Vec::new(),
+            fresh_attestations: Vec::new(),
+            decode_failure_policy: DecodeFailurePolicy::Ignore,
+            wire_format: WireFormat::default(),
+            compression: CompressionLevel::default(),
+            catch_up: None,
+            bootstrap: None,
+            penalties: BTreeMap::new(),
+            single_node: false,
+            small_network_mode: SmallNetworkMode::AllMembers,
+            rumors: BTreeMap::new(),
+            rumor_rounds: None,
+            anti_entropy_every: None,
+            membership_check: None,
+            topics: Topics::default(),
+            reconciliation: MembershipReconciliation::default(),
+            learned_members: BTreeSet::new(),
+            peer_memberships: BTreeMap::new(),
+            members_owed: BTreeSet::new(),
+            capabilities: Capabilities::all(),
+            peer_capabilities: BTreeMap::new(),
+            peer_digests: BTreeMap::new(),
+            config: GossiperConfig::default(),
+            rate_limit: None,
+            peer_usage: BTreeMap::new(),
+            watchdog: None,
+            journal: None,
+            round_hook: None,
+            strict_checks: false,
+            violation: None,
+            #[cfg(feature = "chaos")]
+            chaos: ChaosConfig::default(),
+            #[cfg(feature = "encryption")]
+            group_key: None,
+            dag,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn print_dag(&self) {
+        println!("{:?} has DAG : \n {:?}", self, self.dag);
+    }
+
+    #[cfg(test)]
+    pub fn dag(&self) -> &Dag {
+        &self.dag
+    }
+
+    #[cfg(test)]
+    pub fn has_payload(&self, payload: &[u8]) -> bool {
+        self.dag.contains_payload(&self.seal(payload.to_vec())[..])
+    }
+}
+
+impl Debug for Gossiper {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{:?}", self.id())
+    }
+}
+
+// A message in the format sent before envelopes, a bare serialised DAG, merged like a full DAG
+// for compatibility with older peers.
+#[cfg(feature = "bare-dag")]
+fn decode_bare_dag(bytes: &[u8], max_len: Option<usize>) -> Option<Dag> {
+    if max_len.map_or(false, |max| bytes.len() > max) {
+        return None;
+    }
+    serialisation::deserialise(bytes).ok()
+}
+
+#[cfg(not(feature = "bare-dag"))]
+fn decode_bare_dag(_bytes: &[u8], _max_len: Option<usize>) -> Option<Dag> {
+    None
+}
+
+// Keys and randomised decisions need entropy, which `wasm32-unknown-unknown` doesn't provide.
+#[cfg(not(feature = "wasm"))]
+impl Default for Gossiper {
+    fn default() -> Self {
+        let mut rng = rand::thread_rng();
+        Gossiper::from_keypair(Keypair::generate::<Sha3_512>(&mut rng))
+    }
+}
+
+// The generator of the randomised decisions of a gossiper owning `keys` and not given one by
+// `with_rng`: none, `fallback_rng` being used instead.
+#[cfg(not(feature = "wasm"))]
+fn default_rng(_keys: &Keypair) -> Option<Box<Rng + Send>> {
+    None
+}
+
+// The generator of the randomised decisions of a gossiper owning `keys` and not given one by
+// `with_rng`: without any entropy available, one seeded from the secret key, so that different
+// gossipers still make different decisions.
+#[cfg(feature = "wasm")]
+fn default_rng(keys: &Keypair) -> Option<Box<Rng + Send>> {
+    let hash = sha3_256(&keys.secret.to_bytes());
+    let mut seed = [0u32; 4];
+    for (word, bytes) in seed.iter_mut().zip(hash.chunks(4)) {
+        *word = bytes.iter().fold(0, |word, byte| (word << 8) | u32::from(*byte));
+    }
+    // An all-zero seed is invalid.
+    seed[0] |= 1;
+    Some(Box::new(XorShiftRng::from_seed(seed)))
+}
+
+// The generator used by a gossiper without one of its own.
+#[cfg(not(feature = "wasm"))]
+fn fallback_rng() -> Box<Rng> {
+    Box::new(rand::thread_rng())
+}
+
+// Never used, as every gossiper has its own generator with the `wasm` feature.
+#[cfg(feature = "wasm")]
+fn fallback_rng() -> Box<Rng> {
+    Box::new(XorShiftRng::new_unseeded())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use hasher::Sha3Hasher;
+    use message::RejectReason;
+    use rand::Rng;
+    use sha3::Sha3_512;
+    use simulation::create_network;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::iter;
+    use std::sync::{Arc, Mutex};
+
+    fn send_messages(gossipers: &mut Vec<Gossiper>, num_of_msgs: u32, rng: &mut SeededRng) {
+        let mut msg_pool: Vec<Vec<Vec<u8>>> = Vec::new();
+        let mut msgs = Vec::new();
+        for j in 1..(num_of_msgs + 1) as u8 {
+            msgs.push(vec![j, j, j]);
+        }
+        for _ in 0..gossipers.len() as u8 {
+            msg_pool.push(msgs.clone())
+        }
+
+        // Poll until every message was sent and all gossipers converged, giving up after a bound
+        // well above the expected number of rounds.
+        for _ in 0..100 {
+            let converged = gossipers.iter().all(Gossiper::is_converged);
+            if converged && msg_pool.iter().all(Vec::is_empty) {
+                break;
+            }
+            let mut messages = BTreeMap::new();
+            // Call `next_round()` on each node to gather a list of serialised DAG.
+            for (i, gossiper) in gossipers.iter_mut().enumerate() {
+                if !msg_pool[i].is_empty() && rng.gen() {
+                    let index = rng.gen_range(0, msg_pool[i].len() as u32) as usize;
+                    let _ = gossiper.send_new(&msg_pool[i][index].clone());
+                    let _ = msg_pool[i].remove(index);
+                }
+                let (dst_id, push_msg) = unwrap!(gossiper.next_round());
+                let _ = messages.insert((gossiper.id(), dst_id), push_msg);
+            }
+
+            // Send all Push DAGs.
+            for ((src_id, dst_id), push_msg) in messages {
+                let mut dst = unwrap!(gossipers.iter_mut().find(|node| node.id() == dst_id));
+                let _ = dst.handle_received_message(&src_id, &push_msg);
+            }
+        }
+
+        for gossiper in gossipers {
+            gossiper.print_dag();
+        }
+    }
+
+    #[test]
+    fn malformed_message() {
+        let mut gossipers = create_network(2);
+        let own_id = gossipers[0].id();
+        let peer_id = gossipers[1].id();
+        let events = gossipers[0].events();
+
+        gossipers[0].set_decode_failure_policy(DecodeFailurePolicy::Penalize);
+        assert!(gossipers[0].handle_received_message(&peer_id, &[1, 2, 3]).is_err());
+        assert_eq!(gossipers[0].penalty(&peer_id), 1);
+        assert_eq!(unwrap!(events.try_recv()), Event::MalformedMessage { peer: peer_id });
+
+        // Messages from newer protocol versions are dropped without penalising the sender.
+        let (_, mut message) = unwrap!(gossipers[1].next_round());
+        message[3] = message[3].wrapping_add(1);
+        match gossipers[0].handle_received_message(&peer_id, &message) {
+            Err(Error::IncompatibleProtocol(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(gossipers[0].penalty(&peer_id), 1);
+        assert_eq!(unwrap!(events.try_recv()), Event::IncompatibleMessage { peer: peer_id });
+
+        gossipers[0].set_decode_failure_policy(DecodeFailurePolicy::RequestRetransmission);
+        let request = unwrap!(unwrap!(gossipers[0].handle_received_message(&peer_id, &[1, 2, 3])));
+        assert!(unwrap!(gossipers[1].handle_received_message(&own_id, &request)).is_none());
+
+        gossipers[0].set_decode_failure_policy(DecodeFailurePolicy::Disconnect);
+        assert!(gossipers[0].handle_received_message(&peer_id, &[1, 2, 3]).is_err());
+        assert!(gossipers[0].export_peers().is_empty());
+    }
+
+    #[test]
+    fn message_limits() {
+        let mut gossipers = create_network(2);
+        let peer_id = gossipers[1].id();
+        let _ = unwrap!(gossipers[1].send_new(&"Hello"));
+        let (_, message) = unwrap!(gossipers[1].next_round());
+        gossipers[0].set_decode_failure_policy(DecodeFailurePolicy::Penalize);
+
+        let limits = vec![
+            GossiperConfig {
+                max_message_bytes: Some(16),
+                ..GossiperConfig::unbounded()
+            },
+            GossiperConfig {
+                max_units_per_message: Some(1),
+                ..GossiperConfig::unbounded()
+            },
+            GossiperConfig {
+                max_payload_len: Some(4),
+                ..GossiperConfig::unbounded()
+            },
+        ];
+        for (index, config) in limits.into_iter().enumerate() {
+            gossipers[0].set_config(config);
+            match gossipers[0].handle_received_message(&peer_id, &message) {
+                Err(Error::MessageLimitExceeded(..)) => (),
+                result => panic!("Unexpected result: {:?}", result),
+            }
+            assert_eq!(gossipers[0].penalty(&peer_id), index as u32 + 1);
+        }
+
+        gossipers[0].set_config(GossiperConfig::default());
+        let _ = unwrap!(gossipers[0].handle_received_message(&peer_id, &message));
+        assert!(gossipers[0].has_payload(&unwrap!(serialisation::serialise(&"Hello"))));
+    }
+
+    // SHA3-256 with its bytes reversed, standing in for another algorithm.
+    struct ReversedSha3;
+
+    impl Hasher for ReversedSha3 {
+        fn tag(&self) -> u8 {
+            1
+        }
+
+        fn hash(&self, data: &[u8]) -> [u8; 32] {
+            let mut hash = Sha3Hasher.hash(data);
+            hash.reverse();
+            hash
+        }
+    }
+
+    #[test]
+    fn pluggable_hasher() {
+        let mut rng = SeededRng::new();
+        let hasher: Arc<Hasher> = Arc::new(ReversedSha3);
+        let mut sender = Gossiper::for_network_with_hasher(
+            Keypair::generate::<Sha3_512>(&mut rng),
+            b"network",
+            hasher.clone(),
+        );
+        let mut receiver = Gossiper::for_network_with_hasher(
+            Keypair::generate::<Sha3_512>(&mut rng),
+            b"network",
+            hasher,
+        );
+        let mut other = Gossiper::for_network(Keypair::generate::<Sha3_512>(&mut rng), b"network");
+        let sender_id = sender.id();
+        unwrap!(sender.add_peer(receiver.id()));
+        unwrap!(receiver.add_peer(sender_id));
+        unwrap!(other.add_peer(sender_id));
+        assert_ne!(sender.dag().genesis().identifier, other.dag().genesis().identifier);
+
+        let identifier = unwrap!(sender.send_new(&"Hello"));
+        let (_, message) = unwrap!(sender.next_round());
+        let _ = unwrap!(receiver.handle_received_message(&sender_id, &message));
+        assert!(receiver.dag().contains(&identifier));
+
+        // Peers deriving identifiers differently are told apart before their units are validated.
+        let events = other.events();
+        match other.handle_received_message(&sender_id, &message) {
+            Err(Error::HashAlgorithmMismatch(1)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(other.penalty(&sender_id), 0);
+        assert_eq!(unwrap!(events.try_recv()), Event::IncompatibleMessage { peer: sender_id });
+    }
+
+    #[test]
+    fn columnar_codec() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        for gossiper in &mut gossipers {
+            gossiper.set_wire_codecs(vec![WireCodec::Columnar, WireCodec::Units]);
+        }
+        let _ = unwrap!(gossipers[0].send_new(&"payload"));
+
+        // Until it heard from its peer, a gossiper only sends units one by one.
+        let (_, message) = unwrap!(gossipers[1].next_round());
+        let envelope: Envelope = unwrap!(wire::decode(&message));
+        assert!(match envelope.message {
+            Message::Full(WireDag::Units(_)) => true,
+            _ => false,
+        });
+        assert!(unwrap!(gossipers[0].handle_received_message(&ids[1], &message)).is_none());
+
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        let envelope: Envelope = unwrap!(wire::decode(&message));
+        assert!(match envelope.message {
+            Message::Full(WireDag::Columnar(_)) => true,
+            _ => false,
+        });
+        assert!(unwrap!(gossipers[1].handle_received_message(&ids[0], &message)).is_none());
+        assert!(gossipers[1].has_payload(&unwrap!(serialisation::serialise(&"payload"))));
+    }
+
+    #[test]
+    fn missing_units() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"first"));
+        let _ = unwrap!(gossipers[0].send_new(&"second"));
+        let first = unwrap!(serialisation::serialise(&"first"));
+        let second = unwrap!(serialisation::serialise(&"second"));
+        let child = gossipers[0].dag.ordered_units()[1].identifier;
+
+        // Fetching a unit whose parent isn't held makes us request the parent.
+        let request = unwrap!(gossipers[1].fetch_units(vec![child]));
+        let response = unwrap!(unwrap!(gossipers[0].handle_received_message(&ids[1], &request)));
+        let request = unwrap!(unwrap!(gossipers[1].handle_received_message(&ids[0], &response)));
+        let response = unwrap!(unwrap!(gossipers[0].handle_received_message(&ids[1], &request)));
+        assert!(unwrap!(gossipers[1].handle_received_message(&ids[0], &response)).is_none());
+        assert!(gossipers[1].has_payload(&first));
+        assert!(!gossipers[1].has_payload(&second));
+
+        // An advertisement makes us request whatever we are still missing.
+        let advertisement = unwrap!(gossipers[0].advertise_units());
+        let request = gossipers[1].handle_received_message(&ids[0], &advertisement);
+        let request = unwrap!(unwrap!(request));
+        let response = unwrap!(unwrap!(gossipers[0].handle_received_message(&ids[1], &request)));
+        assert!(unwrap!(gossipers[1].handle_received_message(&ids[0], &response)).is_none());
+        assert!(gossipers[1].has_payload(&second));
+    }
+
+    #[test]
+    fn fetch_units() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"payload"));
+        let identifier = gossipers[0].dag.ordered_units()[0].identifier;
+
+        let request = unwrap!(gossipers[1].fetch_units(vec![identifier]));
+        let response = unwrap!(unwrap!(gossipers[0].handle_received_message(&ids[1], &request)));
+        assert!(unwrap!(gossipers[1].handle_received_message(&ids[0], &response)).is_none());
+        assert!(gossipers[1].has_payload(&unwrap!(serialisation::serialise(&"payload"))));
+    }
+
+    #[test]
+    fn membership_check() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let payload = unwrap!(serialisation::serialise(&"Hello"));
+        let identifier = unwrap!(gossipers[0].send_new(&"Hello"));
+        // A node outside the network observes the unit with a valid signature.
+        let outsider = Keypair::generate::<Sha3_512>(&mut SeededRng::new());
+        let outsider_id: Id = outsider.public.into();
+        let mut dag = gossipers[0].dag().clone();
+        assert_eq!(unwrap!(dag.new_payload(payload.clone(), &outsider)), identifier);
+
+        gossipers[1].set_membership_check(Some(MembershipCheck::Strip));
+        let _ = gossipers[1].merge_from(&ids[0], &dag);
+        let observers = unwrap!(gossipers[1].dag().observers_of(&identifier));
+        assert!(observers.contains(&ids[0]));
+        assert!(!observers.contains(&outsider_id));
+
+        let mut strict = create_network(2);
+        let strict_ids: Vec<Id> = strict.iter().map(Gossiper::id).collect();
+        strict[1].set_membership_check(Some(MembershipCheck::Reject));
+        let identifier = unwrap!(strict[0].send_new(&"Hello"));
+        let mut dag = strict[0].dag().clone();
+        let _ = unwrap!(dag.new_payload(payload, &outsider));
+        match strict[1].merge_from(&strict_ids[0], &dag) {
+            Merged::Rejected(nack) => {
+                assert_eq!(nack.identifiers, vec![identifier]);
+                let expected: BTreeSet<Id> = iter::once(outsider_id).collect();
+                assert_eq!(nack.reason, RejectReason::UnauthorisedObservers(expected));
+            }
+            Merged::Missing(_) => panic!("Unit observed by a non-member was merged"),
+        }
+        assert!(!strict[1].dag().contains(&identifier));
+    }
+
+    #[test]
+    fn nack() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"payload"));
+        let mut corrupted = gossipers[0].dag.ordered_units()[0].clone();
+        corrupted.payload = unwrap!(serialisation::serialise(&"garbage"));
+        let identifier = corrupted.identifier;
+        let events = gossipers[0].events();
+
+        let message = unwrap!(gossipers[0].prepare_to_send(Message::Units(vec![corrupted])));
+        let nack = unwrap!(unwrap!(gossipers[1].handle_received_message(&ids[0], &message)));
+        let envelope: Envelope = unwrap!(wire::decode(&nack));
+        let expected = Nack {
+            identifiers: vec![identifier],
+            reason: RejectReason::InvalidUnit,
+        };
+        assert!(match envelope.message {
+            Message::Nack(ref nack) => *nack == expected,
+            _ => false,
+        });
+
+        // The sender is told, and pulls its peer's units to resync.
+        let request = unwrap!(unwrap!(gossipers[0].handle_received_message(&ids[1], &nack)));
+        let rejected = Event::Rejected {
+            peer: ids[1],
+            nack: expected,
+        };
+        assert_eq!(unwrap!(events.try_recv()), rejected);
+        let envelope: Envelope = unwrap!(wire::decode(&request));
+        assert!(match envelope.message {
+            Message::Pull(_) => true,
+            _ => false,
+        });
+    }
+
+    #[cfg(feature = "bare-dag")]
+    #[test]
+    fn bare_dag() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        let bare = unwrap!(serialisation::serialise(gossipers[0].dag()));
+        let _ = unwrap!(gossipers[1].handle_received_message(&ids[0], &bare));
+        assert!(gossipers[1].has_payload(&unwrap!(serialisation::serialise(&"Hello"))));
+
+        // Anything else is still handled as malformed, without merging anything.
+        let len = gossipers[1].dag().len();
+        let _ = gossipers[1].handle_received_message(&ids[0], &[0xff; 16]);
+        assert_eq!(gossipers[1].dag().len(), len);
+    }
+
+    #[test]
+    fn lazy_validation() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        gossipers[1].set_validation(Validation::Lazy);
+        let _ = unwrap!(gossipers[0].send_new(&"payload"));
+        let mut corrupted = gossipers[0].dag.ordered_units()[0].clone();
+        corrupted.payload = unwrap!(serialisation::serialise(&"garbage"));
+        let identifier = corrupted.identifier;
+
+        // Accepted unchecked, though stripped of its forged observer, then dropped once a new unit
+        // would be built on top of it.
+        let message = unwrap!(gossipers[0].prepare_to_send(Message::Units(vec![corrupted])));
+        let _ = gossipers[1].handle_received_message(&ids[0], &message);
+        assert!(gossipers[1].dag.contains(&identifier));
+        assert!(!gossipers[1].dag.is_validated(&identifier));
+        let _ = unwrap!(gossipers[1].send_new(&"other payload"));
+        assert!(!gossipers[1].dag.contains(&identifier));
+    }
+
+    #[test]
+    fn secret_key() {
+        let gossiper = Gossiper::default();
+        let restored = unwrap!(Gossiper::from_secret_bytes(&gossiper.export_secret_key()));
+        assert_eq!(restored.id(), gossiper.id());
+        assert!(Gossiper::from_secret_bytes(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn injected_rng() {
+        let seed = [1, 2, 3, 4];
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let mut gossiper = Gossiper::with_rng(SeededRng::from_seed(seed));
+            for index in 0..8 {
+                let peer = Gossiper::with_rng(SeededRng::from_seed([index, 2, 3, 4]));
+                unwrap!(gossiper.add_peer(peer.id()));
+            }
+            let choices: Vec<Id> = (0..16)
+                .map(|_| unwrap!(gossiper.next_round()).0)
+                .collect();
+            runs.push((gossiper.id(), choices));
+        }
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[test]
+    fn fanout() {
+        let mut gossipers = create_network(6);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        gossipers[0].set_fanout(3);
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        let messages = unwrap!(gossipers[0].next_round_multi());
+        let recipients: BTreeSet<Id> = messages.iter().map(|&(peer_id, _)| peer_id).collect();
+        assert_eq!(recipients.len(), 3);
+        assert!(!recipients.contains(&ids[0]));
+        for (peer_id, message) in messages {
+            let index = unwrap!(ids.iter().position(|id| *id == peer_id));
+            let _ = gossipers[index].handle_received_message(&ids[0], &message);
+        }
+        let payload = unwrap!(serialisation::serialise(&"Hello"));
+        let holders = gossipers
+            .iter()
+            .filter(|gossiper| gossiper.has_payload(&payload))
+            .count();
+        assert_eq!(holders, 4);
+
+        gossipers[0].set_fanout(10);
+        assert_eq!(unwrap!(gossipers[0].next_round_multi()).len(), 5);
+    }
+
+    #[test]
+    fn permutation_selection() {
+        let mut gossipers = create_network(5);
+        let gossiper = &mut gossipers[0];
+        gossiper.set_peer_selection(PeerSelection::Permutation);
+        let _ = unwrap!(gossiper.send_new(&"Hello"));
+        let recipients: Vec<Id> = (0..8)
+            .map(|_| unwrap!(gossiper.next_round()).0)
+            .collect();
+        // Every peer is contacted once per permutation.
+        for permutation in recipients.chunks(4) {
+            let distinct: BTreeSet<Id> = permutation.iter().cloned().collect();
+            assert_eq!(distinct, gossiper.export_peers().into_iter().collect());
+        }
+    }
+
+    #[test]
+    fn ordered_delivery() {
+        let mut gossipers = create_network(3);
+        let gossiper = &mut gossipers[0];
+        let _ = unwrap!(gossiper.send_new(&"first"));
+        let _ = unwrap!(gossiper.send_new(&"second"));
+        // Only observed by us, out of the two observers required.
+        assert!(gossiper.poll_ordered().is_empty());
+
+        gossiper.dag.set_majority(1);
+        let expected = vec![
+            unwrap!(serialisation::serialise(&"first")),
+            unwrap!(serialisation::serialise(&"second")),
+        ];
+        assert_eq!(gossiper.poll_ordered(), expected);
+        assert!(gossiper.poll_ordered().is_empty());
+        let _ = unwrap!(gossiper.send_new(&"third"));
+        assert_eq!(
+            gossiper.poll_ordered(),
+            vec![unwrap!(serialisation::serialise(&"third"))]
+        );
+    }
+
+    #[test]
+    fn piggybacked_attestations() {
+        let mut gossipers = create_network(3);
+        let gossiper = &mut gossipers[0];
+        let identifier = unwrap!(gossiper.send_new(&"Hello"));
+        let attestation = unwrap!(gossiper.dag().attestation(&identifier, &gossiper.id()));
+
+        // Carried by every message for as many rounds as we have peers.
+        let (_, message) = unwrap!(gossiper.next_round());
+        let envelope: Envelope = unwrap!(wire::decode(&message));
+        assert_eq!(envelope.attestations, vec![attestation]);
+        let (_, message) = unwrap!(gossiper.next_round());
+        let envelope: Envelope = unwrap!(wire::decode(&message));
+        assert!(envelope.attestations.is_empty());
+    }
+
+    #[test]
+    fn pull_rounds() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        // A first push lets the puller learn that the peer answers pulls.
+        let _ = unwrap!(gossipers[0].send_new(&"first"));
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        let _ = unwrap!(gossipers[1].handle_received_message(&ids[0], &message));
+
+        let second = unwrap!(gossipers[0].send_new(&"second"));
+        let _ = unwrap!(gossipers[1].send_new(&"own"));
+        gossipers[1].set_gossip_strategy(GossipStrategy::Pull);
+        let (peer_id, request) = unwrap!(gossipers[1].next_round());
+        assert_eq!(peer_id, ids[0]);
+        let envelope: Envelope = unwrap!(wire::decode(&request));
+        assert!(match envelope.message {
+            Message::Pull(_) => true,
+            _ => false,
+        });
+        let response = unwrap!(unwrap!(gossipers[0].handle_received_message(&ids[1], &request)));
+        let _ = unwrap!(gossipers[1].handle_received_message(&ids[0], &response));
+        assert!(gossipers[1].dag().get(&second).is_some());
+    }
+
+    #[test]
+    fn delta_sync() {
+        let mut gossipers = create_network(2);
+        let peer_id = gossipers[1].id();
+        let gossiper = &mut gossipers[0];
+        gossiper.set_sync_mode(SyncMode::Delta { full_sync_every: 10 });
+        let first = unwrap!(gossiper.send_new(&"first"));
+        gossiper.round = 1;
+
+        // The first push is our whole DAG, then only what was added since.
+        let (dag, full) = gossiper.dag_to_push(&peer_id, true);
+        assert!(!full);
+        assert!(dag.get(&first).is_some());
+        assert_eq!(dag.len(), gossiper.dag().len());
+        let second = unwrap!(gossiper.send_new(&"second"));
+        let (dag, full) = gossiper.dag_to_push(&peer_id, true);
+        assert!(!full);
+        assert_eq!(dag.units().map(|unit| unit.identifier).collect::<Vec<_>>(), vec![second]);
+
+        // The whole DAG is pushed to peers not handling deltas, and every `full_sync_every` rounds.
+        assert!(gossiper.dag_to_push(&peer_id, false).1);
+        gossiper.round = 10;
+        let (dag, full) = gossiper.dag_to_push(&peer_id, true);
+        assert!(full);
+        assert_eq!(dag.len(), gossiper.dag().len());
+    }
+
+    #[test]
+    fn single_node_mode() {
+        let mut gossiper = Gossiper::default();
+        match gossiper.send_new(&"Hello") {
+            Err(Error::NoPeers) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(gossiper.dag().len(), 1);
+
+        gossiper.set_single_node_mode(true);
+        let identifier = unwrap!(gossiper.send_new(&"Hello"));
+        assert!(gossiper.dag().is_stable(unwrap!(gossiper.dag().get(&identifier))));
+    }
+
+    #[test]
+    fn peer_list_bootstrap() {
+        let gossipers = create_network(3);
+        let mut peers = gossipers[0].export_peers();
+        peers.push(gossipers[0].id());
+
+        let mut joining = Gossiper::default();
+        joining.set_peers(peers.clone());
+        assert_eq!(joining.export_peers(), peers);
+        assert_eq!(joining.quorum_info().members, 4);
+        assert_eq!(joining.quorum_info().stability_threshold, 2);
+    }
+
+    #[test]
+    fn bootstrap_rounds() {
+        let mut gossipers = create_network(3);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        for payload in &["one", "two", "three"] {
+            let _ = unwrap!(gossipers[0].send_new(payload));
+        }
+        let _ = unwrap!(gossipers[1].start_bootstrap(&ids[0], 1));
+
+        // Rounds are suspended, both APIs only asking the peer bootstrapping from for the page.
+        let (peer_id, request) = unwrap!(gossipers[1].next_round());
+        assert_eq!(peer_id, ids[0]);
+        let envelope: Envelope = unwrap!(wire::decode(&request));
+        assert!(match envelope.message {
+            Message::BootstrapRequest { start: 0, count: 1 } => true,
+            _ => false,
+        });
+        let messages = unwrap!(gossipers[1].next_round_multi());
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].0, ids[0]);
+        assert_eq!(gossipers[1].statistics().rounds, 0);
+
+        // Answering the requests page after page completes the bootstrap, and rounds resume.
+        let mut request = request;
+        while gossipers[1].is_bootstrapping() {
+            let page = unwrap!(unwrap!(gossipers[0].handle_received_message(&ids[1], &request)));
+            match unwrap!(gossipers[1].handle_received_message(&ids[0], &page)) {
+                Some(next) => request = next,
+                None => break,
+            }
+        }
+        assert!(!gossipers[1].is_bootstrapping());
+        let _ = unwrap!(gossipers[1].next_round());
+        assert_eq!(gossipers[1].statistics().rounds, 1);
+    }
+
+    #[test]
+    fn peer_list() {
+        let mut gossipers = create_network(4);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let gossiper = &mut gossipers[0];
+        unwrap!(gossiper.add_peer(ids[1]));
+        assert_eq!(gossiper.export_peers(), vec![ids[1], ids[2], ids[3]]);
+
+        gossiper.set_peers(vec![ids[2], ids[1], ids[2], ids[2]]);
+        assert_eq!(gossiper.export_peers(), vec![ids[2], ids[1]]);
+        assert_eq!(gossiper.quorum_info().members, 3);
+        assert_eq!(gossiper.quorum_info().stability_threshold, 2);
+
+        gossiper.update_peers(vec![ids[3], ids[3]]);
+        assert_eq!(gossiper.export_peers(), vec![ids[3]]);
+        assert_eq!(gossiper.quorum_info().members, 2);
+    }
+
+    #[test]
+    fn payload_status() {
+        // With 4 peers, 3 observers are needed for stability.
+        let mut gossipers = create_network(5);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let identifier = unwrap!(gossipers[0].send_new(&"Hello"));
+        assert_eq!(gossipers[0].status_of(&identifier), PayloadStatus::Pending);
+        assert_eq!(gossipers[1].status_of(&identifier), PayloadStatus::Unknown);
+
+        let mut statuses = Vec::new();
+        for index in 1..3 {
+            assert_eq!(unwrap!(gossipers[index].send_new(&"Hello")), identifier);
+            let (_, message) = unwrap!(gossipers[index].next_round());
+            let _ = unwrap!(gossipers[0].handle_received_message(&ids[index], &message));
+            statuses.push(gossipers[0].status_of(&identifier));
+        }
+        assert_eq!(statuses, vec![PayloadStatus::SeenBy(1), PayloadStatus::Stable]);
+    }
+
+    #[test]
+    fn digest() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        let _ = gossipers[1].handle_received_message(&ids[0], &message);
+        assert_eq!(gossipers[0].dag().digest(), gossipers[1].dag().digest());
+
+        // Both sides hold the same units, so the round carries no DAG and needs no response.
+        let (_, message) = unwrap!(gossipers[1].next_round());
+        assert_eq!(gossipers[1].statistics().in_sync_rounds, 1);
+        assert!(unwrap!(gossipers[0].handle_received_message(&ids[1], &message)).is_none());
+
+        // A peer which has moved on answers an out-of-date `InSync` with a push.
+        let _ = unwrap!(gossipers[1].send_new(&"World"));
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        assert_eq!(gossipers[0].statistics().in_sync_rounds, 1);
+        let response = unwrap!(gossipers[1].handle_received_message(&ids[0], &message));
+        let _ = gossipers[0].handle_received_message(&ids[1], &unwrap!(response));
+        assert!(gossipers[0].has_payload(&unwrap!(serialisation::serialise(&"World"))));
+    }
+
+    #[test]
+    fn certificates() {
+        let mut gossipers = create_network(3);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        gossipers[0].set_certifying(true);
+        let identifier = unwrap!(gossipers[0].send_new(&"Hello"));
+        for index in 1..3 {
+            assert_eq!(unwrap!(gossipers[index].send_new(&"Hello")), identifier);
+            let (_, message) = unwrap!(gossipers[index].next_round());
+            let _ = unwrap!(gossipers[0].handle_received_message(&ids[index], &message));
+        }
+        assert!(gossipers[0].dag().is_certified(&identifier));
+        assert!(gossipers[2].dag().certificate(&identifier).is_none());
+
+        // The certificate is passed on, and taken over once checked.
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        let _ = unwrap!(gossipers[2].handle_received_message(&ids[0], &message));
+        assert!(gossipers[2].dag().is_certified(&identifier));
+    }
+
+    #[test]
+    fn partition_healing() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let left = unwrap!(gossipers[0].send_new(&"Left"));
+        let _ = unwrap!(gossipers[1].send_new(&"Right"));
+        assert!(unwrap!(gossipers[0].divergence_from(&ids[1])).only_in_self.contains(&left));
+
+        for _ in 0..6 {
+            for &(from, to) in &[(0, 1), (1, 0)] {
+                let (_, message) = unwrap!(gossipers[from].next_round());
+                let response = unwrap!(gossipers[to].handle_received_message(&ids[from], &message));
+                if let Some(response) = response {
+                    let _ = gossipers[from].handle_received_message(&ids[to], &response);
+                }
+            }
+        }
+        assert!(gossipers[0].dag().diff(gossipers[1].dag()).is_empty());
+        assert!(unwrap!(gossipers[0].divergence_from(&ids[1])).is_empty());
+        let stranger = Id::from(Keypair::generate::<Sha3_512>(&mut SeededRng::new()).public);
+        assert!(gossipers[0].divergence_from(&stranger).is_err());
+    }
+
+    #[test]
+    fn cold_suppression() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        gossipers[0].set_rumor_rounds(Some(2));
+        gossipers[0].set_cold_suppression(Some(4));
+        let identifier = unwrap!(gossipers[0].send_new(&"Hello"));
+        for _ in 0..2 {
+            let _ = unwrap!(gossipers[0].next_round());
+        }
+        assert_eq!(gossipers[0].push_count(&identifier), Some(2));
+        let suppressed = gossipers[0].statistics().cold_units_suppressed;
+
+        // Pushed as many times as it takes to turn cold, the unit is left out of the next round.
+        let _ = unwrap!(gossipers[0].next_round());
+        assert_eq!(gossipers[0].push_count(&identifier), Some(2));
+        assert!(gossipers[0].statistics().cold_units_suppressed > suppressed);
+
+        // But still carried by the anti-entropy round.
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        assert_eq!(gossipers[0].push_count(&identifier), Some(3));
+        let _ = gossipers[1].handle_received_message(&ids[0], &message);
+        assert!(gossipers[1].has_payload(&unwrap!(serialisation::serialise(&"Hello"))));
+    }
+
+    #[test]
+    fn membership_reconciliation() {
+        // A star: the hub knows both leaves, which only know the hub.
+        let mut rng = SeededRng::new();
+        let mut gossipers: Vec<Gossiper> = (0..3)
+            .map(|_| Gossiper::from_keypair(Keypair::generate::<Sha3_512>(&mut rng)))
+            .collect();
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        for leaf in 1..3 {
+            unwrap!(gossipers[0].add_peer(ids[leaf]));
+            unwrap!(gossipers[leaf].add_peer(ids[0]));
+        }
+        gossipers[1].set_membership_reconciliation(MembershipReconciliation::Union);
+        let events = gossipers[1].events();
+        assert_eq!(gossipers[1].quorum_info().members, 2);
+
+        let advertisement = unwrap!(gossipers[0].advertise_units());
+        let members = unwrap!(gossipers[1].handle_received_message(&ids[0], &advertisement));
+        assert_eq!(
+            unwrap!(events.try_recv()),
+            Event::MembershipDisagreement { peer: ids[0] }
+        );
+        // The hub answers the leaf's member set with its own, which the leaf adopts.
+        let members = unwrap!(gossipers[0].handle_received_message(&ids[1], &unwrap!(members)));
+        let _ = gossipers[1].handle_received_message(&ids[0], &unwrap!(members));
+        assert_eq!(gossipers[1].members(), gossipers[0].members());
+        assert_eq!(gossipers[1].quorum_info().members, 3);
+        assert_eq!(gossipers[1].export_peers(), vec![ids[0]]);
+    }
+
+    #[test]
+    fn network_isolation() {
+        let mut rng = SeededRng::new();
+        let mut gossipers: Vec<Gossiper> = [&b"first"[..], b"second", b"first"]
+            .iter()
+            .map(|network| Gossiper::for_network(Keypair::generate::<Sha3_512>(&mut rng), network))
+            .collect();
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        assert_ne!(gossipers[0].dag().network(), gossipers[1].dag().network());
+        assert_eq!(gossipers[0].dag().network(), gossipers[2].dag().network());
+
+        for index in 1..3 {
+            let _ = gossipers[0].add_peer(ids[index]);
+            let _ = gossipers[index].add_peer(ids[0]);
+        }
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        for index in 1..3 {
+            let _ = gossipers[index].handle_received_message(&ids[0], &message);
+        }
+        let payload = unwrap!(serialisation::serialise(&"Hello"));
+        assert!(!gossipers[1].has_payload(&payload));
+        assert!(gossipers[2].has_payload(&payload));
+    }
+
+    #[test]
+    fn send_new_batch() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        let _ = unwrap!(gossipers[1].send_new(&"Concurrent"));
+        let (_, message) = unwrap!(gossipers[1].next_round());
+        let _ = unwrap!(gossipers[0].handle_received_message(&ids[1], &message));
+        assert_eq!(gossipers[0].dag().tips().count(), 2);
+
+        let identifiers = unwrap!(gossipers[0].send_new_batch(&["a", "b", "Hello", "c"]));
+        assert_eq!(identifiers[2], unwrap!(gossipers[0].send_new(&"Hello")));
+        assert_eq!(identifiers.len(), 4);
+        assert_eq!(gossipers[0].statistics().units_created, 6);
+        let dag = gossipers[0].dag();
+        let parents = |identifier: &UnitId| unwrap!(dag.get(identifier)).parents.clone();
+        assert_eq!(parents(&identifiers[0]).len(), 2);
+        assert_eq!(parents(&identifiers[1]), iter::once(identifiers[0]).collect());
+        // The payload already held isn't added again, and doesn't break the chain.
+        assert!(!identifiers[..2].contains(&identifiers[2]));
+        assert_eq!(parents(&identifiers[3]), iter::once(identifiers[1]).collect());
+        assert_eq!(dag.tips().collect::<Vec<_>>(), vec![&identifiers[3]]);
+    }
+
+    #[test]
+    fn round_hook() {
+        struct Health {
+            status: Vec<u8>,
+            received: Arc<Mutex<Vec<(Id, Vec<Vec<u8>>)>>>,
+        }
+
+        impl RoundHook for Health {
+            fn outgoing(&mut self) -> Vec<Vec<u8>> {
+                vec![self.status.clone()]
+            }
+
+            fn incoming(&mut self, peer_id: &Id, blobs: &[Vec<u8>]) {
+                unwrap!(self.received.lock()).push((*peer_id, blobs.to_vec()));
+            }
+        }
+
+        let mut gossipers = create_network(3);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        for (index, gossiper) in gossipers.iter_mut().enumerate() {
+            gossiper.set_round_hook(Some(Box::new(Health {
+                status: vec![index as u8],
+                received: received.clone(),
+            })));
+        }
+        let (peer_id, message) = unwrap!(gossipers[0].next_round());
+        let index = unwrap!(ids.iter().position(|id| *id == peer_id));
+        let _ = gossipers[index].handle_received_message(&ids[0], &message);
+        assert_eq!(*unwrap!(received.lock()), vec![(ids[0], vec![vec![0]])]);
+
+        // Blobs claimed to come from another peer fail authentication and aren't handed over.
+        let other = 3 - index;
+        let _ = gossipers[index].handle_received_message(&ids[other], &message);
+        assert_eq!(unwrap!(received.lock()).len(), 1);
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn group_key() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let key = GroupKey::generate();
+        let restored = unwrap!(GroupKey::from_bytes(&key.to_bytes()));
+        gossipers[0].set_group_key(Some(key));
+        gossipers[1].set_group_key(Some(restored));
+        let payload = unwrap!(serialisation::serialise(&"Secret"));
+
+        // Both seal the payload identically, so their observations make it stable.
+        for index in 0..2 {
+            let _ = unwrap!(gossipers[index].send_new(&"Secret"));
+        }
+        for index in 0..2 {
+            let (_, message) = unwrap!(gossipers[index].next_round());
+            let _ = unwrap!(gossipers[1 - index].handle_received_message(&ids[index], &message));
+        }
+        for gossiper in &mut gossipers {
+            assert!(!gossiper.dag.contains_payload(&payload[..]));
+            assert_eq!(gossiper.poll_ordered(), vec![payload.clone()]);
+        }
+
+        // Without the key, the payload can't be read.
+        gossipers[0].set_group_key(Some(GroupKey::generate()));
+        gossipers[0].delivered.clear();
+        assert!(gossipers[0].poll_ordered().is_empty());
+    }
+
+    #[cfg(feature = "chaos")]
+    #[test]
+    fn chaos() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        gossipers[0].set_chaos(ChaosConfig {
+            drop_probability: 1.0,
+            ..ChaosConfig::default()
+        });
+        match gossipers[0].next_round() {
+            Err(Error::ChaosDrop) => (),
+            result => panic!("Unexpected result: {:?}", result.map(|(peer_id, _)| peer_id)),
+        }
+
+        gossipers[0].set_chaos(ChaosConfig {
+            corrupt_probability: 1.0,
+            ..ChaosConfig::default()
+        });
+        let (_, corrupted) = unwrap!(gossipers[0].next_round());
+        gossipers[0].set_chaos(ChaosConfig::default());
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        assert_eq!(corrupted.len(), message.len());
+        let flipped = corrupted
+            .iter()
+            .zip(&message)
+            .filter(|&(lhs, rhs)| lhs != rhs)
+            .count();
+        assert_eq!(flipped, 1);
+        let _ = gossipers[1].handle_received_message(&ids[0], &message);
+        assert!(gossipers[1].has_payload(&unwrap!(serialisation::serialise(&"Hello"))));
+    }
+
+    #[test]
+    fn strict_checks() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        gossipers[1].set_strict_checks(true);
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        let (_, message) = unwrap!(gossipers[0].next_round());
+        let _ = unwrap!(gossipers[1].handle_received_message(&ids[0], &message));
+
+        // A violation fails the call it was found during, once, rather than panicking.
+        gossipers[1].enforce(Err(Error::InvariantViolated("broken".to_string())));
+        match gossipers[1].handle_received_message(&ids[0], &message) {
+            Err(Error::InvariantViolated(ref reason)) if reason == "broken" => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        let _ = unwrap!(gossipers[1].handle_received_message(&ids[0], &message));
+    }
+
+    #[test]
+    fn statistics() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"Hello"));
+        let mut sent = 0;
+        for _ in 0..3 {
+            for index in 0..2 {
+                let (_, message) = unwrap!(gossipers[index].next_round());
+                sent += message.len() as u64;
+                let _ = gossipers[1 - index].handle_received_message(&ids[index], &message);
+            }
+        }
+        let statistics = gossipers[0].statistics().clone();
+        assert_eq!(statistics.rounds, 3);
+        assert_eq!(statistics.units_created, 1);
+        assert_eq!(statistics.units_received, 0);
+        assert!(statistics.duplicate_units > 0);
+        assert_eq!(statistics.rejected_units, 0);
+        assert_eq!(statistics.time_to_stability.count, 1);
+        assert!(statistics.mean_time_to_stability().is_some());
+        assert!(gossipers.iter().all(|gossiper| gossiper.first_held.is_empty()));
+        let statistics = gossipers[1].statistics().clone();
+        assert_eq!(statistics.units_created, 0);
+        assert_eq!(statistics.units_received, 1);
+        let bytes_sent = gossipers[0].statistics().bytes_sent + statistics.bytes_sent;
+        assert!(bytes_sent >= sent);
+        assert!(statistics.bytes_received > 0);
+
+        gossipers[0].reset_statistics();
+        assert_eq!(*gossipers[0].statistics(), Statistics::default());
+    }
+
+    #[test]
+    fn content_type() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new_typed(&"{}", ContentType::Json));
+        for _ in 0..3 {
+            for index in 0..2 {
+                let (_, message) = unwrap!(gossipers[index].next_round());
+                let _ = gossipers[1 - index].handle_received_message(&ids[index], &message);
+            }
+        }
+        let expected = (unwrap!(serialisation::serialise(&"{}")), Some(ContentType::Json));
+        for gossiper in &mut gossipers {
+            assert_eq!(gossiper.poll_ordered_typed(), vec![expected.clone()]);
+        }
+    }
+
+    #[test]
+    fn convergence() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        for gossiper in &mut gossipers {
+            let _ = unwrap!(gossiper.send_new(&"payload"));
+        }
+        let mut rounds = 0;
+        while !gossipers.iter().all(Gossiper::is_converged) {
+            assert!(rounds < 10, "not converged after {} rounds", rounds);
+            rounds += 1;
+            for index in 0..2 {
+                let (_, message) = unwrap!(gossipers[index].next_round());
+                let _ = gossipers[1 - index].handle_received_message(&ids[index], &message);
+            }
+        }
+        let _ = unwrap!(gossipers[0].send_new(&"other payload"));
+        assert!(!gossipers[0].is_converged());
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        let _ = unwrap!(gossipers[0].send_new(&"payload"));
+        let snapshot = unwrap!(gossipers[0].snapshot());
+
+        let other_keys = gossipers.remove(1).keys;
+        assert!(match Gossiper::restore(&snapshot, other_keys) {
+            Err(Error::ForeignSnapshot(owner)) => owner == ids[0],
+            _ => false,
+        });
+
+        let keys = gossipers.remove(0).keys;
+        let restored = unwrap!(Gossiper::restore(&snapshot, keys));
+        assert_eq!(restored.id(), ids[0]);
+        assert_eq!(restored.export_peers(), vec![ids[1]]);
+        assert!(restored.has_payload(&unwrap!(serialisation::serialise(&"payload"))));
+    }
+
+    #[test]
+    // Neither random nor corrupted messages may make a gossiper panic.
+    fn fuzz_received_messages() {
+        let mut rng = SeededRng::new();
+        let mut gossipers = create_network(2);
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        gossipers[0].set_decode_failure_policy(DecodeFailurePolicy::RequestRetransmission);
+        gossipers[1].set_wire_codecs(vec![WireCodec::Columnar, WireCodec::Units]);
+        for round in 0..500u32 {
+            if round % 10 == 0 {
+                let _ = unwrap!(gossipers[1].send_new(&round));
+            }
+            let (_, mut message) = unwrap!(gossipers[1].next_round());
+            if rng.gen() {
+                for _ in 0..rng.gen_range(1, 4) {
+                    let index = rng.gen_range(0, message.len() as u32) as usize;
+                    message[index] = rng.gen();
+                }
+            } else {
+                let len = rng.gen_range(0, 64);
+                message = rng.gen_iter().take(len).collect();
+            }
+            if let Ok(Some(response)) = gossipers[0].handle_received_message(&ids[1], &message) {
+                let _ = gossipers[1].handle_received_message(&ids[0], &response);
+            }
         }
     }
 
@@ -0,0 +1,98 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use id::UnitId;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Report describing a cycle found while travelling along the parents of a unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Identifiers of the units visited, in the order they were visited.
+    pub traversal: Vec<UnitId>,
+    /// The suspected back-edge, as `(child, parent)`, where the parent had already been visited.
+    pub back_edge: (UnitId, UnitId),
+}
+
+impl Diagnostics {
+    /// Create a report from the traversal and the edge closing the cycle.
+    pub fn new(traversal: Vec<UnitId>, back_edge: (UnitId, UnitId)) -> Self {
+        Diagnostics {
+            traversal,
+            back_edge,
+        }
+    }
+
+    /// Write the report to the file at `path`, e.g. to be attached to a bug report.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        write!(file, "{}", self)
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        writeln!(formatter, "Traversal of {} units:", self.traversal.len())?;
+        for identifier in &self.traversal {
+            writeln!(formatter, "    {}", to_hex(&identifier.0))?;
+        }
+        writeln!(
+            formatter,
+            "Suspected back-edge: {} -> {}",
+            to_hex(&(self.back_edge.0).0),
+            to_hex(&(self.back_edge.1).0)
+        )
+    }
+}
+
+/// The bytes as lowercase hex digits.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+
+    #[test]
+    fn report() {
+        let (first, second) = (UnitId([1; 32]), UnitId([0xab; 32]));
+        let diagnostics = Diagnostics::new(vec![first, second], (second, first));
+        let report = diagnostics.to_string();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "Traversal of 2 units:");
+        assert_eq!(lines[1], format!("    {}", "01".repeat(32)));
+        assert_eq!(lines[2], format!("    {}", "ab".repeat(32)));
+        assert_eq!(
+            lines[3],
+            format!("Suspected back-edge: {} -> {}", "ab".repeat(32), "01".repeat(32))
+        );
+
+        let path = env::temp_dir().join("dag_gossip_diagnostics_report.txt");
+        unwrap!(diagnostics.write_to_file(&path));
+        let mut written = String::new();
+        let _ = unwrap!(unwrap!(File::open(&path)).read_to_string(&mut written));
+        let _ = fs::remove_file(&path);
+        assert_eq!(written, report);
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use error::Error;
+use rust_sodium;
+use rust_sodium::crypto::secretbox::{self, KEYBYTES, Key, NONCEBYTES, Nonce};
+use std::fmt::{self, Debug, Formatter};
+use tiny_keccak::sha3_256;
+
+/// A symmetric key shared by the members of a group, sealing the payloads they gossip so that only
+/// members can read them. Only the payloads are sealed: the structure of the DAG and the observers
+/// stay in the clear, for peers to union DAGs without the key.
+///
+/// Sealing is deterministic: the nonce is derived from the key and the payload, so that members
+/// observing the same payload seal it identically and their observations are folded into the
+/// same unit. The flip side is that anyone can tell whether two sealed payloads are equal.
+#[derive(Clone)]
+pub struct GroupKey(Key);
+
+impl GroupKey {
+    /// A new random key, to be shared with the other members of the group out of band.
+    pub fn generate() -> Self {
+        let _ = rust_sodium::init();
+        GroupKey(secretbox::gen_key())
+    }
+
+    /// Restore a key from its bytes, as returned by `to_bytes`. Fails with
+    /// `Error::InvalidGroupKey` if they aren't a valid key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Key::from_slice(bytes).map(GroupKey).ok_or(Error::InvalidGroupKey)
+    }
+
+    /// The bytes of this key, to share it or persist it. To be kept secret.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        (self.0).0.to_vec()
+    }
+
+    /// Seal the payload: the nonce, followed by the authenticated ciphertext.
+    pub fn seal(&self, payload: &[u8]) -> Vec<u8> {
+        let mut keyed = Vec::with_capacity(KEYBYTES + payload.len());
+        keyed.extend_from_slice(&(self.0).0);
+        keyed.extend_from_slice(payload);
+        let digest = sha3_256(&keyed);
+        let mut nonce = [0; NONCEBYTES];
+        nonce.copy_from_slice(&digest[..NONCEBYTES]);
+        let mut sealed = nonce.to_vec();
+        sealed.extend(secretbox::seal(payload, &Nonce(nonce), &self.0));
+        sealed
+    }
+
+    /// Open a payload sealed by `seal`. Fails with `Error::Unsealing` if it wasn't sealed with
+    /// this key, or was tampered with.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, Error> {
+        if sealed.len() < NONCEBYTES {
+            return Err(Error::Unsealing);
+        }
+        let nonce = Nonce::from_slice(&sealed[..NONCEBYTES]).ok_or(Error::Unsealing)?;
+        secretbox::open(&sealed[NONCEBYTES..], &nonce, &self.0).map_err(|()| Error::Unsealing)
+    }
+}
+
+impl Debug for GroupKey {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "GroupKey(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_open() {
+        let key = GroupKey::generate();
+        let restored = unwrap!(GroupKey::from_bytes(&key.to_bytes()));
+        assert!(GroupKey::from_bytes(&[1, 2, 3]).is_err());
+
+        // Sealing is deterministic, so members seal the same payload identically.
+        let sealed = key.seal(b"Secret");
+        assert_eq!(restored.seal(b"Secret"), sealed);
+        assert_ne!(key.seal(b"Other"), sealed);
+        assert_eq!(unwrap!(restored.open(&sealed)), b"Secret".to_vec());
+
+        // Without the key, or once tampered with, the payload can't be read.
+        match GroupKey::generate().open(&sealed) {
+            Err(Error::Unsealing) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(key.open(&tampered).is_err());
+        assert!(key.open(&sealed[..NONCEBYTES - 1]).is_err());
+    }
+}
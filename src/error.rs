@@ -15,7 +15,11 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use diagnostics::Diagnostics;
+use id::{Id, UnitId};
 use maidsafe_utilities::serialisation::SerialisationError;
+use std::collections::BTreeSet;
+use std::io;
 
 quick_error! {
     /// Gossiper error variants.
@@ -26,6 +30,163 @@ quick_error! {
             description("No connected peers")
             display("There are no connected peers with which to gossip.")
         }
+        /// A cycle was found while travelling along the parents of a unit.
+        CycleDetected(diagnostics: Diagnostics) {
+            description("Cycle detected in the DAG")
+            display("Cycle detected in the DAG.\n{}", diagnostics)
+        }
+        /// A unit refers to a parent which is not held.
+        MissingParent(parent: UnitId) {
+            description("Missing parent unit")
+            display("Parent unit {:?} is missing.", parent)
+        }
+        /// An imported foreign event refers to a parent which is not among the imported events.
+        MissingForeignParent(hash: Vec<u8>) {
+            description("Missing foreign parent event")
+            display("Parent event {:?} is missing from the import.", hash)
+        }
+        /// A DAG started from a different genesis than ours.
+        GenesisMismatch {
+            description("Genesis mismatch")
+            display("The DAG started from a different genesis.")
+        }
+        /// A DAG checkpointed ahead of us, at a unit neither held by us nor signed by a majority.
+        UnverifiedCheckpoint(height: usize) {
+            description("Unverified checkpoint")
+            display("Can't verify the checkpoint at height {}.", height)
+        }
+        /// A unit's identifier doesn't match its content.
+        InvalidUnit(identifier: UnitId) {
+            description("Invalid unit")
+            display("Unit {:?} has an identifier not matching its content.", identifier)
+        }
+        /// The columns of a column-wise encoded DAG don't describe the same units.
+        MalformedColumns {
+            description("Malformed columns")
+            display("The columns of the encoded DAG are inconsistent.")
+        }
+        /// Persisted data was written in a format version this version of the crate can't read.
+        UnsupportedVersion(version: u32) {
+            description("Unsupported format version")
+            display("Format version {} is not supported.", version)
+        }
+        /// A message was sent with a protocol version this version of the crate can't speak.
+        IncompatibleProtocol(version: u32) {
+            description("Incompatible protocol version")
+            display("Protocol version {} is not compatible with ours.", version)
+        }
+        /// A message was encoded with a wire format this build doesn't support.
+        UnsupportedWireFormat(tag: u8) {
+            description("Unsupported wire format")
+            display("Wire format {} is not supported by this build.", tag)
+        }
+        /// A message was sent by a peer deriving unit identifiers with another hash algorithm.
+        HashAlgorithmMismatch(tag: u8) {
+            description("Hash algorithm mismatch")
+            display("Hash algorithm {} is not the one of our DAG.", tag)
+        }
+        /// A DAG export doesn't follow its schema.
+        MalformedExport(reason: String) {
+            description("Malformed export")
+            display("Malformed DAG export: {}.", reason)
+        }
+        /// A message couldn't be encoded or decoded with its wire format.
+        Encoding(reason: String) {
+            description("Encoding error")
+            display("Encoding error: {}", reason)
+        }
+        /// The snapshot was taken by another gossiper than the one owning the given keys.
+        ForeignSnapshot(owner: Id) {
+            description("Foreign snapshot")
+            display("The snapshot was taken by {:?}.", owner)
+        }
+        /// The bytes given aren't a valid ed25519 secret key.
+        InvalidSecretKey {
+            description("Invalid secret key")
+            display("The bytes given aren't a valid secret key.")
+        }
+        /// The input isn't an id, or a unit identifier, in hex or base58, or of the right length.
+        InvalidId(input: String) {
+            description("Invalid id")
+            display("{} isn't a valid id.", input)
+        }
+        /// A message received breaks one of the bounds set by `GossiperConfig`: its size in bytes,
+        /// its number of units or the length of one of its payloads, as named by `limit`.
+        MessageLimitExceeded(limit: &'static str, size: usize, max: usize) {
+            description("Message limit exceeded")
+            display("The message's {} of {} exceeds the limit of {}.", limit, size, max)
+        }
+        /// A message was dropped on purpose, as configured by `ChaosConfig`. Only ever returned
+        /// with the `chaos` feature enabled.
+        ChaosDrop {
+            description("Message dropped by fault injection")
+            display("The message was dropped by fault injection.")
+        }
+        /// One of the invariants checked by `check_invariants`, `check_stability_monotone` or
+        /// `check_agreement` doesn't hold.
+        InvariantViolated(reason: String) {
+            description("Invariant violated")
+            display("Invariant violated: {}.", reason)
+        }
+        /// A payload is larger than allowed by `DagConfig::max_payload_size`.
+        PayloadTooLarge(size: usize) {
+            description("Payload too large")
+            display("The payload of {} bytes is larger than allowed.", size)
+        }
+        /// The DAG holds as many units as allowed by `DagConfig::max_units`, and no room could be
+        /// made.
+        CapacityExceeded(max_units: usize) {
+            description("Capacity exceeded")
+            display("The DAG can't hold more than {} units.", max_units)
+        }
+        /// The bytes given aren't a valid group key. Only ever returned with the `encryption`
+        /// feature enabled.
+        InvalidGroupKey {
+            description("Invalid group key")
+            display("The bytes given aren't a valid group key.")
+        }
+        /// A sealed payload couldn't be opened with the group key. Only ever returned with the
+        /// `encryption` feature enabled.
+        Unsealing {
+            description("Unsealing error")
+            display("The payload couldn't be opened with the group key.")
+        }
+        /// A message was dropped as the peer which sent it is muted for exceeding the rate limit.
+        PeerMuted(peer: Id) {
+            description("Peer muted")
+            display("Peer {:?} is muted for exceeding the rate limit.", peer)
+        }
+        /// The DAG holds no childless unit for a new unit to take as parent.
+        NoTips {
+            description("No tips")
+            display("There is no childless unit to take as parent of a new unit.")
+        }
+        /// Observers without a valid signature were dropped from received units.
+        ForgedObservers(observers: BTreeSet<Id>) {
+            description("Forged observers")
+            display("Dropped observers without a valid signature: {:?}", observers)
+        }
+        /// A received unit has observers which aren't among the authorised members.
+        UnauthorisedObservers(identifier: UnitId, observers: BTreeSet<Id>) {
+            description("Unauthorised observers")
+            display("Unit {:?} is observed by non-members {:?}.", identifier, observers)
+        }
+        /// The topic isn't one subscribed to.
+        NotSubscribed(topic: String) {
+            description("Not subscribed")
+            display("Not subscribed to topic {:?}.", topic)
+        }
+        /// No address is known for the peer.
+        UnknownPeer(peer: Id) {
+            description("Unknown peer")
+            display("No address is known for peer {:?}.", peer)
+        }
+        /// I/O error from the transport.
+        Io(error: io::Error) {
+            description(error.description())
+            display("I/O error: {}", error)
+            from()
+        }
         /// Serialisation Error.
         Serialisation(error: SerialisationError) {
             description(error.description())
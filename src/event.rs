@@ -0,0 +1,102 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use catch_up::CatchUpProgress;
+use id::{Id, UnitId};
+use message::Nack;
+use watchdog::Stall;
+
+/// A change of state of the DAG, or of our view of the peers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event<T = Vec<u8>> {
+    /// A payload appeared for the first time, in a new unit.
+    NewUnit {
+        /// Identifier of the new unit.
+        identifier: UnitId,
+        /// The payload it holds.
+        payload: T,
+    },
+    /// A unit gathered enough observers to become stable.
+    UnitStable {
+        /// Identifier of the unit.
+        identifier: UnitId,
+    },
+    /// A message from a peer couldn't be deserialised, and was handled according to the
+    /// `DecodeFailurePolicy` in use.
+    MalformedMessage {
+        /// The peer which sent the message.
+        peer: Id,
+    },
+    /// A message from a peer was sent with a protocol version, a wire format or a hash algorithm
+    /// we don't support, and was dropped without penalising the peer.
+    IncompatibleMessage {
+        /// The peer which sent the message.
+        peer: Id,
+    },
+    /// A peer rejected units we sent it, which may mean our DAG got corrupted. The gossiper
+    /// rebuilt its indices and pulls the peer's units to resync.
+    Rejected {
+        /// The peer which rejected the units.
+        peer: Id,
+        /// The units rejected, and why.
+        nack: Nack,
+    },
+    /// Stable units were evicted to make room for new ones, as per `DagConfig`.
+    Evicted {
+        /// Number of units evicted.
+        count: usize,
+    },
+    /// Every part of an atomic payload of a `ShardedDag` became stable in its shard.
+    AtomicStable {
+        /// Identifier of the atomic payload, as returned by `ShardedDag::new_atomic_payload`.
+        identifier: UnitId,
+    },
+    /// Catching up with the units held by peers progressed. Sent whenever units were received or
+    /// more were found missing, the last one once nothing is known to be missing anymore. While
+    /// bootstrapping, sent for every page received instead, the last one once all were.
+    CatchingUp {
+        /// The progress made.
+        progress: CatchUpProgress,
+    },
+    /// The member set of a peer differs from ours, as per the membership digest carried by its
+    /// messages, so that we may disagree on the stability threshold. Sent whenever the digest
+    /// the peer carries changes to one differing from ours.
+    MembershipDisagreement {
+        /// The peer.
+        peer: Id,
+    },
+    /// A peer exceeded the rate limit of the `Gossiper`, and its messages are dropped until it is
+    /// unmuted.
+    PeerMuted {
+        /// The peer muted.
+        peer: Id,
+    },
+    /// The watchdog of the `Gossiper` detected a stall, and took the configured corrective actions.
+    Stalled {
+        /// The stall detected.
+        stall: Stall,
+    },
+    /// The watchdog of the `Gossiper` found a unit unstable for too many rounds, e.g. as peers
+    /// dropped the messages carrying it. Sent once per unit, which is regossiped aggressively if
+    /// configured so until it becomes stable.
+    UnitStalled {
+        /// Identifier of the unit.
+        identifier: UnitId,
+        /// Number of rounds it stayed unstable, while peers were live.
+        rounds: u64,
+    },
+}
@@ -0,0 +1,411 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Renderings of a DAG for external tooling: DOT and web graph renderings to visualise it, and
+//! `DagExport`, plain serde structs to inspect or rebuild it, e.g. from dashboards or Python
+//! analysis scripts.
+//!
+//! Serialised as JSON, a `DagExport` follows a schema only changed along with
+//! `EXPORT_SCHEMA_VERSION`. All identifiers, keys and bytes are rendered as lowercase hex:
+//!
+//! ```text
+//! {
+//!   "schema_version": 1,
+//!   "network": "<unit id>",           // identifier of the original genesis
+//!   "genesis": "<unit id>",           // identifier of the current genesis, among `units`
+//!   "majority": 2,
+//!   "checkpoint_height": 0,           // number of units pruned since the original genesis
+//!   "checkpoint_digest": "<bytes>",   // digest of their payloads, empty if none was pruned
+//!   "units": [                        // in identifier order
+//!     {
+//!       "id": "<unit id>",
+//!       "parents": ["<unit id>"],
+//!       "payload": "<bytes>",         // the payload as encoded by `Unit::encoded_payload`
+//!       "observers": ["<node id>"],
+//!       "signatures": {"<node id>": "<bytes>"},
+//!       "content_type": null,         // or "Json", "Cbor", "Text", "Binary", {"Other": "..."}
+//!       "clock": 1,
+//!       "metadata": {"<name>": "<bytes>"},
+//!       "certificate": null,          // or the certifying signatures, as `signatures`
+//!       "stable": true,
+//!       "depth": 1
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `stable` and `depth` are derived by the exporting node, as per its own stability policy, and
+//! ignored by `Dag::from_export`, which derives them again.
+
+use dag::Dag;
+use diagnostics::to_hex;
+use error::Error;
+use id::{self, Id, UnitId};
+use maidsafe_utilities::serialisation;
+use std::collections::{BTreeMap, BTreeSet};
+use unit::{ContentType, Payload, QuorumCertificate, Unit};
+
+/// Version of the schema of `DagExport`, bumped on any change to it.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// A DAG as plain serde structs, as per the schema documented in this module.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DagExport {
+    /// Version of the schema, `EXPORT_SCHEMA_VERSION` when exported by this version of the crate.
+    pub schema_version: u32,
+    /// Identifier of the original genesis, in hex.
+    pub network: String,
+    /// Identifier of the current genesis, in hex.
+    pub genesis: String,
+    /// Number of observers making a unit stable, or 0 for the default threshold.
+    pub majority: u8,
+    /// Number of units pruned since the original genesis.
+    pub checkpoint_height: usize,
+    /// Digest of the payloads of the pruned units, in hex.
+    pub checkpoint_digest: String,
+    /// The units, in identifier order.
+    pub units: Vec<UnitExport>,
+}
+
+/// A unit as a plain serde struct, as per the schema documented in this module.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitExport {
+    /// Identifier of the unit, in hex.
+    pub id: String,
+    /// Identifiers of its parents, in hex.
+    pub parents: Vec<String>,
+    /// The payload as encoded by `Unit::encoded_payload`, in hex.
+    pub payload: String,
+    /// Identifiers of its observers, in hex.
+    pub observers: Vec<String>,
+    /// The observers' signatures over the payload, in hex, by observer.
+    pub signatures: BTreeMap<String, String>,
+    /// How the payload is encoded, if its creator said.
+    pub content_type: Option<ContentType>,
+    /// Lamport clock of the unit.
+    pub clock: u64,
+    /// Application metadata, the values in hex, by name.
+    pub metadata: BTreeMap<String, String>,
+    /// The signatures of the quorum certificate, if certified, as `signatures`.
+    pub certificate: Option<BTreeMap<String, String>>,
+    /// Whether the unit was stable for the exporting node.
+    pub stable: bool,
+    /// Length of the longest path from the unit back to the genesis, for the exporting node.
+    pub depth: usize,
+}
+
+impl UnitExport {
+    /// Export `unit`, along with the given derived properties. Fails if its payload can't be
+    /// encoded.
+    pub fn new<T: Payload>(unit: &Unit<T>, stable: bool, depth: usize) -> Result<Self, Error> {
+        Ok(UnitExport {
+            id: unit.identifier.to_hex(),
+            parents: unit.parents.iter().map(UnitId::to_hex).collect(),
+            payload: to_hex(&unit.encoded_payload()?),
+            observers: unit.observers.iter().map(Id::to_hex).collect(),
+            signatures: export_signatures(&unit.signatures),
+            content_type: unit.content_type.clone(),
+            clock: unit.clock,
+            metadata: unit.metadata
+                .iter()
+                .map(|(name, value)| (name.clone(), to_hex(value)))
+                .collect(),
+            certificate: unit.certificate.as_ref().map(|certificate| {
+                export_signatures(&certificate.signatures)
+            }),
+            stable,
+            depth,
+        })
+    }
+
+    /// The unit exported. Fails with `Error::InvalidId` if an identifier is malformed, with
+    /// `Error::MalformedExport` if bytes aren't valid hex, or if the payload can't be decoded.
+    pub fn to_unit<T: Payload>(&self) -> Result<Unit<T>, Error> {
+        let identifier = parse_unit_id(&self.id)?;
+        let mut parents = BTreeSet::new();
+        for parent in &self.parents {
+            let _ = parents.insert(parse_unit_id(parent)?);
+        }
+        let mut observers = BTreeSet::new();
+        for observer in &self.observers {
+            let _ = observers.insert(parse_id(observer)?);
+        }
+        let mut metadata = BTreeMap::new();
+        for (name, value) in &self.metadata {
+            let _ = metadata.insert(name.clone(), parse_hex(value)?);
+        }
+        let certificate = match self.certificate {
+            Some(ref signatures) => Some(QuorumCertificate {
+                identifier,
+                signatures: import_signatures(signatures)?,
+            }),
+            None => None,
+        };
+        Ok(Unit {
+            identifier,
+            parents,
+            payload: serialisation::deserialise(&parse_hex(&self.payload)?)?,
+            observers,
+            signatures: import_signatures(&self.signatures)?,
+            content_type: self.content_type.clone(),
+            clock: self.clock,
+            metadata,
+            certificate,
+        })
+    }
+}
+
+/// What `Dag::to_dot` renders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DotOptions {
+    /// Label each unit with its payload.
+    pub payload_labels: bool,
+    /// Highlight the units observed by this node, typically ourself.
+    pub highlight: Option<Id>,
+    /// Label each unit with its number of observers.
+    pub observer_counts: bool,
+    /// Number of hex digits of the identifiers shown in labels, or `None` to show them in full.
+    /// Units are always named by their full identifier, so truncation never merges them. 6 by
+    /// default, matching the short form rendered by `Debug`.
+    pub identifier_digits: Option<usize>,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions {
+            payload_labels: true,
+            highlight: None,
+            observer_counts: false,
+            identifier_digits: Some(6),
+        }
+    }
+}
+
+/// The DAG in the DOT language. Stable units and the edges to their parent are drawn in black,
+/// unstable ones in red with dashed edges.
+pub fn to_dot<T: Payload>(dag: &Dag<T>, options: &DotOptions) -> String {
+    let mut dot = String::from("digraph dag {\n    node [fontname=Courier]\n");
+    for unit in dag.units() {
+        let name = to_hex(&unit.identifier.0);
+        let mut label = match options.identifier_digits {
+            Some(digits) if digits < name.len() => format!("{}..", &name[..digits]),
+            _ => name.clone(),
+        };
+        if options.payload_labels {
+            label += &format!("\n{:?}", unit.payload);
+        }
+        if options.observer_counts {
+            label += &format!("\n{} observers", unit.observers.len());
+        }
+        let color = if dag.is_stable(unit) { "black" } else { "red" };
+        let highlighted = options.highlight.map_or(false, |id| unit.observers.contains(&id));
+        let fill = if highlighted {
+            ", style=filled, fillcolor=lightblue"
+        } else {
+            ""
+        };
+        dot += &format!(
+            "    \"{}\" [label=\"{}\", color={}{}]\n",
+            name,
+            escape(&label),
+            color,
+            fill
+        );
+    }
+    for unit in dag.units() {
+        let style = if dag.is_stable(unit) {
+            "color=black"
+        } else {
+            "color=red, style=dashed"
+        };
+        for parent in held_parents(dag, unit) {
+            dot += &format!(
+                "    \"{}\" -> \"{}\" [{}]\n",
+                to_hex(&unit.identifier.0),
+                to_hex(&parent.0),
+                style
+            );
+        }
+    }
+    dot += "}\n";
+    dot
+}
+
+/// The DAG as a JSON object holding a `nodes` array, one per unit, and a `links` array, one per
+/// edge from a unit to one of its parents, as expected by most web graph visualisers.
+pub fn to_json_graph<T: Payload>(dag: &Dag<T>) -> String {
+    let nodes: Vec<String> = dag.units()
+        .map(|unit| {
+            let observers: Vec<String> = unit.observers
+                .iter()
+                .map(|observer| format!("\"{}\"", to_hex(&observer.0)))
+                .collect();
+            format!(
+                "{{\"id\":\"{}\",\"payload\":\"{}\",\"observers\":[{}],\"stable\":{}}}",
+                to_hex(&unit.identifier.0),
+                escape(&format!("{:?}", unit.payload)),
+                observers.join(","),
+                dag.is_stable(unit)
+            )
+        })
+        .collect();
+    let mut links: Vec<String> = Vec::new();
+    for unit in dag.units() {
+        for parent in held_parents(dag, unit) {
+            links.push(format!(
+                "{{\"source\":\"{}\",\"target\":\"{}\"}}",
+                to_hex(&unit.identifier.0),
+                to_hex(&parent.0)
+            ));
+        }
+    }
+    format!(
+        "{{\"nodes\":[{}],\"links\":[{}]}}",
+        nodes.join(","),
+        links.join(",")
+    )
+}
+
+// The unit's parents which are held, each making for an edge.
+fn held_parents<'a, T: Payload>(dag: &Dag<T>, unit: &'a Unit<T>) -> Vec<&'a UnitId> {
+    unit.parents
+        .iter()
+        .filter(|parent| dag.contains(parent))
+        .collect()
+}
+
+fn export_signatures(signatures: &BTreeMap<Id, Vec<u8>>) -> BTreeMap<String, String> {
+    signatures
+        .iter()
+        .map(|(observer, signature)| (observer.to_hex(), to_hex(signature)))
+        .collect()
+}
+
+fn import_signatures(
+    signatures: &BTreeMap<String, String>,
+) -> Result<BTreeMap<Id, Vec<u8>>, Error> {
+    let mut imported = BTreeMap::new();
+    for (observer, signature) in signatures {
+        let _ = imported.insert(parse_id(observer)?, parse_hex(signature)?);
+    }
+    Ok(imported)
+}
+
+// Identifiers are exported in hex only, so any other rendering is rejected rather than parsed.
+fn parse_id(hex: &str) -> Result<Id, Error> {
+    match id::from_hex(hex) {
+        Some(ref bytes) => Id::from_bytes(bytes),
+        None => Err(Error::InvalidId(hex.to_string())),
+    }
+}
+
+fn parse_unit_id(hex: &str) -> Result<UnitId, Error> {
+    match id::from_hex(hex) {
+        Some(ref bytes) => UnitId::from_bytes(bytes),
+        None => Err(Error::InvalidId(hex.to_string())),
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    id::from_hex(hex).ok_or_else(|| Error::MalformedExport(format!("invalid hex {:?}", hex)))
+}
+
+// Escape the text for use in a double-quoted string, in DOT as in JSON.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            character if character.is_control() => {
+                escaped += &format!("\\u{:04x}", character as u32)
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+
+    #[test]
+    fn export() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let _ = unwrap!(dag.new_payload("a \"quoted\" payload".to_string(), &keys));
+        let _ = unwrap!(dag.new_payload("second".to_string(), &keys));
+
+        let options = DotOptions {
+            highlight: Some(Id::from(keys.public)),
+            observer_counts: true,
+            ..DotOptions::default()
+        };
+        let dot = dag.to_dot(&options);
+        assert!(dot.starts_with("digraph dag {"));
+        assert_eq!(dot.matches(" -> ").count(), 2);
+        assert_eq!(dot.matches("fillcolor=lightblue").count(), 3);
+        assert!(dot.contains(r#"\\\"quoted\\\""#));
+        assert!(dot.contains("\\n1 observers"));
+
+        let json = dag.to_json_graph();
+        assert!(json.starts_with("{\"nodes\":["));
+        assert_eq!(json.matches("\"source\"").count(), 2);
+    }
+
+    #[test]
+    fn dag_export() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag<String> = Dag::new(&keys);
+        let first = unwrap!(dag.new_payload("first".to_string(), &keys));
+        let _ = unwrap!(dag.new_payload("second".to_string(), &keys));
+
+        let export = unwrap!(dag.export());
+        assert_eq!(export.schema_version, EXPORT_SCHEMA_VERSION);
+        assert_eq!(export.units.len(), 3);
+        let unit = unwrap!(export.units.iter().find(|unit| unit.id == first.to_hex()));
+        assert_eq!(unit.depth, 1);
+        assert_eq!(unit.observers, vec![Id::from(keys.public).to_hex()]);
+
+        let imported: Dag<String> = unwrap!(Dag::from_export(&export));
+        assert_eq!(imported.digest(), dag.digest());
+        assert_eq!(unwrap!(imported.export()), export);
+
+        let mut malformed = export.clone();
+        malformed.units[0].payload.push('x');
+        assert!(Dag::<String>::from_export(&malformed).is_err());
+        let mut malformed = export.clone();
+        malformed.genesis = UnitId([7; 32]).to_hex();
+        match Dag::<String>::from_export(&malformed) {
+            Err(Error::MalformedExport(_)) => (),
+            result => panic!("Unexpected result: {:?}", result.map(|dag| dag.len())),
+        }
+        let mut newer = export;
+        newer.schema_version += 1;
+        match Dag::<String>::from_export(&newer) {
+            Err(Error::UnsupportedVersion(_)) => (),
+            result => panic!("Unexpected result: {:?}", result.map(|dag| dag.len())),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use ed25519_dalek::Keypair;
+use error::Error;
+use id::Id;
+use maidsafe_utilities::serialisation;
+use message::Message;
+use sha3::Sha3_512;
+
+/// The most bytes of extension blobs attached to a single message. The blobs beyond are dropped
+/// rather than sent.
+pub const MAX_EXTENSIONS_LEN: usize = 4 * 1024;
+
+/// Hooks letting the application piggyback small opaque blobs on gossip messages, e.g. health
+/// information, without changing the wire format. The blobs are signed by the sender together
+/// with the message they are attached to, and only handed over once that signature is checked.
+pub trait RoundHook: Send {
+    /// The blobs to attach to the message about to be sent. None by default.
+    fn outgoing(&mut self) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
+
+    /// Consume the blobs attached to a message received from `peer_id`, authenticated as sent by
+    /// that peer. Ignored by default.
+    fn incoming(&mut self, _peer_id: &Id, _blobs: &[Vec<u8>]) {}
+}
+
+/// Extension blobs attached to a message, with the sender's signature over them and the message.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Extensions {
+    /// The blobs, in the order the sender's hook returned them.
+    pub blobs: Vec<Vec<u8>>,
+    /// The sender's signature over the message and the blobs.
+    pub signature: Vec<u8>,
+}
+
+impl Extensions {
+    /// Attach `blobs` to `message`, signed with `keys`. Blobs past `MAX_EXTENSIONS_LEN` bytes in
+    /// total are dropped.
+    pub fn sign(mut blobs: Vec<Vec<u8>>, message: &Message, keys: &Keypair) -> Result<Self, Error> {
+        let mut len = 0;
+        let kept = blobs
+            .iter()
+            .take_while(|blob| {
+                len += blob.len();
+                len <= MAX_EXTENSIONS_LEN
+            })
+            .count();
+        if kept < blobs.len() {
+            warn!("Dropped {} extension blobs over the size limit", blobs.len() - kept);
+            blobs.truncate(kept);
+        }
+        let signed = serialisation::serialise(&(message, &blobs))?;
+        let signature = keys.sign::<Sha3_512>(&signed).to_bytes().to_vec();
+        Ok(Extensions { blobs, signature })
+    }
+
+    /// Whether the signature is the valid signature of `sender` over `message` and the blobs.
+    pub fn verify(&self, message: &Message, sender: &Id) -> bool {
+        match serialisation::serialise(&(message, &self.blobs)) {
+            Ok(signed) => sender.verify(&signed, &self.signature),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+
+    #[test]
+    fn signed_blobs() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let sender = Id::from(keys.public);
+        let stranger = Id::from(Keypair::generate::<Sha3_512>(&mut rng).public);
+        let message = Message::BootstrapRequest { start: 0, count: 10 };
+        let other = Message::BootstrapRequest { start: 10, count: 10 };
+
+        let extensions = unwrap!(Extensions::sign(vec![vec![1], vec![2, 3]], &message, &keys));
+        assert_eq!(extensions.blobs, vec![vec![1], vec![2, 3]]);
+        assert!(extensions.verify(&message, &sender));
+        // Blobs are bound to their sender and to the message they came with.
+        assert!(!extensions.verify(&message, &stranger));
+        assert!(!extensions.verify(&other, &sender));
+        let mut tampered = extensions.clone();
+        tampered.blobs[0] = vec![4];
+        assert!(!tampered.verify(&message, &sender));
+
+        // Blobs past the size limit are dropped.
+        let blobs = vec![vec![0; MAX_EXTENSIONS_LEN - 1], vec![1], vec![2]];
+        let extensions = unwrap!(Extensions::sign(blobs, &message, &keys));
+        assert_eq!(extensions.blobs.len(), 2);
+        assert!(extensions.verify(&message, &sender));
+    }
+}
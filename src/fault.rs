@@ -0,0 +1,96 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use id::Id;
+use unit::{Payload, Unit};
+
+/// Evidence that an observer equivocated: it signed two different units sharing the same parents,
+/// i.e. it claimed two conflicting successors of the same events.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FaultProof<T = Vec<u8>> {
+    /// The faulty observer.
+    pub observer: Id,
+    /// The unit first seen signed by the observer, stripped of the other observers.
+    pub first: Unit<T>,
+    /// The conflicting unit, stripped of the other observers.
+    pub second: Unit<T>,
+}
+
+impl<T: Payload> FaultProof<T> {
+    /// The proof that `observer` signed both units, or `None` if it didn't sign them both.
+    pub fn new(observer: Id, first: &Unit<T>, second: &Unit<T>) -> Option<Self> {
+        let proof = FaultProof {
+            observer,
+            first: signed_only_by(first, &observer)?,
+            second: signed_only_by(second, &observer)?,
+        };
+        Some(proof)
+    }
+
+    /// Whether this is valid evidence, checkable by anyone: two different units sharing the same
+    /// parents, both carrying a valid signature of the observer.
+    pub fn verify(&self) -> bool {
+        self.first.identifier != self.second.identifier &&
+            self.first.parents == self.second.parents &&
+            self.first.is_attested_by(&self.observer) &&
+            self.second.is_attested_by(&self.observer)
+    }
+}
+
+// A copy of the unit with only the observer's signature, if it has one.
+fn signed_only_by<T: Payload>(unit: &Unit<T>, observer: &Id) -> Option<Unit<T>> {
+    let signature = unit.signatures.get(observer)?;
+    let mut signed = unit.without_observers();
+    let _ = signed.observers.insert(*observer);
+    let _ = signed.signatures.insert(*observer, signature.clone());
+    Some(signed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dag::Dag;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+
+    #[test]
+    fn equivocation() {
+        let mut rng = SeededRng::new();
+        let equivocator = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut first: Dag = Dag::new(&equivocator);
+        let _ = unwrap!(first.new_payload(vec![1], &equivocator));
+        let mut second: Dag = Dag::new(&equivocator);
+        let _ = unwrap!(second.new_payload(vec![2], &equivocator));
+
+        let honest = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag = Dag::new(&honest);
+        unwrap!(dag.union(&first));
+        assert!(dag.detected_faults().is_empty());
+        unwrap!(dag.union(&second));
+        unwrap!(dag.union(&first));
+
+        let faults = dag.detected_faults();
+        assert_eq!(faults.len(), 1);
+        assert_eq!(faults[0].observer, Id::from(equivocator.public));
+        assert!(faults[0].verify());
+
+        let mut tampered = faults[0].clone();
+        tampered.second = tampered.first.clone();
+        assert!(!tampered.verify());
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use dag::{Checkpoint, Dag};
+use id::{Id, UnitId};
+use std::usize;
+use unit::Payload;
+
+// Marks the genesis as not held.
+const NO_GENESIS: usize = usize::MAX;
+
+/// A compact, read-only copy of a `Dag`, for analytics, proofs and export workloads.
+///
+/// Units are stored in flat arrays sorted by identifier and referred to by their index in those.
+/// Parents, children, depths, stability and the total order are all computed once, when freezing,
+/// so that queries neither walk the graph nor pay for map lookups, and the copy can be shared
+/// without holding any lock on the gossiper.
+#[derive(Clone, Debug)]
+pub struct FrozenDag<T = Vec<u8>> {
+    identifiers: Vec<UnitId>,
+    payloads: Vec<T>,
+    // The observers of unit `i` are `observers[observer_offsets[i]..observer_offsets[i + 1]]`.
+    observer_offsets: Vec<usize>,
+    observers: Vec<Id>,
+    // Likewise for the held parents and the children, by index.
+    parent_offsets: Vec<usize>,
+    parents: Vec<usize>,
+    child_offsets: Vec<usize>,
+    children: Vec<usize>,
+    depths: Vec<usize>,
+    stable: Vec<bool>,
+    // The units other than the genesis in their total order, by index.
+    order: Vec<usize>,
+    genesis: usize,
+    checkpoint: Checkpoint,
+}
+
+impl<T: Payload> FrozenDag<T> {
+    /// Freeze the current state of `dag`.
+    pub fn new(dag: &Dag<T>) -> Self {
+        let units: Vec<_> = dag.units().collect();
+        let identifiers: Vec<UnitId> = units.iter().map(|unit| unit.identifier).collect();
+        let index_of = |identifier: &UnitId| identifiers.binary_search(identifier).ok();
+        let genesis = index_of(&dag.genesis().identifier).unwrap_or(NO_GENESIS);
+        let parents_of: Vec<Vec<usize>> = units
+            .iter()
+            .map(|unit| unit.parents.iter().filter_map(&index_of).collect())
+            .collect();
+
+        let mut observer_offsets = vec![0];
+        let mut observers = Vec::new();
+        for unit in &units {
+            observers.extend(unit.observers.iter().cloned());
+            observer_offsets.push(observers.len());
+        }
+
+        let mut children_of = vec![Vec::new(); units.len()];
+        let mut parent_offsets = vec![0];
+        let mut parents = Vec::new();
+        for (index, unit_parents) in parents_of.iter().enumerate() {
+            for &parent in unit_parents {
+                children_of[parent].push(index);
+            }
+            parents.extend(unit_parents.iter().cloned());
+            parent_offsets.push(parents.len());
+        }
+        let mut child_offsets = vec![0];
+        let mut children = Vec::new();
+        for unit_children in children_of {
+            children.extend(unit_children);
+            child_offsets.push(children.len());
+        }
+
+        let depths = compute_depths(&parents_of);
+        let mut order: Vec<usize> = (0..units.len()).filter(|&index| index != genesis).collect();
+        order.sort_by_key(|&index| (depths[index], index));
+
+        FrozenDag {
+            payloads: units.iter().map(|unit| unit.payload.clone()).collect(),
+            stable: units.iter().map(|unit| dag.is_stable(unit)).collect(),
+            identifiers,
+            observer_offsets,
+            observers,
+            parent_offsets,
+            parents,
+            child_offsets,
+            children,
+            depths,
+            order,
+            genesis,
+            checkpoint: dag.last_checkpoint().clone(),
+        }
+    }
+
+    /// Number of units held.
+    pub fn len(&self) -> usize {
+        self.identifiers.len()
+    }
+
+    /// Whether no units are held at all.
+    pub fn is_empty(&self) -> bool {
+        self.identifiers.is_empty()
+    }
+
+    /// The index of the unit with the given identifier, if held.
+    pub fn index_of(&self, identifier: &UnitId) -> Option<usize> {
+        self.identifiers.binary_search(identifier).ok()
+    }
+
+    /// The index of the genesis, if held.
+    pub fn genesis(&self) -> Option<usize> {
+        if self.genesis == NO_GENESIS {
+            None
+        } else {
+            Some(self.genesis)
+        }
+    }
+
+    /// The stable prefix pruned from the DAG before it was frozen.
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    /// The identifier of the unit at `index`.
+    pub fn identifier(&self, index: usize) -> Option<&UnitId> {
+        self.identifiers.get(index)
+    }
+
+    /// The indices of the held parents of the unit at `index`, sorted.
+    pub fn parents(&self, index: usize) -> &[usize] {
+        slice(&self.parent_offsets, &self.parents, index)
+    }
+
+    /// The payload of the unit at `index`.
+    pub fn payload(&self, index: usize) -> Option<&T> {
+        self.payloads.get(index)
+    }
+
+    /// The observers of the unit at `index`, sorted.
+    pub fn observers(&self, index: usize) -> &[Id] {
+        slice(&self.observer_offsets, &self.observers, index)
+    }
+
+    /// The indices of the children of the unit at `index`, sorted.
+    pub fn children(&self, index: usize) -> &[usize] {
+        slice(&self.child_offsets, &self.children, index)
+    }
+
+    /// The length of the longest path from the genesis to the unit at `index`.
+    pub fn depth(&self, index: usize) -> Option<usize> {
+        self.depths.get(index).cloned()
+    }
+
+    /// Whether the unit at `index` was stable when frozen.
+    pub fn is_stable(&self, index: usize) -> bool {
+        self.stable.get(index).cloned().unwrap_or(false)
+    }
+
+    /// The indices of the units other than the genesis, in the total order of
+    /// `Dag::ordered_units`.
+    pub fn ordered(&self) -> &[usize] {
+        &self.order
+    }
+}
+
+// The entries of `values` belonging to `index` as per `offsets`, empty if out of range.
+fn slice<'a, V>(offsets: &[usize], values: &'a [V], index: usize) -> &'a [V] {
+    match (offsets.get(index), offsets.get(index + 1)) {
+        (Some(&start), Some(&end)) => &values[start..end],
+        _ => &[],
+    }
+}
+
+// The depth of every unit given the indices of each unit's held parents: zero for units without
+// a parent held, one more than the deepest parent's otherwise.
+fn compute_depths(parents: &[Vec<usize>]) -> Vec<usize> {
+    let mut depths: Vec<Option<usize>> = vec![None; parents.len()];
+    let mut on_stack = vec![false; parents.len()];
+    for start in 0..parents.len() {
+        // Walk depth first down to units of known depth or to roots, each unit on the stack being
+        // resolved once all its parents are. A unit met again while on the stack would make the
+        // parents loop, and is then taken as a root.
+        let mut stack = vec![start];
+        while let Some(&current) = stack.last() {
+            if depths[current].is_some() {
+                let _ = stack.pop();
+                continue;
+            }
+            on_stack[current] = true;
+            let pending: Vec<usize> = parents[current]
+                .iter()
+                .cloned()
+                .filter(|&parent| depths[parent].is_none() && !on_stack[parent])
+                .collect();
+            if pending.is_empty() {
+                let depth = parents[current]
+                    .iter()
+                    .filter_map(|&parent| depths[parent])
+                    .max()
+                    .map_or(0, |depth| depth + 1);
+                depths[current] = Some(depth);
+                on_stack[current] = false;
+                let _ = stack.pop();
+            } else {
+                stack.extend(pending);
+            }
+        }
+    }
+    depths.into_iter().map(|depth| depth.unwrap_or(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+
+    #[test]
+    fn freeze() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag = Dag::new(&keys);
+        for payload in 0..3 {
+            let _ = unwrap!(dag.new_payload(vec![payload], &keys));
+        }
+        let frozen = dag.freeze();
+        assert_eq!(frozen.len(), dag.len());
+
+        let ordered: Vec<UnitId> = frozen
+            .ordered()
+            .iter()
+            .filter_map(|&index| frozen.identifier(index).cloned())
+            .collect();
+        let expected: Vec<UnitId> = dag.ordered_units()
+            .iter()
+            .map(|unit| unit.identifier)
+            .collect();
+        assert_eq!(ordered, expected);
+
+        let genesis = unwrap!(frozen.genesis());
+        assert_eq!(frozen.depth(genesis), Some(0));
+        assert!(frozen.parents(genesis).is_empty());
+        for (position, &index) in frozen.ordered().iter().enumerate() {
+            assert_eq!(frozen.depth(index), Some(position + 1));
+            assert_eq!(frozen.parents(index).len(), 1);
+            let parent = frozen.parents(index)[0];
+            assert!(frozen.children(parent).contains(&index));
+            assert_eq!(frozen.observers(index), &[Id::from(keys.public)]);
+        }
+        assert!(frozen.observers(frozen.len()).is_empty());
+    }
+
+    #[test]
+    fn merge() {
+        let mut rng = SeededRng::new();
+        let first_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let second_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag = Dag::new(&first_keys);
+        let mut other: Dag = Dag::new(&first_keys);
+        let first = unwrap!(dag.new_payload(vec![1], &first_keys));
+        let second = unwrap!(other.new_payload(vec![2], &second_keys));
+        unwrap!(dag.union(&other));
+        assert_eq!(dag.tips().count(), 2);
+
+        let merged = unwrap!(dag.new_payload(vec![3], &first_keys));
+        assert_eq!(dag.tips().cloned().collect::<Vec<_>>(), vec![merged]);
+        let frozen = dag.freeze();
+        let index = unwrap!(frozen.index_of(&merged));
+        let mut parents: Vec<UnitId> = frozen
+            .parents(index)
+            .iter()
+            .filter_map(|&parent| frozen.identifier(parent).cloned())
+            .collect();
+        parents.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(parents, expected);
+        assert_eq!(frozen.depth(index), Some(2));
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::sync::Arc;
+use tiny_keccak::sha3_256;
+
+/// The hash function unit identifiers are derived with, e.g. BLAKE2 or BLAKE3 for speed, or to
+/// match the hashing used elsewhere in a deployment. All the nodes of a network shall use the
+/// same: each message carries the tag of the sender's algorithm, and messages tagged with another
+/// one are rejected with `Error::HashAlgorithmMismatch` rather than merged.
+///
+/// Only identifiers depend on it. Digests only ever compared locally, e.g. of the DAG or of the
+/// checkpointed payloads, are computed with SHA3-256 whatever the hasher.
+pub trait Hasher: Send + Sync {
+    /// Tag identifying the algorithm, distinct for every algorithm used in a network. 0 is taken
+    /// by `Sha3Hasher`.
+    fn tag(&self) -> u8;
+
+    /// The 32-byte hash of `data`.
+    fn hash(&self, data: &[u8]) -> [u8; 32];
+}
+
+/// SHA3-256, the default hasher.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sha3Hasher;
+
+impl Hasher for Sha3Hasher {
+    fn tag(&self) -> u8 {
+        0
+    }
+
+    fn hash(&self, data: &[u8]) -> [u8; 32] {
+        sha3_256(data)
+    }
+}
+
+// The hasher used unless another one is given.
+pub fn default_hasher() -> Arc<Hasher> {
+    Arc::new(Sha3Hasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dag::Dag;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+
+    // SHA3-256 with its bytes reversed, standing in for another algorithm.
+    struct ReversedSha3;
+
+    impl Hasher for ReversedSha3 {
+        fn tag(&self) -> u8 {
+            1
+        }
+
+        fn hash(&self, data: &[u8]) -> [u8; 32] {
+            let mut hash = Sha3Hasher.hash(data);
+            hash.reverse();
+            hash
+        }
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(default_hasher().tag(), 0);
+        assert_eq!(default_hasher().hash(b"data"), sha3_256(b"data"));
+
+        let keys = Keypair::generate::<Sha3_512>(&mut SeededRng::new());
+        let mut sha3: Dag<String> = Dag::new_with_genesis(b"network", &keys);
+        let mut default: Dag<String> =
+            Dag::new_with_hasher(b"network", &keys, default_hasher());
+        let mut reversed: Dag<String> =
+            Dag::new_with_hasher(b"network", &keys, Arc::new(ReversedSha3));
+        assert_eq!(sha3.genesis().identifier, default.genesis().identifier);
+        assert_ne!(sha3.genesis().identifier, reversed.genesis().identifier);
+        assert_eq!(reversed.hasher().tag(), 1);
+
+        // Every identifier depends on the hasher, and DAGs hashed differently are never merged.
+        let identifier = unwrap!(sha3.new_payload("Hello".to_string(), &keys));
+        assert_eq!(unwrap!(default.new_payload("Hello".to_string(), &keys)), identifier);
+        assert_ne!(unwrap!(reversed.new_payload("Hello".to_string(), &keys)), identifier);
+        assert!(sha3.union(&reversed).is_err());
+        assert!(sha3.union(&default).is_ok());
+    }
+}
@@ -15,14 +15,94 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use ed25519_dalek::{PUBLIC_KEY_LENGTH, PublicKey};
+use diagnostics::to_hex;
+use ed25519_dalek::{PUBLIC_KEY_LENGTH, PublicKey, Signature};
+use error::Error;
+use sha3::Sha3_512;
 use std::convert::From;
-use std::fmt::{self, Debug, Formatter};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::iter;
+use std::str::{self, FromStr};
+
+// The digits of base58, as used by Bitcoin.
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+// The number of hex digits of the short form of ids and unit identifiers.
+const SHORT_DIGITS: usize = 6;
 
 /// The ID of a node - equivalent to its public key.
+///
+/// Ids are ordered by their bytes, so the order is the same on every node and across versions:
+/// orderings derived from ids, e.g. of observers, don't depend on how ids are rendered or parsed.
+///
+/// Displayed in hex in full, and in short, first digits only, by `Debug` and in the DOT output,
+/// so that logs of different nodes can be correlated. Parsed from hex or base58.
 #[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Id(pub [u8; PUBLIC_KEY_LENGTH]);
 
+impl Id {
+    /// The id made of the given bytes. Fails with `Error::InvalidId` if there aren't
+    /// `PUBLIC_KEY_LENGTH` of them.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != PUBLIC_KEY_LENGTH {
+            return Err(Error::InvalidId(to_hex(bytes)));
+        }
+        let mut id = [0; PUBLIC_KEY_LENGTH];
+        id.copy_from_slice(bytes);
+        Ok(Id(id))
+    }
+
+    /// The id in hex.
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    /// The id in base58, as used by Bitcoin.
+    pub fn to_base58(&self) -> String {
+        to_base58(&self.0)
+    }
+
+    /// The first hex digits of the id, as rendered by `Debug`.
+    pub fn short(&self) -> String {
+        short(&self.0)
+    }
+
+    /// Whether `signature` is the signature over `message` by the owner of this ID.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match (PublicKey::from_bytes(&self.0), Signature::from_bytes(signature)) {
+            (Ok(key), Ok(signature)) => key.verify::<Sha3_512>(message, &signature),
+            _ => false,
+        }
+    }
+}
+
+/// The identifier of a unit: the hash of its parent's payload and of its own payload. Rendered
+/// and parsed like an `Id`.
+#[derive(Clone, Copy, Default, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct UnitId(pub [u8; 32]);
+
+impl UnitId {
+    /// The identifier made of the given bytes. Fails with `Error::InvalidId` if there aren't 32
+    /// of them.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Id::from_bytes(bytes).map(|id| UnitId(id.0))
+    }
+
+    /// The identifier in hex.
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    /// The identifier in base58, as used by Bitcoin.
+    pub fn to_base58(&self) -> String {
+        to_base58(&self.0)
+    }
+
+    /// The first hex digits of the identifier, as rendered by `Debug`.
+    pub fn short(&self) -> String {
+        short(&self.0)
+    }
+}
+
 impl From<PublicKey> for Id {
     fn from(key: PublicKey) -> Self {
         Id(key.to_bytes())
@@ -31,12 +111,186 @@ impl From<PublicKey> for Id {
 
 impl Debug for Id {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
-        write!(
-            formatter,
-            "{:02x}{:02x}{:02x}..",
-            self.0[0],
-            self.0[1],
-            self.0[2]
-        )
+        write!(formatter, "{}", self.short())
+    }
+}
+
+impl Display for Id {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.to_hex())
+    }
+}
+
+impl FromStr for Id {
+    type Err = Error;
+
+    /// Parses 64 hex digits, or base58 otherwise.
+    fn from_str(encoded: &str) -> Result<Self, Error> {
+        match decode(encoded) {
+            Some(bytes) => Id::from_bytes(&bytes),
+            None => Err(Error::InvalidId(encoded.to_string())),
+        }
+    }
+}
+
+impl Debug for UnitId {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.short())
+    }
+}
+
+impl Display for UnitId {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.to_hex())
+    }
+}
+
+impl FromStr for UnitId {
+    type Err = Error;
+
+    /// Parses 64 hex digits, or base58 otherwise.
+    fn from_str(encoded: &str) -> Result<Self, Error> {
+        Id::from_str(encoded).map(|id| UnitId(id.0))
+    }
+}
+
+// The first hex digits of the bytes, followed by "..".
+fn short(bytes: &[u8]) -> String {
+    let mut short = to_hex(&bytes[..SHORT_DIGITS / 2]);
+    short.push_str("..");
+    short
+}
+
+fn to_base58(bytes: &[u8]) -> String {
+    // The base58 digits, least significant first.
+    let mut digits: Vec<u8> = Vec::new();
+    for byte in bytes {
+        let mut carry = u32::from(*byte);
+        for digit in &mut digits {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    // Each leading zero byte is encoded as a leading "1".
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    iter::repeat('1')
+        .take(zeros)
+        .chain(digits.iter().rev().map(|digit| {
+            char::from(BASE58_ALPHABET[*digit as usize])
+        }))
+        .collect()
+}
+
+fn from_base58(encoded: &str) -> Option<Vec<u8>> {
+    // The bytes, least significant first.
+    let mut bytes: Vec<u8> = Vec::new();
+    for character in encoded.bytes() {
+        let mut carry = match BASE58_ALPHABET.iter().position(|digit| *digit == character) {
+            Some(digit) => digit as u32,
+            None => return None,
+        };
+        for byte in &mut bytes {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = encoded.bytes().take_while(|character| *character == b'1').count();
+    bytes.extend(iter::repeat(0).take(zeros));
+    bytes.reverse();
+    Some(bytes)
+}
+
+pub fn from_hex(encoded: &str) -> Option<Vec<u8>> {
+    if encoded.len() % 2 != 0 {
+        return None;
+    }
+    encoded
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            str::from_utf8(pair).ok().and_then(
+                |pair| u8::from_str_radix(pair, 16).ok(),
+            )
+        })
+        .collect()
+}
+
+// The bytes encoded as 64 hex digits, or in base58 otherwise.
+fn decode(encoded: &str) -> Option<Vec<u8>> {
+    if encoded.len() == 2 * PUBLIC_KEY_LENGTH {
+        from_hex(encoded)
+    } else {
+        from_base58(encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use maidsafe_utilities::serialisation;
+
+    #[test]
+    fn rendering() {
+        let mut rng = SeededRng::new();
+        let id = Id::from(Keypair::generate::<Sha3_512>(&mut rng).public);
+        assert_eq!(format!("{}", id).len(), 64);
+        assert_eq!(format!("{:?}", id), format!("{}..", &id.to_hex()[..6]));
+        assert_eq!(unwrap!(Id::from_str(&id.to_hex())), id);
+        assert_eq!(unwrap!(Id::from_str(&id.to_base58())), id);
+        assert_eq!(unwrap!(Id::from_bytes(&id.0)), id);
+        assert!(Id::from_bytes(&id.0[1..]).is_err());
+        assert!(Id::from_str("not an id").is_err());
+        assert!(Id::from_str(&id.to_hex()[2..]).is_err());
+
+        // Leading zeros survive base58.
+        let zeros = UnitId([0; 32]);
+        assert_eq!(zeros.to_base58(), "1".repeat(32));
+        assert_eq!(unwrap!(UnitId::from_str(&zeros.to_base58())), zeros);
+        let mut bytes = [0; 32];
+        bytes[31] = 57;
+        assert!(UnitId(bytes).to_base58().ends_with('z'));
+    }
+
+    #[test]
+    fn ordering() {
+        let mut ids: Vec<Id> = (0..8u8).rev().map(|byte| Id([byte; PUBLIC_KEY_LENGTH])).collect();
+        ids.sort();
+        let parsed: Vec<Id> = ids.iter().map(|id| unwrap!(Id::from_str(&id.to_base58()))).collect();
+        assert_eq!(parsed, ids);
+        assert!(ids.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn unit_id_serialisation() {
+        let mut bytes = [0; 32];
+        bytes[0] = 1;
+        bytes[31] = 255;
+        let identifier = UnitId(bytes);
+        let copy = identifier;
+        assert_eq!(copy, identifier);
+        assert_ne!(UnitId([0; 32]), identifier);
+        assert!(UnitId([0; 32]) < identifier);
+
+        // Encoded as exactly its 32 bytes, without a length prefix.
+        let encoded = unwrap!(serialisation::serialise(&identifier));
+        assert_eq!(encoded, bytes.to_vec());
+        let decoded: UnitId = unwrap!(serialisation::deserialise(&encoded));
+        assert_eq!(decoded, identifier);
+        assert!(serialisation::deserialise::<UnitId>(&encoded[..31]).is_err());
+        let mut extended = encoded;
+        extended.push(0);
+        assert!(serialisation::deserialise::<UnitId>(&extended).is_err());
     }
 }
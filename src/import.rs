@@ -0,0 +1,130 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// An event of a foreign event graph, which can be imported into a `Dag`.
+///
+/// Each event becomes a unit whose parents are the units its parents became, so the structure of
+/// the foreign graph is kept; events are imported parents first.
+pub trait ForeignEvent {
+    /// The type identifying the creator of an event in the foreign graph.
+    type Creator;
+
+    /// The hash identifying this event in the foreign graph.
+    fn hash(&self) -> Vec<u8>;
+    /// The creator of this event.
+    fn creator(&self) -> &Self::Creator;
+    /// The hashes of the parents of this event, all becoming parents of the imported unit.
+    /// Events without parents are attached to the genesis.
+    fn parents(&self) -> Vec<Vec<u8>>;
+    /// The payload carried by this event.
+    fn payload(&self) -> Vec<u8>;
+}
+
+/// A hashgraph-style event, pointing at the previous event of its creator (the self-parent) and
+/// at the last event received from another node (the other-parent).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashgraphEvent<C> {
+    /// Hash of the event.
+    pub hash: Vec<u8>,
+    /// Creator of the event.
+    pub creator: C,
+    /// Hash of the previous event by the same creator.
+    pub self_parent: Option<Vec<u8>>,
+    /// Hash of the event received from another node.
+    pub other_parent: Option<Vec<u8>>,
+    /// The transactions carried by the event.
+    pub transactions: Vec<u8>,
+}
+
+impl<C> ForeignEvent for HashgraphEvent<C> {
+    type Creator = C;
+
+    fn hash(&self) -> Vec<u8> {
+        self.hash.clone()
+    }
+
+    fn creator(&self) -> &C {
+        &self.creator
+    }
+
+    fn parents(&self) -> Vec<Vec<u8>> {
+        self.self_parent
+            .iter()
+            .chain(self.other_parent.iter())
+            .cloned()
+            .collect()
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        self.transactions.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dag::Dag;
+    use ed25519_dalek::Keypair;
+    use error::Error;
+    use id::Id;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+
+    fn event(hash: u8, creator: usize, parents: &[u8]) -> HashgraphEvent<usize> {
+        HashgraphEvent {
+            hash: vec![hash],
+            creator,
+            self_parent: parents.get(0).map(|parent| vec![*parent]),
+            other_parent: parents.get(1).map(|parent| vec![*parent]),
+            transactions: vec![hash; 3],
+        }
+    }
+
+    #[test]
+    fn hashgraph_import() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let creators: Vec<Id> = (0..2)
+            .map(|_| Id::from(Keypair::generate::<Sha3_512>(&mut rng).public))
+            .collect();
+        // Given children first, so that their import waits for their parents.
+        let events = vec![event(3, 0, &[1, 2]), event(1, 0, &[]), event(2, 1, &[])];
+        let mut dag: Dag = Dag::new(&keys);
+        unwrap!(dag.import(&events, |creator| creators[*creator]));
+        assert_eq!(dag.len(), 4);
+
+        let (first, second, merged) = {
+            let unit_of = |payload: u8| {
+                unwrap!(dag.units().find(|unit| unit.payload == vec![payload; 3])).clone()
+            };
+            (unit_of(1), unit_of(2), unit_of(3))
+        };
+        assert_eq!(
+            merged.parents,
+            vec![first.identifier, second.identifier].into_iter().collect()
+        );
+        assert!(first.parents.contains(&dag.genesis().identifier));
+        assert!(second.observers.contains(&creators[1]));
+        assert!(merged.observers.contains(&creators[0]));
+
+        let orphan = vec![event(5, 0, &[4])];
+        match dag.import(&orphan, |creator| creators[*creator]) {
+            Err(Error::MissingForeignParent(hash)) => assert_eq!(hash, vec![4]),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
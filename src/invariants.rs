@@ -0,0 +1,209 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+//! The invariants the DAG claims to uphold, as executable checks shared by the tests, the
+//! simulations and the strict checks mode of the `Gossiper`.
+
+use dag::Dag;
+use error::Error;
+use id::{Id, UnitId};
+use std::fmt::{self, Display, Formatter};
+use unit::Payload;
+
+/// An invariant found broken by `Dag::check_invariants`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The genesis isn't held.
+    MissingGenesis(UnitId),
+    /// A unit other than the genesis has no parent.
+    NoParent(UnitId),
+    /// A parent of a unit other than the genesis isn't held.
+    MissingParent {
+        /// The unit.
+        unit: UnitId,
+        /// Its parent not held.
+        parent: UnitId,
+    },
+    /// The unit is its own ancestor.
+    Cycle(UnitId),
+    /// The index of the children of the unit doesn't match the units having it as parent.
+    ChildrenMismatch(UnitId),
+    /// The unit's identifier doesn't match its content.
+    InvalidIdentifier(UnitId),
+    /// The unit is observed by an id outside the authorised members, as set by
+    /// `Dag::set_authorised_members`.
+    UnauthorisedObserver {
+        /// The unit.
+        unit: UnitId,
+        /// Its observer outside the members.
+        observer: Id,
+    },
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            InvariantViolation::MissingGenesis(genesis) => {
+                write!(formatter, "the genesis {:?} isn't held", genesis)
+            }
+            InvariantViolation::NoParent(unit) => {
+                write!(formatter, "unit {:?} has no parent", unit)
+            }
+            InvariantViolation::MissingParent { unit, parent } => {
+                write!(formatter, "the parent {:?} of unit {:?} isn't held", parent, unit)
+            }
+            InvariantViolation::Cycle(unit) => {
+                write!(formatter, "unit {:?} is its own ancestor", unit)
+            }
+            InvariantViolation::ChildrenMismatch(unit) => {
+                write!(formatter, "the children indexed for unit {:?} are wrong", unit)
+            }
+            InvariantViolation::InvalidIdentifier(unit) => {
+                write!(formatter, "unit {:?} has an identifier not matching its content", unit)
+            }
+            InvariantViolation::UnauthorisedObserver { unit, observer } => {
+                write!(formatter, "unit {:?} is observed by non-member {:?}", unit, observer)
+            }
+        }
+    }
+}
+
+/// Check the invariants holding within a single DAG, as per `Dag::check_invariants`.
+pub fn check_invariants<T: Payload>(dag: &Dag<T>) -> Result<(), Error> {
+    dag.check_invariants().map_err(
+        |violated| violation(violated.to_string()),
+    )
+}
+
+/// Check that stability is monotone: every unit stable in `before` is still stable in `after`,
+/// the same DAG after a union, unless a later checkpoint pruned it.
+pub fn check_stability_monotone<T: Payload>(before: &Dag<T>, after: &Dag<T>) -> Result<(), Error> {
+    let pruned = after.last_checkpoint().height > before.last_checkpoint().height;
+    for unit in before.units().filter(|unit| before.is_stable(unit)) {
+        let still_stable = match after.get(&unit.identifier) {
+            Some(unit) => after.is_stable(unit),
+            None => pruned,
+        };
+        if !still_stable {
+            return Err(violation(format!("unit {:?} is no longer stable", unit.identifier)));
+        }
+    }
+    Ok(())
+}
+
+/// Check that any two converged DAGs, i.e. holding the same units with the same observers on top
+/// of the same checkpoint, agree on the sequence of stable payloads.
+pub fn check_agreement<T: Payload>(dags: &[Dag<T>]) -> Result<(), Error> {
+    for (index, lhs) in dags.iter().enumerate() {
+        for rhs in &dags[index + 1..] {
+            let converged = lhs.last_checkpoint() == rhs.last_checkpoint() &&
+                lhs.unit_versions() == rhs.unit_versions();
+            if converged && stable_sequence(lhs) != stable_sequence(rhs) {
+                return Err(violation(
+                    "converged DAGs disagree on the stable sequence".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// The identifiers of the stable units, in total order.
+fn stable_sequence<T: Payload>(dag: &Dag<T>) -> Vec<UnitId> {
+    dag.ordered_units()
+        .into_iter()
+        .filter(|unit| dag.is_stable(unit))
+        .map(|unit| unit.identifier)
+        .collect()
+}
+
+fn violation(reason: String) -> Error {
+    Error::InvariantViolated(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dag::MembershipCheck;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+    use std::iter;
+
+    #[test]
+    fn invariants() {
+        let mut rng = SeededRng::new();
+        let first_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let second_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut first: Dag = Dag::new(&first_keys);
+        let mut second: Dag = Dag::new(&first_keys);
+        let _ = unwrap!(first.new_payload(vec![1], &first_keys));
+        let _ = unwrap!(second.new_payload(vec![2], &second_keys));
+        unwrap!(check_invariants(&first));
+
+        let before = first.clone();
+        unwrap!(first.union(&second));
+        unwrap!(check_invariants(&first));
+        unwrap!(check_stability_monotone(&before, &first));
+        unwrap!(second.union(&first));
+        unwrap!(check_agreement(&[first.clone(), second]));
+
+        // A unit whose parents aren't held breaks the invariants.
+        let merged = unwrap!(first.new_payload(vec![3], &first_keys));
+        let genesis = first.genesis().identifier;
+        let partial = first.partial(first.units_by_ids(&[genesis, merged]));
+        let parent = *unwrap!(unwrap!(first.get(&merged)).parents.iter().next());
+        assert_eq!(
+            partial.check_invariants(),
+            Err(InvariantViolation::MissingParent {
+                unit: merged,
+                parent,
+            })
+        );
+        assert!(check_invariants(&partial).is_err());
+
+        // As does a DAG without its genesis.
+        let partial = first.partial(first.units_by_ids(&[merged]));
+        assert_eq!(
+            partial.check_invariants(),
+            Err(InvariantViolation::MissingGenesis(genesis))
+        );
+
+        // So does a unit whose content was tampered with.
+        let mut tampered = unwrap!(first.get(&merged)).clone();
+        tampered.payload = vec![4];
+        let mut units: Vec<_> = first
+            .units()
+            .filter(|unit| unit.identifier != merged)
+            .cloned()
+            .collect();
+        units.push(tampered);
+        assert_eq!(
+            first.partial(units).check_invariants(),
+            Err(InvariantViolation::InvalidIdentifier(merged))
+        );
+
+        // Or observed by a non-member.
+        let members = iter::once(Id::from(first_keys.public)).collect();
+        first.set_authorised_members(Some(members), MembershipCheck::Strip);
+        match first.check_invariants() {
+            Err(InvariantViolation::UnauthorisedObserver { observer, .. }) => {
+                assert_eq!(observer, Id::from(second_keys.public));
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+}
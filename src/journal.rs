@@ -0,0 +1,211 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use dag::Dag;
+use ed25519_dalek::Keypair;
+use error::Error;
+use id::{Id, UnitId};
+use maidsafe_utilities::serialisation;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use unit::{Payload, Unit};
+
+const SNAPSHOT_FILE: &str = "snapshot";
+const LOG_FILE: &str = "journal";
+
+/// A change to a `Dag`, as recorded when journaling is enabled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum JournalEntry<T = Vec<u8>> {
+    /// A unit was inserted, holding the given observers.
+    Unit(Unit<T>),
+    /// Observers were added to a unit already held.
+    Observers {
+        /// Identifier of the unit.
+        identifier: UnitId,
+        /// The observers added.
+        observers: BTreeSet<Id>,
+        /// The signatures of those of the added observers which have one.
+        signatures: BTreeMap<Id, Vec<u8>>,
+    },
+}
+
+/// A write-ahead log of the changes made to a `Dag`, kept in a directory alongside a snapshot of
+/// the DAG.
+///
+/// Changes are appended to the log as they happen, so the cost of making a round durable only
+/// depends on what changed in that round. Once the log holds enough entries, it is compacted: a
+/// new snapshot is written and the log emptied. Recovery loads the snapshot and replays the log,
+/// ignoring a tail left incomplete by a crash.
+pub struct Journal {
+    snapshot_path: PathBuf,
+    log: File,
+    // Entries appended since the last compaction.
+    entries: usize,
+    compact_every: usize,
+}
+
+impl Journal {
+    /// Open the journal kept in `dir`, creating it if need be, and recover the DAG it holds. A new
+    /// DAG owned by `keys` is returned if the journal is empty. The log is compacted once it holds
+    /// `compact_every` entries.
+    pub fn open<T: Payload, P: AsRef<Path>>(
+        dir: P,
+        keys: &Keypair,
+        compact_every: usize,
+    ) -> Result<(Self, Dag<T>), Error> {
+        fs::create_dir_all(dir.as_ref())?;
+        let snapshot_path = dir.as_ref().join(SNAPSHOT_FILE);
+        let mut dag = match File::open(&snapshot_path) {
+            Ok(mut file) => {
+                let mut bytes = Vec::new();
+                let _ = file.read_to_end(&mut bytes)?;
+                Dag::load(&bytes)?
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => Dag::new(keys),
+            Err(error) => return Err(Error::Io(error)),
+        };
+
+        let mut log = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(dir.as_ref().join(LOG_FILE))?;
+        let mut bytes = Vec::new();
+        let _ = log.read_to_end(&mut bytes)?;
+        let mut entries = 0;
+        let mut offset = 0;
+        while let Some((entry, len)) = read_entry(&bytes[offset..]) {
+            dag.replay(entry);
+            entries += 1;
+            offset += len;
+        }
+        if offset < bytes.len() {
+            warn!("Dropping {} bytes of incomplete journal entries", bytes.len() - offset);
+            log.set_len(offset as u64)?;
+        }
+        let _ = log.seek(SeekFrom::End(0))?;
+
+        let journal = Journal {
+            snapshot_path,
+            log,
+            entries,
+            compact_every,
+        };
+        Ok((journal, dag))
+    }
+
+    /// Append the entries to the log, compacting it into a snapshot of `dag` if it is due. `dag`
+    /// shall be the DAG the entries were recorded by, with all of them applied.
+    pub fn record<T: Payload>(
+        &mut self,
+        dag: &Dag<T>,
+        entries: &[JournalEntry<T>],
+    ) -> Result<(), Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut bytes = Vec::new();
+        for entry in entries {
+            let frame = serialisation::serialise(entry)?;
+            let len = frame.len() as u32;
+            bytes.extend(&[(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]);
+            bytes.extend(frame);
+        }
+        self.log.write_all(&bytes)?;
+        self.log.sync_data()?;
+        self.entries += entries.len();
+        if self.entries >= self.compact_every {
+            self.compact(dag)?;
+        }
+        Ok(())
+    }
+
+    /// Write a new snapshot of `dag` and empty the log. The snapshot replaces the previous one
+    /// atomically, and replaying the log onto either leads to the same DAG, so a crash at any
+    /// point leaves a recoverable journal.
+    pub fn compact<T: Payload>(&mut self, dag: &Dag<T>) -> Result<(), Error> {
+        let temporary = self.snapshot_path.with_extension("tmp");
+        {
+            let mut file = File::create(&temporary)?;
+            file.write_all(&dag.save()?)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, &self.snapshot_path)?;
+        self.log.set_len(0)?;
+        let _ = self.log.seek(SeekFrom::Start(0))?;
+        self.log.sync_data()?;
+        self.entries = 0;
+        Ok(())
+    }
+}
+
+// The entry at the start of `bytes` and its length, including the length prefix, if complete.
+fn read_entry<T: Payload>(bytes: &[u8]) -> Option<(JournalEntry<T>, usize)> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    let len = bytes[..4].iter().fold(0usize, |len, byte| (len << 8) | *byte as usize);
+    if bytes.len() - 4 < len {
+        return None;
+    }
+    serialisation::deserialise(&bytes[4..len + 4]).ok().map(|entry| (entry, len + 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+    use rand::Rng;
+    use sha3::Sha3_512;
+    use std::env;
+
+    #[test]
+    fn recovery() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let dir = env::temp_dir().join(format!("dag_gossip_journal_{}", rng.gen::<u64>()));
+
+        let (mut journal, mut dag): (Journal, Dag) = unwrap!(Journal::open(&dir, &keys, 3));
+        dag.set_journaling(true);
+        for payload in 0..2 {
+            let _ = unwrap!(dag.new_payload(vec![payload], &keys));
+            let entries = dag.take_journal_entries();
+            unwrap!(journal.record(&dag, &entries));
+        }
+
+        // A crash in the middle of an append leaves an incomplete entry behind.
+        {
+            let mut log = unwrap!(OpenOptions::new().append(true).open(dir.join(LOG_FILE)));
+            unwrap!(log.write_all(&[0, 0, 1, 0, 7]));
+        }
+        let (mut journal, mut recovered): (Journal, Dag) = unwrap!(Journal::open(&dir, &keys, 3));
+        assert_eq!(recovered.unit_versions(), dag.unit_versions());
+
+        // Compaction happens once the log holds enough entries.
+        recovered.set_journaling(true);
+        let _ = unwrap!(recovered.new_payload(vec![2], &keys));
+        let entries = recovered.take_journal_entries();
+        unwrap!(journal.record(&recovered, &entries));
+        assert_eq!(unwrap!(fs::metadata(dir.join(LOG_FILE))).len(), 0);
+        let (_, reloaded): (Journal, Dag) = unwrap!(Journal::open(&dir, &keys, 3));
+        assert_eq!(reloaded.unit_versions(), recovered.unit_versions());
+
+        unwrap!(fs::remove_dir_all(&dir));
+    }
+}
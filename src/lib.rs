@@ -16,6 +16,17 @@
 // relating to use of the SAFE Network Software.
 
 //! An implementation of a push-pull gossip protocol.
+//!
+//! The types most users need are gathered in the `prelude` module, which is the stable part of
+//! the API. The other types exported at the root are more likely to change between versions.
+//!
+//! No input, whether received from peers or passed by the caller, makes the library panic: all
+//! failures are reported as an `Error`, or through the decode failure policy for messages.
+//!
+//! The `wasm` feature makes `Dag`, `Unit` and `Gossiper` usable on `wasm32-unknown-unknown`, e.g.
+//! in a browser, which has no threads, sockets, clock nor entropy: the threaded and networked
+//! parts of the API are left out, gossipers are created with `Gossiper::with_rng` or from given
+//! keys, and the features relying on time, e.g. rate limits, are disabled.
 
 #![forbid(exceeding_bitshifts, mutable_transmutes, no_mangle_const_items,
           unknown_crate_types, warnings)]
@@ -29,6 +40,8 @@
          unused_extern_crates, variant_size_differences, non_camel_case_types)]
 
 extern crate ed25519_dalek;
+#[cfg(feature = "async")]
+extern crate futures;
 #[cfg(test)]
 extern crate itertools;
 #[macro_use]
@@ -37,20 +50,135 @@ extern crate maidsafe_utilities;
 #[macro_use]
 extern crate quick_error;
 extern crate rand;
+#[cfg(feature = "encryption")]
+extern crate rust_sodium;
 extern crate serde;
+#[cfg(feature = "cbor")]
+extern crate serde_cbor;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "json")]
+extern crate serde_json;
 extern crate sha3;
 #[cfg(test)]
 #[macro_use]
 #[cfg(test)]
 extern crate unwrap;
 extern crate tiny_keccak;
+#[cfg(feature = "async")]
+extern crate tokio_timer;
+#[cfg(feature = "zstd")]
+extern crate zstd;
 
+mod arena;
+#[cfg(feature = "async")]
+mod async_gossiper;
+mod audit;
+mod block;
+mod capabilities;
+mod catch_up;
+mod clock;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod codec;
+mod config;
+mod conflict;
+mod crdt;
 mod dag;
 mod dag_gossiper;
+mod diagnostics;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
+mod event;
+pub mod export;
+mod extension;
+mod fault;
+mod frozen;
+mod hasher;
 mod id;
+mod import;
+mod invariants;
+mod journal;
+mod message;
+#[cfg(not(feature = "wasm"))]
+mod node;
+mod outbound;
+mod peer_score;
+pub mod prelude;
+mod quorum;
+mod rate_limit;
+mod receipt;
+#[cfg(test)]
+mod scenario;
+#[cfg(not(feature = "wasm"))]
+mod scheduler;
+mod sharded;
+mod shared;
+pub mod simulation;
+mod small_payload;
+mod snapshot;
+mod stability;
+mod stats;
+mod telemetry;
+mod topic;
+#[cfg(not(feature = "wasm"))]
+mod transport;
+pub mod tuning;
 mod unit;
+mod watchdog;
+mod wire;
 
-pub use id::Id;
+#[cfg(feature = "async")]
+pub use async_gossiper::{AsyncGossiper, Rounds};
+pub use audit::SignedSnapshot;
+pub use block::{Block, BlockProducer};
+pub use capabilities::Capabilities;
+pub use catch_up::{CatchUpProgress, MAX_BOOTSTRAP_PAGE};
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosConfig;
+pub use codec::{ColumnarDag, WireCodec};
+pub use config::GossiperConfig;
+pub use conflict::PayloadPolicy;
+pub use crdt::CvRdt;
+pub use dag::{Checkpoint, Dag, DagConfig, DagDiff, Duplicates, Eviction, MembershipCheck,
+              Traversal, UnionOutcome, Validation};
+pub use dag_gossiper::{DecodeFailurePolicy, GossipStrategy, Gossiper, PayloadStatus, PeerSelection,
+                       RumorState, SyncMode};
+pub use diagnostics::Diagnostics;
+#[cfg(feature = "encryption")]
+pub use encryption::GroupKey;
+pub use error::Error;
+pub use event::Event;
+pub use export::DotOptions;
+pub use extension::{MAX_EXTENSIONS_LEN, RoundHook};
+pub use fault::FaultProof;
+pub use frozen::FrozenDag;
+pub use hasher::{Hasher, Sha3Hasher};
+pub use id::{Id, UnitId};
+pub use import::{ForeignEvent, HashgraphEvent};
+pub use invariants::{InvariantViolation, check_agreement, check_invariants,
+                     check_stability_monotone};
+pub use journal::{Journal, JournalEntry};
+pub use message::{Attestation, Nack, RejectReason};
+#[cfg(not(feature = "wasm"))]
+pub use node::Node;
+pub use outbound::{CHUNK_HEADER_LEN, OutboundQueue, QueuePolicy, Reassembler};
+pub use peer_score::{PeerScore, PeerSelector};
+pub use quorum::{MembershipReconciliation, QuorumInfo, SmallNetworkMode};
+pub use rate_limit::{PeerStatus, RateLimit};
+pub use receipt::Receipt;
+#[cfg(not(feature = "wasm"))]
+pub use scheduler::{Schedule, Scheduler};
+pub use sharded::{KeyExtractor, ShardedDag};
+pub use shared::SharedGossiper;
+pub use small_payload::{INLINE_CAPACITY, SmallPayload};
+pub use snapshot::{DAG_FORMAT_VERSION, GOSSIPER_FORMAT_VERSION};
+pub use stability::StabilityPolicy;
+pub use stats::{Bucket, StabilityTimes, Statistics, UnitBreakdown};
+pub use telemetry::{Operation, SlowOperation};
+#[cfg(not(feature = "wasm"))]
+pub use transport::{TcpTransport, Transport};
+pub use unit::{ContentType, Payload, QuorumCertificate, Unit};
+pub use watchdog::{Stall, WatchdogConfig};
+pub use wire::{CompressionLevel, PROTOCOL_VERSION, WireFormat};
@@ -0,0 +1,257 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use capabilities::Capabilities;
+use codec::{ColumnarDag, WireCodec};
+use dag::Dag;
+use error::Error;
+use extension::Extensions;
+use id::{Id, UnitId};
+use std::collections::{BTreeMap, BTreeSet};
+use unit::Unit;
+
+/// What is actually sent on the wire: a message, with attestations piggybacked on it.
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    /// The message.
+    pub message: Message,
+    /// Recent observations made by the sender, to be applied to any unit the receiver holds.
+    pub attestations: Vec<Attestation>,
+    /// The protocol features the sender supports.
+    pub capabilities: Capabilities,
+    /// Application blobs attached by the sender's `RoundHook`, if it has one.
+    pub extensions: Option<Extensions>,
+    /// The digest of the sender's DAG when sending, as per `Dag::digest`.
+    pub digest: [u8; 32],
+    /// The digest of the sender's member set: itself, its peers and the members learned from them,
+    /// as per `quorum::membership_digest`.
+    pub membership: [u8; 32],
+    /// The tag of the hash algorithm the sender derives unit identifiers with, as per
+    /// `Hasher::tag`.
+    pub hash_algorithm: u8,
+}
+
+/// A DAG carried in a message, encoded with the codec negotiated with the receiver.
+#[derive(Serialize, Deserialize)]
+pub enum WireDag {
+    /// Encoded with `WireCodec::Units`.
+    Units(Dag),
+    /// Encoded with `WireCodec::Columnar`.
+    Columnar(ColumnarDag),
+}
+
+impl WireDag {
+    /// Encode `dag` with `codec`.
+    pub fn encode(dag: Dag, codec: WireCodec) -> Result<Self, Error> {
+        Ok(match codec {
+            WireCodec::Units => WireDag::Units(dag),
+            WireCodec::Columnar => WireDag::Columnar(dag.to_columnar()?),
+        })
+    }
+
+    /// The DAG carried.
+    pub fn decode(self) -> Result<Dag, Error> {
+        match self {
+            WireDag::Units(dag) => Ok(dag),
+            WireDag::Columnar(columns) => Dag::from_columnar(columns),
+        }
+    }
+
+    /// Number of units carried, the genesis included.
+    pub fn unit_count(&self) -> usize {
+        match *self {
+            WireDag::Units(ref dag) => dag.len(),
+            WireDag::Columnar(ref columns) => columns.identifiers.len(),
+        }
+    }
+
+    /// Length of the largest payload carried, encoded if columnar.
+    pub fn largest_payload(&self) -> usize {
+        match *self {
+            WireDag::Units(ref dag) => largest_payload(dag.units()),
+            WireDag::Columnar(ref columns) => {
+                columns.payload_lengths.iter().max().map_or(0, |len| *len as usize)
+            }
+        }
+    }
+}
+
+/// An observer's signed statement that it observed a unit's payload.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attestation {
+    /// Identifier of the unit observed.
+    pub identifier: UnitId,
+    /// The observer.
+    pub observer: Id,
+    /// The observer's signature over the unit's payload.
+    pub signature: Vec<u8>,
+}
+
+/// Why units received from a peer were rejected.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RejectReason {
+    /// The units' identifiers don't match their content.
+    InvalidUnit,
+    /// The units form a cycle. The identifiers are those of the child and of the parent closing
+    /// it.
+    Cycle,
+    /// The DAG started from a different genesis than the receiver's.
+    GenesisMismatch,
+    /// The DAG was checkpointed at a unit the receiver could not verify.
+    UnverifiedCheckpoint,
+    /// The units carried observers without a valid signature, which were dropped.
+    ForgedObservers(BTreeSet<Id>),
+    /// The unit was observed by these ids, which the receiver doesn't know as members.
+    UnauthorisedObservers(BTreeSet<Id>),
+}
+
+/// A negative acknowledgement, telling a peer which of the units it sent were rejected and why, so
+/// that a peer whose DAG got corrupted notices and resyncs rather than sending them forever.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nack {
+    /// The offending units, as far as the receiver could tell.
+    pub identifiers: Vec<UnitId>,
+    /// Why they were rejected.
+    pub reason: RejectReason,
+}
+
+impl Nack {
+    /// The NACK to send for a union which failed with `error`, or `None` if the error doesn't come
+    /// from invalid units, e.g. if parents are just missing.
+    pub fn for_error(error: &Error) -> Option<Self> {
+        let (identifiers, reason) = match *error {
+            Error::InvalidUnit(identifier) => (vec![identifier], RejectReason::InvalidUnit),
+            Error::CycleDetected(ref diagnostics) => {
+                let (child, parent) = diagnostics.back_edge;
+                (vec![child, parent], RejectReason::Cycle)
+            }
+            Error::GenesisMismatch => (vec![], RejectReason::GenesisMismatch),
+            Error::UnverifiedCheckpoint(_) => (vec![], RejectReason::UnverifiedCheckpoint),
+            Error::ForgedObservers(ref observers) => {
+                (vec![], RejectReason::ForgedObservers(observers.clone()))
+            }
+            Error::UnauthorisedObservers(identifier, ref observers) => {
+                (vec![identifier], RejectReason::UnauthorisedObservers(observers.clone()))
+            }
+            _ => return None,
+        };
+        Some(Nack {
+            identifiers,
+            reason,
+        })
+    }
+}
+
+// Length of the largest payload of the units.
+fn largest_payload<'a, I: Iterator<Item = &'a Unit>>(units: I) -> usize {
+    units.map(|unit| unit.payload.len()).max().unwrap_or(0)
+}
+
+/// Messages exchanged between gossipers.
+#[derive(Serialize, Deserialize)]
+pub enum Message {
+    /// The sender's whole DAG.
+    Full(WireDag),
+    /// Only the units of the sender's DAG which the receiver is believed to be missing, or to hold
+    /// an older version of.
+    Delta(WireDag),
+    /// Request for the units the sender is missing. Carries the version of each unit the sender
+    /// holds, keyed by unit identifier.
+    Pull(BTreeMap<UnitId, usize>),
+    /// A push and a pull request combined.
+    PushPull(WireDag, BTreeMap<UnitId, usize>),
+    /// Response to a pull request: the units the requester is missing.
+    PullResponse(WireDag),
+    /// Advertisement of the identifiers of the units held by the sender.
+    Have(Vec<UnitId>),
+    /// Request for the units with the given identifiers.
+    FetchUnits(Vec<UnitId>),
+    /// Response to a fetch request: those of the requested units held by the sender.
+    Units(Vec<Unit>),
+    /// Report of units sent by the receiver which the sender rejected.
+    Nack(Nack),
+    /// Sent instead of a round's push or pull when the receiver is known to hold the same units
+    /// as the sender, as per the digest carried by the last message received from it.
+    InSync,
+    /// The sender's member set, sent to a peer whose membership digest differs from the sender's.
+    Members(BTreeSet<Id>),
+    /// The topics the sender subscribed to, replacing those it announced before.
+    Subscriptions(BTreeSet<String>),
+    /// A push of the sender's whole DAG for a topic the receiver announced it subscribed to.
+    TopicPush {
+        /// The topic.
+        topic: String,
+        /// The topic's DAG.
+        dag: WireDag,
+    },
+    /// Request, sent while bootstrapping, for up to `count` units of the receiver's DAG, starting
+    /// at position `start` in its total order.
+    BootstrapRequest {
+        /// Position of the first unit requested.
+        start: u32,
+        /// Maximum number of units requested.
+        count: u32,
+    },
+    /// Response to a bootstrap request: a page of units of the sender's DAG in total order, along
+    /// with its checkpoint.
+    BootstrapPage {
+        /// Position of the first unit carried.
+        start: u32,
+        /// Number of units held by the sender, other than its genesis.
+        total: u32,
+        /// The units carried.
+        dag: WireDag,
+    },
+}
+
+impl Message {
+    /// Number of units carried by the message, or referred to by it, e.g. by identifier or by
+    /// version. For the messages carrying members or topics, the number of those.
+    pub fn unit_count(&self) -> usize {
+        match *self {
+            Message::Full(ref dag) |
+            Message::Delta(ref dag) |
+            Message::PullResponse(ref dag) |
+            Message::TopicPush { ref dag, .. } |
+            Message::BootstrapPage { ref dag, .. } => dag.unit_count(),
+            Message::Pull(ref versions) => versions.len(),
+            Message::PushPull(ref dag, ref versions) => dag.unit_count() + versions.len(),
+            Message::Have(ref identifiers) |
+            Message::FetchUnits(ref identifiers) => identifiers.len(),
+            Message::Units(ref units) => units.len(),
+            Message::Nack(ref nack) => nack.identifiers.len(),
+            Message::Members(ref members) => members.len(),
+            Message::Subscriptions(ref topics) => topics.len(),
+            Message::InSync |
+            Message::BootstrapRequest { .. } => 0,
+        }
+    }
+
+    /// Length of the largest payload carried by the message, 0 if it carries none.
+    pub fn largest_payload(&self) -> usize {
+        match *self {
+            Message::Full(ref dag) |
+            Message::Delta(ref dag) |
+            Message::PullResponse(ref dag) |
+            Message::PushPull(ref dag, _) |
+            Message::TopicPush { ref dag, .. } |
+            Message::BootstrapPage { ref dag, .. } => dag.largest_payload(),
+            Message::Units(ref units) => largest_payload(units.iter()),
+            _ => 0,
+        }
+    }
+}
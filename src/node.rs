@@ -0,0 +1,156 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use dag_gossiper::Gossiper;
+use error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
+use transport::Transport;
+
+// How long `run_for` sleeps between two polls.
+const POLL_INTERVAL_MS: u64 = 10;
+
+/// A `Gossiper` running its rounds over a `Transport`, one round every `round_interval`.
+pub struct Node<T: Transport> {
+    gossiper: Gossiper,
+    transport: T,
+    round_interval: Duration,
+    next_round_at: Instant,
+}
+
+impl<T: Transport> Node<T> {
+    /// Run `gossiper` over `transport`, starting the first round at once.
+    pub fn new(gossiper: Gossiper, transport: T, round_interval: Duration) -> Self {
+        Node {
+            gossiper,
+            transport,
+            round_interval,
+            next_round_at: Instant::now(),
+        }
+    }
+
+    /// The gossiper being run.
+    pub fn gossiper(&self) -> &Gossiper {
+        &self.gossiper
+    }
+
+    /// The gossiper being run, e.g. to send new payloads or poll the delivered ones.
+    pub fn gossiper_mut(&mut self) -> &mut Gossiper {
+        &mut self.gossiper
+    }
+
+    /// The transport in use, e.g. to add peer addresses.
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Handle all the messages waiting on the transport, sending back the responses, then start
+    /// a round if one is due. Never blocks. Failures to handle a message or to send to a peer are
+    /// only logged, as peers may come and go, or misbehave.
+    pub fn poll(&mut self) -> Result<(), Error> {
+        while let Some((peer_id, message)) = self.transport.receive() {
+            let response = match self.gossiper.handle_received_message(&peer_id, &message) {
+                Ok(Some(response)) => response,
+                Ok(None) => continue,
+                Err(error) => {
+                    warn!(
+                        "{:?} failed to handle message from {:?}: {}",
+                        self.gossiper,
+                        peer_id,
+                        error
+                    );
+                    continue;
+                }
+            };
+            if let Err(error) = self.transport.send(&peer_id, &response) {
+                error!("{:?} failed to respond to {:?}: {}", self.gossiper, peer_id, error);
+            }
+        }
+        let now = Instant::now();
+        if now < self.next_round_at {
+            return Ok(());
+        }
+        self.next_round_at = now + self.round_interval;
+        let (peer_id, message) = self.gossiper.next_round()?;
+        if let Err(error) = self.transport.send(&peer_id, &message) {
+            error!("{:?} failed to gossip to {:?}: {}", self.gossiper, peer_id, error);
+        }
+        Ok(())
+    }
+
+    /// Keep polling for `duration`.
+    pub fn run_for(&mut self, duration: Duration) -> Result<(), Error> {
+        let end = Instant::now() + duration;
+        while Instant::now() < end {
+            self.poll()?;
+            thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dag_gossiper::DecodeFailurePolicy;
+    use id::Id;
+    use simulation::create_network;
+    use std::collections::VecDeque;
+
+    // A transport handing out the messages queued in its inbox, and keeping those sent.
+    #[derive(Default)]
+    struct MemoryTransport {
+        inbox: VecDeque<(Id, Vec<u8>)>,
+        sent: Vec<(Id, Vec<u8>)>,
+    }
+
+    impl Transport for MemoryTransport {
+        fn send(&mut self, peer_id: &Id, message: &[u8]) -> Result<(), Error> {
+            self.sent.push((*peer_id, message.to_vec()));
+            Ok(())
+        }
+
+        fn receive(&mut self) -> Option<(Id, Vec<u8>)> {
+            self.inbox.pop_front()
+        }
+    }
+
+    #[test]
+    fn poll() {
+        let mut gossipers = create_network(2);
+        let peer_id = gossipers[1].id();
+        let gossiper = gossipers.remove(0);
+        let round_interval = Duration::from_secs(3600);
+        let mut node = Node::new(gossiper, MemoryTransport::default(), round_interval);
+
+        // A message which can't be handled doesn't fail the poll, nor the round due.
+        node.transport_mut().inbox.push_back((peer_id, vec![1, 2, 3]));
+        unwrap!(node.poll());
+        assert!(node.transport_mut().inbox.is_empty());
+        assert_eq!(node.transport_mut().sent.len(), 1);
+        assert_eq!(node.transport_mut().sent[0].0, peer_id);
+
+        // Responses are sent back to the sender, even when no round is due.
+        node.gossiper_mut().set_decode_failure_policy(
+            DecodeFailurePolicy::RequestRetransmission,
+        );
+        node.transport_mut().inbox.push_back((peer_id, vec![1, 2, 3]));
+        unwrap!(node.poll());
+        assert_eq!(node.transport_mut().sent.len(), 2);
+        assert_eq!(node.transport_mut().sent[1].0, peer_id);
+    }
+}
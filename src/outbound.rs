@@ -0,0 +1,345 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use error::Error;
+use id::Id;
+use std::cmp;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Length of the header of each chunk a message is split into: its message's sequence number,
+/// its index and the number of chunks, each a little-endian `u32`.
+pub const CHUNK_HEADER_LEN: usize = 12;
+// The most chunks a message may be split into, bounding what a receiver allocates for it.
+const MAX_CHUNKS: u32 = 1 << 16;
+
+/// What an `OutboundQueue` does with a message pushed for a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueuePolicy {
+    /// Queue the message, dropping the oldest queued one if the peer's queue is full.
+    DropOldest,
+    /// Replace everything queued for the peer with the message. Each push carries our whole DAG,
+    /// so the newest one supersedes all those before it.
+    Coalesce,
+}
+
+/// Bounded per-peer queue of outgoing messages, sitting between the `Gossiper` and the transport,
+/// so that a slow link can't make the sender's memory grow without bound. Callers apply
+/// backpressure, e.g. skipping rounds, as per `len` or `queued_bytes`.
+///
+/// With an MTU set, messages are popped as chunks of at most the MTU, each with a header, which
+/// the receiver reassembles with a `Reassembler` before handing the message to its `Gossiper`.
+/// Both sides shall agree on chunking, as every message is then chunked, however small.
+#[derive(Debug)]
+pub struct OutboundQueue {
+    capacity: usize,
+    policy: QueuePolicy,
+    queues: BTreeMap<Id, VecDeque<Vec<u8>>>,
+    dropped: u64,
+    mtu: Option<usize>,
+    // The sequence number of the next message to be chunked.
+    next_sequence: u32,
+    // Per peer, the chunks of the message being sent still to be popped.
+    in_flight: BTreeMap<Id, VecDeque<Vec<u8>>>,
+}
+
+impl OutboundQueue {
+    /// Create a queue holding at most `capacity` messages per peer.
+    pub fn new(capacity: usize, policy: QueuePolicy) -> Self {
+        OutboundQueue {
+            capacity,
+            policy,
+            queues: BTreeMap::new(),
+            dropped: 0,
+            mtu: None,
+            next_sequence: 0,
+            in_flight: BTreeMap::new(),
+        }
+    }
+
+    /// Split the messages popped into chunks of at most `mtu` bytes, header included, or not if
+    /// `None`, the default. An MTU not larger than `CHUNK_HEADER_LEN` is raised above it. Applies
+    /// to the messages not started yet.
+    pub fn set_mtu(&mut self, mtu: Option<usize>) {
+        self.mtu = mtu.map(|mtu| cmp::max(mtu, CHUNK_HEADER_LEN + 1));
+    }
+
+    /// Queue `message` to be sent to `peer`.
+    pub fn push(&mut self, peer: Id, message: Vec<u8>) {
+        if self.policy == QueuePolicy::Coalesce && self.in_flight.remove(&peer).is_some() {
+            self.dropped += 1;
+        }
+        let queue = self.queues.entry(peer).or_insert_with(VecDeque::new);
+        if self.policy == QueuePolicy::Coalesce {
+            self.dropped += queue.len() as u64;
+            queue.clear();
+        }
+        while !queue.is_empty() && queue.len() >= self.capacity {
+            let _ = queue.pop_front();
+            self.dropped += 1;
+        }
+        if self.capacity == 0 {
+            self.dropped += 1;
+            return;
+        }
+        queue.push_back(message);
+    }
+
+    /// Take the next message to be sent to `peer`, if any, or its next chunk if an MTU is set.
+    pub fn pop(&mut self, peer: &Id) -> Option<Vec<u8>> {
+        let chunk = match self.in_flight.get_mut(peer) {
+            Some(chunks) => chunks.pop_front(),
+            None => None,
+        };
+        if chunk.is_some() {
+            self.end_in_flight(peer);
+            return chunk;
+        }
+        let message = match self.queues.get_mut(peer).and_then(|queue| queue.pop_front()) {
+            Some(message) => message,
+            None => return None,
+        };
+        let mtu = match self.mtu {
+            Some(mtu) => mtu,
+            None => return Some(message),
+        };
+        let mut chunks = split(self.next_sequence, &message, mtu);
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        let chunk = chunks.pop_front();
+        let _ = self.in_flight.insert(*peer, chunks);
+        self.end_in_flight(peer);
+        chunk
+    }
+
+    // Forget the message being sent to the peer once all its chunks were popped.
+    fn end_in_flight(&mut self, peer: &Id) {
+        if self.in_flight.get(peer).map_or(false, |chunks| chunks.is_empty()) {
+            let _ = self.in_flight.remove(peer);
+        }
+    }
+
+    /// Number of messages queued for `peer`, a message whose chunks are being popped included.
+    pub fn len(&self, peer: &Id) -> usize {
+        let in_flight = if self.in_flight.contains_key(peer) { 1 } else { 0 };
+        self.queues.get(peer).map_or(0, |queue| queue.len()) + in_flight
+    }
+
+    /// Number of messages queued for all peers, those whose chunks are being popped included.
+    pub fn total_len(&self) -> usize {
+        self.queues.values().map(|queue| queue.len()).sum::<usize>() + self.in_flight.len()
+    }
+
+    /// Number of bytes queued for `peer`, chunk headers included for the chunks already split.
+    pub fn queued_bytes(&self, peer: &Id) -> usize {
+        let queued: usize = self.queues.get(peer).map_or(0, |queue| {
+            queue.iter().map(|message| message.len()).sum()
+        });
+        let in_flight: usize = self.in_flight.get(peer).map_or(0, |chunks| {
+            chunks.iter().map(|chunk| chunk.len()).sum()
+        });
+        queued + in_flight
+    }
+
+    /// Number of bytes queued for all peers.
+    pub fn total_queued_bytes(&self) -> usize {
+        let mut peers: Vec<&Id> = self.queues.keys().collect();
+        peers.extend(self.in_flight.keys().filter(|peer| !self.queues.contains_key(*peer)));
+        peers.into_iter().map(|peer| self.queued_bytes(peer)).sum()
+    }
+
+    /// Number of messages dropped so far because of the capacity or the policy.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+/// Reassembles the messages chunked by a peer's `OutboundQueue` with an MTU set. A chunk of a newer
+/// message from a peer discards the message still being reassembled for it, as the sender only
+/// starts a message once done with the previous one, so the missing chunks were lost.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    partial: BTreeMap<Id, Partial>,
+    discarded: u64,
+}
+
+// A message being reassembled.
+#[derive(Debug)]
+struct Partial {
+    sequence: u32,
+    chunks: Vec<Option<Vec<u8>>>,
+    missing: usize,
+}
+
+impl Reassembler {
+    /// Create a reassembler without any message in progress.
+    pub fn new() -> Self {
+        Reassembler::default()
+    }
+
+    /// Handle a chunk received from `peer`, returning its message once complete. Fails with
+    /// `Error::Encoding` if the chunk is malformed.
+    pub fn receive(&mut self, peer: Id, chunk: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        if chunk.len() < CHUNK_HEADER_LEN {
+            return Err(Error::Encoding(format!("chunk of {} bytes", chunk.len())));
+        }
+        let sequence = read_u32(&chunk[0..4]);
+        let index = read_u32(&chunk[4..8]);
+        let count = read_u32(&chunk[8..12]);
+        if count == 0 || count > MAX_CHUNKS || index >= count {
+            return Err(Error::Encoding(format!("chunk {} of {}", index, count)));
+        }
+        let stale = self.partial.get(&peer).map_or(false, |partial| {
+            partial.sequence != sequence || partial.chunks.len() != count as usize
+        });
+        if stale {
+            let _ = self.partial.remove(&peer);
+            self.discarded += 1;
+        }
+        let complete = {
+            let partial = self.partial.entry(peer).or_insert_with(|| {
+                Partial {
+                    sequence,
+                    chunks: vec![None; count as usize],
+                    missing: count as usize,
+                }
+            });
+            let slot = &mut partial.chunks[index as usize];
+            if slot.is_none() {
+                *slot = Some(chunk[CHUNK_HEADER_LEN..].to_vec());
+                partial.missing -= 1;
+            }
+            partial.missing == 0
+        };
+        if !complete {
+            return Ok(None);
+        }
+        Ok(self.partial.remove(&peer).map(|partial| {
+            partial.chunks.into_iter().flat_map(|chunk| chunk.unwrap_or_default()).collect()
+        }))
+    }
+
+    /// Number of messages being reassembled.
+    pub fn pending(&self) -> usize {
+        self.partial.len()
+    }
+
+    /// Number of messages discarded so far as some of their chunks were lost.
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    /// Forget the message being reassembled for `peer`, if any, e.g. once disconnected.
+    pub fn forget_peer(&mut self, peer: &Id) {
+        let _ = self.partial.remove(peer);
+    }
+}
+
+// The message split into chunks of at most `mtu` bytes, header included. An empty message makes
+// for a single chunk.
+fn split(sequence: u32, message: &[u8], mtu: usize) -> VecDeque<Vec<u8>> {
+    let capacity = mtu - CHUNK_HEADER_LEN;
+    let pieces: Vec<&[u8]> = if message.is_empty() {
+        vec![message]
+    } else {
+        message.chunks(capacity).collect()
+    };
+    let count = pieces.len() as u32;
+    pieces
+        .into_iter()
+        .enumerate()
+        .map(|(index, piece)| {
+            let mut chunk = Vec::with_capacity(CHUNK_HEADER_LEN + piece.len());
+            push_u32(&mut chunk, sequence);
+            push_u32(&mut chunk, index as u32);
+            push_u32(&mut chunk, count);
+            chunk.extend_from_slice(piece);
+            chunk
+        })
+        .collect()
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    for shift in 0..4 {
+        bytes.push((value >> (8 * shift)) as u8);
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    bytes.iter().rev().fold(0, |value, byte| (value << 8) | u32::from(*byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drop_oldest() {
+        let peer = Id([1; 32]);
+        let mut queue = OutboundQueue::new(2, QueuePolicy::DropOldest);
+        for i in 0..4 {
+            queue.push(peer, vec![i]);
+        }
+        assert_eq!(queue.len(&peer), 2);
+        assert_eq!(queue.dropped(), 2);
+        assert_eq!(queue.pop(&peer), Some(vec![2]));
+        assert_eq!(queue.pop(&peer), Some(vec![3]));
+        assert_eq!(queue.pop(&peer), None);
+    }
+
+    #[test]
+    fn coalesce() {
+        let peer = Id([1; 32]);
+        let mut queue = OutboundQueue::new(2, QueuePolicy::Coalesce);
+        for i in 0..4 {
+            queue.push(peer, vec![i]);
+        }
+        assert_eq!(queue.total_len(), 1);
+        assert_eq!(queue.dropped(), 3);
+        assert_eq!(queue.pop(&peer), Some(vec![3]));
+    }
+
+    #[test]
+    fn chunking() {
+        let peer = Id([1; 32]);
+        let mut queue = OutboundQueue::new(4, QueuePolicy::DropOldest);
+        queue.set_mtu(Some(CHUNK_HEADER_LEN + 4));
+        let message: Vec<u8> = (0..10).collect();
+        queue.push(peer, message.clone());
+        queue.push(peer, vec![]);
+        assert_eq!(queue.len(&peer), 2);
+        assert_eq!(queue.queued_bytes(&peer), 10);
+
+        let mut reassembler = Reassembler::new();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = queue.pop(&peer) {
+            assert!(chunk.len() <= CHUNK_HEADER_LEN + 4);
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(queue.len(&peer), 0);
+        assert_eq!(unwrap!(reassembler.receive(peer, &chunks[0])), None);
+        assert_eq!(unwrap!(reassembler.receive(peer, &chunks[2])), None);
+        assert_eq!(unwrap!(reassembler.receive(peer, &chunks[1])), Some(message));
+        assert_eq!(unwrap!(reassembler.receive(peer, &chunks[3])), Some(vec![]));
+        assert!(reassembler.receive(peer, &chunks[0][..4]).is_err());
+
+        // A chunk of a newer message discards the message still missing chunks.
+        assert_eq!(unwrap!(reassembler.receive(peer, &chunks[0])), None);
+        assert_eq!(unwrap!(reassembler.receive(peer, &chunks[3])), Some(vec![]));
+        assert_eq!(reassembler.discarded(), 1);
+        assert_eq!(reassembler.pending(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use id::Id;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+/// How useful gossiping with a peer has been so far, as tracked by a `Gossiper`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PeerScore {
+    /// Units we first received from the peer.
+    pub new_units: u64,
+    /// Messages received from the peer.
+    pub messages_received: u64,
+    /// Messages we sent to the peer when picking it for a round.
+    pub messages_sent: u64,
+    /// Rounds since we last picked the peer, i.e. how out of sync with us it may be.
+    pub rounds_since_contact: u64,
+}
+
+impl PeerScore {
+    /// The average number of new units per message received from the peer.
+    pub fn productivity(&self) -> f64 {
+        if self.messages_received == 0 {
+            return 0.0;
+        }
+        self.new_units as f64 / self.messages_received as f64
+    }
+
+    /// The ratio of messages received from the peer to messages sent to it, capped at 1. A peer
+    /// we never sent anything to counts as fully responsive.
+    pub fn responsiveness(&self) -> f64 {
+        if self.messages_sent == 0 {
+            return 1.0;
+        }
+        (self.messages_received as f64 / self.messages_sent as f64).min(1.0)
+    }
+
+    /// The relative chance of the peer being picked by `PeerSelection::Adaptive`. Grows with the
+    /// peer's productivity and the rounds since we last picked it, and shrinks by at most half
+    /// for an unresponsive peer, so that every peer keeps being picked now and then.
+    pub fn weight(&self) -> f64 {
+        (1.0 + self.productivity()) * (0.5 + 0.5 * self.responsiveness()) *
+            (1.0 + self.rounds_since_contact as f64)
+    }
+}
+
+/// A custom strategy choosing the peers a `Gossiper` gossips with, given their scores.
+pub trait PeerSelector: Send {
+    /// Choose one of the candidates, or none to end the round's choice early. Called as many
+    /// times per round as the fanout, the peers already chosen this round not being candidates.
+    fn choose(&mut self, candidates: &BTreeMap<Id, PeerScore>, rng: &mut Rng) -> Option<Id>;
+}
+
+// Choose one of the candidates at random, with a chance proportional to its weight.
+pub fn choose_weighted(candidates: &BTreeMap<Id, PeerScore>, rng: &mut Rng) -> Option<Id> {
+    let total: f64 = candidates.values().map(PeerScore::weight).sum();
+    let mut sample = rng.next_f64() * total;
+    for (peer_id, score) in candidates {
+        sample -= score.weight();
+        if sample < 0.0 {
+            return Some(*peer_id);
+        }
+    }
+    // Only reached through rounding errors.
+    candidates.keys().last().cloned()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+
+    #[test]
+    fn scores() {
+        let fresh = PeerScore::default();
+        assert_eq!(fresh.productivity(), 0.0);
+        assert_eq!(fresh.responsiveness(), 1.0);
+        assert_eq!(fresh.weight(), 1.0);
+
+        let productive = PeerScore {
+            new_units: 6,
+            messages_received: 2,
+            messages_sent: 4,
+            rounds_since_contact: 1,
+        };
+        assert_eq!(productive.productivity(), 3.0);
+        assert_eq!(productive.responsiveness(), 0.5);
+        assert_eq!(productive.weight(), 4.0 * 0.75 * 2.0);
+
+        // Responsiveness is capped, and an unresponsive peer keeps half its weight.
+        let chatty = PeerScore {
+            messages_received: 5,
+            messages_sent: 1,
+            ..PeerScore::default()
+        };
+        assert_eq!(chatty.responsiveness(), 1.0);
+        let silent = PeerScore {
+            messages_sent: 3,
+            ..PeerScore::default()
+        };
+        assert_eq!(silent.weight(), 0.5);
+    }
+
+    #[test]
+    fn weighted_choice() {
+        let mut rng = SeededRng::new();
+        let (heavy, light) = (Id([1; 32]), Id([2; 32]));
+        let mut candidates = BTreeMap::new();
+        let _ = candidates.insert(
+            heavy,
+            PeerScore {
+                rounds_since_contact: 9,
+                ..PeerScore::default()
+            },
+        );
+        let _ = candidates.insert(light, PeerScore::default());
+
+        let mut heavy_chosen = 0;
+        for _ in 0..1000 {
+            if unwrap!(choose_weighted(&candidates, &mut rng)) == heavy {
+                heavy_chosen += 1;
+            }
+        }
+        assert!(heavy_chosen > 800 && heavy_chosen < 1000);
+        assert!(choose_weighted(&BTreeMap::new(), &mut rng).is_none());
+    }
+}
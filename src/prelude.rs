@@ -0,0 +1,71 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! The types most users need, for glob importing with `use dag_gossip::prelude::*;`.
+//!
+//! Everything exported here is part of the stable API: it only changes in a way breaking
+//! downstream users along with a major version bump. Internal details, e.g. how units are indexed
+//! or encoded on the wire, are deliberately left out so they can keep evolving.
+
+pub use dag::{Checkpoint, Dag, UnionOutcome};
+pub use dag_gossiper::{DecodeFailurePolicy, GossipStrategy, Gossiper, PayloadStatus, PeerSelection,
+                       RumorState, SyncMode};
+pub use error::Error;
+pub use event::Event;
+pub use id::{Id, UnitId};
+#[cfg(not(feature = "wasm"))]
+pub use node::Node;
+pub use quorum::{QuorumInfo, SmallNetworkMode};
+pub use stability::StabilityPolicy;
+#[cfg(not(feature = "wasm"))]
+pub use transport::{TcpTransport, Transport};
+pub use unit::{ContentType, Payload, Unit};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A typical use, with nothing imported but the prelude.
+    #[test]
+    fn glob_import() {
+        let mut gossipers: Vec<Gossiper> = (0..2).map(|_| Gossiper::default()).collect();
+        let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+        unwrap!(gossipers[0].add_peer(ids[1]));
+        unwrap!(gossipers[1].add_peer(ids[0]));
+        gossipers[0].set_gossip_strategy(GossipStrategy::Push);
+        gossipers[0].set_peer_selection(PeerSelection::Uniform);
+        gossipers[0].set_sync_mode(SyncMode::Full);
+        let events = gossipers[1].events();
+
+        let identifier: UnitId = unwrap!(gossipers[0].send_new(&"Hello"));
+        let (peer_id, message) = unwrap!(gossipers[0].next_round());
+        assert_eq!(peer_id, ids[1]);
+        let result: Result<Option<Vec<u8>>, Error> =
+            gossipers[1].handle_received_message(&ids[0], &message);
+        let _ = unwrap!(result);
+
+        let dag: &Dag = gossipers[1].dag();
+        let unit: &Unit = unwrap!(dag.get(&identifier));
+        assert!(unit.observers.contains(&ids[0]));
+        assert!(events.try_iter().any(|event| match event {
+            Event::NewUnit { identifier: new, .. } => new == identifier,
+            _ => false,
+        }));
+        let info: QuorumInfo = gossipers[1].quorum_info();
+        assert_eq!(info.members, 2);
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use ed25519_dalek::PUBLIC_KEY_LENGTH;
+use id::Id;
+use std::collections::BTreeSet;
+use tiny_keccak::sha3_256;
+
+/// The agreement parameters a `Gossiper` is currently running with.
+///
+/// There is no separate finality stage: a unit is final as soon as it is stable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuorumInfo {
+    /// Number of configured members, including ourself.
+    pub members: usize,
+    /// Number of members known to be live: ourself plus the peers we have received a message from.
+    pub live_members: usize,
+    /// Number of observers a unit requires to become stable, or their total weight under
+    /// `StabilityPolicy::Weighted`.
+    pub stability_threshold: usize,
+    /// How `stability_threshold` was computed.
+    pub stability_rule: String,
+}
+
+/// What stability means in a network too small for a meaningful majority, i.e. where we have at
+/// most one peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SmallNetworkMode {
+    /// A unit is stable once observed by a single member, e.g. ourself.
+    SelfAttestation,
+    /// A unit is stable once observed by every member.
+    AllMembers,
+}
+
+/// How a `Gossiper` reconciles its member set with those of peers which know other members, as
+/// detected from the membership digest their messages carry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MembershipReconciliation {
+    /// Only report the disagreement, with `Event::MembershipDisagreement`.
+    Ignore,
+    /// Also count the members peers know of towards the stability threshold, even if we don't
+    /// gossip with them, so that the thresholds of all members converge to the one of the union
+    /// of their member sets.
+    Union,
+}
+
+impl Default for MembershipReconciliation {
+    fn default() -> Self {
+        MembershipReconciliation::Ignore
+    }
+}
+
+/// The digest of a member set, carried by every message so that peers notice when they disagree
+/// on membership, hence on the stability threshold.
+pub fn membership_digest(members: &BTreeSet<Id>) -> [u8; 32] {
+    let mut bytes = Vec::with_capacity(members.len() * PUBLIC_KEY_LENGTH);
+    for member in members {
+        bytes.extend_from_slice(&member.0);
+    }
+    sha3_256(&bytes)
+}
+
+/// The number of observers required for stability in a network where we have `peer_count` peers.
+pub fn majority(peer_count: usize) -> usize {
+    peer_count / 2 + 1
+}
+
+/// Describes how `majority(peer_count)` is computed.
+pub fn majority_rule(peer_count: usize) -> String {
+    format!(
+        "majority of {} peers: {} / 2 + 1 = {}",
+        peer_count,
+        peer_count,
+        majority(peer_count)
+    )
+}
+
+/// The number of observers making more than two thirds of `member_count` members.
+pub fn supermajority(member_count: usize) -> usize {
+    2 * member_count / 3 + 1
+}
+
+/// Describes how `supermajority(member_count)` is computed.
+pub fn supermajority_rule(member_count: usize) -> String {
+    format!(
+        "supermajority of {} members: 2 * {} / 3 + 1 = {}",
+        member_count,
+        member_count,
+        supermajority(member_count)
+    )
+}
+
+/// The number of observers required for stability where we have `peer_count` peers: the
+/// majority, unless the network is small enough for `mode` to apply.
+pub fn stability_threshold(peer_count: usize, mode: SmallNetworkMode) -> usize {
+    if peer_count > 1 {
+        return majority(peer_count);
+    }
+    match mode {
+        SmallNetworkMode::SelfAttestation => 1,
+        SmallNetworkMode::AllMembers => peer_count + 1,
+    }
+}
+
+/// Describes how `stability_threshold(peer_count, mode)` is computed.
+pub fn stability_rule(peer_count: usize, mode: SmallNetworkMode) -> String {
+    if peer_count > 1 {
+        return majority_rule(peer_count);
+    }
+    match mode {
+        SmallNetworkMode::SelfAttestation => "small network: self-attestation = 1".to_string(),
+        SmallNetworkMode::AllMembers => {
+            format!("small network: all {} members", peer_count + 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_networks() {
+        // A lone node can only ever rely on itself.
+        assert_eq!(stability_threshold(0, SmallNetworkMode::SelfAttestation), 1);
+        assert_eq!(stability_threshold(0, SmallNetworkMode::AllMembers), 1);
+        // With a single peer, either our own observation suffices, or both are needed.
+        assert_eq!(stability_threshold(1, SmallNetworkMode::SelfAttestation), 1);
+        assert_eq!(stability_threshold(1, SmallNetworkMode::AllMembers), 2);
+        // From three members on, the mode doesn't matter.
+        assert_eq!(stability_threshold(2, SmallNetworkMode::SelfAttestation), 2);
+        assert_eq!(stability_threshold(2, SmallNetworkMode::AllMembers), 2);
+        assert_eq!(stability_threshold(5, SmallNetworkMode::AllMembers), majority(5));
+    }
+
+    #[test]
+    fn thresholds() {
+        let majorities: Vec<usize> = (0..6).map(majority).collect();
+        assert_eq!(majorities, vec![1, 1, 2, 2, 3, 3]);
+        let supermajorities: Vec<usize> = (1..8).map(supermajority).collect();
+        assert_eq!(supermajorities, vec![1, 2, 3, 3, 4, 5, 5]);
+        assert_eq!(majority_rule(4), "majority of 4 peers: 4 / 2 + 1 = 3");
+        assert_eq!(
+            supermajority_rule(4),
+            "supermajority of 4 members: 2 * 4 / 3 + 1 = 3"
+        );
+        assert_eq!(stability_rule(4, SmallNetworkMode::AllMembers), majority_rule(4));
+        assert_eq!(
+            stability_rule(1, SmallNetworkMode::AllMembers),
+            "small network: all 2 members"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// The inbound traffic accepted from each peer per time window, to protect us from spammy or
+/// buggy peers. A peer exceeding any limit is muted: its messages are dropped for a while.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The length of a window.
+    pub window: Duration,
+    /// The most messages accepted per window, if limited.
+    pub max_messages: Option<u32>,
+    /// The most new units accepted per window, if limited. As the units a message carries are
+    /// only known once merged, the message exceeding the limit is still accepted.
+    pub max_new_units: Option<u32>,
+    /// How long a peer exceeding a limit stays muted.
+    pub mute_for: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            window: Duration::from_secs(1),
+            max_messages: Some(100),
+            max_new_units: Some(1000),
+            mute_for: Duration::from_secs(10),
+        }
+    }
+}
+
+/// How a peer stands with respect to the rate limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerStatus {
+    /// Not a peer of ours.
+    Unknown,
+    /// Its messages are accepted.
+    Active {
+        /// Messages accepted from it in the current window.
+        messages: u32,
+        /// New units accepted from it in the current window.
+        new_units: u32,
+    },
+    /// Its messages are dropped.
+    Muted {
+        /// How long until it is unmuted.
+        remaining: Duration,
+    },
+}
+
+// The traffic received from a peer in the current window.
+pub struct PeerUsage {
+    window_start: Instant,
+    messages: u32,
+    new_units: u32,
+    muted_until: Option<Instant>,
+}
+
+impl PeerUsage {
+    pub fn new(now: Instant) -> Self {
+        PeerUsage {
+            window_start: now,
+            messages: 0,
+            new_units: 0,
+            muted_until: None,
+        }
+    }
+
+    // Record a message received at `now`. Returns whether it is accepted, muting the peer if it
+    // exceeds the limit.
+    pub fn record_message(&mut self, limit: &RateLimit, now: Instant) -> bool {
+        if self.is_muted(now) {
+            return false;
+        }
+        self.roll_window(limit, now);
+        self.messages = self.messages.saturating_add(1);
+        if limit.max_messages.map_or(false, |max| self.messages > max) {
+            self.mute(limit, now);
+            return false;
+        }
+        true
+    }
+
+    // Record `count` new units merged from a message received at `now`, muting the peer if they
+    // exceed the limit.
+    pub fn record_new_units(&mut self, count: usize, limit: &RateLimit, now: Instant) {
+        self.roll_window(limit, now);
+        self.new_units = self.new_units.saturating_add(cmp::min(count, u32::max_value() as usize)
+            as u32);
+        if limit.max_new_units.map_or(false, |max| self.new_units > max) {
+            self.mute(limit, now);
+        }
+    }
+
+    pub fn status(&self, now: Instant) -> PeerStatus {
+        match self.muted_until {
+            Some(until) if until > now => PeerStatus::Muted { remaining: until - now },
+            _ => {
+                PeerStatus::Active {
+                    messages: self.messages,
+                    new_units: self.new_units,
+                }
+            }
+        }
+    }
+
+    pub fn is_muted(&self, now: Instant) -> bool {
+        self.muted_until.map_or(false, |until| until > now)
+    }
+
+    fn mute(&mut self, limit: &RateLimit, now: Instant) {
+        self.muted_until = Some(now + limit.mute_for);
+        self.window_start = now + limit.mute_for;
+        self.messages = 0;
+        self.new_units = 0;
+    }
+
+    // Start a new window if the current one is over.
+    fn roll_window(&mut self, limit: &RateLimit, now: Instant) {
+        if now >= self.window_start + limit.window {
+            self.window_start = now;
+            self.messages = 0;
+            self.new_units = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_usage() {
+        let limit = RateLimit {
+            window: Duration::from_secs(10),
+            max_messages: Some(2),
+            max_new_units: Some(3),
+            mute_for: Duration::from_secs(60),
+        };
+        let start = Instant::now();
+        let mut usage = PeerUsage::new(start);
+        assert!(usage.record_message(&limit, start));
+        usage.record_new_units(3, &limit, start);
+        assert!(usage.record_message(&limit, start + Duration::from_secs(1)));
+        assert_eq!(
+            usage.status(start),
+            PeerStatus::Active {
+                messages: 2,
+                new_units: 3,
+            }
+        );
+
+        // Counts restart with each window.
+        let later = start + Duration::from_secs(10);
+        assert!(usage.record_message(&limit, later));
+        assert!(usage.record_message(&limit, later));
+        assert!(!usage.record_message(&limit, later));
+        assert!(usage.is_muted(later));
+        assert_eq!(
+            usage.status(later + Duration::from_secs(20)),
+            PeerStatus::Muted { remaining: Duration::from_secs(40) }
+        );
+        assert!(!usage.record_message(&limit, later + Duration::from_secs(59)));
+
+        // Once unmuted, too many new units get the peer muted again.
+        let unmuted = later + Duration::from_secs(60);
+        assert!(!usage.is_muted(unmuted));
+        assert!(usage.record_message(&limit, unmuted));
+        usage.record_new_units(4, &limit, unmuted);
+        assert!(usage.is_muted(unmuted));
+        assert!(!usage.record_message(&limit, unmuted));
+    }
+}
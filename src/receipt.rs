@@ -0,0 +1,146 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use id::Id;
+use stability::StabilityPolicy;
+use std::collections::{BTreeMap, BTreeSet};
+use unit::{Payload, Unit};
+
+/// Evidence that a unit's payload was acknowledged by enough members to be stable: the unit, its
+/// observers' signatures over the payload and the membership it was judged against.
+///
+/// Checkable by anyone through `verify`, without running a gossiper. The identifier can't be
+/// checked without the parents' payloads, so a receipt vouches for the payload rather than for
+/// where the unit sits in the DAG.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Receipt<T = Vec<u8>> {
+    /// The unit acknowledged, carrying only the observers with a signature.
+    pub unit: Unit<T>,
+    /// The members allowed to acknowledge the unit when the receipt was issued, or `None` if the
+    /// membership wasn't restricted.
+    pub members: Option<BTreeSet<Id>>,
+    /// The weight of each member, if the stability policy was weighted. Otherwise each member
+    /// weighs one.
+    pub weights: Option<BTreeMap<Id, u64>>,
+    /// The number of acknowledgements, or their total weight, the unit required to be stable.
+    pub threshold: u64,
+}
+
+impl<T: Payload> Receipt<T> {
+    /// The receipt for `unit`, judged stable against `threshold` as per `policy`, with `members`
+    /// the authorised members if restricted. Observers without a signature are left out.
+    pub fn new(
+        unit: &Unit<T>,
+        members: Option<BTreeSet<Id>>,
+        policy: &StabilityPolicy,
+        threshold: u64,
+    ) -> Self {
+        let mut signed = unit.without_observers();
+        for (observer, signature) in &unit.signatures {
+            if unit.observers.contains(observer) {
+                let _ = signed.observers.insert(*observer);
+                let _ = signed.signatures.insert(*observer, signature.clone());
+            }
+        }
+        let weights = match *policy {
+            StabilityPolicy::Weighted { ref weights, .. } => Some(weights.clone()),
+            _ => None,
+        };
+        Receipt {
+            unit: signed,
+            members,
+            weights,
+            threshold,
+        }
+    }
+
+    /// The members with a valid signature over the payload, among `members` if restricted.
+    pub fn acknowledgers(&self) -> BTreeSet<Id> {
+        self.unit
+            .signatures
+            .keys()
+            .filter(|observer| {
+                self.members.as_ref().map_or(true, |members| members.contains(observer))
+            })
+            .filter(|observer| self.unit.is_attested_by(observer))
+            .cloned()
+            .collect()
+    }
+
+    /// The number of acknowledgements, or their total weight if weighted.
+    pub fn weight(&self) -> u64 {
+        let acknowledgers = self.acknowledgers();
+        match self.weights {
+            Some(ref weights) => {
+                acknowledgers
+                    .iter()
+                    .filter_map(|observer| weights.get(observer))
+                    .fold(0u64, |total, weight| total.saturating_add(*weight))
+            }
+            None => acknowledgers.len() as u64,
+        }
+    }
+
+    /// Whether this is valid evidence, checkable by anyone: every signature carried is valid, and
+    /// the members signing weigh at least the threshold. A zero threshold proves nothing, so such
+    /// a receipt is never valid.
+    pub fn verify(&self) -> bool {
+        let all_valid = self.unit.signatures.keys().all(|observer| {
+            self.unit.is_attested_by(observer)
+        });
+        all_valid && self.threshold > 0 && self.weight() >= self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dag::Dag;
+    use ed25519_dalek::Keypair;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+    use std::iter;
+
+    #[test]
+    fn receipt() {
+        let mut rng = SeededRng::new();
+        let alice = Keypair::generate::<Sha3_512>(&mut rng);
+        let bob = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut dag: Dag = Dag::new(&alice);
+        dag.set_majority(2);
+        let identifier = unwrap!(dag.new_payload(vec![1], &alice));
+        assert!(dag.receipt(&identifier).is_none());
+
+        let mut other: Dag = Dag::new(&bob);
+        unwrap!(other.union(&dag));
+        let _ = unwrap!(other.new_payload(vec![1], &bob));
+        unwrap!(dag.union(&other));
+        let receipt = unwrap!(dag.receipt(&identifier));
+        assert!(receipt.verify());
+        assert_eq!(receipt.weight(), 2);
+        assert_eq!(receipt.members, None);
+
+        let mut forged = receipt.clone();
+        let signature = unwrap!(forged.unit.signatures.get(&Id::from(alice.public))).clone();
+        let _ = forged.unit.signatures.insert(Id::from(bob.public), signature);
+        assert!(!forged.verify());
+
+        let mut restricted = receipt.clone();
+        restricted.members = Some(iter::once(Id::from(alice.public)).collect());
+        assert!(!restricted.verify());
+    }
+}
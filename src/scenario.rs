@@ -0,0 +1,140 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! A small DSL to describe gossip scenarios in tests, e.g.
+//!
+//! ```ignore
+//! scenario()
+//!     .nodes(9)
+//!     .send("a", at_round(2))
+//!     .partition(0..5, 5..9, rounds(3..6))
+//!     .expect_converged_by(30);
+//! ```
+
+use invariants;
+use itertools::Itertools;
+use maidsafe_utilities::serialisation;
+use simulation::{Simulation, SimulationConfig};
+use std::ops::Range;
+
+/// Start describing a new scenario.
+pub fn scenario() -> Scenario {
+    Scenario::default()
+}
+
+/// The round at which an action happens.
+pub fn at_round(round: usize) -> usize {
+    round
+}
+
+/// The rounds during which a condition holds.
+pub fn rounds(rounds: Range<usize>) -> Range<usize> {
+    rounds
+}
+
+/// A gossip scenario: a network of fully connected nodes, the payloads each one sends and the
+/// partitions the network goes through.
+#[derive(Default)]
+pub struct Scenario {
+    node_count: u32,
+    // (round, sending node, payload)
+    sends: Vec<(usize, usize, String)>,
+    // (lhs, rhs, rounds)
+    partitions: Vec<(Range<usize>, Range<usize>, Range<usize>)>,
+}
+
+impl Scenario {
+    /// Set the number of nodes in the network.
+    pub fn nodes(mut self, node_count: u32) -> Self {
+        self.node_count = node_count;
+        self
+    }
+
+    /// Have node 0 send `payload` at the start of `round`.
+    pub fn send(self, payload: &str, round: usize) -> Self {
+        self.send_from(0, payload, round)
+    }
+
+    /// Have `node` send `payload` at the start of `round`.
+    pub fn send_from(mut self, node: usize, payload: &str, round: usize) -> Self {
+        self.sends.push((round, node, payload.to_string()));
+        self
+    }
+
+    /// Drop all messages between the nodes in `lhs` and the nodes in `rhs` during `rounds`.
+    pub fn partition(mut self, lhs: Range<usize>, rhs: Range<usize>, rounds: Range<usize>) -> Self {
+        self.partitions.push((lhs, rhs, rounds));
+        self
+    }
+
+    /// Run the scenario, panicking unless every node holds every sent payload within
+    /// `max_rounds` rounds. The invariants are checked all along, and the nodes are checked to
+    /// agree on the stable sequence. Returns the number of rounds it took.
+    pub fn expect_converged_by(self, max_rounds: usize) -> usize {
+        let mut simulation = Simulation::new(SimulationConfig {
+            node_count: self.node_count,
+            max_rounds,
+            strict_checks: true,
+            ..SimulationConfig::default()
+        });
+        for (lhs, rhs, rounds) in self.partitions {
+            simulation.partition(lhs, rhs, rounds);
+        }
+        let last_send = self.sends.iter().map(|send| send.0).max().unwrap_or(0);
+        let expected = self.sends
+            .iter()
+            .map(|send| unwrap!(serialisation::serialise(&send.2)))
+            .collect_vec();
+
+        for round in 0..max_rounds {
+            for &(_, node, ref payload) in self.sends.iter().filter(|send| send.0 == round) {
+                let _ = unwrap!(simulation.send(node, payload));
+            }
+            simulation.step();
+
+            let gossipers = simulation.gossipers();
+            if round >= last_send &&
+                gossipers.iter().all(|gossiper| {
+                    expected.iter().all(|payload| gossiper.has_payload(payload))
+                })
+            {
+                let dags = gossipers
+                    .iter()
+                    .map(|gossiper| gossiper.dag().clone())
+                    .collect_vec();
+                unwrap!(invariants::check_agreement(&dags));
+                return round + 1;
+            }
+        }
+        panic!("scenario did not converge within {} rounds", max_rounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partitioned_network_converges_once_healed() {
+        let _ = scenario()
+            .nodes(9)
+            .send("a", at_round(2))
+            .send_from(6, "b", at_round(4))
+            .partition(0..5, 5..9, rounds(3..6))
+            .expect_converged_by(30);
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use dag_gossiper::Gossiper;
+use id::Id;
+use rand::{self, Rng};
+use shared::SharedGossiper;
+use std::cmp;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use transport::Transport;
+
+// Longest the scheduler's thread sleeps at once, bounding how late it notices being stopped or a
+// message waiting on the transport.
+const POLL_INTERVAL_MS: u64 = 10;
+
+/// When a `Scheduler` starts rounds: every `period`, give or take up to `jitter`. The deviation is
+/// drawn afresh for every round, so that nodes started together don't keep gossiping in lockstep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    /// The mean interval between two rounds.
+    pub period: Duration,
+    /// The largest deviation of an interval from `period`. Capped at `period`.
+    pub jitter: Duration,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            period: Duration::from_secs(1),
+            jitter: Duration::from_millis(100),
+        }
+    }
+}
+
+impl Schedule {
+    // The interval until the next round.
+    fn next_interval(&self) -> Duration {
+        let period = millis(&self.period);
+        let jitter = cmp::min(millis(&self.jitter), period);
+        let offset = rand::thread_rng().gen_range(0, 2 * jitter + 1);
+        Duration::from_millis(period - jitter + offset)
+    }
+}
+
+// Where the messages of each round go.
+enum Outbound {
+    Channel(Sender<(Id, Vec<u8>)>),
+    Transport(Box<Transport + Send>),
+}
+
+/// Runs the rounds of a `Gossiper` on a background thread as per a `Schedule`, instead of every
+/// application writing its own loop around `next_round`. The gossiper stays reachable through
+/// `gossiper`, e.g. to send payloads or poll the ordered ones. Dropping the scheduler stops it.
+pub struct Scheduler {
+    gossiper: SharedGossiper,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    /// Start the rounds of `gossiper`, passing each message to send to `sender`, along with the
+    /// peer to send it to. Messages received from peers are to be handed to the gossiper, and its
+    /// responses sent, by the application. Stops starting rounds once the receiver is dropped.
+    pub fn with_channel(
+        gossiper: Gossiper,
+        schedule: Schedule,
+        sender: Sender<(Id, Vec<u8>)>,
+    ) -> Self {
+        Scheduler::start(gossiper, schedule, Outbound::Channel(sender))
+    }
+
+    /// Start the rounds of `gossiper` over `transport`. Messages received on the transport are
+    /// handled in between rounds and the responses sent back, as by `Node::poll`.
+    pub fn with_transport<T: Transport + Send + 'static>(
+        gossiper: Gossiper,
+        schedule: Schedule,
+        transport: T,
+    ) -> Self {
+        Scheduler::start(gossiper, schedule, Outbound::Transport(Box::new(transport)))
+    }
+
+    fn start(gossiper: Gossiper, schedule: Schedule, outbound: Outbound) -> Self {
+        let gossiper = SharedGossiper::new(gossiper);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let gossiper = gossiper.clone();
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || run(&gossiper, schedule, outbound, &stop))
+        };
+        Scheduler {
+            gossiper,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// The gossiper being run.
+    pub fn gossiper(&self) -> &SharedGossiper {
+        &self.gossiper
+    }
+
+    /// Stop starting rounds, waiting for the round in progress, if any, to complete.
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("The scheduler of {:?} panicked", self.gossiper.id());
+            }
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// The loop run by the scheduler's thread until `stop` is set.
+fn run(gossiper: &SharedGossiper, schedule: Schedule, mut outbound: Outbound, stop: &AtomicBool) {
+    let mut next_round_at = Instant::now();
+    while !stop.load(Ordering::SeqCst) {
+        if let Outbound::Transport(ref mut transport) = outbound {
+            receive(gossiper, transport);
+        }
+        let now = Instant::now();
+        if now >= next_round_at {
+            next_round_at = now + schedule.next_interval();
+            let messages = match gossiper.next_round_multi() {
+                Ok(messages) => messages,
+                Err(error) => {
+                    warn!("{:?} failed to start a round: {}", gossiper.id(), error);
+                    Vec::new()
+                }
+            };
+            for (peer_id, message) in messages {
+                if !send(gossiper, &mut outbound, &peer_id, message) {
+                    return;
+                }
+            }
+        }
+        let now = Instant::now();
+        if next_round_at > now {
+            thread::sleep(cmp::min(
+                next_round_at - now,
+                Duration::from_millis(POLL_INTERVAL_MS),
+            ));
+        }
+    }
+}
+
+// Handle all the messages waiting on the transport, sending back the responses.
+fn receive(gossiper: &SharedGossiper, transport: &mut Box<Transport + Send>) {
+    while let Some((peer_id, message)) = transport.receive() {
+        let response = match gossiper.handle_received_message(&peer_id, &message) {
+            Ok(Some(response)) => response,
+            Ok(None) => continue,
+            Err(error) => {
+                warn!(
+                    "{:?} failed to handle message from {:?}: {}",
+                    gossiper.id(),
+                    peer_id,
+                    error
+                );
+                continue;
+            }
+        };
+        if let Err(error) = transport.send(&peer_id, &response) {
+            error!("{:?} failed to respond to {:?}: {}", gossiper.id(), peer_id, error);
+        }
+    }
+}
+
+// Pass the message on. Returns whether to keep going, i.e. false once the channel is closed.
+fn send(
+    gossiper: &SharedGossiper,
+    outbound: &mut Outbound,
+    peer_id: &Id,
+    message: Vec<u8>,
+) -> bool {
+    match *outbound {
+        Outbound::Channel(ref sender) => sender.send((*peer_id, message)).is_ok(),
+        Outbound::Transport(ref mut transport) => {
+            if let Err(error) = transport.send(peer_id, &message) {
+                error!("{:?} failed to gossip to {:?}: {}", gossiper.id(), peer_id, error);
+            }
+            true
+        }
+    }
+}
+
+fn millis(duration: &Duration) -> u64 {
+    duration.as_secs().saturating_mul(1000) + u64::from(duration.subsec_nanos() / 1_000_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn channel() {
+        let schedule = Schedule {
+            period: Duration::from_millis(20),
+            jitter: Duration::from_millis(10),
+        };
+        for _ in 0..100 {
+            let interval = schedule.next_interval();
+            assert!(interval >= Duration::from_millis(10) && interval <= Duration::from_millis(30));
+        }
+
+        let mut gossiper = Gossiper::default();
+        let peer_id = Gossiper::default().id();
+        unwrap!(gossiper.add_peer(peer_id));
+        let (sender, receiver) = mpsc::channel();
+        let mut scheduler = Scheduler::with_channel(gossiper, schedule, sender);
+        let _ = unwrap!(scheduler.gossiper().send_new(&"Hello"));
+        let rounds: Vec<_> = (0..3)
+            .map(|_| unwrap!(receiver.recv_timeout(Duration::from_secs(5))))
+            .collect();
+        assert!(rounds.iter().all(|&(id, _)| id == peer_id));
+        scheduler.stop();
+        let rounds = scheduler.gossiper().with(|gossiper| gossiper.statistics().rounds);
+        assert!(rounds >= 3);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(scheduler.gossiper().with(|gossiper| gossiper.statistics().rounds), rounds);
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use crdt::CvRdt;
+use dag::Dag;
+use ed25519_dalek::Keypair;
+use error::Error;
+use event::Event;
+use id::UnitId;
+use maidsafe_utilities::serialisation;
+use std::collections::{BTreeMap, BTreeSet};
+use tiny_keccak::sha3_256;
+
+/// Gives the key of the shard a payload belongs to.
+pub type KeyExtractor = fn(&[u8]) -> u64;
+
+/// A set of independent DAGs, each holding the payloads of one part of the keyspace, with its
+/// own tips and stability. They are unioned and serialised together, so a single gossip session
+/// replicates all of them, while traversals only ever walk a single shard.
+///
+/// Payloads spanning several shards can be sent atomically, as parts of an atomic payload which
+/// is only reported stable once all its parts are.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShardedDag {
+    shards: BTreeMap<u64, Dag>,
+    majority: u8,
+    // The parts of each atomic payload, as (shard key, unit identifier), keyed by the identifier
+    // of the atomic payload.
+    atomics: BTreeMap<UnitId, BTreeSet<(u64, UnitId)>>,
+    // The atomic payloads reported stable so far. Local only.
+    #[serde(skip)]
+    stable_atomics: BTreeSet<UnitId>,
+    // Local setting only. Not needed to union a received `ShardedDag`.
+    #[serde(skip, default = "single_shard_extractor")]
+    key_extractor: KeyExtractor,
+    #[serde(skip)]
+    events_enabled: bool,
+    #[serde(skip)]
+    atomic_events: Vec<Event>,
+}
+
+impl ShardedDag {
+    /// Create an empty sharded DAG, placing payloads by `key_extractor`. Shards are created as
+    /// payloads for them appear.
+    pub fn new(key_extractor: KeyExtractor) -> Self {
+        ShardedDag {
+            shards: BTreeMap::new(),
+            majority: 0,
+            atomics: BTreeMap::new(),
+            stable_atomics: BTreeSet::new(),
+            key_extractor,
+            events_enabled: false,
+            atomic_events: Vec::new(),
+        }
+    }
+
+    /// Update the majority counter of every shard.
+    pub fn set_majority(&mut self, majority: u8) {
+        self.majority = majority;
+        for dag in self.shards.values_mut() {
+            dag.set_majority(majority);
+        }
+        self.check_atomics();
+    }
+
+    /// A new event being observed, inserted into the shard its key belongs to. Returns the
+    /// identifier of the unit holding the payload within that shard.
+    pub fn new_payload(&mut self, payload: Vec<u8>, keys: &Keypair) -> Result<UnitId, Error> {
+        let key = (self.key_extractor)(&payload);
+        let majority = self.majority;
+        let events_enabled = self.events_enabled;
+        self.shards
+            .entry(key)
+            .or_insert_with(|| {
+                let mut dag = Dag::new(keys);
+                dag.set_majority(majority);
+                dag.set_events_enabled(events_enabled);
+                dag
+            })
+            .new_payload(payload, keys)
+    }
+
+    /// Insert each payload into the shard its key belongs to, as the parts of a single atomic
+    /// payload. Returns the identifier of the atomic payload, derived from its parts, for which an
+    /// `Event::AtomicStable` is recorded once every part is stable.
+    pub fn new_atomic_payload(
+        &mut self,
+        payloads: Vec<Vec<u8>>,
+        keys: &Keypair,
+    ) -> Result<UnitId, Error> {
+        let mut parts = BTreeSet::new();
+        for payload in payloads {
+            let key = (self.key_extractor)(&payload);
+            let _ = parts.insert((key, self.new_payload(payload, keys)?));
+        }
+        let identifier = UnitId(sha3_256(&serialisation::serialise(&parts)?));
+        let _ = self.atomics.insert(identifier, parts);
+        self.check_atomics();
+        Ok(identifier)
+    }
+
+    /// The parts of the atomic payload, as (shard key, unit identifier), if known.
+    pub fn atomic_parts(&self, identifier: &UnitId) -> Option<&BTreeSet<(u64, UnitId)>> {
+        self.atomics.get(identifier)
+    }
+
+    /// Whether every part of the atomic payload is held and stable in its shard.
+    pub fn is_atomic_stable(&self, identifier: &UnitId) -> bool {
+        self.atomics.get(identifier).map_or(false, |parts| {
+            parts.iter().all(|&(key, ref part)| match self.shards.get(&key) {
+                Some(dag) => dag.get(part).map_or(false, |unit| dag.is_stable(unit)),
+                None => false,
+            })
+        })
+    }
+
+    /// Take the `Event::AtomicStable` recorded since the last call, one per atomic payload which
+    /// became stable. Only recorded while events are enabled.
+    pub fn take_atomic_events(&mut self) -> Vec<Event> {
+        ::std::mem::replace(&mut self.atomic_events, Vec::new())
+    }
+
+    // Record the atomic payloads which became stable.
+    fn check_atomics(&mut self) {
+        let newly_stable: Vec<UnitId> = self.atomics
+            .keys()
+            .filter(|identifier| {
+                !self.stable_atomics.contains(identifier) && self.is_atomic_stable(identifier)
+            })
+            .cloned()
+            .collect();
+        for identifier in newly_stable {
+            let _ = self.stable_atomics.insert(identifier);
+            if self.events_enabled {
+                self.atomic_events.push(Event::AtomicStable { identifier });
+            }
+        }
+    }
+
+    /// Union each shard with the matching shard of `other`, and take over its atomic payloads.
+    /// All the shards are merged even if some fail; the first error is returned.
+    pub fn union(&mut self, other: &ShardedDag) -> Result<(), Error> {
+        for (identifier, parts) in &other.atomics {
+            let _ = self.atomics.entry(*identifier).or_insert_with(|| parts.clone());
+        }
+        let mut result = Ok(());
+        for (key, other_dag) in &other.shards {
+            if let Some(dag) = self.shards.get_mut(key) {
+                let shard_result = dag.union(other_dag);
+                if result.is_ok() {
+                    result = shard_result;
+                }
+                continue;
+            }
+            let mut dag = other_dag.clone();
+            dag.set_majority(self.majority);
+            dag.set_events_enabled(self.events_enabled);
+            let _ = self.shards.insert(*key, dag);
+        }
+        self.check_atomics();
+        result
+    }
+
+    /// Start or stop recording the changes made to every shard as events.
+    pub fn set_events_enabled(&mut self, enabled: bool) {
+        self.events_enabled = enabled;
+        if !enabled {
+            self.atomic_events.clear();
+        }
+        for dag in self.shards.values_mut() {
+            dag.set_events_enabled(enabled);
+        }
+    }
+
+    /// Take the events recorded by all the shards since the last call, with their shard key.
+    pub fn take_events(&mut self) -> Vec<(u64, Event)> {
+        let mut events = Vec::new();
+        for (key, dag) in &mut self.shards {
+            events.extend(dag.take_events().into_iter().map(|event| (*key, event)));
+        }
+        events
+    }
+
+    /// The shard holding the payloads of `key`, if any were seen.
+    pub fn shard(&self, key: u64) -> Option<&Dag> {
+        self.shards.get(&key)
+    }
+
+    /// The keys of all shards held.
+    pub fn shard_keys(&self) -> Vec<u64> {
+        self.shards.keys().cloned().collect()
+    }
+}
+
+impl CvRdt for ShardedDag {
+    type Error = Error;
+
+    fn join(&mut self, other: &Self) -> Result<(), Error> {
+        self.union(other)
+    }
+
+    fn is_subset_of(&self, other: &Self) -> bool {
+        self.shards.iter().all(|(key, dag)| {
+            other.shards.get(key).map_or(false, |theirs| dag.is_subset_of(theirs))
+        })
+    }
+}
+
+fn single_shard(_payload: &[u8]) -> u64 {
+    0
+}
+
+fn single_shard_extractor() -> KeyExtractor {
+    single_shard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+
+    fn first_byte(payload: &[u8]) -> u64 {
+        payload.first().cloned().unwrap_or(0) as u64
+    }
+
+    #[test]
+    fn sharding() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut lhs = ShardedDag::new(first_byte);
+        let first = unwrap!(lhs.new_payload(vec![1, 0], &keys));
+        let _ = unwrap!(lhs.new_payload(vec![1, 1], &keys));
+        let other = unwrap!(lhs.new_payload(vec![3], &keys));
+        assert_eq!(lhs.shard_keys(), vec![1, 3]);
+        // Each shard only holds its own payloads, on top of its own genesis.
+        assert_eq!(unwrap!(lhs.shard(1)).len(), 3);
+        assert!(unwrap!(lhs.shard(1)).get(&first).is_some());
+        assert!(unwrap!(lhs.shard(1)).get(&other).is_none());
+        assert_eq!(unwrap!(lhs.shard(3)).len(), 2);
+        assert!(lhs.shard(2).is_none());
+
+        // Shards unknown to the receiver are taken over, the others unioned.
+        let mut rhs = ShardedDag::new(first_byte);
+        let _ = unwrap!(rhs.new_payload(vec![3, 1], &keys));
+        unwrap!(rhs.union(&lhs));
+        assert_eq!(rhs.shard_keys(), vec![1, 3]);
+        assert_eq!(unwrap!(rhs.shard(3)).len(), 3);
+        assert!(lhs.is_subset_of(&rhs));
+        assert!(!rhs.is_subset_of(&lhs));
+    }
+
+    #[test]
+    fn atomic_payload() {
+        let mut rng = SeededRng::new();
+        let lhs_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let rhs_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let mut lhs = ShardedDag::new(first_byte);
+        let mut rhs = ShardedDag::new(first_byte);
+        for dag in vec![&mut lhs, &mut rhs] {
+            dag.set_majority(2);
+            dag.set_events_enabled(true);
+        }
+
+        let atomic = unwrap!(lhs.new_atomic_payload(vec![vec![1], vec![2]], &lhs_keys));
+        assert_eq!(unwrap!(lhs.atomic_parts(&atomic)).len(), 2);
+        unwrap!(rhs.union(&lhs));
+        let _ = unwrap!(rhs.new_payload(vec![1], &rhs_keys));
+        unwrap!(lhs.union(&rhs));
+        assert!(!lhs.is_atomic_stable(&atomic));
+        assert!(lhs.take_atomic_events().is_empty());
+
+        let _ = unwrap!(rhs.new_payload(vec![2], &rhs_keys));
+        unwrap!(lhs.union(&rhs));
+        assert!(lhs.is_atomic_stable(&atomic));
+        assert_eq!(
+            lhs.take_atomic_events(),
+            vec![Event::AtomicStable { identifier: atomic }]
+        );
+        unwrap!(lhs.union(&rhs));
+        assert!(lhs.take_atomic_events().is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use dag_gossiper::{Gossiper, PayloadStatus};
+use error::Error;
+use id::{Id, UnitId};
+use serde::Serialize;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A handle to a `Gossiper` shared between threads, e.g. to drive rounds from a timer thread while
+/// the application sends payloads from its own. Clones refer to the same `Gossiper`, and each call
+/// locks it for its duration only.
+#[derive(Clone)]
+pub struct SharedGossiper {
+    id: Id,
+    gossiper: Arc<Mutex<Gossiper>>,
+}
+
+impl SharedGossiper {
+    /// Share `gossiper` between threads.
+    pub fn new(gossiper: Gossiper) -> Self {
+        SharedGossiper {
+            id: gossiper.id(),
+            gossiper: Arc::new(Mutex::new(gossiper)),
+        }
+    }
+
+    /// The ID of the gossiper, without locking it.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Run `f` on the gossiper, for everything not covered by the other methods, e.g. changing its
+    /// settings. Calls from other threads block until `f` returns.
+    pub fn with<R, F: FnOnce(&mut Gossiper) -> R>(&self, f: F) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Add the ID of another node on the network.
+    pub fn add_peer(&self, peer_id: Id) -> Result<(), Error> {
+        self.lock().add_peer(peer_id)
+    }
+
+    /// Remove a peer, forgetting everything known about it. Returns whether it was a peer.
+    pub fn remove_peer(&self, peer_id: &Id) -> bool {
+        self.lock().remove_peer(peer_id)
+    }
+
+    /// Send a new message, as per `Gossiper::send_new`.
+    pub fn send_new<T: Serialize>(&self, message: &T) -> Result<UnitId, Error> {
+        self.lock().send_new(message)
+    }
+
+    /// Send several new messages at once, as per `Gossiper::send_new_batch`.
+    pub fn send_new_batch<T: Serialize>(&self, messages: &[T]) -> Result<Vec<UnitId>, Error> {
+        self.lock().send_new_batch(messages)
+    }
+
+    /// Start a new round. Returns the message to send, with the peer to send it to.
+    pub fn next_round(&self) -> Result<(Id, Vec<u8>), Error> {
+        self.lock().next_round()
+    }
+
+    /// Start a new round, gossiping to as many peers as the fanout, as per
+    /// `Gossiper::next_round_multi`.
+    pub fn next_round_multi(&self) -> Result<Vec<(Id, Vec<u8>)>, Error> {
+        self.lock().next_round_multi()
+    }
+
+    /// Handle an incoming message from the peer, as per `Gossiper::handle_received_message`.
+    pub fn handle_received_message(
+        &self,
+        peer_id: &Id,
+        serialised_msg: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.lock().handle_received_message(peer_id, serialised_msg)
+    }
+
+    /// The payloads which became deliverable since the last call, as per
+    /// `Gossiper::poll_ordered`.
+    pub fn poll_ordered(&self) -> Vec<Vec<u8>> {
+        self.lock().poll_ordered()
+    }
+
+    /// How far the unit got towards stability.
+    pub fn status_of(&self, identifier: &UnitId) -> PayloadStatus {
+        self.lock().status_of(identifier)
+    }
+
+    // A panic while holding the lock can't leave the gossiper half updated in a way the next
+    // call wouldn't cope with, so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<Gossiper> {
+        self.gossiper.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simulation::create_network;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn threads() {
+        assert_send_sync::<SharedGossiper>();
+
+        let mut gossipers = create_network(2).into_iter().map(SharedGossiper::new);
+        let first = unwrap!(gossipers.next());
+        let second = unwrap!(gossipers.next());
+        let sender = {
+            let first = first.clone();
+            thread::spawn(move || for index in 0..10u8 {
+                let _ = unwrap!(first.send_new(&index));
+            })
+        };
+        let rounds = {
+            let first = first.clone();
+            thread::spawn(move || for _ in 0..10 {
+                let (peer_id, message) = unwrap!(first.next_round());
+                assert_eq!(peer_id, second.id());
+                let _ = unwrap!(second.handle_received_message(&first.id(), &message));
+            })
+        };
+        unwrap!(sender.join());
+        unwrap!(rounds.join());
+        assert_eq!(first.with(|gossiper| gossiper.statistics().units_created), 10);
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+//! A network of gossipers exchanging messages in memory, for experiments and tests. Message loss,
+//! latency and partitions are configurable, and runs report how long the network took to converge.
+//!
+//! ```ignore
+//! let mut simulation = Simulation::new(SimulationConfig {
+//!     node_count: 16,
+//!     loss_rate: 0.1,
+//!     ..SimulationConfig::default()
+//! });
+//! simulation.send(0, &"payload")?;
+//! let stats = simulation.run();
+//! ```
+
+use dag_gossiper::Gossiper;
+use error::Error;
+use id::{Id, UnitId};
+use maidsafe_utilities::SeededRng;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Create `node_count` gossipers, all connected to each other.
+#[cfg(not(feature = "wasm"))]
+pub fn create_network(node_count: u32) -> Vec<Gossiper> {
+    let mut gossipers: Vec<Gossiper> = (0..node_count).map(|_| Gossiper::default()).collect();
+    connect(&mut gossipers);
+    gossipers
+}
+
+/// How the simulated network behaves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationConfig {
+    /// Number of nodes, all connected to each other.
+    pub node_count: u32,
+    /// Probability for each message, responses included, to be lost.
+    pub loss_rate: f64,
+    /// The number of rounds a message takes to be delivered, drawn uniformly from this range for
+    /// each message. `0..1` delivers every message within the round it was sent in.
+    pub latency: Range<usize>,
+    /// The rounds after which `run` gives up if the network didn't converge.
+    pub max_rounds: usize,
+    /// The seed of every random decision, of the gossipers included, to reproduce a run. A random
+    /// seed if `None`, or a fixed one with the `wasm` feature as no entropy is available then.
+    pub seed: Option<[u32; 4]>,
+    /// Whether the gossipers check the invariants after every change, the simulation panicking on
+    /// a violation.
+    pub strict_checks: bool,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            node_count: 8,
+            loss_rate: 0.0,
+            latency: 0..1,
+            max_rounds: 100,
+            seed: None,
+            strict_checks: false,
+        }
+    }
+}
+
+/// The outcome of a simulation run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConvergenceStats {
+    /// Number of rounds run.
+    pub rounds: usize,
+    /// Whether every node converged, as per `Gossiper::is_converged`, with no message left in
+    /// flight, within the maximum number of rounds.
+    pub converged: bool,
+    /// Number of messages sent, responses included.
+    pub messages_sent: u64,
+    /// Number of messages lost or blocked by a partition.
+    pub messages_lost: u64,
+    /// Total size of the messages sent, in bytes.
+    pub bytes_sent: u64,
+}
+
+// A message on its way, delivered at the start of round `due`.
+struct InFlight {
+    due: usize,
+    src: usize,
+    dst: usize,
+    message: Vec<u8>,
+}
+
+// All messages between the nodes of `lhs` and the nodes of `rhs` are dropped during `rounds`.
+struct Partition {
+    lhs: Range<usize>,
+    rhs: Range<usize>,
+    rounds: Range<usize>,
+}
+
+impl Partition {
+    fn blocks(&self, round: usize, src: usize, dst: usize) -> bool {
+        in_range(&self.rounds, round) &&
+            ((in_range(&self.lhs, src) && in_range(&self.rhs, dst)) ||
+                 (in_range(&self.rhs, src) && in_range(&self.lhs, dst)))
+    }
+}
+
+/// A simulated network of gossipers, advanced one round at a time.
+pub struct Simulation {
+    config: SimulationConfig,
+    rng: SeededRng,
+    gossipers: Vec<Gossiper>,
+    indices: BTreeMap<Id, usize>,
+    partitions: Vec<Partition>,
+    in_flight: Vec<InFlight>,
+    round: usize,
+    stats: ConvergenceStats,
+}
+
+impl Simulation {
+    /// Create a network as per `config`.
+    pub fn new(config: SimulationConfig) -> Self {
+        let mut rng = match config.seed {
+            Some(seed) => SeededRng::from_seed(seed),
+            None => random_rng(),
+        };
+        let mut gossipers: Vec<Gossiper> = (0..config.node_count)
+            .map(|_| Gossiper::with_rng(rng.new_rng()))
+            .collect();
+        connect(&mut gossipers);
+        for gossiper in &mut gossipers {
+            gossiper.set_strict_checks(config.strict_checks);
+        }
+        let indices = gossipers
+            .iter()
+            .enumerate()
+            .map(|(index, gossiper)| (gossiper.id(), index))
+            .collect();
+        Simulation {
+            config,
+            rng,
+            gossipers,
+            indices,
+            partitions: Vec::new(),
+            in_flight: Vec::new(),
+            round: 0,
+            stats: ConvergenceStats::default(),
+        }
+    }
+
+    /// Drop all messages between the nodes in `lhs` and the nodes in `rhs` during `rounds`.
+    pub fn partition(&mut self, lhs: Range<usize>, rhs: Range<usize>, rounds: Range<usize>) {
+        self.partitions.push(Partition { lhs, rhs, rounds });
+    }
+
+    /// Have `node` send `payload`. Returns the identifier of the unit holding it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such node.
+    pub fn send<T: Serialize>(&mut self, node: usize, payload: &T) -> Result<UnitId, Error> {
+        self.gossipers[node].send_new(payload)
+    }
+
+    /// The gossipers, by node index.
+    pub fn gossipers(&self) -> &[Gossiper] {
+        &self.gossipers
+    }
+
+    /// The gossiper of `node`, e.g. to configure it.
+    pub fn gossiper_mut(&mut self, node: usize) -> Option<&mut Gossiper> {
+        self.gossipers.get_mut(node)
+    }
+
+    /// The number of rounds run so far.
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// The statistics of the run so far.
+    pub fn stats(&self) -> &ConvergenceStats {
+        &self.stats
+    }
+
+    /// Whether every node converged, with no message left in flight.
+    pub fn is_converged(&self) -> bool {
+        self.in_flight.is_empty() && self.gossipers.iter().all(Gossiper::is_converged)
+    }
+
+    /// Run one round: every node gossips to as many peers as its fanout, then the messages due
+    /// are delivered along with the responses they trigger, for as long as these are delivered
+    /// within the round.
+    pub fn step(&mut self) {
+        for src in 0..self.gossipers.len() {
+            match self.gossipers[src].next_round_multi() {
+                Ok(messages) => {
+                    for (dst_id, message) in messages {
+                        if let Some(&dst) = self.indices.get(&dst_id) {
+                            self.transmit(src, dst, message);
+                        }
+                    }
+                }
+                Err(error) => debug!("Node {} didn't gossip: {}", src, error),
+            }
+        }
+        self.deliver_due();
+        self.round += 1;
+        self.stats.rounds = self.round;
+    }
+
+    /// Run rounds until the network converges or the maximum number of rounds is reached.
+    pub fn run(&mut self) -> ConvergenceStats {
+        while !self.is_converged() && self.round < self.config.max_rounds {
+            self.step();
+        }
+        self.stats.converged = self.is_converged();
+        self.stats.clone()
+    }
+
+    // Send `message` from `src` to `dst`, unless it gets lost.
+    fn transmit(&mut self, src: usize, dst: usize, message: Vec<u8>) {
+        self.stats.messages_sent += 1;
+        self.stats.bytes_sent += message.len() as u64;
+        let round = self.round;
+        let blocked = self.partitions.iter().any(
+            |partition| partition.blocks(round, src, dst),
+        );
+        if blocked || self.rng.gen::<f64>() < self.config.loss_rate {
+            self.stats.messages_lost += 1;
+            return;
+        }
+        let latency = &self.config.latency;
+        let delay = if latency.end > latency.start + 1 {
+            self.rng.gen_range(latency.start, latency.end)
+        } else {
+            latency.start
+        };
+        self.in_flight.push(InFlight {
+            due: round + delay,
+            src,
+            dst,
+            message,
+        });
+    }
+
+    // Deliver the messages due by the current round, and the responses they trigger.
+    fn deliver_due(&mut self) {
+        loop {
+            let round = self.round;
+            let (due, pending): (Vec<InFlight>, Vec<InFlight>) = self.in_flight
+                .drain(..)
+                .partition(|in_flight| in_flight.due <= round);
+            self.in_flight = pending;
+            if due.is_empty() {
+                return;
+            }
+            for in_flight in due {
+                let src_id = self.gossipers[in_flight.src].id();
+                let response = self.gossipers[in_flight.dst].handle_received_message(
+                    &src_id,
+                    &in_flight.message,
+                );
+                match response {
+                    Ok(Some(response)) => self.transmit(in_flight.dst, in_flight.src, response),
+                    Ok(None) => (),
+                    Err(Error::InvariantViolated(reason)) => {
+                        panic!("Node {} violated an invariant: {}", in_flight.dst, reason)
+                    }
+                    Err(error) => debug!("Node {} dropped a message: {}", in_flight.dst, error),
+                }
+            }
+        }
+    }
+}
+
+// A generator with a random seed, or a fixed one if no entropy is available.
+#[cfg(not(feature = "wasm"))]
+fn random_rng() -> SeededRng {
+    SeededRng::new()
+}
+
+#[cfg(feature = "wasm")]
+fn random_rng() -> SeededRng {
+    SeededRng::from_seed([1, 2, 3, 4])
+}
+
+// Connect all the gossipers to each other.
+fn connect(gossipers: &mut [Gossiper]) {
+    let ids: Vec<Id> = gossipers.iter().map(Gossiper::id).collect();
+    for (index, gossiper) in gossipers.iter_mut().enumerate() {
+        for (peer_index, peer_id) in ids.iter().enumerate() {
+            if peer_index != index {
+                let _ = gossiper.add_peer(*peer_id);
+            }
+        }
+    }
+}
+
+fn in_range(range: &Range<usize>, value: usize) -> bool {
+    value >= range.start && value < range.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lossy_network_converges() {
+        let mut simulation = Simulation::new(SimulationConfig {
+            node_count: 6,
+            loss_rate: 0.2,
+            latency: 0..3,
+            max_rounds: 300,
+            seed: Some([1, 2, 3, 4]),
+            strict_checks: true,
+        });
+        let _ = unwrap!(simulation.send(0, &"a"));
+        let _ = unwrap!(simulation.send(3, &"b"));
+        let stats = simulation.run();
+        assert!(stats.converged);
+        assert!(stats.messages_lost > 0);
+        assert!(stats.messages_sent > stats.messages_lost);
+        assert_eq!(stats.rounds, simulation.round());
+    }
+}
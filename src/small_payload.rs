@@ -0,0 +1,232 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use serde::de::{Deserialize, Deserializer, Error as DeserialiseError, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// Number of bytes a `SmallPayload` holds without allocating.
+pub const INLINE_CAPACITY: usize = 32;
+
+/// A payload of bytes held inline when no longer than `INLINE_CAPACITY`, and on the heap
+/// otherwise, to save an allocation per unit in DAGs of tiny payloads, e.g. votes or hashes:
+/// `Dag<SmallPayload>`.
+///
+/// It serialises, compares and hashes exactly like the `Vec<u8>` of its bytes, so units holding
+/// it have the same identifiers and signatures as with `Vec<u8>` payloads.
+#[derive(Clone)]
+pub struct SmallPayload(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, bytes: [u8; INLINE_CAPACITY] },
+    Heap(Vec<u8>),
+}
+
+impl SmallPayload {
+    /// A payload holding a copy of `bytes`.
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        if bytes.len() > INLINE_CAPACITY {
+            return SmallPayload(Repr::Heap(bytes.to_vec()));
+        }
+        let mut inline = [0; INLINE_CAPACITY];
+        inline[..bytes.len()].copy_from_slice(bytes);
+        SmallPayload(Repr::Inline {
+            len: bytes.len() as u8,
+            bytes: inline,
+        })
+    }
+
+    /// The bytes held.
+    pub fn as_slice(&self) -> &[u8] {
+        match self.0 {
+            Repr::Inline { len, ref bytes } => &bytes[..len as usize],
+            Repr::Heap(ref bytes) => bytes,
+        }
+    }
+
+    /// Whether the bytes are held inline, without any allocation.
+    pub fn is_inline(&self) -> bool {
+        match self.0 {
+            Repr::Inline { .. } => true,
+            Repr::Heap(_) => false,
+        }
+    }
+
+    /// The bytes held, as a vector.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.0 {
+            Repr::Inline { len, bytes } => bytes[..len as usize].to_vec(),
+            Repr::Heap(bytes) => bytes,
+        }
+    }
+
+    // Append a byte, moving the bytes to the heap once they don't fit inline anymore.
+    fn push(&mut self, byte: u8) {
+        let spilled = match self.0 {
+            Repr::Inline {
+                ref mut len,
+                ref mut bytes,
+            } => {
+                if (*len as usize) < INLINE_CAPACITY {
+                    bytes[*len as usize] = byte;
+                    *len += 1;
+                    return;
+                }
+                let mut spilled = Vec::with_capacity(2 * INLINE_CAPACITY);
+                spilled.extend_from_slice(&bytes[..]);
+                spilled.push(byte);
+                spilled
+            }
+            Repr::Heap(ref mut bytes) => {
+                bytes.push(byte);
+                return;
+            }
+        };
+        self.0 = Repr::Heap(spilled);
+    }
+}
+
+impl Default for SmallPayload {
+    fn default() -> Self {
+        SmallPayload::from_slice(&[])
+    }
+}
+
+impl Deref for SmallPayload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl Borrow<[u8]> for SmallPayload {
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<'a> From<&'a [u8]> for SmallPayload {
+    fn from(bytes: &'a [u8]) -> Self {
+        SmallPayload::from_slice(bytes)
+    }
+}
+
+impl From<Vec<u8>> for SmallPayload {
+    fn from(bytes: Vec<u8>) -> Self {
+        if bytes.len() > INLINE_CAPACITY {
+            SmallPayload(Repr::Heap(bytes))
+        } else {
+            SmallPayload::from_slice(&bytes)
+        }
+    }
+}
+
+impl PartialEq for SmallPayload {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for SmallPayload {}
+
+impl PartialOrd for SmallPayload {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallPayload {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl Hash for SmallPayload {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl Debug for SmallPayload {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        self.as_slice().fmt(formatter)
+    }
+}
+
+impl Serialize for SmallPayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de> Deserialize<'de> for SmallPayload {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SmallPayloadVisitor)
+    }
+}
+
+// Reads the bytes as they come, only allocating past `INLINE_CAPACITY` of them.
+struct SmallPayloadVisitor;
+
+impl<'de> Visitor<'de> for SmallPayloadVisitor {
+    type Value = SmallPayload;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of bytes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<SmallPayload, A::Error> {
+        let mut payload = SmallPayload::default();
+        while let Some(byte) = seq.next_element()? {
+            payload.push(byte);
+        }
+        Ok(payload)
+    }
+
+    fn visit_bytes<E: DeserialiseError>(self, bytes: &[u8]) -> Result<SmallPayload, E> {
+        Ok(SmallPayload::from_slice(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::serialisation;
+
+    #[test]
+    fn small_payload() {
+        for len in &[0, 1, INLINE_CAPACITY, INLINE_CAPACITY + 1, 3 * INLINE_CAPACITY] {
+            let bytes: Vec<u8> = (0..*len).map(|byte| byte as u8).collect();
+            let payload = SmallPayload::from(bytes.clone());
+            assert_eq!(payload.is_inline(), *len <= INLINE_CAPACITY);
+            assert_eq!(payload.as_slice(), &bytes[..]);
+
+            let serialised = unwrap!(serialisation::serialise(&payload));
+            assert_eq!(serialised, unwrap!(serialisation::serialise(&bytes)));
+            let deserialised: SmallPayload = unwrap!(serialisation::deserialise(&serialised));
+            assert_eq!(deserialised, payload);
+            assert_eq!(deserialised.is_inline(), payload.is_inline());
+        }
+        assert!(SmallPayload::from_slice(&[1]) < SmallPayload::from_slice(&[1, 0]));
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use error::Error;
+use id::{Id, UnitId};
+use maidsafe_utilities::serialisation;
+use quorum::SmallNetworkMode;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Version of the format written by `Dag::save`. Version 2 added the units' content types,
+/// version 3 let units have several parents, version 4 added the units' clocks, version 5 the
+/// identifier of the network the DAG belongs to, version 6 the units' metadata, and version 7 their
+/// certificates.
+pub const DAG_FORMAT_VERSION: u32 = 7;
+/// Version of the format written by `Gossiper::snapshot`.
+pub const GOSSIPER_FORMAT_VERSION: u32 = 1;
+
+// Persisted data, tagged with the version of the format it was written in so that data written by
+// older versions of this crate can still be read.
+#[derive(Serialize, Deserialize)]
+struct Versioned {
+    version: u32,
+    body: Vec<u8>,
+}
+
+/// The state of a `Gossiper` persisted by `Gossiper::snapshot`, as of version 1 of the format.
+#[derive(Serialize, Deserialize)]
+pub struct GossiperState {
+    /// The ID of the snapshotted gossiper.
+    pub owner: Id,
+    /// The DAG, as saved by `Dag::save`.
+    pub dag: Vec<u8>,
+    /// The peers.
+    pub peers: Vec<Id>,
+    /// Whether payloads may be sent without any peers.
+    pub single_node: bool,
+    /// The stability rule for small networks.
+    pub small_network_mode: SmallNetworkMode,
+    /// The gossip round reached.
+    pub round: u64,
+    /// Per peer, the version of each unit the peer is known to hold.
+    pub peer_cursors: BTreeMap<Id, BTreeMap<UnitId, usize>>,
+    /// Identifiers of the units whose payload was delivered in total order.
+    pub delivered: BTreeSet<UnitId>,
+}
+
+/// Serialise `body`, tagged with the `version` of its format.
+pub fn encode_versioned<T: Serialize>(version: u32, body: &T) -> Result<Vec<u8>, Error> {
+    let versioned = Versioned {
+        version,
+        body: serialisation::serialise(body)?,
+    };
+    Ok(serialisation::serialise(&versioned)?)
+}
+
+/// The version of the format and the serialised body of data written by `encode_versioned`.
+pub fn decode_versioned(bytes: &[u8]) -> Result<(u32, Vec<u8>), Error> {
+    let versioned: Versioned = serialisation::deserialise(bytes)?;
+    Ok((versioned.version, versioned.body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::iter;
+
+    #[test]
+    fn versioned_state() {
+        let owner = Id([1; 32]);
+        let unit = UnitId([2; 32]);
+        let state = GossiperState {
+            owner,
+            dag: vec![1, 2, 3],
+            peers: vec![Id([3; 32])],
+            single_node: true,
+            small_network_mode: SmallNetworkMode::AllMembers,
+            round: 7,
+            peer_cursors: iter::once((Id([3; 32]), iter::once((unit, 4)).collect())).collect(),
+            delivered: iter::once(unit).collect(),
+        };
+        let bytes = unwrap!(encode_versioned(GOSSIPER_FORMAT_VERSION, &state));
+
+        let (version, body) = unwrap!(decode_versioned(&bytes));
+        assert_eq!(version, GOSSIPER_FORMAT_VERSION);
+        let decoded: GossiperState = unwrap!(serialisation::deserialise(&body));
+        assert_eq!(decoded.owner, owner);
+        assert_eq!(decoded.dag, state.dag);
+        assert_eq!(decoded.peers, state.peers);
+        assert!(decoded.single_node);
+        assert_eq!(decoded.small_network_mode, SmallNetworkMode::AllMembers);
+        assert_eq!(decoded.round, 7);
+        assert_eq!(decoded.peer_cursors, state.peer_cursors);
+        assert_eq!(decoded.delivered, state.delivered);
+
+        assert!(decode_versioned(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
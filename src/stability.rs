@@ -0,0 +1,145 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use id::Id;
+use quorum;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What makes a unit stable. Evaluated by `Dag::is_stable`, hence consistently by parent selection,
+/// the stable payload and total order APIs, and the graphviz output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StabilityPolicy {
+    /// Observed by at least the majority threshold set with `Dag::set_majority`. A `Gossiper`
+    /// keeps it at a simple majority of its peers, or as per its `SmallNetworkMode` in small
+    /// networks.
+    Majority,
+    /// Observed by more than two thirds of the members, as set with `Dag::set_members`.
+    Supermajority,
+    /// Observed by at least the given number of observers.
+    FixedCount(usize),
+    /// Observed by observers whose weights add up to at least `threshold`. Observers without a
+    /// weight count for nothing.
+    Weighted {
+        /// The weight of each observer.
+        weights: BTreeMap<Id, u64>,
+        /// The total weight required.
+        threshold: u64,
+    },
+}
+
+impl Default for StabilityPolicy {
+    fn default() -> Self {
+        StabilityPolicy::Majority
+    }
+}
+
+impl StabilityPolicy {
+    /// A weighted policy requiring more than half of the total weight of `weights`.
+    pub fn weighted_majority(weights: BTreeMap<Id, u64>) -> Self {
+        let total = weights.values().fold(0u64, |total, weight| {
+            total.saturating_add(*weight)
+        });
+        StabilityPolicy::Weighted {
+            weights,
+            threshold: total / 2 + 1,
+        }
+    }
+
+    /// Whether `observers` make a unit stable, given the majority threshold and the number of
+    /// members in use.
+    pub fn is_met(&self, observers: &BTreeSet<Id>, majority: usize, members: usize) -> bool {
+        self.weight_of(observers) >= self.threshold(majority, members)
+    }
+
+    /// The number of observers, or their total weight for a weighted policy, a unit requires to
+    /// become stable.
+    pub fn threshold(&self, majority: usize, members: usize) -> u64 {
+        match *self {
+            StabilityPolicy::Majority => majority as u64,
+            StabilityPolicy::Supermajority => quorum::supermajority(members) as u64,
+            StabilityPolicy::FixedCount(count) => count as u64,
+            StabilityPolicy::Weighted { threshold, .. } => threshold,
+        }
+    }
+
+    /// Describes how `threshold(majority, members)` is computed.
+    pub fn rule(&self, majority: usize, members: usize) -> String {
+        match *self {
+            StabilityPolicy::Majority => format!("majority threshold of {}", majority),
+            StabilityPolicy::Supermajority => quorum::supermajority_rule(members),
+            StabilityPolicy::FixedCount(count) => format!("fixed count of {}", count),
+            StabilityPolicy::Weighted {
+                ref weights,
+                threshold,
+            } => {
+                let total = weights.values().fold(0u64, |total, weight| {
+                    total.saturating_add(*weight)
+                });
+                format!("weight of {} out of {}", threshold, total)
+            }
+        }
+    }
+
+    fn weight_of(&self, observers: &BTreeSet<Id>) -> u64 {
+        match *self {
+            StabilityPolicy::Weighted { ref weights, .. } => {
+                observers
+                    .iter()
+                    .filter_map(|observer| weights.get(observer))
+                    .fold(0u64, |total, weight| total.saturating_add(*weight))
+            }
+            _ => observers.len() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies() {
+        let ids: Vec<Id> = (0..4).map(|index| Id([index; 32])).collect();
+        let mut observers: BTreeSet<Id> = ids[..3].iter().cloned().collect();
+
+        assert!(StabilityPolicy::Majority.is_met(&observers, 3, 7));
+        assert!(!StabilityPolicy::Majority.is_met(&observers, 4, 7));
+        // Three is more than two thirds of four members, but not of seven.
+        assert!(StabilityPolicy::Supermajority.is_met(&observers, 0, 4));
+        assert!(!StabilityPolicy::Supermajority.is_met(&observers, 0, 7));
+        assert!(StabilityPolicy::FixedCount(3).is_met(&observers, 0, 0));
+        assert!(!StabilityPolicy::FixedCount(4).is_met(&observers, 0, 0));
+
+        let weighted = StabilityPolicy::Weighted {
+            weights: ids.iter().cloned().zip(vec![1, 1, 1, 10]).collect(),
+            threshold: 10,
+        };
+        assert!(!weighted.is_met(&observers, 0, 0));
+        let _ = observers.remove(&ids[0]);
+        let _ = observers.insert(ids[3]);
+        assert!(weighted.is_met(&observers, 0, 0));
+
+        // Observers weighing 12 out of 13 are a majority, those weighing 3 aren't.
+        let majority = StabilityPolicy::weighted_majority(
+            ids.iter().cloned().zip(vec![1, 1, 1, 10]).collect(),
+        );
+        assert_eq!(majority.threshold(0, 0), 7);
+        assert!(majority.is_met(&observers, 0, 0));
+        let light: BTreeSet<Id> = ids[..3].iter().cloned().collect();
+        assert!(!majority.is_met(&light, 0, 0));
+    }
+}
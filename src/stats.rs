@@ -0,0 +1,190 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Number of units, and the total size of their payloads, falling into one bucket.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bucket {
+    /// Number of units in the bucket.
+    pub units: usize,
+    /// Sum of the payload sizes of these units, in bytes.
+    pub payload_bytes: usize,
+}
+
+impl Bucket {
+    fn add(&mut self, payload_len: usize) {
+        self.units += 1;
+        self.payload_bytes += payload_len;
+    }
+}
+
+/// Breakdown of the units held in a DAG by age and by payload size.
+///
+/// The age of a unit is given as its depth, i.e. the number of hops along its path back to the
+/// genesis, which unlike its clock is the same for all nodes. The shallower a unit, the older it
+/// is.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnitBreakdown {
+    /// Units keyed by their depth. The genesis has depth 0.
+    pub by_depth: BTreeMap<usize, Bucket>,
+    /// Units keyed by the smallest power of two not less than their payload size.
+    pub by_payload_size: BTreeMap<usize, Bucket>,
+}
+
+impl UnitBreakdown {
+    /// Record a unit of the given depth and payload length.
+    pub fn add_unit(&mut self, depth: usize, payload_len: usize) {
+        self.by_depth.entry(depth).or_insert_with(Bucket::default).add(payload_len);
+        self.by_payload_size
+            .entry(payload_len.next_power_of_two())
+            .or_insert_with(Bucket::default)
+            .add(payload_len);
+    }
+
+    /// The units and payload bytes which would be reclaimed by collapsing every unit up to and
+    /// including `depth` into a checkpoint.
+    pub fn reclaimable_up_to(&self, depth: usize) -> Bucket {
+        let mut total = Bucket::default();
+        for bucket in self.by_depth.range(..depth + 1).map(|(_, bucket)| bucket) {
+            total.units += bucket.units;
+            total.payload_bytes += bucket.payload_bytes;
+        }
+        total
+    }
+}
+
+/// Counters of a gossiper's activity since it was created or its statistics were last reset, to
+/// tune e.g. the majority thresholds or the number of rounds empirically.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// Number of gossip rounds started.
+    pub rounds: u64,
+    /// Bytes of the messages prepared to be sent, requests and responses included.
+    pub bytes_sent: u64,
+    /// Bytes of the messages received, malformed ones included.
+    pub bytes_received: u64,
+    /// Units created by us, i.e. new payloads sent.
+    pub units_created: u64,
+    /// Units inserted from DAGs received from peers.
+    pub units_received: u64,
+    /// Units received from peers which we already held.
+    pub duplicate_units: u64,
+    /// Units received from peers and rejected, as reported to them in NACKs.
+    pub rejected_units: u64,
+    /// Rounds in which only a digest was sent, the peer being known to hold the same units.
+    pub in_sync_rounds: u64,
+    /// Units left out of round pushes as cold rumors, as per `Gossiper::set_cold_suppression`.
+    pub cold_units_suppressed: u64,
+    /// The times from when we first held units to their stability, aggregated over the units
+    /// which became stable. Empty with the `wasm` feature, for lack of a clock.
+    pub time_to_stability: StabilityTimes,
+}
+
+impl Statistics {
+    /// The mean time to stability of the units which became stable, if any did.
+    pub fn mean_time_to_stability(&self) -> Option<Duration> {
+        self.time_to_stability.mean()
+    }
+}
+
+/// Running aggregate of the times units took to become stable, kept in constant space however
+/// many units became stable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StabilityTimes {
+    /// Number of units which became stable.
+    pub count: u64,
+    /// Sum of their times to stability, saturating at the largest `Duration`.
+    pub total: Duration,
+    /// The longest time to stability.
+    pub max: Duration,
+}
+
+impl StabilityTimes {
+    /// Record a unit which took `duration` to become stable.
+    pub fn add(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total = self.total.checked_add(duration).unwrap_or_else(|| {
+            Duration::new(u64::max_value(), 999_999_999)
+        });
+        if duration > self.max {
+            self.max = duration;
+        }
+    }
+
+    /// The mean time to stability, if any unit became stable.
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let secs = self.total.as_secs();
+        // Less than a second's worth of nanoseconds per unit, so the division fits.
+        let remainder = (secs % self.count) * 1_000_000_000 + u64::from(self.total.subsec_nanos());
+        Some(Duration::new(
+            secs / self.count,
+            (remainder / self.count) as u32,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_breakdown() {
+        let mut breakdown = UnitBreakdown::default();
+        breakdown.add_unit(0, 0);
+        breakdown.add_unit(1, 3);
+        breakdown.add_unit(1, 4);
+        breakdown.add_unit(2, 100);
+
+        let bucket = |units, payload_bytes| Bucket { units, payload_bytes };
+        assert_eq!(breakdown.by_depth[&1], bucket(2, 7));
+        assert_eq!(breakdown.by_payload_size[&4], bucket(2, 7));
+        assert_eq!(breakdown.by_payload_size[&128], bucket(1, 100));
+        assert_eq!(breakdown.reclaimable_up_to(0), bucket(1, 0));
+        assert_eq!(breakdown.reclaimable_up_to(1), bucket(3, 7));
+        assert_eq!(breakdown.reclaimable_up_to(5), bucket(4, 107));
+    }
+
+    #[test]
+    fn stability_times() {
+        let mut times = StabilityTimes::default();
+        assert!(times.mean().is_none());
+        times.add(Duration::from_millis(1500));
+        times.add(Duration::from_millis(500));
+        times.add(Duration::from_millis(2000));
+        assert_eq!(times.count, 3);
+        assert_eq!(times.total, Duration::from_secs(4));
+        assert_eq!(times.max, Duration::from_secs(2));
+        assert_eq!(times.mean(), Some(Duration::new(1, 333_333_333)));
+
+        let statistics = Statistics {
+            time_to_stability: times,
+            ..Statistics::default()
+        };
+        assert_eq!(statistics.mean_time_to_stability(), times.mean());
+
+        // The total saturates rather than overflows.
+        times.add(Duration::new(u64::max_value(), 0));
+        assert_eq!(times.count, 4);
+        assert_eq!(times.max, Duration::new(u64::max_value(), 0));
+        assert!(times.mean().is_some());
+    }
+}
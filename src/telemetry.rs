@@ -0,0 +1,114 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::time::{Duration, Instant};
+
+/// An operation whose latency is watched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Union of a received DAG into ours.
+    Union,
+    /// Serialisation of an outgoing message.
+    Serialisation,
+    /// Insertion of a new payload on top of all the tips.
+    NewPayload,
+}
+
+/// Record of an operation which took longer than the latency budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlowOperation {
+    /// The operation.
+    pub operation: Operation,
+    /// How long it took.
+    pub duration: Duration,
+    /// The budget it exceeded.
+    pub budget: Duration,
+    /// Number of units in our DAG when it completed.
+    pub unit_count: usize,
+}
+
+/// Watches operations against a latency budget.
+#[derive(Default)]
+pub struct Telemetry {
+    budget: Option<Duration>,
+    slow_operations: Vec<SlowOperation>,
+}
+
+impl Telemetry {
+    /// Set the latency budget. `None` disables the watch.
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget = budget;
+    }
+
+    /// Record the operation started at `start` if it exceeded the budget, emitting a warning.
+    /// Skipped if the start is unknown, for lack of a clock.
+    pub fn check(&mut self, operation: Operation, start: Option<Instant>, unit_count: usize) {
+        let (budget, start) = match (self.budget, start) {
+            (Some(budget), Some(start)) => (budget, start),
+            _ => return,
+        };
+        let duration = start.elapsed();
+        if duration <= budget {
+            return;
+        }
+        warn!(
+            "{:?} took {:?}, over the budget of {:?}, with {} units",
+            operation,
+            duration,
+            budget,
+            unit_count
+        );
+        self.slow_operations.push(SlowOperation {
+            operation,
+            duration,
+            budget,
+            unit_count,
+        });
+    }
+
+    /// Take the slow operations recorded since the last call.
+    pub fn take_slow_operations(&mut self) -> Vec<SlowOperation> {
+        ::std::mem::replace(&mut self.slow_operations, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn latency_budget() {
+        let mut telemetry = Telemetry::default();
+        let start = Instant::now();
+        thread::sleep(Duration::from_millis(2));
+        telemetry.check(Operation::Union, Some(start), 3);
+        assert!(telemetry.take_slow_operations().is_empty());
+
+        telemetry.set_budget(Some(Duration::from_millis(1)));
+        telemetry.check(Operation::Union, Some(start), 3);
+        telemetry.check(Operation::Serialisation, None, 3);
+        telemetry.check(Operation::NewPayload, Some(Instant::now()), 4);
+        let slow = telemetry.take_slow_operations();
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow[0].operation, Operation::Union);
+        assert_eq!(slow[0].budget, Duration::from_millis(1));
+        assert!(slow[0].duration >= Duration::from_millis(2));
+        assert_eq!(slow[0].unit_count, 3);
+        assert!(telemetry.take_slow_operations().is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+use dag::Dag;
+use ed25519_dalek::Keypair;
+use hasher::Hasher;
+use id::{Id, UnitId};
+use quorum::{self, SmallNetworkMode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+// The DAGs of the topics a `Gossiper` subscribed to, independent of its main DAG and of each
+// other, along with the topics each peer announced it subscribed to.
+#[derive(Default)]
+pub struct Topics {
+    dags: BTreeMap<String, Dag>,
+    peer_topics: BTreeMap<Id, BTreeSet<String>>,
+}
+
+impl Topics {
+    // Subscribe to `topic`, creating its DAG as part of the network of `network`, with the same
+    // hasher as the main DAG. Returns whether we weren't subscribed yet.
+    pub fn subscribe(
+        &mut self,
+        topic: &str,
+        network: &UnitId,
+        keys: &Keypair,
+        hasher: Arc<Hasher>,
+    ) -> bool {
+        if self.dags.contains_key(topic) {
+            return false;
+        }
+        let dag = Dag::new_with_hasher(&network_id(network, topic), keys, hasher);
+        let _ = self.dags.insert(topic.to_string(), dag);
+        true
+    }
+
+    // Unsubscribe from `topic`, dropping its DAG. Returns whether we were subscribed.
+    pub fn unsubscribe(&mut self, topic: &str) -> bool {
+        self.dags.remove(topic).is_some()
+    }
+
+    pub fn subscriptions(&self) -> BTreeSet<String> {
+        self.dags.keys().cloned().collect()
+    }
+
+    pub fn dag(&self, topic: &str) -> Option<&Dag> {
+        self.dags.get(topic)
+    }
+
+    pub fn dag_mut(&mut self, topic: &str) -> Option<&mut Dag> {
+        self.dags.get_mut(topic)
+    }
+
+    // Recompute the stability threshold of each topic's DAG from the number of peers which
+    // subscribed to it.
+    pub fn update_majorities(&mut self, mode: SmallNetworkMode) {
+        for (topic, dag) in &mut self.dags {
+            let subscribers = self.peer_topics
+                .values()
+                .filter(|topics| topics.contains(topic))
+                .count();
+            dag.set_majority(quorum::stability_threshold(subscribers, mode) as u8);
+            dag.set_members(subscribers + 1);
+        }
+    }
+
+    // Record the topics `peer` announced, replacing those it announced before.
+    pub fn set_peer_topics(&mut self, peer: Id, topics: BTreeSet<String>) {
+        let _ = self.peer_topics.insert(peer, topics);
+    }
+
+    pub fn forget_peer(&mut self, peer: &Id) {
+        let _ = self.peer_topics.remove(peer);
+    }
+
+    // The peers which announced they subscribed to `topic`.
+    pub fn subscribers(&self, topic: &str) -> Vec<Id> {
+        self.peer_topics
+            .iter()
+            .filter(|&(_, topics)| topics.contains(topic))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+}
+
+// The identifier of the network of the topic's DAG: that of the main DAG's network, followed by
+// the topic, so that DAGs of different topics, or of the same topic in different networks, are
+// never merged.
+fn network_id(network: &UnitId, topic: &str) -> Vec<u8> {
+    let mut network_id = network.0.to_vec();
+    network_id.extend_from_slice(topic.as_bytes());
+    network_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hasher::Sha3Hasher;
+    use maidsafe_utilities::SeededRng;
+    use sha3::Sha3_512;
+    use std::iter;
+
+    #[test]
+    fn topics() {
+        let keys = Keypair::generate::<Sha3_512>(&mut SeededRng::new());
+        let network = UnitId([1; 32]);
+        let mut topics = Topics::default();
+        assert!(topics.subscribe("news", &network, &keys, Arc::new(Sha3Hasher)));
+        assert!(!topics.subscribe("news", &network, &keys, Arc::new(Sha3Hasher)));
+        assert!(topics.subscribe("sport", &network, &keys, Arc::new(Sha3Hasher)));
+        let expected: BTreeSet<String> = vec!["news".to_string(), "sport".to_string()]
+            .into_iter()
+            .collect();
+        assert_eq!(topics.subscriptions(), expected);
+        // Each topic's DAG belongs to a network of its own.
+        assert_ne!(
+            unwrap!(topics.dag("news")).genesis().identifier,
+            unwrap!(topics.dag("sport")).genesis().identifier
+        );
+
+        let (first, second) = (Id([1; 32]), Id([2; 32]));
+        topics.set_peer_topics(first, iter::once("news".to_string()).collect());
+        topics.set_peer_topics(second, expected.clone());
+        assert_eq!(topics.subscribers("news"), vec![first, second]);
+        assert_eq!(topics.subscribers("sport"), vec![second]);
+        assert!(topics.subscribers("weather").is_empty());
+
+        // Stability only depends on the subscribers of the topic.
+        topics.update_majorities(SmallNetworkMode::SelfAttestation);
+        for (topic, stable) in vec![("news", false), ("sport", true)] {
+            let dag = unwrap!(topics.dag_mut(topic));
+            let identifier = unwrap!(dag.new_payload(b"Hello".to_vec(), &keys));
+            assert_eq!(dag.is_stable(unwrap!(dag.get(&identifier))), stable);
+        }
+
+        topics.forget_peer(&second);
+        assert!(topics.subscribers("sport").is_empty());
+        assert!(topics.unsubscribe("sport"));
+        assert!(!topics.unsubscribe("sport"));
+        assert!(topics.dag("sport").is_none());
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use error::Error;
+use id::Id;
+use maidsafe_utilities::serialisation;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+// Frames larger than this are rejected, to bound the memory a peer can make us allocate.
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// A way of exchanging messages with peers addressed by their `Id`.
+pub trait Transport {
+    /// Send `message` to the peer.
+    fn send(&mut self, peer_id: &Id, message: &[u8]) -> Result<(), Error>;
+
+    /// A message received from a peer, along with the peer's `Id`, if any is waiting. Never
+    /// blocks.
+    fn receive(&mut self) -> Option<(Id, Vec<u8>)>;
+}
+
+/// A `Transport` over TCP. Each message is sent as a length-prefixed frame carrying the sender's
+/// `Id`. The `Id` isn't authenticated by the transport: only the signatures within the DAG are.
+pub struct TcpTransport {
+    own_id: Id,
+    local_addr: SocketAddr,
+    addresses: BTreeMap<Id, SocketAddr>,
+    // Outgoing connections, opened on first send and dropped on failure.
+    connections: BTreeMap<Id, TcpStream>,
+    incoming: Receiver<(Id, Vec<u8>)>,
+}
+
+impl TcpTransport {
+    /// Listen on `address` for messages to the node `own_id`. Incoming connections are read on
+    /// background threads.
+    pub fn bind<A: ToSocketAddrs>(own_id: Id, address: A) -> Result<Self, Error> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let (sender, incoming) = mpsc::channel();
+        let _ = thread::spawn(move || accept(&listener, &sender));
+        Ok(TcpTransport {
+            own_id,
+            local_addr,
+            addresses: BTreeMap::new(),
+            connections: BTreeMap::new(),
+            incoming,
+        })
+    }
+
+    /// The address we are listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Record the address the peer listens on.
+    pub fn add_peer(&mut self, peer_id: Id, address: SocketAddr) {
+        let _ = self.addresses.insert(peer_id, address);
+        let _ = self.connections.remove(&peer_id);
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, peer_id: &Id, message: &[u8]) -> Result<(), Error> {
+        if !self.connections.contains_key(peer_id) {
+            let address = match self.addresses.get(peer_id) {
+                Some(address) => *address,
+                None => return Err(Error::UnknownPeer(*peer_id)),
+            };
+            let _ = self.connections.insert(*peer_id, TcpStream::connect(address)?);
+        }
+        let frame = serialisation::serialise(&(self.own_id, message))?;
+        let result = match self.connections.get_mut(peer_id) {
+            Some(stream) => write_frame(stream, &frame),
+            None => return Err(Error::UnknownPeer(*peer_id)),
+        };
+        if result.is_err() {
+            let _ = self.connections.remove(peer_id);
+        }
+        Ok(result?)
+    }
+
+    fn receive(&mut self) -> Option<(Id, Vec<u8>)> {
+        self.incoming.try_recv().ok()
+    }
+}
+
+// Accept connections until the listener fails, reading each on its own thread.
+fn accept(listener: &TcpListener, sender: &Sender<(Id, Vec<u8>)>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let sender = sender.clone();
+                let _ = thread::spawn(move || read_frames(stream, &sender));
+            }
+            Err(error) => {
+                error!("Failed to accept connection: {}", error);
+                return;
+            }
+        }
+    }
+}
+
+// Pass the frames read from the stream on, until either the stream or the receiver goes away.
+fn read_frames(mut stream: TcpStream, sender: &Sender<(Id, Vec<u8>)>) {
+    loop {
+        let frame = match read_frame(&mut stream) {
+            Ok(frame) => frame,
+            Err(error) => {
+                debug!("Closing incoming connection: {}", error);
+                return;
+            }
+        };
+        let message = match serialisation::deserialise(&frame) {
+            Ok(message) => message,
+            Err(error) => {
+                error!("Failed to deserialise frame: {}", error);
+                return;
+            }
+        };
+        if sender.send(message).is_err() {
+            return;
+        }
+    }
+}
+
+fn write_frame(stream: &mut TcpStream, frame: &[u8]) -> io::Result<()> {
+    let len = frame.len() as u32;
+    let prefix = [(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+    stream.write_all(&prefix)?;
+    stream.write_all(frame)?;
+    stream.flush()
+}
+
+fn read_frame(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut prefix = [0u8; 4];
+    stream.read_exact(&mut prefix)?;
+    let len = prefix.iter().fold(0usize, |len, byte| (len << 8) | *byte as usize);
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut frame = vec![0; len];
+    stream.read_exact(&mut frame)?;
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn tcp_round_trip() {
+        let lhs_id = Id([1; 32]);
+        let rhs_id = Id([2; 32]);
+        let mut lhs = unwrap!(TcpTransport::bind(lhs_id, "127.0.0.1:0"));
+        let mut rhs = unwrap!(TcpTransport::bind(rhs_id, "127.0.0.1:0"));
+        lhs.add_peer(rhs_id, rhs.local_addr());
+
+        unwrap!(lhs.send(&rhs_id, &[1, 2, 3]));
+        let mut received = None;
+        for _ in 0..100 {
+            received = rhs.receive();
+            if received.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(received, Some((lhs_id, vec![1, 2, 3])));
+        assert!(rhs.send(&lhs_id, &[4]).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Sweeps of the gossip parameters over simulated networks, to help picking those of a deployment.
+//! Every combination of the parameters swept is run on a network of the given profile, and the
+//! combinations neither sending more bytes nor converging later than any other form the Pareto
+//! frontier of bandwidth against convergence latency.
+//!
+//! ```ignore
+//! let results = tuning::sweep(&TuningProfile::default(), &ParameterSweep::default());
+//! println!("{}", tuning::report(&tuning::pareto_frontier(&results)));
+//! ```
+
+use capabilities::Capabilities;
+use simulation::{Simulation, SimulationConfig};
+use std::cmp;
+
+/// The network the parameters are tuned for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningProfile {
+    /// Number of nodes, all connected to each other.
+    pub node_count: u32,
+    /// Probability for each message to be lost.
+    pub loss_rate: f64,
+    /// The time a message takes to be delivered, in milliseconds.
+    pub latency_ms: u64,
+    /// Number of payloads sent at the start of each run, by as many distinct nodes as possible.
+    pub payloads: usize,
+    /// The rounds after which a run is given up if the network didn't converge.
+    pub max_rounds: usize,
+    /// The seed of every run, so that all combinations face the same losses.
+    pub seed: [u32; 4],
+}
+
+impl Default for TuningProfile {
+    fn default() -> Self {
+        TuningProfile {
+            node_count: 16,
+            loss_rate: 0.05,
+            latency_ms: 50,
+            payloads: 4,
+            max_rounds: 500,
+            seed: [1, 2, 3, 4],
+        }
+    }
+}
+
+/// The values swept for each parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterSweep {
+    /// Numbers of peers gossiped to per round, as per `Gossiper::set_fanout`.
+    pub fanouts: Vec<usize>,
+    /// Intervals between two rounds, in milliseconds.
+    pub round_intervals_ms: Vec<u64>,
+    /// Whether rounds between peers holding the same units are reduced to the digest of their
+    /// DAG, as per `Capabilities::digest`.
+    pub digests: Vec<bool>,
+}
+
+impl Default for ParameterSweep {
+    fn default() -> Self {
+        ParameterSweep {
+            fanouts: vec![1, 2, 3, 4],
+            round_intervals_ms: vec![25, 50, 100, 200],
+            digests: vec![false, true],
+        }
+    }
+}
+
+/// A combination of the parameters swept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parameters {
+    /// Number of peers gossiped to per round.
+    pub fanout: usize,
+    /// Interval between two rounds, in milliseconds.
+    pub round_interval_ms: u64,
+    /// Whether digests replace the rounds between peers in sync.
+    pub digest: bool,
+}
+
+/// The outcome of a run with a combination of the parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TuningResult {
+    /// The parameters of the run.
+    pub parameters: Parameters,
+    /// Whether the network converged within the maximum number of rounds.
+    pub converged: bool,
+    /// Number of rounds run.
+    pub rounds: usize,
+    /// Time the network took to converge, or gave up after, in milliseconds.
+    pub latency_ms: u64,
+    /// Total size of the messages sent, in bytes.
+    pub bytes_sent: u64,
+}
+
+impl TuningResult {
+    // Whether this result is at least as good as `other` on both axes, and better on one.
+    fn dominates(&self, other: &TuningResult) -> bool {
+        self.bytes_sent <= other.bytes_sent && self.latency_ms <= other.latency_ms &&
+            (self.bytes_sent < other.bytes_sent || self.latency_ms < other.latency_ms)
+    }
+}
+
+/// Run every combination of the swept parameters on a network of `profile`, in the order of the
+/// sweep.
+pub fn sweep(profile: &TuningProfile, sweep: &ParameterSweep) -> Vec<TuningResult> {
+    let mut results = Vec::new();
+    for &fanout in &sweep.fanouts {
+        for &round_interval_ms in &sweep.round_intervals_ms {
+            for &digest in &sweep.digests {
+                let parameters = Parameters {
+                    fanout,
+                    round_interval_ms,
+                    digest,
+                };
+                results.push(run(profile, parameters));
+            }
+        }
+    }
+    results
+}
+
+/// The results of converged runs not dominated by any other, i.e. such that no other run sent at
+/// most as many bytes and converged at least as soon, being better on either. Sorted by
+/// increasing latency, hence decreasing bandwidth.
+pub fn pareto_frontier(results: &[TuningResult]) -> Vec<TuningResult> {
+    let converged: Vec<&TuningResult> = results.iter().filter(|result| result.converged).collect();
+    let mut frontier: Vec<TuningResult> = converged
+        .iter()
+        .filter(|result| !converged.iter().any(|other| other.dominates(result)))
+        .map(|result| **result)
+        .collect();
+    frontier.sort_by_key(|result| (result.latency_ms, result.bytes_sent));
+    frontier.dedup();
+    frontier
+}
+
+/// The results as a plain text table, one line per result.
+pub fn report(results: &[TuningResult]) -> String {
+    let mut report = String::from("fanout  interval (ms)  digest  latency (ms)  bytes sent\n");
+    for result in results {
+        let latency = if result.converged {
+            result.latency_ms.to_string()
+        } else {
+            format!(">{}", result.latency_ms)
+        };
+        report += &format!(
+            "{:>6}  {:>13}  {:>6}  {:>12}  {:>10}\n",
+            result.parameters.fanout,
+            result.parameters.round_interval_ms,
+            if result.parameters.digest { "on" } else { "off" },
+            latency,
+            result.bytes_sent
+        );
+    }
+    report
+}
+
+// Run a network of `profile` with `parameters` until it converges or the run is given up.
+fn run(profile: &TuningProfile, parameters: Parameters) -> TuningResult {
+    let interval = cmp::max(parameters.round_interval_ms, 1);
+    // Messages slower than a round are delivered that many rounds later.
+    let delay = (profile.latency_ms / interval) as usize;
+    let mut simulation = Simulation::new(SimulationConfig {
+        node_count: profile.node_count,
+        loss_rate: profile.loss_rate,
+        latency: delay..delay + 1,
+        max_rounds: profile.max_rounds,
+        seed: Some(profile.seed),
+        strict_checks: false,
+    });
+    let capabilities = Capabilities {
+        digest: parameters.digest,
+        ..Capabilities::all()
+    };
+    for node in 0..profile.node_count as usize {
+        if let Some(gossiper) = simulation.gossiper_mut(node) {
+            gossiper.set_fanout(parameters.fanout);
+            gossiper.set_capabilities(capabilities.clone());
+        }
+    }
+    let nodes = profile.node_count as usize;
+    for index in 0..profile.payloads {
+        if nodes > 0 {
+            let _ = simulation.send(index % nodes, &index);
+        }
+    }
+    let stats = simulation.run();
+    TuningResult {
+        parameters,
+        converged: stats.converged,
+        rounds: stats.rounds,
+        latency_ms: stats.rounds as u64 * interval,
+        bytes_sent: stats.bytes_sent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(latency_ms: u64, bytes_sent: u64, converged: bool) -> TuningResult {
+        TuningResult {
+            parameters: Parameters {
+                fanout: 1,
+                round_interval_ms: latency_ms,
+                digest: true,
+            },
+            converged,
+            rounds: 1,
+            latency_ms,
+            bytes_sent,
+        }
+    }
+
+    #[test]
+    fn frontier() {
+        let results = vec![
+            result(100, 1_000, true),
+            result(50, 4_000, true),
+            result(100, 2_000, true),
+            result(200, 800, true),
+            result(10, 100, false),
+        ];
+        assert_eq!(
+            pareto_frontier(&results),
+            vec![results[1], results[0], results[3]]
+        );
+    }
+
+    #[test]
+    fn sweep_small_network() {
+        let profile = TuningProfile {
+            node_count: 4,
+            payloads: 2,
+            max_rounds: 200,
+            ..TuningProfile::default()
+        };
+        let parameters = ParameterSweep {
+            fanouts: vec![1, 3],
+            round_intervals_ms: vec![50],
+            digests: vec![true],
+        };
+        let results = sweep(&profile, &parameters);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.converged));
+        assert!(!pareto_frontier(&results).is_empty());
+        assert_eq!(report(&results).lines().count(), 3);
+    }
+}
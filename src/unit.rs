@@ -15,107 +15,460 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use id::Id;
+use ed25519_dalek::Keypair;
+use error::Error;
+use hasher::{Hasher, Sha3Hasher};
+use id::{Id, UnitId};
 use maidsafe_utilities::serialisation;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha3::Sha3_512;
 
-use std::collections::BTreeSet;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
-use tiny_keccak::sha3_256;
+
+// The payload the genesis identifier is computed over as its parent's, as it has no parent.
+const GENESIS_PARENT_PAYLOAD: [u8; 3] = [0, 0, 0];
+
+/// A payload carried by the units of a `Dag`. Its serialisation is the canonical encoding which
+/// identifiers and signatures are computed over, and its default value is held by the genesis.
+pub trait Payload: Clone + Debug + Default + Ord + Serialize + DeserializeOwned {}
+
+impl<T> Payload for T
+where
+    T: Clone + Debug + Default + Ord + Serialize + DeserializeOwned,
+{
+}
+
+/// A hint of how the payload of a unit is encoded, for applications to route it to the right
+/// decoder. It is covered neither by the identifier nor by the signatures, so it can't be relied
+/// upon: a decoder shall still handle payloads it fails to decode.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ContentType {
+    /// JSON text.
+    Json,
+    /// CBOR data.
+    Cbor,
+    /// Plain text.
+    Text,
+    /// Opaque binary data.
+    Binary,
+    /// Any other encoding, e.g. a MIME type.
+    Other(String),
+}
+
+/// Evidence that a quorum of observers signed a unit's payload, attached to the unit by the first
+/// node which noticed it became stable, if certifying. A node joining later can then tell the
+/// unit is stable from its certificate alone, without counting its observers.
+///
+/// The signatures are those the observers made over the payload when observing the unit, bound to
+/// the unit by its identifier. Like the metadata, the certificate is covered neither by the
+/// identifier nor by the signatures, so a certificate is only taken over from a peer once all its
+/// signatures are checked.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumCertificate {
+    /// Identifier of the unit certified.
+    pub identifier: UnitId,
+    /// The signatures of the observers over the unit's payload.
+    pub signatures: BTreeMap<Id, Vec<u8>>,
+}
+
+impl QuorumCertificate {
+    /// The observers having signed.
+    pub fn signers(&self) -> BTreeSet<Id> {
+        self.signatures.keys().cloned().collect()
+    }
+
+    /// Whether this certifies `unit`: it names the unit, and carries at least one signature, all
+    /// of them valid over its payload.
+    pub fn verify<T: Payload>(&self, unit: &Unit<T>) -> bool {
+        let encoded = match unit.encoded_payload() {
+            Ok(encoded) => encoded,
+            Err(_) => return false,
+        };
+        self.identifier == unit.identifier && !self.signatures.is_empty() &&
+            self.signatures.iter().all(|(observer, signature)| {
+                observer.verify(&encoded, signature)
+            })
+    }
+}
 
 /// A unit in the DAG.
 #[derive(Clone, Serialize, Deserialize)]
-pub struct Unit {
+pub struct Unit<T = Vec<u8>> {
     /// Identifier of this unit in the DAG.
-    pub identifier: Vec<u8>,
-    /// The identifier of the parent unit that pointing to.
-    pub parent: Vec<u8>,
+    pub identifier: UnitId,
+    /// The identifiers of the parent units pointing to, i.e. the tips of the DAG the unit was
+    /// created on top of. Empty for the genesis.
+    pub parents: BTreeSet<UnitId>,
     /// The event observed or notified.
-    pub payload: Vec<u8>,
+    pub payload: T,
     /// The peers witnessed the same unit.
     pub observers: BTreeSet<Id>,
-    /// The clidren field is only for the quick check of childless state.
-    pub children: BTreeSet<Vec<u8>>,
+    /// The observers' signatures over the payload. The payload is signed rather than the
+    /// identifier, as observations get folded into any unit along the path holding that payload.
+    pub signatures: BTreeMap<Id, Vec<u8>>,
+    /// How the payload is encoded, if its creator said.
+    pub content_type: Option<ContentType>,
+    /// Lamport clock of the unit, greater than the clocks of its parents. Set by its creator from
+    /// its own clock, and covered neither by the identifier nor by the signatures: should the
+    /// same unit be created by several nodes, the smallest clock is kept. 0 for the original
+    /// genesis.
+    pub clock: u64,
+    /// Application metadata, e.g. a topic, a priority or an origin timestamp, keyed by name. Like
+    /// the content type, it is covered neither by the identifier nor by the signatures, so it
+    /// can't be relied upon. Merged key by key as per `merge_metadata`.
+    pub metadata: BTreeMap<String, Vec<u8>>,
+    /// The evidence the unit became stable, if certified.
+    pub certificate: Option<QuorumCertificate>,
 }
 
-impl Unit {
-    /// Generate a genesis unit. The parent and payload is hard coded.
+impl<T: Payload> Unit<T> {
+    /// Generate a genesis unit. The parent is hard coded, and the payload is the default one.
+    /// Should the default payload fail to serialise, the identifier is computed over the parent
+    /// alone: such a payload type is unusable anyway, as every operation encoding a payload fails.
     pub fn new_genesis(observers: BTreeSet<Id>) -> Self {
-        if let Ok(serialised) = serialisation::serialise(&(vec![0, 0, 0], vec![0, 0, 0])) {
-            Unit {
-                identifier: sha3_256(&serialised).to_vec(),
-                parent: vec![0, 0, 0],
-                payload: vec![0, 0, 0],
-                observers,
-                children: BTreeSet::new(),
-            }
-        } else {
-            panic!("cannot generate genesis identifier");
-        }
+        Unit::new_network_genesis(&GENESIS_PARENT_PAYLOAD, observers)
     }
 
-    /// Create a new unit based on the input infos.
-    pub fn new(parent: Self, payload: Vec<u8>, observers: BTreeSet<Id>) -> Self {
-        let identifier =
-            if let Ok(serialised) = serialisation::serialise(&(parent.payload, payload.clone())) {
-                sha3_256(&serialised)
-            } else {
-                panic!("cannot generate identifier for a unit");
-            };
+    /// Generate the genesis unit of the network identified by `network_id`, its identifier being
+    /// computed with `network_id` in place of the hard-coded parent.
+    pub fn new_network_genesis(network_id: &[u8], observers: BTreeSet<Id>) -> Self {
+        Unit::new_network_genesis_with(&Sha3Hasher, network_id, observers)
+    }
+
+    /// As `new_network_genesis`, the identifier being computed with `hasher`.
+    pub fn new_network_genesis_with(
+        hasher: &Hasher,
+        network_id: &[u8],
+        observers: BTreeSet<Id>,
+    ) -> Self {
+        let payload = T::default();
+        let identifier = compute_identifier_with(hasher, &[network_id], &payload)
+            .unwrap_or_else(|_| UnitId(hasher.hash(network_id)));
         Unit {
-            identifier: identifier.to_vec(),
-            parent: parent.identifier.clone(),
+            identifier,
+            parents: BTreeSet::new(),
             payload,
             observers,
-            children: BTreeSet::new(),
+            signatures: BTreeMap::new(),
+            content_type: None,
+            clock: 0,
+            metadata: BTreeMap::new(),
+            certificate: None,
         }
     }
 
-    /// Union with the other unit.
-    pub fn union(&mut self, other: &Unit) {
-        self.observers = self.observers.union(&other.observers).cloned().collect();
+    /// Create a new unit on top of the given parents, based on the input infos, with a clock one
+    /// more than the greatest of theirs. Fails if any payload can't be serialised.
+    pub fn new(parents: &[&Self], payload: T, observers: BTreeSet<Id>) -> Result<Self, Error> {
+        Unit::new_with(&Sha3Hasher, parents, payload, observers)
     }
 
-    /// Add a new child.
-    pub fn add_child(&mut self, child: Vec<u8>) {
-        let _ = self.children.insert(child);
+    /// As `new`, the identifier being computed with `hasher`.
+    pub fn new_with(
+        hasher: &Hasher,
+        parents: &[&Self],
+        payload: T,
+        observers: BTreeSet<Id>,
+    ) -> Result<Self, Error> {
+        let clock = parents.iter().map(|parent| parent.clock).max().map_or(
+            0,
+            |clock| clock.saturating_add(1),
+        );
+        let parents: BTreeMap<UnitId, &T> = parents
+            .iter()
+            .map(|parent| (parent.identifier, &parent.payload))
+            .collect();
+        let parent_payloads: Vec<&T> = parents.values().cloned().collect();
+        let identifier = compute_identifier_with(hasher, &parent_payloads, &payload)?;
+        Ok(Unit {
+            identifier,
+            parents: parents.keys().cloned().collect(),
+            payload,
+            observers,
+            signatures: BTreeMap::new(),
+            content_type: None,
+            clock,
+            metadata: BTreeMap::new(),
+            certificate: None,
+        })
     }
 
-    /// Add a new observer.
+    /// A copy of this unit without any observers, nor the certificate made of their signatures.
+    pub fn without_observers(&self) -> Self {
+        Unit {
+            observers: BTreeSet::new(),
+            signatures: BTreeMap::new(),
+            certificate: None,
+            ..self.clone()
+        }
+    }
+
+    /// Union with the other unit, only taking over the observers with a valid signature, its
+    /// content type if we have none, its clock if smaller, its metadata as per `merge_metadata`,
+    /// and its certificate if valid and carrying more signatures than ours. Returns the observers
+    /// dropped for lacking a signature.
+    pub fn union(&mut self, other: &Unit<T>) -> BTreeSet<Id> {
+        if self.content_type.is_none() {
+            self.content_type = other.content_type.clone();
+        }
+        if let Some(ref certificate) = other.certificate {
+            let larger = self.certificate.as_ref().map_or(true, |ours| {
+                certificate.signatures.len() > ours.signatures.len()
+            });
+            if larger && certificate.verify(self) {
+                self.certificate = Some(certificate.clone());
+            }
+        }
+        self.clock = cmp::min(self.clock, other.clock);
+        self.merge_metadata(&other.metadata);
+        let mut forged = BTreeSet::new();
+        for observer in &other.observers {
+            if self.observers.contains(observer) {
+                continue;
+            }
+            match other.signatures.get(observer) {
+                Some(signature) if self.is_signed_by(observer, signature) => {
+                    let _ = self.observers.insert(*observer);
+                    let _ = self.signatures.insert(*observer, signature.clone());
+                }
+                _ => {
+                    let _ = forged.insert(*observer);
+                }
+            }
+        }
+        forged
+    }
+
+    /// Merge `metadata` into ours: entries for names we lack are taken over, and of two values
+    /// for the same name the smallest is kept, so that every node ends up with the same metadata
+    /// whatever the order it merged units in.
+    pub fn merge_metadata(&mut self, metadata: &BTreeMap<String, Vec<u8>>) {
+        for (name, value) in metadata {
+            let entry = self.metadata.entry(name.clone()).or_insert_with(|| value.clone());
+            if *value < *entry {
+                *entry = value.clone();
+            }
+        }
+    }
+
+    /// Attach a certificate made of the valid signatures of the observers, replacing any held.
+    /// Nothing is attached if there are none.
+    pub fn certify(&mut self) {
+        let signatures: BTreeMap<Id, Vec<u8>> = self.signatures
+            .iter()
+            .filter(|&(observer, _)| {
+                self.observers.contains(observer) && self.is_attested_by(observer)
+            })
+            .map(|(observer, signature)| (*observer, signature.clone()))
+            .collect();
+        if !signatures.is_empty() {
+            self.certificate = Some(QuorumCertificate {
+                identifier: self.identifier,
+                signatures,
+            });
+        }
+    }
+
+    /// The version of this unit. Observers only ever grow, so a unit whose version changed has
+    /// new information to pass on.
+    pub fn version(&self) -> usize {
+        self.observers.len()
+    }
+
+    /// Add `observer` if `signature` is its valid signature over the payload. Returns whether it
+    /// was.
+    pub fn add_attested_observer(&mut self, observer: &Id, signature: &[u8]) -> bool {
+        if !self.is_signed_by(observer, signature) {
+            return false;
+        }
+        let _ = self.observers.insert(*observer);
+        let _ = self.signatures.insert(*observer, signature.to_vec());
+        true
+    }
+
+    /// Whether `observer` is an observer with a valid signature over the payload.
+    pub fn is_attested_by(&self, observer: &Id) -> bool {
+        self.signatures.get(observer).map_or(false, |signature| {
+            self.is_signed_by(observer, signature)
+        })
+    }
+
+    /// Add a new observer, without a signature.
     pub fn add_observer(&mut self, id: &Id) {
         let _ = self.observers.insert(*id);
     }
 
-    /// Print self in graphviz format.
-    pub fn graphviz(&self) -> String {
-        let mut graphviz: String = format!("\"{:02x}(", self.payload[self.payload.len() - 1]);
-        let mut num_of_observers = self.observers.len();
-        for observer in &self.observers {
-            graphviz += &format!("{:02x}", observer.0[0]);
-            num_of_observers -= 1;
-            if num_of_observers != 0 {
-                graphviz += ", ";
+    /// Add the owner of `keys` as an observer, signing the payload. Nothing is added if the
+    /// payload can't be encoded.
+    pub fn add_signed_observer(&mut self, keys: &Keypair) {
+        let encoded = match self.encoded_payload() {
+            Ok(encoded) => encoded,
+            Err(error) => {
+                error!("Failed to encode payload to sign: {}", error);
+                return;
             }
-        }
-        graphviz += ")\"";
-        graphviz
+        };
+        let id = Id::from(keys.public);
+        let signature = keys.sign::<Sha3_512>(&encoded).to_bytes().to_vec();
+        let _ = self.observers.insert(id);
+        let _ = self.signatures.insert(id, signature);
+    }
+
+    /// The canonical encoding of the payload.
+    pub fn encoded_payload(&self) -> Result<Vec<u8>, Error> {
+        Ok(serialisation::serialise(&self.payload)?)
     }
+
+    /// Print self in graphviz format, as the short forms of the identifier and observers.
+    pub fn graphviz(&self) -> String {
+        let observers: Vec<String> = self.observers.iter().map(Id::short).collect();
+        format!("\"{}({})\"", self.identifier.short(), observers.join(", "))
+    }
+
+    // Whether `signature` is the signature of `observer` over the payload.
+    fn is_signed_by(&self, observer: &Id, signature: &[u8]) -> bool {
+        self.encoded_payload().map_or(false, |encoded| {
+            observer.verify(&encoded, signature)
+        })
+    }
+}
+
+/// The identifier of a unit holding `payload`, whose parents hold `parent_payloads`, in the order
+/// of the parents' identifiers. A unit with a single parent has the identifier it had before units
+/// could have several.
+pub fn compute_identifier<P, T>(parent_payloads: &[&P], payload: &T) -> Result<UnitId, Error>
+where
+    P: Serialize + ?Sized,
+    T: Serialize + ?Sized,
+{
+    compute_identifier_with(&Sha3Hasher, parent_payloads, payload)
+}
+
+/// As `compute_identifier`, hashing with `hasher`.
+pub fn compute_identifier_with<P, T>(
+    hasher: &Hasher,
+    parent_payloads: &[&P],
+    payload: &T,
+) -> Result<UnitId, Error>
+where
+    P: Serialize + ?Sized,
+    T: Serialize + ?Sized,
+{
+    let serialised = if parent_payloads.len() == 1 {
+        serialisation::serialise(&(parent_payloads[0], payload))?
+    } else {
+        serialisation::serialise(&(parent_payloads, payload))?
+    };
+    Ok(UnitId(hasher.hash(&serialised)))
 }
 
-impl Debug for Unit {
+impl<T: Debug> Debug for Unit<T> {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(
             formatter,
-            "Unit identifier: {:02x}{:02x}{:02x}.. , parent: {:02x}{:02x}{:02x}.. , \
-             payload: {:?} , observers: {:?}, children: {:?}",
-            self.identifier[0],
-            self.identifier[1],
-            self.identifier[2],
-            self.parent[0],
-            self.parent[1],
-            self.parent[2],
+            "Unit identifier: {:?} , parents: {:?} , payload: {:?} , observers: {:?} , clock: {}",
+            self.identifier,
+            self.parents,
             self.payload,
             self.observers,
-            self.children
-        )
+            self.clock
+        )?;
+        if let Some(ref content_type) = self.content_type {
+            write!(formatter, " , content type: {:?}", content_type)?;
+        }
+        if !self.metadata.is_empty() {
+            write!(formatter, " , metadata: {:?}", self.metadata)?;
+        }
+        if let Some(ref certificate) = self.certificate {
+            write!(formatter, " , certified by: {:?}", certificate.signers())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::SeededRng;
+
+    #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    struct Transfer {
+        from: String,
+        to: String,
+        amount: u64,
+    }
+
+    fn transfer(amount: u64) -> Transfer {
+        Transfer {
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            amount,
+        }
+    }
+
+    #[test]
+    fn typed_payload() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let genesis: Unit<Transfer> = Unit::new_genesis(BTreeSet::new());
+        assert_eq!(genesis.payload, Transfer::default());
+
+        // Identifiers and signatures are computed over the canonical encoding of the payload.
+        let mut unit = unwrap!(Unit::new(&[&genesis], transfer(10), BTreeSet::new()));
+        let mut same = unwrap!(Unit::new(&[&genesis], transfer(10), BTreeSet::new()));
+        let mut other = unwrap!(Unit::new(&[&genesis], transfer(11), BTreeSet::new()));
+        assert_eq!(unit.identifier, same.identifier);
+        assert_ne!(unit.identifier, other.identifier);
+        let encoded = unwrap!(serialisation::serialise(&transfer(10)));
+        assert_eq!(unwrap!(unit.encoded_payload()), encoded);
+        assert_eq!(unit.clock, genesis.clock + 1);
+
+        unit.add_signed_observer(&keys);
+        let observer = Id::from(keys.public);
+        assert!(unit.is_attested_by(&observer));
+        let signature = unwrap!(unit.signatures.get(&observer)).clone();
+        assert!(!other.add_attested_observer(&observer, &signature));
+        assert!(same.add_attested_observer(&observer, &signature));
+    }
+
+    #[test]
+    fn certificates() {
+        let mut rng = SeededRng::new();
+        let keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let peer_keys = Keypair::generate::<Sha3_512>(&mut rng);
+        let genesis: Unit<Transfer> = Unit::new_genesis(BTreeSet::new());
+        let mut unit = unwrap!(Unit::new(&[&genesis], transfer(10), BTreeSet::new()));
+        let other = unwrap!(Unit::new(&[&genesis], transfer(11), BTreeSet::new()));
+        unit.certify();
+        assert!(unit.certificate.is_none());
+
+        unit.add_signed_observer(&keys);
+        let mut fresh = unit.without_observers();
+        unit.certify();
+        let single = unwrap!(unit.certificate.clone());
+        unit.add_signed_observer(&peer_keys);
+        unit.certify();
+        let certificate = unwrap!(unit.certificate.clone());
+        assert_eq!(certificate.signers().len(), 2);
+        assert!(certificate.verify(&unit));
+        assert!(!certificate.verify(&other));
+
+        // A certificate is only taken over once checked, and if it carries more signatures.
+        let mut forged = unit.clone();
+        if let Some(ref mut forged) = forged.certificate {
+            let _ = forged.signatures.insert(Id::from(peer_keys.public), vec![0; 64]);
+            assert!(!forged.verify(&unit));
+        }
+        let _ = fresh.union(&forged);
+        assert!(fresh.certificate.is_none());
+        let _ = fresh.union(&unit);
+        assert_eq!(fresh.certificate, Some(certificate.clone()));
+        let mut smaller = unit.clone();
+        smaller.certificate = Some(single);
+        let _ = fresh.union(&smaller);
+        assert_eq!(fresh.certificate, Some(certificate));
     }
 }
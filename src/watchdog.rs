@@ -0,0 +1,261 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use id::UnitId;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An internal stall detected by the watchdog of a `Gossiper`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stall {
+    /// No unit became stable for this many rounds, though peers are live and units are unstable.
+    NoStabilityProgress {
+        /// Number of rounds without progress.
+        rounds: u64,
+    },
+    /// Units were received whose parents we don't hold, and are still missing.
+    Orphans {
+        /// Number of distinct missing parents.
+        missing_parents: usize,
+    },
+    /// This many unions with peers' DAGs failed in a row.
+    UnionErrors {
+        /// Number of consecutive failures.
+        count: u32,
+    },
+}
+
+/// When the watchdog of a `Gossiper` reports a stall, and what it does about it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchdogConfig {
+    /// Rounds without any unit becoming stable before reporting `Stall::NoStabilityProgress`.
+    pub stall_rounds: u64,
+    /// Missing parents tolerated before reporting `Stall::Orphans`.
+    pub max_missing_parents: usize,
+    /// Consecutive union failures tolerated before reporting `Stall::UnionErrors`.
+    pub max_union_errors: u32,
+    /// On a stall, push the whole DAG and pull everything we are missing in the next round.
+    pub full_sync: bool,
+    /// On a stall, rebuild the DAG's indices from its units.
+    pub rebuild_indices: bool,
+    /// On a stall, send an `Event::Stalled` to subscribers, and on a unit stalling an
+    /// `Event::UnitStalled`.
+    pub alert: bool,
+    /// Rounds a unit may stay unstable, while peers are live, before it is reported as stalled by
+    /// an `Event::UnitStalled`, or `None` not to track units.
+    pub unit_stall_rounds: Option<u64>,
+    /// Until a stalled unit becomes stable, gossip first to the peers which didn't observe it,
+    /// and push it to them in every round, even if they should hold it already.
+    pub regossip_stalled: bool,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig {
+            stall_rounds: 50,
+            max_missing_parents: 100,
+            max_union_errors: 10,
+            full_sync: true,
+            rebuild_indices: false,
+            alert: true,
+            unit_stall_rounds: Some(100),
+            regossip_stalled: true,
+        }
+    }
+}
+
+// The progress tracked by the watchdog between two checks.
+#[derive(Default)]
+pub struct Watchdog {
+    pub config: WatchdogConfig,
+    stable_units: usize,
+    rounds_without_progress: u64,
+    missing_parents: BTreeSet<UnitId>,
+    union_errors: u32,
+    // The rounds each unstable unit stayed so, while progress was expected.
+    unit_ages: BTreeMap<UnitId, u64>,
+    // The unstable units reported as stalled.
+    stalled_units: BTreeSet<UnitId>,
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig) -> Self {
+        Watchdog {
+            config,
+            ..Watchdog::default()
+        }
+    }
+
+    // Record the outcome of a union, with the parent it was missing if it failed for that reason.
+    pub fn record_union(&mut self, succeeded: bool, missing_parent: Option<UnitId>) {
+        if succeeded {
+            self.union_errors = 0;
+        } else {
+            self.union_errors = self.union_errors.saturating_add(1);
+        }
+        if let Some(parent) = missing_parent {
+            let _ = self.missing_parents.insert(parent);
+        }
+    }
+
+    // Check for stalls once per round. `is_held` tells which missing parents were received since.
+    pub fn check<F: Fn(&UnitId) -> bool>(
+        &mut self,
+        stable_units: usize,
+        progress_expected: bool,
+        is_held: F,
+    ) -> Vec<Stall> {
+        if stable_units != self.stable_units || !progress_expected {
+            self.rounds_without_progress = 0;
+        } else {
+            self.rounds_without_progress += 1;
+        }
+        self.stable_units = stable_units;
+        self.missing_parents = self.missing_parents
+            .iter()
+            .filter(|parent| !is_held(parent))
+            .cloned()
+            .collect();
+
+        let mut stalls = Vec::new();
+        if self.rounds_without_progress >= self.config.stall_rounds {
+            stalls.push(Stall::NoStabilityProgress { rounds: self.rounds_without_progress });
+            self.rounds_without_progress = 0;
+        }
+        if self.missing_parents.len() > self.config.max_missing_parents {
+            stalls.push(Stall::Orphans { missing_parents: self.missing_parents.len() });
+            self.missing_parents.clear();
+        }
+        if self.union_errors > self.config.max_union_errors {
+            stalls.push(Stall::UnionErrors { count: self.union_errors });
+            self.union_errors = 0;
+        }
+        stalls
+    }
+
+    // Age the unstable units once per round, forgetting those which became stable or were
+    // dropped. Returns those which just stalled, with their age.
+    pub fn check_units<I: IntoIterator<Item = UnitId>>(
+        &mut self,
+        unstable: I,
+        progress_expected: bool,
+    ) -> Vec<(UnitId, u64)> {
+        let threshold = match self.config.unit_stall_rounds {
+            Some(threshold) => threshold,
+            None => return Vec::new(),
+        };
+        let mut ages = BTreeMap::new();
+        for identifier in unstable {
+            let age = self.unit_ages.get(&identifier).cloned().unwrap_or(0);
+            let age = if progress_expected { age + 1 } else { age };
+            let _ = ages.insert(identifier, age);
+        }
+        self.stalled_units = self.stalled_units
+            .iter()
+            .filter(|identifier| ages.contains_key(identifier))
+            .cloned()
+            .collect();
+        self.unit_ages = ages;
+
+        let mut stalled = Vec::new();
+        for (identifier, age) in &self.unit_ages {
+            if *age >= threshold && self.stalled_units.insert(*identifier) {
+                stalled.push((*identifier, *age));
+            }
+        }
+        stalled
+    }
+
+    // The units reported as stalled which are still unstable.
+    pub fn stalled_units(&self) -> &BTreeSet<UnitId> {
+        &self.stalled_units
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::iter;
+
+    #[test]
+    fn stalls() {
+        let mut watchdog = Watchdog::new(WatchdogConfig {
+            stall_rounds: 3,
+            max_missing_parents: 1,
+            max_union_errors: 1,
+            ..WatchdogConfig::default()
+        });
+
+        // Rounds only count while progress is expected, and restart once a unit becomes stable.
+        assert!(watchdog.check(0, false, |_| false).is_empty());
+        assert!(watchdog.check(0, true, |_| false).is_empty());
+        assert!(watchdog.check(1, true, |_| false).is_empty());
+        assert!(watchdog.check(1, true, |_| false).is_empty());
+        assert!(watchdog.check(1, true, |_| false).is_empty());
+        assert_eq!(
+            watchdog.check(1, true, |_| false),
+            vec![Stall::NoStabilityProgress { rounds: 3 }]
+        );
+        assert!(watchdog.check(1, true, |_| false).is_empty());
+
+        // Missing parents received since the last check are forgotten.
+        watchdog.record_union(false, Some(UnitId([1; 32])));
+        watchdog.record_union(true, Some(UnitId([2; 32])));
+        assert!(watchdog.check(2, true, |parent| *parent == UnitId([1; 32])).is_empty());
+        watchdog.record_union(false, Some(UnitId([3; 32])));
+        watchdog.record_union(false, None);
+        assert_eq!(
+            watchdog.check(3, true, |_| false),
+            vec![
+                Stall::Orphans { missing_parents: 2 },
+                Stall::UnionErrors { count: 2 },
+            ]
+        );
+        assert!(watchdog.check(4, true, |_| false).is_empty());
+    }
+
+    #[test]
+    fn stalled_units() {
+        let mut watchdog = Watchdog::new(WatchdogConfig {
+            unit_stall_rounds: Some(2),
+            ..WatchdogConfig::default()
+        });
+        let (first, second) = (UnitId([1; 32]), UnitId([2; 32]));
+
+        // Units only age while progress is expected, and are reported once when they stall.
+        assert!(watchdog.check_units(vec![first], true).is_empty());
+        assert!(watchdog.check_units(vec![first, second], false).is_empty());
+        assert_eq!(watchdog.check_units(vec![first, second], true), vec![(first, 2)]);
+        assert_eq!(watchdog.check_units(vec![first, second], true), vec![(second, 2)]);
+        assert!(watchdog.check_units(vec![first, second], true).is_empty());
+        let both: BTreeSet<_> = vec![first, second].into_iter().collect();
+        assert_eq!(watchdog.stalled_units(), &both);
+
+        // Units which became stable are forgotten, and start aging afresh if seen again.
+        assert!(watchdog.check_units(vec![second], true).is_empty());
+        assert_eq!(watchdog.stalled_units(), &iter::once(second).collect::<BTreeSet<_>>());
+        assert!(watchdog.check_units(vec![first, second], true).is_empty());
+
+        let mut untracked = Watchdog::new(WatchdogConfig {
+            unit_stall_rounds: None,
+            ..WatchdogConfig::default()
+        });
+        for _ in 0..200 {
+            assert!(untracked.check_units(vec![first], true).is_empty());
+        }
+        assert!(untracked.stalled_units().is_empty());
+    }
+}
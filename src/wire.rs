@@ -0,0 +1,282 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use error::Error;
+use maidsafe_utilities::serialisation;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+#[cfg(feature = "cbor")]
+use serde_cbor;
+#[cfg(feature = "json")]
+use serde_json;
+#[cfg(feature = "zstd")]
+use std::io::Read;
+#[cfg(feature = "zstd")]
+use zstd;
+
+/// Version of the protocol spoken by this version of the crate. Every message sent carries it, and
+/// messages carrying another version are rejected rather than misread.
+pub const PROTOCOL_VERSION: u32 = 7;
+
+// Length of the header preceding the body of a message: the protocol version, big-endian, then
+// a byte holding the tag of the wire format the body is encoded with in its low four bits, and the
+// tag of the compression applied to it in its high four bits.
+const HEADER_LEN: usize = 5;
+// Tags of the compression algorithms.
+const UNCOMPRESSED: u8 = 0;
+#[cfg(feature = "zstd")]
+const ZSTD: u8 = 1;
+// The name of the limit on the length of messages, as reported by `Error::MessageLimitExceeded`.
+pub const MESSAGE_BYTES: &str = "length in bytes";
+
+/// The serialisation format of the messages sent by a `Gossiper`. Received messages are decoded
+/// with the format they are tagged with, so peers may send with different formats, as long as
+/// every peer was built with support for all the formats in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireFormat {
+    /// Bincode, as used by `maidsafe_utilities::serialisation`. The most compact.
+    Bincode,
+    /// CBOR, for peers written in other languages. Requires the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// JSON, for debugging and for peers written in other languages. Requires the `json` feature.
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl WireFormat {
+    fn tag(&self) -> u8 {
+        match *self {
+            WireFormat::Bincode => 0,
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => 1,
+            #[cfg(feature = "json")]
+            WireFormat::Json => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(WireFormat::Bincode),
+            #[cfg(feature = "cbor")]
+            1 => Some(WireFormat::Cbor),
+            #[cfg(feature = "json")]
+            2 => Some(WireFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+impl Default for WireFormat {
+    fn default() -> Self {
+        WireFormat::Bincode
+    }
+}
+
+/// How much to compress the messages sent by a `Gossiper`, with zstd. Compression only applies
+/// with the `zstd` feature, messages being sent uncompressed otherwise, and is skipped for messages
+/// it wouldn't make smaller. Received messages are decompressed whatever the level set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionLevel {
+    /// No compression.
+    None,
+    /// Favour speed over size.
+    Fast,
+    /// A balance between speed and size.
+    Default,
+    /// Favour size over speed, for slow links.
+    Best,
+}
+
+impl CompressionLevel {
+    #[cfg(feature = "zstd")]
+    fn zstd_level(&self) -> Option<i32> {
+        match *self {
+            CompressionLevel::None => None,
+            CompressionLevel::Fast => Some(1),
+            CompressionLevel::Default => Some(3),
+            CompressionLevel::Best => Some(19),
+        }
+    }
+}
+
+impl Default for CompressionLevel {
+    fn default() -> Self {
+        CompressionLevel::None
+    }
+}
+
+/// Encode `message` with `format`, compressed as per `compression`, preceded by the protocol
+/// version and the tags of the format and of the compression.
+pub fn encode<T: Serialize>(
+    format: WireFormat,
+    compression: CompressionLevel,
+    message: &T,
+) -> Result<Vec<u8>, Error> {
+    let body = match format {
+        WireFormat::Bincode => serialisation::serialise(message)?,
+        #[cfg(feature = "cbor")]
+        WireFormat::Cbor => {
+            serde_cbor::to_vec(message).map_err(|error| Error::Encoding(error.to_string()))?
+        }
+        #[cfg(feature = "json")]
+        WireFormat::Json => {
+            serde_json::to_vec(message).map_err(|error| Error::Encoding(error.to_string()))?
+        }
+    };
+    let (compression, body) = compress(compression, body)?;
+    let mut encoded = Vec::with_capacity(HEADER_LEN + body.len());
+    for shift in &[24, 16, 8, 0] {
+        encoded.push((PROTOCOL_VERSION >> shift) as u8);
+    }
+    encoded.push(compression << 4 | format.tag());
+    encoded.extend(body);
+    Ok(encoded)
+}
+
+/// Decode a message encoded by `encode`. Fails with `Error::IncompatibleProtocol` if it was sent
+/// with another protocol version, and with `Error::UnsupportedWireFormat` if its format or its
+/// compression isn't supported by this build.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    decode_bounded(bytes, None)
+}
+
+/// As `decode`, failing with `Error::MessageLimitExceeded` if the message is longer than
+/// `max_len` bytes, compressed or once decompressed.
+pub fn decode_bounded<T: DeserializeOwned>(
+    bytes: &[u8],
+    max_len: Option<usize>,
+) -> Result<T, Error> {
+    if bytes.len() < HEADER_LEN {
+        return Err(Error::Encoding("message shorter than its header".to_string()));
+    }
+    if let Some(max) = max_len {
+        if bytes.len() > max {
+            return Err(Error::MessageLimitExceeded(MESSAGE_BYTES, bytes.len(), max));
+        }
+    }
+    let version = bytes[..4].iter().fold(0, |version, &byte| (version << 8) | u32::from(byte));
+    if version != PROTOCOL_VERSION {
+        return Err(Error::IncompatibleProtocol(version));
+    }
+    let body = match decompress(bytes[4] >> 4, &bytes[HEADER_LEN..], max_len) {
+        Some(body) => body?,
+        None => return Err(Error::UnsupportedWireFormat(bytes[4])),
+    };
+    let body = &body[..];
+    match WireFormat::from_tag(bytes[4] & 0x0f) {
+        Some(WireFormat::Bincode) => Ok(serialisation::deserialise(body)?),
+        #[cfg(feature = "cbor")]
+        Some(WireFormat::Cbor) => {
+            serde_cbor::from_slice(body).map_err(|error| Error::Encoding(error.to_string()))
+        }
+        #[cfg(feature = "json")]
+        Some(WireFormat::Json) => {
+            serde_json::from_slice(body).map_err(|error| Error::Encoding(error.to_string()))
+        }
+        None => Err(Error::UnsupportedWireFormat(bytes[4])),
+    }
+}
+
+// The tag of the compression applied to `body`, and the possibly compressed body.
+#[cfg(feature = "zstd")]
+fn compress(compression: CompressionLevel, body: Vec<u8>) -> Result<(u8, Vec<u8>), Error> {
+    if let Some(level) = compression.zstd_level() {
+        let compressed = zstd::encode_all(&body[..], level)?;
+        if compressed.len() < body.len() {
+            return Ok((ZSTD, compressed));
+        }
+    }
+    Ok((UNCOMPRESSED, body))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compress(_compression: CompressionLevel, body: Vec<u8>) -> Result<(u8, Vec<u8>), Error> {
+    Ok((UNCOMPRESSED, body))
+}
+
+// The body decompressed as per the compression tag, or `None` if the compression isn't supported.
+// Decompression stops as soon as the body gets longer than `max_len`, failing.
+fn decompress(
+    compression: u8,
+    body: &[u8],
+    max_len: Option<usize>,
+) -> Option<Result<Vec<u8>, Error>> {
+    match compression {
+        UNCOMPRESSED => Some(Ok(body.to_vec())),
+        #[cfg(feature = "zstd")]
+        ZSTD => Some(decompress_zstd(body, max_len)),
+        _ => {
+            let _ = max_len;
+            None
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(body: &[u8], max_len: Option<usize>) -> Result<Vec<u8>, Error> {
+    let max = match max_len {
+        Some(max) => max,
+        None => return Ok(zstd::decode_all(body)?),
+    };
+    let mut decompressed = Vec::new();
+    let decoder = zstd::stream::Decoder::new(body)?;
+    let _ = decoder.take(max as u64 + 1).read_to_end(&mut decompressed)?;
+    if decompressed.len() > max {
+        return Err(Error::MessageLimitExceeded(MESSAGE_BYTES, decompressed.len(), max));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope() {
+        let message = (7u64, "payload".to_string());
+        let encoded = unwrap!(encode(WireFormat::Bincode, CompressionLevel::None, &message));
+        let decoded: (u64, String) = unwrap!(decode(&encoded));
+        assert_eq!(decoded, message);
+
+        let mut newer = encoded.clone();
+        newer[3] = newer[3].wrapping_add(1);
+        match decode::<(u64, String)>(&newer) {
+            Err(Error::IncompatibleProtocol(version)) => assert_eq!(version, PROTOCOL_VERSION + 1),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let mut unknown = encoded.clone();
+        unknown[4] = 0xff;
+        match decode::<(u64, String)>(&unknown) {
+            Err(Error::UnsupportedWireFormat(0xff)) => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(decode::<(u64, String)>(&encoded[..3]).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compression() {
+        let message = vec![7u8; 1000];
+        let plain = unwrap!(encode(WireFormat::Bincode, CompressionLevel::None, &message));
+        let compressed = unwrap!(encode(WireFormat::Bincode, CompressionLevel::Best, &message));
+        assert!(compressed.len() < plain.len());
+        let decoded: Vec<u8> = unwrap!(decode(&compressed));
+        assert_eq!(decoded, message);
+    }
+}